@@ -16,7 +16,7 @@ use arborio_utils::vizia::prelude::Canvas;
 use arborio_utils::vizia::vg::{Color, ImageFlags, ImageId, ImageSource, Paint, Path};
 use arborio_walker::{ConfigSource, ConfigSourceTrait};
 
-use crate::autotiler::TileReference;
+use crate::autotiler::{TextureTile, TileReference};
 
 #[derive(Debug)]
 enum BlobData {
@@ -237,24 +237,83 @@ pub fn load_data_file(
     Ok(Img::new(buf, width as usize, height as usize))
 }
 
+/// A texture which object tiles are cut from, numbered left to right then top to bottom.
+#[derive(Copy, Clone, Debug)]
+pub struct ObjectTileset {
+    pub texture: Interned,
+    pub columns: i32,
+    pub rows: i32,
+}
+
+impl ObjectTileset {
+    pub fn tile(&self, idx: i32) -> TileReference {
+        TileReference {
+            tile: TextureTile {
+                x: (idx % self.columns) as u32,
+                y: (idx / self.columns) as u32,
+            },
+            texture: self.texture,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct MultiAtlas {
     sprites_map: InternedMap<Arc<AtlasSprite>>,
+    scenery: Interned,
 }
 
 impl MultiAtlas {
     pub fn from(sprites_map: InternedMap<Arc<AtlasSprite>>) -> Self {
-        Self { sprites_map }
+        Self {
+            sprites_map,
+            scenery: "tilesets/scenery".into(),
+        }
     }
 
     pub fn iter_paths(&self) -> impl Iterator<Item = &Interned> + '_ {
         self.sprites_map.keys()
     }
 
+    fn sprite(&self, sprite_path: &str) -> Option<Arc<AtlasSprite>> {
+        let path = sprite_path.replace('\\', "/");
+        if let Some(found) = self.sprites_map.get(path.as_str()) {
+            return Some(found.clone());
+        }
+        // like in game, a decal path with no sprite of its own stands for its animation, so show
+        // the first frame
+        if !path.starts_with("decals/") {
+            return None;
+        }
+        ["00", "0", "000"]
+            .iter()
+            .find_map(|frame| self.sprites_map.get(format!("{path}{frame}").as_str()))
+            .cloned()
+    }
+
     pub fn sprite_dimensions(&self, sprite_path: &str) -> Option<Size2D<u16, UnknownUnit>> {
-        self.sprites_map
-            .get(sprite_path.replace('\\', "/").as_str())
-            .map(|s| s.untrimmed_size)
+        self.sprite(sprite_path).map(|s| s.untrimmed_size)
+    }
+
+    /// The image a sprite is cut from, uploading it if it hasn't been yet.
+    pub fn sprite_image(&self, canvas: &mut Canvas, sprite_path: &str) -> Option<ImageId> {
+        let sprite = self.sprite(sprite_path)?;
+        let id = sprite.blob.lock().unwrap().image_id(canvas);
+        Some(id)
+    }
+
+    /// The object tiles cut from `tileset`, a texture such as `tilesets/scenery`. Rooms which
+    /// don't name one use `scenery`.
+    pub fn object_tileset(&self, tileset: Option<Interned>) -> ObjectTileset {
+        let texture = tileset.unwrap_or(self.scenery);
+        let size = self
+            .sprite_dimensions(&texture)
+            .map_or(Size2D::new(32, 32), |size| size / 8);
+        ObjectTileset {
+            texture,
+            columns: size.width.max(1) as i32,
+            rows: size.height as i32,
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -270,8 +329,7 @@ impl MultiAtlas {
         rot: f32,
     ) -> Result<(), String> {
         let sprite = self
-            .sprites_map
-            .get(sprite_path.replace('\\', "/").as_str())
+            .sprite(sprite_path)
             .ok_or_else(|| format!("No such texture: {sprite_path}"))?;
         let color = color.unwrap_or_else(Color::white);
 
@@ -353,3 +411,28 @@ impl MultiAtlas {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_decals_fall_back_to_first_frame() {
+        let sprite = || {
+            Arc::new(AtlasSprite {
+                blob: Arc::new(Mutex::new(BlobData::Waiting(Img::new(vec![], 0, 0)))),
+                bounding_box: Rect::zero(),
+                trim_offset: Vector2D::zero(),
+                untrimmed_size: Size2D::new(8, 8),
+            })
+        };
+        let atlas = MultiAtlas::from(InternedMap::from([
+            ("decals/flag00".into(), sprite()),
+            ("objects/flag00".into(), sprite()),
+        ]));
+        assert!(atlas.sprite("decals/flag").is_some());
+        assert!(atlas.sprite("decals/flag00").is_some());
+        assert!(atlas.sprite("objects/flag").is_none());
+        assert!(atlas.sprite("objects/flag00").is_some());
+    }
+}
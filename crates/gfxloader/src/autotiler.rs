@@ -5,13 +5,13 @@ use std::io;
 use arborio_utils::interned::Interned;
 use arborio_utils::units::*;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct TextureTile {
     pub x: u32,
     pub y: u32,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct TileReference {
     pub tile: TextureTile,
     pub texture: Interned,
@@ -22,6 +22,9 @@ pub struct Tileset {
     pub id: char,
     pub name: Interned,
     pub texture: Interned,
+    /// The masks in the order they are tried, which is also the order they were baked into `edges`.
+    pub masks: Vec<TileMask>,
+    /// For each combination of filled neighbors, the tiles of the first mask which matches it.
     pub edges: Vec<Vec<TextureTile>>,
     pub padding: Vec<TextureTile>,
     pub center: Vec<TextureTile>,
@@ -29,6 +32,19 @@ pub struct Tileset {
     pub ignores_all: bool,
 }
 
+/// A `set` of a tileset with a 3x3 mask, like `x0x-111-x1x`. Neighbors are numbered from the top
+/// left to the bottom right, skipping the tile itself.
+#[derive(Clone, Debug)]
+pub struct TileMask {
+    /// Which of the eight neighbors the mask cares about.
+    pub mask: u8,
+    /// Which of those neighbors must be filled.
+    pub value: u8,
+    /// The number of wildcard cells. Like the game, masks with fewer wildcards are tried first.
+    pub wildcards: usize,
+    pub tiles: Vec<TextureTile>,
+}
+
 pub type Autotiler = HashMap<char, Tileset>;
 
 #[derive(serde::Deserialize)]
@@ -116,7 +132,8 @@ impl Tileset {
                     id: ch,
                     name,
                     texture,
-                    edges: vec![Vec::new(); 256],
+                    masks: vec![],
+                    edges: vec![],
                     padding: vec![],
                     center: vec![],
                     ignores: vec![],
@@ -158,7 +175,7 @@ impl Tileset {
                 tileset.ignores = s_tileset
                     .ignores
                     .split(',')
-                    .map(|x| x.chars().next().unwrap())
+                    .filter_map(|x| x.trim().chars().next())
                     .collect();
                 // assert_ascii!(s_tileset.ignores);
                 // if s_tileset.ignores.len() > 1 {
@@ -167,10 +184,12 @@ impl Tileset {
                 // tileset.ignores.push(s_tileset.ignores.chars().next().unwrap());
             }
 
-            for s_set in s_tileset.set.iter().rev() {
-                let mut mask = 0_usize;
-                let mut value = 0_usize;
-
+            // the tileset's own sets come before any it copied, and replace copied padding and
+            // center tiles outright
+            let mut masks = vec![];
+            let mut padding = None;
+            let mut center = None;
+            for s_set in s_tileset.set.iter() {
                 // todo: gotta fucking parse animatedtiles I guess
                 //let sprite = if s_set.sprites.is_empty() {
                 //    None
@@ -183,50 +202,25 @@ impl Tileset {
                 //};
                 let tiles = TextureTile::parse_list(&s_set.tiles)?;
                 if s_set.mask == "padding" {
-                    tileset.padding = tiles;
+                    padding.get_or_insert_with(Vec::new).extend(tiles);
                 } else if s_set.mask == "center" {
-                    tileset.center = tiles;
+                    center.get_or_insert_with(Vec::new).extend(tiles);
                 } else {
-                    assert_ascii!(s_set.mask);
-                    let mask_vec: Vec<char> = s_set.mask.chars().collect();
-                    if mask_vec.len() != 11 || mask_vec[3] != '-' || mask_vec[7] != '-' {
-                        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Tileset mask (\"{}\" for tileset {} must be of the form xxx-xxx-xxx, or the literals `padding` or `center`", s_set.mask, s_tileset.id)));
-                    }
-
-                    let mut process_bit = |ch: char, bit: usize| -> bool {
-                        match ch {
-                            'x' => {}
-                            '0' => mask |= bit,
-                            '1' => {
-                                mask |= bit;
-                                value |= bit;
-                            }
-                            _ => return false,
-                        }
-                        true
+                    let Some(mut mask) = TileMask::parse(&s_set.mask) else {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Tileset mask (\"{}\" for tileset {} must be nine of 0, 1, or x, like xxx-xxx-xxx, or the literals `padding` or `center`", s_set.mask, s_tileset.id)));
                     };
-
-                    let mut success = true;
-                    success |= process_bit(mask_vec[0], 1 << 0);
-                    success |= process_bit(mask_vec[1], 1 << 1);
-                    success |= process_bit(mask_vec[2], 1 << 2);
-                    success |= process_bit(mask_vec[4], 1 << 3);
-                    success |= process_bit(mask_vec[6], 1 << 4);
-                    success |= process_bit(mask_vec[8], 1 << 5);
-                    success |= process_bit(mask_vec[9], 1 << 6);
-                    success |= process_bit(mask_vec[10], 1 << 7);
-
-                    if !success {
-                        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Tileset mask (\"{}\" for tileset {} must be of the form xxx-xxx-xxx, or the literals `padding` or `center`", s_set.mask, s_tileset.id)));
-                    }
-
-                    for i in 0..256_usize {
-                        if i & mask == value {
-                            tileset.edges[i] = tiles.clone();
-                        }
-                    }
+                    mask.tiles = tiles;
+                    masks.push(mask);
                 }
             }
+            if let Some(padding) = padding {
+                tileset.padding = padding;
+            }
+            if let Some(center) = center {
+                tileset.center = center;
+            }
+            masks.append(&mut tileset.masks);
+            tileset.set_masks(masks);
 
             out.insert(ch, tileset);
         }
@@ -234,6 +228,20 @@ impl Tileset {
         Ok(out)
     }
 
+    /// Put the masks in the order the game tries them and bake them into the edge table.
+    fn set_masks(&mut self, mut masks: Vec<TileMask>) {
+        masks.sort_by_key(|mask| mask.wildcards);
+        self.edges = vec![Vec::new(); 256];
+        for mask in masks.iter().rev() {
+            for i in 0..256_usize {
+                if i as u8 & mask.mask == mask.value {
+                    self.edges[i] = mask.tiles.clone();
+                }
+            }
+        }
+        self.masks = masks;
+    }
+
     fn ignores(&self, tile: char) -> bool {
         self.ignores_all || self.ignores.contains(&tile)
     }
@@ -248,6 +256,14 @@ impl Tileset {
     }
 
     pub fn tile<F>(&self, pt: TilePoint, tile: &mut F) -> Option<TileReference>
+    where
+        F: Fn(TilePoint) -> Option<char>,
+    {
+        self.tile_or_center(pt, tile).map(|(tile, _)| tile)
+    }
+
+    /// Like [`Self::tile`], but also says whether the tile is one of the center tiles.
+    pub fn tile_or_center<F>(&self, pt: TilePoint, tile: &mut F) -> Option<(TileReference, bool)>
     where
         F: Fn(TilePoint) -> Option<char>,
     {
@@ -255,9 +271,6 @@ impl Tileset {
             return None;
         }
 
-        let hash = ((pt.x as u32).wrapping_mul(536870909) ^ (pt.y as u32).wrapping_mul(1073741789))
-            as usize;
-
         let mut lookup = 0_usize;
         if self.is_filled(tile(pt + TileVector::new(-1, -1))) {
             lookup |= 1 << 0;
@@ -284,28 +297,94 @@ impl Tileset {
             lookup |= 1 << 7;
         }
 
-        let tiles = if lookup == 0xff {
+        let (tiles, center) = if lookup == 0xff {
             if self.is_filled(tile(pt + TileVector::new(-2, 0)))
                 && self.is_filled(tile(pt + TileVector::new(2, 0)))
                 && self.is_filled(tile(pt + TileVector::new(0, -2)))
                 && self.is_filled(tile(pt + TileVector::new(0, 2)))
             {
-                &self.center
+                (&self.center, true)
             } else {
-                &self.padding
+                (&self.padding, false)
             }
         } else {
-            &self.edges[lookup]
+            (&self.edges[lookup], false)
         };
 
         if tiles.is_empty() {
             return None;
         }
 
-        Some(TileReference {
-            tile: tiles[hash % tiles.len()],
-            texture: self.texture,
-        })
+        Some((
+            TileReference {
+                tile: tiles[variant_hash(pt) % tiles.len()],
+                texture: self.texture,
+            },
+            center,
+        ))
+    }
+
+    /// How many tiles apart the center tiles repeat, both across and down, if they do. The variant
+    /// hash is a product of the position, whose low bits only depend on the position's low bits, so
+    /// they repeat whenever there is a power of two of them.
+    pub fn center_period(&self) -> Option<i32> {
+        let len = self.center.len();
+        len.is_power_of_two().then_some(len as i32)
+    }
+
+    /// The center tile drawn at `pt`, if `pt` gets a center tile.
+    pub fn center_tile(&self, pt: TilePoint) -> Option<TextureTile> {
+        if self.center.is_empty() {
+            return None;
+        }
+        Some(self.center[variant_hash(pt) % self.center.len()])
+    }
+}
+
+/// Picks which of a list of tiles to draw at a position. This matches the game.
+fn variant_hash(pt: TilePoint) -> usize {
+    ((pt.x as u32).wrapping_mul(536870909) ^ (pt.y as u32).wrapping_mul(1073741789)) as usize
+}
+
+impl TileMask {
+    /// Parse a mask string. Like the game, only `0`, `1`, and `x` count, so any separators are
+    /// allowed. The cell for the tile itself is ignored.
+    fn parse(text: &str) -> Option<TileMask> {
+        let cells = text
+            .chars()
+            .filter_map(|ch| match ch {
+                '0' => Some(Some(false)),
+                '1' => Some(Some(true)),
+                'x' | 'X' => Some(None),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if cells.len() != 9 {
+            return None;
+        }
+
+        let mut result = TileMask {
+            mask: 0,
+            value: 0,
+            wildcards: 0,
+            tiles: vec![],
+        };
+        let neighbors = cells[..4].iter().chain(cells[5..].iter());
+        for (bit, cell) in neighbors.enumerate() {
+            match cell {
+                Some(filled) => {
+                    result.mask |= 1 << bit;
+                    if *filled {
+                        result.value |= 1 << bit;
+                    }
+                }
+                None => result.wildcards += 1,
+            }
+        }
+        if cells[4].is_none() {
+            result.wildcards += 1;
+        }
+        Some(result)
     }
 }
 
@@ -337,3 +416,136 @@ impl TextureTile {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TILESET_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<Data>
+  <Tileset id="a" path="dirt">
+    <set mask="x1x-111-xxx" tiles="0,0"/>
+    <set mask="010-111-000" tiles="1,0"/>
+    <set mask="XXX X1X XXX" tiles="2,0"/>
+    <set mask="padding" tiles="3,0"/>
+    <set mask="center" tiles="4,0;5,0"/>
+  </Tileset>
+  <Tileset id="b" path="snow" copy="a">
+    <set mask="padding" tiles="6,0"/>
+    <set mask="x1x-111-xxx" tiles="7,0"/>
+  </Tileset>
+</Data>"#;
+
+    fn load() -> Autotiler {
+        Tileset::new(TILESET_XML.as_bytes(), "tilesets/").unwrap()
+    }
+
+    /// The texture column of the tile drawn at `(x, y)` in a grid given as rows of chars.
+    fn tile_at(tiler: &Autotiler, rows: &[&str], x: i32, y: i32) -> Option<u32> {
+        let rows = rows
+            .iter()
+            .map(|r| r.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let mut lookup = |pt: TilePoint| {
+            Some(
+                *rows
+                    .get(pt.y as usize)
+                    .and_then(|row| row.get(pt.x as usize))
+                    .unwrap_or(&'0'),
+            )
+        };
+        let pt = TilePoint::new(x, y);
+        let id = lookup(pt).unwrap();
+        tiler[&id].tile(pt, &mut lookup).map(|t| t.tile.x)
+    }
+
+    #[test]
+    fn fewer_wildcards_win() {
+        let tiler = load();
+        // matches both x1x-111-xxx and 010-111-000, listed in that order
+        assert_eq!(tile_at(&tiler, &["0a0", "aaa", "000"], 1, 1), Some(1));
+        // only the first one
+        assert_eq!(tile_at(&tiler, &["aa0", "aaa", "000"], 1, 1), Some(0));
+        // neither, so the all wildcard mask with no separators
+        assert_eq!(tile_at(&tiler, &["000", "0a0", "000"], 1, 1), Some(2));
+    }
+
+    #[test]
+    fn padding_and_center() {
+        let tiler = load();
+        let solid = ["aaaaa", "aaaaa", "aaaaa", "aaaaa", "aaaaa"];
+        assert!(matches!(tile_at(&tiler, &solid, 2, 2), Some(4 | 5)));
+        let thin = ["aaaa0", "aaaa0", "aaaa0", "aaaa0", "aaaa0"];
+        assert_eq!(tile_at(&tiler, &thin, 2, 2), Some(3));
+    }
+
+    #[test]
+    fn center_tiles_repeat() {
+        let xml = r#"<Data><Tileset id="a" path="dirt">
+            <set mask="center" tiles="0,0;1,0;2,0;3,0"/>
+        </Tileset></Data>"#;
+        let tiler = Tileset::new(xml.as_bytes(), "").unwrap();
+        let a = &tiler[&'a'];
+        assert_eq!(a.center_period(), Some(4));
+        for y in 0..12 {
+            for x in 0..12 {
+                let pt = TilePoint::new(x, y);
+                let tile = a.center_tile(pt);
+                assert_eq!(a.center_tile(TilePoint::new(x % 4, y % 4)), tile);
+            }
+        }
+        // two center tiles repeat too, but three don't
+        assert_eq!(load()[&'a'].center_period(), Some(2));
+        let xml = r#"<Data><Tileset id="a" path="dirt">
+            <set mask="center" tiles="0,0;1,0;2,0"/>
+        </Tileset></Data>"#;
+        assert_eq!(
+            Tileset::new(xml.as_bytes(), "").unwrap()[&'a'].center_period(),
+            None
+        );
+    }
+
+    #[test]
+    fn center_tiles_are_flagged() {
+        let tiler = load();
+        let solid = ["aaaaa", "aaaaa", "aaaaa", "aaaaa", "aaaaa"];
+        let rows = solid
+            .iter()
+            .map(|r| r.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let mut lookup = |pt: TilePoint| {
+            Some(
+                *rows
+                    .get(pt.y as usize)
+                    .and_then(|row| row.get(pt.x as usize))
+                    .unwrap_or(&'0'),
+            )
+        };
+        let a = &tiler[&'a'];
+        let center = TilePoint::new(2, 2);
+        let (tile, is_center) = a.tile_or_center(center, &mut lookup).unwrap();
+        assert!(is_center);
+        assert_eq!(Some(tile.tile), a.center_tile(center));
+        let (_, is_center) = a.tile_or_center(TilePoint::new(1, 2), &mut lookup).unwrap();
+        assert!(!is_center);
+    }
+
+    #[test]
+    fn copy_keeps_own_sets_first() {
+        let tiler = load();
+        let b = &tiler[&'b'];
+        assert_eq!(b.padding.len(), 1);
+        assert_eq!(b.padding[0].x, 6);
+        assert_eq!(b.center.len(), 2);
+        assert_eq!(tile_at(&tiler, &["bb0", "bbb", "000"], 1, 1), Some(7));
+        assert_eq!(tile_at(&tiler, &["0b0", "bbb", "000"], 1, 1), Some(1));
+    }
+
+    #[test]
+    fn reject_bad_masks() {
+        let xml = r#"<Data><Tileset id="a" path="dirt"><set mask="x1x-111" tiles="0,0"/></Tileset></Data>"#;
+        assert!(Tileset::new(xml.as_bytes(), "").is_err());
+        let xml = r#"<Data><Tileset id="a" path="dirt"><set mask="x1x-1y1-xxx" tiles="0,0"/></Tileset></Data>"#;
+        assert!(Tileset::new(xml.as_bytes(), "").is_err());
+    }
+}
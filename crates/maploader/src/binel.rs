@@ -21,7 +21,8 @@ pub struct BinEl {
     pub name: String,
     /// All attributes of the `BinEl`. Unlike XML, these are strongly typed.
     pub attributes: HashMap<String, BinElAttr>,
-    children: HashMap<String, Vec<BinEl>>,
+    /// Kept in file order, since the order of e.g. stylegrounds is significant.
+    children: Vec<BinEl>,
 }
 
 impl BinEl {
@@ -29,34 +30,43 @@ impl BinEl {
         Self {
             name: name.to_owned(),
             attributes: HashMap::new(),
-            children: HashMap::new(),
+            children: Vec::new(),
         }
     }
 
     pub(crate) fn insert(&mut self, child: Self) {
-        self.children
-            .entry(child.name.clone())
-            .or_default()
-            .push(child);
+        self.children.push(child);
     }
 
     pub(crate) fn children(&self) -> impl Iterator<Item = &BinEl> {
-        self.children.values().flatten()
+        self.children.iter()
     }
 
-    pub(crate) fn get(&self, key: &str) -> &[Self] {
-        self.children
-            .get(key)
-            .map(AsRef::as_ref)
-            .unwrap_or_default()
+    pub(crate) fn get<'a: 'k, 'k>(&'a self, key: &'k str) -> impl Iterator<Item = &'a BinEl> + 'k {
+        self.children.iter().filter(move |child| child.name == key)
     }
 
-    pub(crate) fn get_mut(&mut self, key: &str) -> &mut Vec<Self> {
-        self.children.entry(key.to_owned()).or_default()
+    /// The first child named `key`.
+    pub(crate) fn get_mut(&mut self, key: &str) -> Option<&mut BinEl> {
+        self.children.iter_mut().find(|child| child.name == key)
+    }
+
+    /// Replace all children named `key` with `child`, in the position of the first of them.
+    pub(crate) fn replace(&mut self, key: &str, child: Self) {
+        let Some(idx) = self.children.iter().position(|c| c.name == key) else {
+            self.children.push(child);
+            return;
+        };
+        self.children[idx] = child;
+        let mut i = 0;
+        self.children.retain(|c| {
+            i += 1;
+            i <= idx + 1 || c.name != key
+        });
     }
 
     pub fn drain(&mut self) -> impl Iterator<Item = BinEl> + '_ {
-        self.children.drain().flat_map(|(_, v)| v)
+        self.children.drain(..)
     }
 
     pub fn text(&self) -> Option<&str> {
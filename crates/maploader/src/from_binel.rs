@@ -2,7 +2,7 @@ use std::collections::{HashMap, HashSet};
 
 use crate::{
     binel::{BinEl, BinElAttr},
-    map_struct::{get_child_mut, get_optional_child, CelesteMapError},
+    map_struct::{bool_from_attr, get_child_mut, get_optional_child, CelesteMapError},
 };
 pub use arborio_derive::TryFromBinEl;
 use itertools::Itertools;
@@ -81,7 +81,7 @@ pub trait GetAttrOrChild: Sized {
 
 impl GetAttrOrChild for BinEl {
     fn attr_or_child<'b>(elem: &'b BinEl, key: &str) -> Option<&'b Self> {
-        let (x,) = elem.get(key).iter().collect_tuple()?;
+        let (x,) = elem.get(key).collect_tuple()?;
         Some(x)
     }
 
@@ -89,7 +89,7 @@ impl GetAttrOrChild for BinEl {
         if value.name.is_empty() {
             value.name = key.to_owned();
         }
-        *elem.get_mut(key) = vec![value];
+        elem.replace(key, value);
     }
 }
 
@@ -148,7 +148,6 @@ static ATTRS_IGNORE: Lazy<HashSet<(&'static str, &'static str)>> = Lazy::new(||
         ("fgdecals", "tileset"),
         ("bgtiles", "tileset"),
         ("fgtiles", "tileset"),
-        ("objtiles", "tileset"),
     ])
 });
 // entries in this list are made default when missing by celeste.exe
@@ -484,18 +483,78 @@ fn make_like_default(like: &BinElAttr) -> BinElAttr {
 fn bin_el_attr_fuzzy_equal_required(first: Option<&BinElAttr>, second: Option<&BinElAttr>) -> bool {
     let (Some(first), Some(second)) = (first, second) else { return false };
     match (first, second) {
-        (BinElAttr::Bool(_), BinElAttr::Int(_)) => todo!(),
-        (BinElAttr::Bool(_), BinElAttr::Float(_)) => todo!(),
-        (BinElAttr::Bool(_), BinElAttr::Text(_)) => todo!(),
-        (BinElAttr::Int(_), BinElAttr::Bool(_)) => todo!(),
+        // equal exactly when the other value would load as this bool
+        (BinElAttr::Bool(b), other) | (other, BinElAttr::Bool(b)) => {
+            bool_from_attr(other) == Some(*b)
+        }
+        // the same comparison as the other way around
+        (BinElAttr::Float(_), BinElAttr::Text(_)) => {
+            bin_el_attr_fuzzy_equal_required(Some(second), Some(first))
+        }
         (BinElAttr::Int(i), BinElAttr::Float(f)) => *i as f32 == *f && *f as i32 == *i,
         (BinElAttr::Int(i), BinElAttr::Text(t)) => *t == i.to_string(),
-        (BinElAttr::Float(_), BinElAttr::Bool(_)) => todo!(),
         (BinElAttr::Float(f), BinElAttr::Int(i)) => *f as i32 == *i,
-        (BinElAttr::Float(_), BinElAttr::Text(_)) => todo!(),
-        (BinElAttr::Text(_), BinElAttr::Bool(_)) => todo!(),
-        (BinElAttr::Text(t), BinElAttr::Int(i)) => *t == i.to_string(),
-        (BinElAttr::Text(_), BinElAttr::Float(_)) => todo!(),
+        (BinElAttr::Text(t), BinElAttr::Int(i)) => t.trim() == i.to_string(),
+        (BinElAttr::Text(t), BinElAttr::Float(f)) => t.trim().parse::<f32>() == Ok(*f),
         _ => first == second,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(s: &str) -> BinElAttr {
+        BinElAttr::Text(s.to_owned())
+    }
+
+    // attribute values as other editors write them, next to what we save them as
+    fn quirky_pairs() -> Vec<(BinElAttr, BinElAttr)> {
+        vec![
+            (BinElAttr::Int(1), BinElAttr::Bool(true)),
+            (BinElAttr::Int(0), BinElAttr::Bool(false)),
+            (text("True"), BinElAttr::Bool(true)),
+            (text("false"), BinElAttr::Bool(false)),
+            (BinElAttr::Float(-8.0), BinElAttr::Int(-8)),
+            (text("16"), BinElAttr::Int(16)),
+            (text("1.5"), BinElAttr::Float(1.5)),
+        ]
+    }
+
+    #[test]
+    fn quirky_attrs_compare_equal_both_ways() {
+        for (quirky, saved) in quirky_pairs() {
+            assert!(
+                bin_el_attr_fuzzy_equal_required(Some(&quirky), Some(&saved)),
+                "{quirky:?} vs {saved:?}"
+            );
+            assert!(
+                bin_el_attr_fuzzy_equal_required(Some(&saved), Some(&quirky)),
+                "{saved:?} vs {quirky:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn mismatched_attrs_compare_unequal_both_ways() {
+        let pairs = [
+            (BinElAttr::Int(2), BinElAttr::Bool(true)),
+            (BinElAttr::Float(0.5), BinElAttr::Bool(false)),
+            // floats never load as bools, so they never compare equal to one either
+            (BinElAttr::Float(1.0), BinElAttr::Bool(true)),
+            (BinElAttr::Float(0.0), BinElAttr::Bool(false)),
+            (text("yes"), BinElAttr::Bool(true)),
+            (text("1.25"), BinElAttr::Float(1.5)),
+        ];
+        for (first, second) in pairs {
+            assert!(!bin_el_attr_fuzzy_equal_required(
+                Some(&first),
+                Some(&second)
+            ));
+            assert!(!bin_el_attr_fuzzy_equal_required(
+                Some(&second),
+                Some(&first)
+            ));
+        }
+    }
+}
@@ -12,6 +12,7 @@ use std::path::Path;
 use std::str::FromStr;
 use std::{fmt, io};
 
+use arborio_utils::interned::{intern_owned, Interned};
 use arborio_utils::units::*;
 use arborio_utils::uuid::next_uuid;
 use arborio_utils::vizia::prelude::*;
@@ -187,6 +188,9 @@ pub struct CelesteMapLevel {
     pub solids: TileGrid<char>,
     pub bg: TileGrid<char>,
     pub object_tiles: TileGrid<i32>,
+    /// The texture the room's object tiles are cut from, e.g. `tilesets/scenery` for a tileset
+    /// attribute of `scenery`. Interned when the map loads so drawing doesn't have to.
+    pub object_tileset: Option<Interned>,
     pub entities: Vec<CelesteMapEntity>,
     pub triggers: Vec<CelesteMapEntity>,
     pub fg_decals: Vec<CelesteMapDecal>,
@@ -221,6 +225,7 @@ impl Default for CelesteMapLevel {
             solids: TileGrid::new(tile_size, '0'),
             bg: TileGrid::new(tile_size, '0'),
             object_tiles: TileGrid::new(tile_size, -1),
+            object_tileset: None,
             entities: vec![],
             triggers: vec![],
             fg_decals: vec![],
@@ -267,7 +272,104 @@ macro_rules! optional_field_swap {
     };
 }
 
+impl CelesteMapLevelUpdate {
+    /// An update setting every field to match `level`.
+    pub fn from_level(level: &CelesteMapLevel) -> Self {
+        Self {
+            name: Some(level.name.clone()),
+            color: Some(level.color),
+            camera_offset_x: Some(level.camera_offset_x),
+            camera_offset_y: Some(level.camera_offset_y),
+            wind_pattern: Some(level.wind_pattern.clone()),
+            space: Some(level.space),
+            underwater: Some(level.underwater),
+            whisper: Some(level.whisper),
+            dark: Some(level.dark),
+            disable_down_transition: Some(level.disable_down_transition),
+            enforce_dash_number: Some(level.enforce_dash_number),
+            music: Some(level.music.clone()),
+            alt_music: Some(level.alt_music.clone()),
+            ambience: Some(level.ambience.clone()),
+            music_layers: level.music_layers.map(Some),
+            music_progress: Some(level.music_progress.clone()),
+            ambience_progress: Some(level.ambience_progress.clone()),
+            delay_alt_music_fade: Some(level.delay_alt_music_fade),
+        }
+    }
+}
+
 impl CelesteMapLevel {
+    /// The attributes this room is saved with, minus its bounds. Its contents aren't serialized.
+    pub fn misc_attributes(&self) -> HashMap<String, BinElAttr> {
+        let mut elem = BinEl::new("level");
+        self.set_misc_attrs(&mut elem);
+        elem.attributes
+    }
+
+    /// Parse a set of attributes as returned by [`Self::misc_attributes`] into an update which
+    /// makes this room match them.
+    pub fn misc_update_from_attributes(
+        &self,
+        attrs: HashMap<String, BinElAttr>,
+    ) -> Result<CelesteMapLevelUpdate, CelesteMapError> {
+        let mut elem = BinEl::new("level");
+        self.set_bounds_attrs(&mut elem);
+        elem.attributes.extend(attrs);
+        // only the attributes matter, but leaving out the lists would be logged as a quirk
+        elem.insert(BinEl::new("entities"));
+        elem.insert(BinEl::new("triggers"));
+        let parsed = CelesteMapLevel::try_from_bin_el(&elem)?;
+        Ok(CelesteMapLevelUpdate::from_level(&parsed))
+    }
+
+    fn set_bounds_attrs(&self, elem: &mut BinEl) {
+        let MapRectStrict {
+            origin: Point2D { ref x, ref y, .. },
+            size:
+                Size2D {
+                    ref width,
+                    ref height,
+                    ..
+                },
+        } = &self.bounds;
+        DefaultConverter::set_bin_el(elem, "x", x);
+        DefaultConverter::set_bin_el(elem, "y", y);
+        DefaultConverter::set_bin_el(elem, "width", width);
+        DefaultConverter::set_bin_el(elem, "height", height);
+    }
+
+    fn set_misc_attrs(&self, elem: &mut BinEl) {
+        DefaultConverter::set_bin_el(elem, "name", &self.name);
+        DefaultConverter::set_bin_el(elem, "c", &self.color);
+        DefaultConverter::set_bin_el_default(elem, "cameraOffsetX", &self.camera_offset_x);
+        DefaultConverter::set_bin_el_default(elem, "cameraOffsetY", &self.camera_offset_y);
+        DefaultConverter::set_bin_el_default(elem, "windPattern", &self.wind_pattern);
+        DefaultConverter::set_bin_el_default(elem, "space", &self.space);
+        DefaultConverter::set_bin_el_default(elem, "underwater", &self.underwater);
+        DefaultConverter::set_bin_el_default(elem, "whisper", &self.whisper);
+        DefaultConverter::set_bin_el_default(elem, "dark", &self.dark);
+        DefaultConverter::set_bin_el_default(elem, "space", &self.space);
+        DefaultConverter::set_bin_el_default(
+            elem,
+            "disableDownTransition",
+            &self.disable_down_transition,
+        );
+        DefaultConverter::set_bin_el_default(elem, "enforceDashNumber", &self.enforce_dash_number);
+
+        DefaultConverter::set_bin_el_default(elem, "music", &self.music);
+        DefaultConverter::set_bin_el_default(elem, "alt_music", &self.alt_music);
+        DefaultConverter::set_bin_el_default(elem, "ambience", &self.ambience);
+        DefaultConverter::set_bin_el(elem, "musicLayer1", &self.music_layers[0]);
+        DefaultConverter::set_bin_el(elem, "musicLayer2", &self.music_layers[1]);
+        DefaultConverter::set_bin_el(elem, "musicLayer3", &self.music_layers[2]);
+        DefaultConverter::set_bin_el(elem, "musicLayer4", &self.music_layers[3]);
+        //DefaultConverter::set_bin_el(elem, "musicLayer5", &self.music_layers[4]);
+        //DefaultConverter::set_bin_el(elem, "musicLayer6", &self.music_layers[5]);
+        DefaultConverter::set_bin_el_default(elem, "musicProgress", &self.music_progress);
+        DefaultConverter::set_bin_el_default(elem, "ambienceProgress", &self.ambience_progress);
+        DefaultConverter::set_bin_el_default(elem, "delayAltMusicFade", &self.delay_alt_music_fade);
+    }
+
     pub fn apply(&mut self, update: &mut CelesteMapLevelUpdate) {
         optional_field_swap!(
             self,
@@ -340,6 +442,27 @@ pub struct CelesteMapDecal {
     #[name("scaleY")]
     pub scale_y: f32,
     pub texture: String,
+    #[optional]
+    pub depth: Option<i32>,
+    /// Clockwise, in degrees, about the decal's center.
+    #[default]
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+impl CelesteMapDecal {
+    /// The depth the game gives decals which don't specify one.
+    pub fn default_depth(fg: bool) -> i32 {
+        if fg {
+            -10500
+        } else {
+            9000
+        }
+    }
+
+    pub fn effective_depth(&self, fg: bool) -> i32 {
+        self.depth.unwrap_or_else(|| Self::default_depth(fg))
+    }
 }
 
 #[derive(Debug, TryFromBinEl, Lens, Clone)]
@@ -574,6 +697,12 @@ impl CelesteMapStyleground {
                     .map_or(true, |exclude| !exclude.matches(room))
                 && self.only.as_ref().map_or(true, |only| only.matches(room))))
     }
+
+    /// Is this one of the special effect stylegrounds (snow, mirror, etc) rather than a texture?
+    /// These have no fields of their own beyond what's in `attributes`.
+    pub fn is_effect(&self) -> bool {
+        self.name != "parallax" && self.name != "apply"
+    }
 }
 
 // struct ParenFlipper;
@@ -698,7 +827,15 @@ pub fn from_reader(mut reader: impl std::io::Read) -> Result<CelesteMap, std::io
 
 pub fn from_binfile(binfile: BinFile) -> Result<CelesteMap, CelesteMapError> {
     expect_elem!(binfile.root, "Map");
-    CelesteMap::try_from_bin_el(&binfile.root)
+    let mut root = binfile.root;
+    // a map with no rooms may be written without these elements at all. the game copes with
+    // that, so we should too.
+    for name in ["Filler", "levels"] {
+        if root.get(name).next().is_none() {
+            root.insert(BinEl::new(name));
+        }
+    }
+    CelesteMap::try_from_bin_el(&root)
 }
 
 pub fn save_as(map: &CelesteMap, package: &str, path: &Path) -> Result<(), io::Error> {
@@ -739,7 +876,7 @@ impl TryFromBinEl for CelesteMapLevel {
             DefaultConverter::from_bin_el_optional(elem, "fgdecals")?.unwrap_or_default();
         let bg_decals =
             DefaultConverter::from_bin_el_optional(elem, "bgdecals")?.unwrap_or_default();
-        let name = DefaultConverter::from_bin_el(elem, "name")?;
+        let name: String = DefaultConverter::from_bin_el(elem, "name")?;
         let color = DefaultConverter::from_bin_el_optional(elem, "c")?.unwrap_or_default();
         let camera_offset_x =
             DefaultConverter::from_bin_el_optional(elem, "cameraOffsetX")?.unwrap_or_default();
@@ -776,21 +913,29 @@ impl TryFromBinEl for CelesteMapLevel {
         let delay_alt_music_fade =
             DefaultConverter::from_bin_el_optional(elem, "delayAltMusicFade")?.unwrap_or_default();
 
-        let solids = parse_fgbg_tiles(get_child(elem, "solids")?, width / 8, height / 8)?;
-        let bg = parse_fgbg_tiles(get_child(elem, "bg")?, width / 8, height / 8)?;
+        let solids = parse_optional_fgbg_tiles(elem, "solids", width / 8, height / 8)?;
+        let bg = parse_optional_fgbg_tiles(elem, "bg", width / 8, height / 8)?;
         let object_tiles = get_optional_child(elem, "objtiles").map_or_else(
             || {
                 Ok(TileGrid {
-                    tiles: vec![-1; (width / 8 * height / 8) as usize],
+                    tiles: vec![-1; (width / 8 * height / 8) as usize].into(),
                     stride: (width / 8) as usize,
                 })
             },
             |v| parse_object_tiles(v, width / 8, height / 8),
         )?;
+        let object_tileset = match get_optional_child(elem, "objtiles") {
+            Some(objtiles) => {
+                DefaultConverter::from_bin_el_optional::<String>(objtiles, "tileset")?
+                    .filter(|name| !name.is_empty())
+                    .map(|name| intern_owned(format!("tilesets/{name}")))
+            }
+            None => None,
+        };
         let fg_tiles = get_optional_child(elem, "fgtiles").map_or_else(
             || {
                 Ok(TileGrid {
-                    tiles: vec![-1; (width / 8 * height / 8) as usize],
+                    tiles: vec![-1; (width / 8 * height / 8) as usize].into(),
                     stride: (width / 8) as usize,
                 })
             },
@@ -799,14 +944,22 @@ impl TryFromBinEl for CelesteMapLevel {
         let bg_tiles = get_optional_child(elem, "bgtiles").map_or_else(
             || {
                 Ok(TileGrid {
-                    tiles: vec![-1; (width / 8 * height / 8) as usize],
+                    tiles: vec![-1; (width / 8 * height / 8) as usize].into(),
                     stride: (width / 8) as usize,
                 })
             },
             |v| parse_object_tiles(v, width / 8, height / 8),
         )?;
-        let entities = DefaultConverter::from_bin_el(elem, "entities")?;
-        let triggers = TryFromBinEl::try_from_bin_el(get_child(elem, "triggers")?)?;
+        let entities =
+            DefaultConverter::from_bin_el_optional(elem, "entities")?.unwrap_or_else(|| {
+                log::info!("Normalized missing entities list in room {}", name);
+                vec![]
+            });
+        let triggers =
+            DefaultConverter::from_bin_el_optional(elem, "triggers")?.unwrap_or_else(|| {
+                log::info!("Normalized missing triggers list in room {}", name);
+                vec![]
+            });
 
         Ok(CelesteMapLevel {
             bounds,
@@ -833,6 +986,7 @@ impl TryFromBinEl for CelesteMapLevel {
             solids,
             bg,
             object_tiles,
+            object_tileset,
             entities,
             triggers,
             fg_decals,
@@ -845,62 +999,10 @@ impl TryFromBinEl for CelesteMapLevel {
     fn to_binel(&self) -> BinEl {
         let mut elem = BinEl::new("level");
 
-        let MapRectStrict {
-            origin: Point2D { ref x, ref y, .. },
-            size:
-                Size2D {
-                    ref width,
-                    ref height,
-                    ..
-                },
-        } = &self.bounds;
-        DefaultConverter::set_bin_el(&mut elem, "x", x);
-        DefaultConverter::set_bin_el(&mut elem, "y", y);
-        DefaultConverter::set_bin_el(&mut elem, "width", width);
-        DefaultConverter::set_bin_el(&mut elem, "height", height);
+        self.set_bounds_attrs(&mut elem);
         DefaultConverter::set_bin_el(&mut elem, "fgdecals", &self.fg_decals);
         DefaultConverter::set_bin_el(&mut elem, "bgdecals", &self.bg_decals);
-        DefaultConverter::set_bin_el(&mut elem, "name", &self.name);
-        DefaultConverter::set_bin_el(&mut elem, "c", &self.color);
-        DefaultConverter::set_bin_el_default(&mut elem, "cameraOffsetX", &self.camera_offset_x);
-        DefaultConverter::set_bin_el_default(&mut elem, "cameraOffsetY", &self.camera_offset_y);
-        DefaultConverter::set_bin_el_default(&mut elem, "windPattern", &self.wind_pattern);
-        DefaultConverter::set_bin_el_default(&mut elem, "space", &self.space);
-        DefaultConverter::set_bin_el_default(&mut elem, "underwater", &self.underwater);
-        DefaultConverter::set_bin_el_default(&mut elem, "whisper", &self.whisper);
-        DefaultConverter::set_bin_el_default(&mut elem, "dark", &self.dark);
-        DefaultConverter::set_bin_el_default(&mut elem, "space", &self.space);
-        DefaultConverter::set_bin_el_default(
-            &mut elem,
-            "disableDownTransition",
-            &self.disable_down_transition,
-        );
-        DefaultConverter::set_bin_el_default(
-            &mut elem,
-            "enforceDashNumber",
-            &self.enforce_dash_number,
-        );
-
-        DefaultConverter::set_bin_el_default(&mut elem, "music", &self.music);
-        DefaultConverter::set_bin_el_default(&mut elem, "alt_music", &self.alt_music);
-        DefaultConverter::set_bin_el_default(&mut elem, "ambience", &self.ambience);
-        DefaultConverter::set_bin_el(&mut elem, "musicLayer1", &self.music_layers[0]);
-        DefaultConverter::set_bin_el(&mut elem, "musicLayer2", &self.music_layers[1]);
-        DefaultConverter::set_bin_el(&mut elem, "musicLayer3", &self.music_layers[2]);
-        DefaultConverter::set_bin_el(&mut elem, "musicLayer4", &self.music_layers[3]);
-        //DefaultConverter::set_bin_el(&mut elem, "musicLayer5", &self.music_layers[4]);
-        //DefaultConverter::set_bin_el(&mut elem, "musicLayer6", &self.music_layers[5]);
-        DefaultConverter::set_bin_el_default(&mut elem, "musicProgress", &self.music_progress);
-        DefaultConverter::set_bin_el_default(
-            &mut elem,
-            "ambienceProgress",
-            &self.ambience_progress,
-        );
-        DefaultConverter::set_bin_el_default(
-            &mut elem,
-            "delayAltMusicFade",
-            &self.delay_alt_music_fade,
-        );
+        self.set_misc_attrs(&mut elem);
 
         GetAttrOrChild::nested_apply_attr_or_child(
             &mut elem,
@@ -912,11 +1014,15 @@ impl TryFromBinEl for CelesteMapLevel {
             "bg",
             serialize_tiles(&self.bg, '0', ""),
         );
-        GetAttrOrChild::nested_apply_attr_or_child(
-            &mut elem,
-            "objtiles",
-            serialize_tiles(&self.object_tiles, -1, ","),
-        );
+        let mut object_tiles = serialize_tiles(&self.object_tiles, -1, ",");
+        let object_tileset = self.object_tileset.map(|texture| {
+            texture
+                .strip_prefix("tilesets/")
+                .unwrap_or(*texture)
+                .to_owned()
+        });
+        DefaultConverter::set_bin_el_optional(&mut object_tiles, "tileset", &object_tileset);
+        GetAttrOrChild::nested_apply_attr_or_child(&mut elem, "objtiles", object_tiles);
         GetAttrOrChild::nested_apply_attr_or_child(
             &mut elem,
             "fgtiles",
@@ -959,10 +1065,18 @@ fn serialize_tiles<T: Copy + PartialEq + ToString>(
     elem
 }
 
+/// Split tile text into rows. Accepts `\n`, `\r\n`, and bare `\r` line endings, and ignores
+/// trailing whitespace on each row.
+fn tile_lines(s: &str) -> impl Iterator<Item = &str> {
+    s.split("\r\n")
+        .flat_map(|line| line.split(['\r', '\n']))
+        .map(str::trim_end)
+}
+
 fn fgbg_transform(
     s: &str,
 ) -> impl Iterator<Item = impl Iterator<Item = Result<char, CelesteMapError>> + '_> + '_ {
-    s.lines().map(|line| line.chars().map(Ok))
+    tile_lines(s).map(|line| line.chars().map(Ok))
 }
 
 fn parse_fgbg_tiles(
@@ -973,6 +1087,23 @@ fn parse_fgbg_tiles(
     parse_tiles(elem, width, height, fgbg_transform, '0')
 }
 
+fn parse_optional_fgbg_tiles(
+    elem: &BinEl,
+    name: &str,
+    width: i32,
+    height: i32,
+) -> Result<TileGrid<char>, CelesteMapError> {
+    if let Some(child) = get_optional_child(elem, name) {
+        parse_fgbg_tiles(child, width, height)
+    } else {
+        log::info!("Normalized missing {} tiles", name);
+        Ok(TileGrid::new(
+            TileSize::new(width.max(0), height.max(0)),
+            '0',
+        ))
+    }
+}
+
 fn parse_tiles<'a, T, F, I>(
     elem: &'a BinEl,
     width: i32,
@@ -1005,7 +1136,7 @@ where
     }
 
     Ok(TileGrid {
-        tiles: data,
+        tiles: data.into(),
         stride: width,
     })
 }
@@ -1013,13 +1144,16 @@ where
 fn obj_transform(
     s: &str,
 ) -> impl Iterator<Item = impl Iterator<Item = Result<i32, CelesteMapError>> + '_> + '_ {
-    s.lines().map(|line| {
-        line.split(',').filter(|&s| !s.is_empty()).map(|tile| {
-            tile.parse::<i32>().map_err(|_| CelesteMapError {
-                kind: CelesteMapErrorType::ParseError,
-                description: format!("Could not parse {tile} as int"),
+    tile_lines(s).map(|line| {
+        line.split(',')
+            .map(str::trim)
+            .filter(|&s| !s.is_empty())
+            .map(|tile| {
+                tile.parse::<i32>().map_err(|_| CelesteMapError {
+                    kind: CelesteMapErrorType::ParseError,
+                    description: format!("Could not parse {tile} as int"),
+                })
             })
-        })
     })
 }
 
@@ -1284,23 +1418,16 @@ impl<T: Copy + TryFrom<i32> + TryInto<i32>> TwoWayConverter<T> for MapComponentC
 
 pub fn get_optional_child<'a>(elem: &'a BinEl, name: &str) -> Option<&'a BinEl> {
     // if there is exactly one child
-    let [ref child] = elem.get(name) else {
-        return None
-    };
-    Some(child)
-}
-
-fn get_child<'a>(elem: &'a BinEl, name: &str) -> Result<&'a BinEl, CelesteMapError> {
-    get_optional_child(elem, name).ok_or_else(|| CelesteMapError::missing_child(&elem.name, name))
+    elem.get(name).exactly_one().ok()
 }
 
 pub fn get_child_mut<'a>(elem: &'a mut BinEl, name: &str) -> &'a mut BinEl {
-    let children = elem.get_mut(name);
-    if children.is_empty() {
-        children.push(BinEl::new(name));
+    match elem.get(name).count() {
+        0 => elem.insert(BinEl::new(name)),
+        1 => {}
+        _ => panic!(),
     }
-    let [child] = children.as_mut_slice() else { panic!() };
-    child
+    elem.get_mut(name).unwrap()
 }
 
 fn get_optional_attr<T>(elem: &BinEl, name: &str) -> Result<Option<T>, CelesteMapError>
@@ -1334,12 +1461,22 @@ trait AttrCoercion: Sized {
     fn serialize(&self) -> BinElAttr;
 }
 
+// Other editors occasionally store attributes with a different type than celeste.exe would.
+// We accept these when the conversion is lossless and log what we did about it.
+fn normalized<T: AttrCoercion>(attr: &BinElAttr, result: Option<T>) -> Option<T> {
+    if result.is_some() {
+        log::info!("Normalized {:?} to {}", attr, T::NICE_NAME);
+    }
+    result
+}
+
 impl AttrCoercion for i32 {
     const NICE_NAME: &'static str = "integer";
     fn try_coerce(attr: &BinElAttr) -> Option<Self> {
         match *attr {
             BinElAttr::Int(i) => Some(i),
             BinElAttr::Float(f) => Some(f as i32),
+            BinElAttr::Text(ref s) => normalized(attr, s.trim().parse().ok()),
             _ => None,
         }
     }
@@ -1353,6 +1490,7 @@ impl AttrCoercion for u32 {
         match *attr {
             BinElAttr::Int(i) => i.try_into().ok(),
             BinElAttr::Float(f) => Some(f as u32),
+            BinElAttr::Text(ref s) => normalized(attr, s.trim().parse().ok()),
             _ => None,
         }
     }
@@ -1361,13 +1499,25 @@ impl AttrCoercion for u32 {
         BinElAttr::Int(*self as i32)
     }
 }
+// Which values load as bools. The round-trip comparison uses this too, so that a value only
+// compares equal to a bool if it would load as that bool.
+pub(crate) fn bool_from_attr(attr: &BinElAttr) -> Option<bool> {
+    match *attr {
+        BinElAttr::Bool(value) => Some(value),
+        BinElAttr::Int(0) => Some(false),
+        BinElAttr::Int(1) => Some(true),
+        BinElAttr::Text(ref s) if s.eq_ignore_ascii_case("true") => Some(true),
+        BinElAttr::Text(ref s) if s.eq_ignore_ascii_case("false") => Some(false),
+        _ => None,
+    }
+}
+
 impl AttrCoercion for bool {
     const NICE_NAME: &'static str = "bool";
     fn try_coerce(attr: &BinElAttr) -> Option<Self> {
-        if let BinElAttr::Bool(value) = *attr {
-            Some(value)
-        } else {
-            None
+        match *attr {
+            BinElAttr::Bool(value) => Some(value),
+            _ => normalized(attr, bool_from_attr(attr)),
         }
     }
 
@@ -1381,6 +1531,7 @@ impl AttrCoercion for f32 {
         match *attr {
             BinElAttr::Float(f) => Some(f),
             BinElAttr::Int(i) => Some(i as f32),
+            BinElAttr::Text(ref s) => normalized(attr, s.trim().parse().ok()),
             _ => None,
         }
     }
@@ -1403,3 +1554,303 @@ impl AttrCoercion for String {
         BinElAttr::Text(self.clone())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // a small corpus of rooms as they come out of other editors: valid as far as celeste.exe is
+    // concerned, but not what it would write itself
+
+    fn el(name: &str, attrs: &[(&str, BinElAttr)], children: Vec<BinEl>) -> BinEl {
+        let mut result = BinEl::new(name);
+        for (key, value) in attrs {
+            result.attributes.insert((*key).to_owned(), value.clone());
+        }
+        for child in children {
+            result.insert(child);
+        }
+        result
+    }
+
+    fn text(s: &str) -> BinElAttr {
+        BinElAttr::Text(s.to_owned())
+    }
+
+    fn quirky_level() -> BinEl {
+        el(
+            "level",
+            &[
+                ("name", text("lvl_a-00")),
+                ("x", text("0")),
+                ("y", BinElAttr::Float(-8.0)),
+                ("width", text("320")),
+                ("height", BinElAttr::Int(184)),
+                ("dark", BinElAttr::Int(1)),
+                ("space", text("False")),
+                ("cameraOffsetX", text("1.5")),
+            ],
+            vec![
+                el("solids", &[("innerText", text("11\n1"))], vec![]),
+                el(
+                    "entities",
+                    &[],
+                    vec![el(
+                        "spring",
+                        &[
+                            ("id", BinElAttr::Int(1)),
+                            ("x", text("16")),
+                            ("y", BinElAttr::Float(24.0)),
+                        ],
+                        vec![],
+                    )],
+                ),
+                el(
+                    "fgdecals",
+                    &[],
+                    vec![el(
+                        "decal",
+                        &[
+                            ("x", BinElAttr::Int(4)),
+                            ("y", BinElAttr::Int(4)),
+                            ("scaleX", BinElAttr::Int(1)),
+                            ("scaleY", text("-1")),
+                            ("texture", text("foo.png")),
+                        ],
+                        vec![],
+                    )],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn load_quirky_level() {
+        let level = CelesteMapLevel::try_from_bin_el(&quirky_level()).unwrap();
+        assert_eq!(
+            level.bounds,
+            MapRectStrict::new((0, -8).into(), (320, 184).into())
+        );
+        assert!(level.dark);
+        assert!(!level.space);
+        assert_eq!(level.camera_offset_x, 1.5);
+        assert_eq!(level.solids.get(TilePoint::new(1, 0)), Some(&'1'));
+        assert_eq!(level.bg.size(), TileSize::new(40, 23));
+        assert_eq!(level.entities.len(), 1);
+        assert_eq!((level.entities[0].x, level.entities[0].y), (16, 24));
+        assert!(level.triggers.is_empty());
+        assert_eq!(level.fg_decals[0].scale_y, -1.0);
+    }
+
+    #[test]
+    fn quirky_level_saves_normalized() {
+        let level = CelesteMapLevel::try_from_bin_el(&quirky_level()).unwrap();
+        let saved = level.to_binel();
+        assert_eq!(saved.attributes.get("x"), Some(&BinElAttr::Int(0)));
+        assert_eq!(saved.attributes.get("dark"), Some(&BinElAttr::Bool(true)));
+        assert_eq!(
+            get_optional_child(&saved, "triggers").map(|t| t.children().count()),
+            Some(0)
+        );
+        let reloaded = CelesteMapLevel::try_from_bin_el(&saved).unwrap();
+        assert_eq!(reloaded.entities, level.entities);
+    }
+
+    #[test]
+    fn misc_attributes_round_trip() {
+        let level = CelesteMapLevel::try_from_bin_el(&quirky_level()).unwrap();
+        let mut attrs = level.misc_attributes();
+        assert!(!attrs.contains_key("x"));
+        attrs.insert("dark".to_owned(), BinElAttr::Bool(false));
+        attrs.insert("music".to_owned(), text("event:/music/lvl1/main"));
+
+        let mut update = level.misc_update_from_attributes(attrs).unwrap();
+        let mut edited = CelesteMapLevel::try_from_bin_el(&quirky_level()).unwrap();
+        edited.apply(&mut update);
+        assert!(!edited.dark);
+        assert_eq!(edited.music, "event:/music/lvl1/main");
+        assert_eq!(edited.camera_offset_x, 1.5);
+        assert_eq!(edited.bounds, level.bounds);
+    }
+
+    fn roomless_map(mut children: Vec<BinEl>) -> BinFile {
+        children.push(el(
+            "Style",
+            &[],
+            vec![
+                el("Foregrounds", &[], vec![]),
+                el("Backgrounds", &[], vec![]),
+            ],
+        ));
+        BinFile {
+            root: el("Map", &[], children),
+            package: "empty".to_owned(),
+        }
+    }
+
+    #[test]
+    fn load_roomless_map() {
+        let explicit = roomless_map(vec![el("Filler", &[], vec![]), el("levels", &[], vec![])]);
+        let map = from_binfile(explicit).unwrap();
+        assert!(map.levels.is_empty());
+        assert!(map.filler.is_empty());
+
+        let implicit = from_binfile(roomless_map(vec![])).unwrap();
+        assert!(implicit.levels.is_empty());
+
+        let saved = implicit.to_binel();
+        assert_eq!(
+            get_optional_child(&saved, "levels").map(|l| l.children().count()),
+            Some(0)
+        );
+        let reloaded = CelesteMap::try_from_bin_el(&saved).unwrap();
+        assert!(reloaded.levels.is_empty());
+    }
+
+    #[test]
+    fn decal_rotation_round_trip() {
+        let attrs = [
+            ("x", BinElAttr::Int(8)),
+            ("y", BinElAttr::Int(16)),
+            ("scaleX", BinElAttr::Float(1.0)),
+            ("scaleY", BinElAttr::Float(1.0)),
+            ("texture", text("generic/grass_a")),
+        ];
+        let plain = CelesteMapDecal::try_from_bin_el(&el("decal", &attrs, vec![])).unwrap();
+        assert_eq!(plain.rotation, 0.0);
+        assert!(!plain.to_binel().attributes.contains_key("rotation"));
+
+        let mut rotated = el("decal", &attrs, vec![]);
+        rotated
+            .attributes
+            .insert("rotation".to_owned(), BinElAttr::Float(90.0));
+        let rotated = CelesteMapDecal::try_from_bin_el(&rotated).unwrap();
+        assert_eq!(rotated.rotation, 90.0);
+        assert_eq!(
+            rotated.to_binel().attributes.get("rotation"),
+            Some(&BinElAttr::Float(90.0))
+        );
+    }
+
+    #[test]
+    fn reject_ambiguous_values() {
+        let mut level = quirky_level();
+        level.attributes.insert("dark".to_owned(), text("maybe"));
+        assert!(CelesteMapLevel::try_from_bin_el(&level).is_err());
+        level
+            .attributes
+            .insert("dark".to_owned(), BinElAttr::Int(2));
+        assert!(CelesteMapLevel::try_from_bin_el(&level).is_err());
+    }
+
+    #[test]
+    fn effect_stylegrounds_round_trip() {
+        let styles = el(
+            "Foregrounds",
+            &[],
+            vec![
+                el("snowfg", &[], vec![]),
+                el(
+                    "mirrorfg",
+                    &[
+                        ("only", text("a-*")),
+                        ("customSpeed", BinElAttr::Float(2.5)),
+                    ],
+                    vec![],
+                ),
+                el(
+                    "apply",
+                    &[("flag", text("foo"))],
+                    vec![el("stars", &[], vec![])],
+                ),
+            ],
+        );
+        let parsed = Vec::<CelesteMapStyleground>::try_from_bin_el(&styles).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert!(parsed[0].is_effect());
+        assert!(!parsed[2].is_effect());
+
+        let saved = parsed.to_binel();
+        let saved = saved.children().collect::<Vec<_>>();
+        assert_eq!(saved[0].name, "snowfg");
+        assert!(saved[0].attributes.is_empty());
+        assert_eq!(saved[1].name, "mirrorfg");
+        assert_eq!(saved[1].attributes.get("only"), Some(&text("a-*")));
+        assert_eq!(
+            saved[1].attributes.get("customSpeed"),
+            Some(&BinElAttr::Float(2.5))
+        );
+        assert_eq!(saved[2].name, "apply");
+        assert_eq!(saved[2].attributes.get("flag"), Some(&text("foo")));
+        assert_eq!(saved[2].children().next().unwrap().name, "stars");
+    }
+
+    #[test]
+    fn positional_styleground_attributes_round_trip() {
+        // fields we don't model, such as the regions some mods restrict stylegrounds to, must
+        // survive a load and save untouched, both on groups and on their children
+        let region = [
+            ("x", BinElAttr::Float(8.0)),
+            ("y", BinElAttr::Float(16.0)),
+            ("width", BinElAttr::Int(320)),
+            ("height", BinElAttr::Int(180)),
+        ];
+        let styles = el(
+            "Backgrounds",
+            &[],
+            vec![el(
+                "apply",
+                &region,
+                vec![el(
+                    "parallax",
+                    &[("texture", text("bgs/07/00/bg0"))],
+                    vec![el("rect", &region, vec![])],
+                )],
+            )],
+        );
+        let parsed = Vec::<CelesteMapStyleground>::try_from_bin_el(&styles).unwrap();
+        let saved = parsed.to_binel();
+        let group = saved.children().next().unwrap();
+        for (key, value) in &region {
+            assert_eq!(group.attributes.get(*key), Some(value), "{key}");
+        }
+        let parallax = group.children().next().unwrap();
+        assert_eq!(parallax.name, "parallax");
+        let rect = parallax.children().next().unwrap();
+        assert_eq!(rect.name, "rect");
+        for (key, value) in &region {
+            assert_eq!(rect.attributes.get(*key), Some(value), "{key}");
+        }
+    }
+
+    #[test]
+    fn crlf_tiles() {
+        let solids = el(
+            "solids",
+            &[("innerText", text("10 \r\n01\r\n\r\n1"))],
+            vec![],
+        );
+        let grid = parse_fgbg_tiles(&solids, 3, 4).unwrap();
+        assert_eq!(grid.size(), TileSize::new(3, 4));
+        assert_eq!(grid.get(TilePoint::new(0, 0)), Some(&'1'));
+        assert_eq!(grid.get(TilePoint::new(1, 0)), Some(&'0'));
+        assert_eq!(grid.get(TilePoint::new(2, 0)), Some(&'0'));
+        assert_eq!(grid.get(TilePoint::new(1, 1)), Some(&'1'));
+        assert_eq!(grid.get(TilePoint::new(0, 2)), Some(&'0'));
+        assert_eq!(grid.get(TilePoint::new(0, 3)), Some(&'1'));
+
+        let objtiles = el(
+            "objtiles",
+            &[("innerText", text("1,2\r\n-1, 3\r\n"))],
+            vec![],
+        );
+        let grid = parse_object_tiles(&objtiles, 2, 2).unwrap();
+        assert_eq!(grid.get(TilePoint::new(1, 0)), Some(&2));
+        assert_eq!(grid.get(TilePoint::new(1, 1)), Some(&3));
+
+        let cr_only = el("bg", &[("innerText", text("1\r01"))], vec![]);
+        let grid = parse_fgbg_tiles(&cr_only, 2, 2).unwrap();
+        assert_eq!(grid.get(TilePoint::new(0, 0)), Some(&'1'));
+        assert_eq!(grid.get(TilePoint::new(1, 1)), Some(&'1'));
+    }
+}
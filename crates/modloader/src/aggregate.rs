@@ -1,6 +1,6 @@
 use arborio_utils::vizia::prelude::*;
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -11,6 +11,7 @@ use arborio_utils::interned::{intern_str, Interned, InternedMap};
 use arborio_walker::{open_module, ConfigSourceTrait};
 
 use crate::config::{EntityConfig, StylegroundConfig, TriggerConfig};
+use crate::dialog::{dialog_keyify, Dialog, DEFAULT_LANGUAGE};
 use crate::module::{CelesteModule, ModuleID};
 use crate::selectable::{DecalSelectable, EntitySelectable, TileSelectable, TriggerSelectable};
 
@@ -21,6 +22,11 @@ pub struct ModuleAggregate {
     pub entity_config: InternedMap<Arc<EntityConfig>>,
     pub trigger_config: InternedMap<Arc<TriggerConfig>>,
     pub styleground_config: InternedMap<Arc<StylegroundConfig>>,
+    /// Every dependency's dialog in the selected language, with later dependencies overriding
+    /// earlier ones.
+    pub dialog: Arc<Dialog>,
+    /// The same, in English, for keys the selected language is missing.
+    pub fallback_dialog: Arc<Dialog>,
 
     pub fg_tiles_palette: Vec<TileSelectable>,
     pub bg_tiles_palette: Vec<TileSelectable>,
@@ -37,6 +43,7 @@ impl ModuleAggregate {
         modules_lookup: &HashMap<String, ModuleID>,
         map_meta: &Option<CelesteMapMeta>,
         current_module: ModuleID,
+        language: &str,
         emit_logs: bool,
     ) -> Self {
         if let Some(mymod) = modules.get(&current_module) {
@@ -57,23 +64,30 @@ impl ModuleAggregate {
         Self::new_core(
             map_meta,
             dep_mods(modules, modules_lookup, current_module),
+            language,
             emit_logs,
         )
     }
 
-    pub fn new_omni(modules: &HashMap<ModuleID, CelesteModule>, emit_logs: bool) -> Self {
-        Self::new_core(
-            &None,
-            modules
-                .values()
-                .map(|y| (y.everest_metadata.name.as_str(), y)),
-            emit_logs,
-        )
+    pub fn new_omni(
+        modules: &HashMap<ModuleID, CelesteModule>,
+        language: &str,
+        emit_logs: bool,
+    ) -> Self {
+        // merge in a fixed order, with the builtin modules first like in a dependency list, so
+        // that which module wins a conflict doesn't depend on hash order
+        let deps = modules
+            .values()
+            .map(|y| (y.everest_metadata.name.as_str(), y))
+            .sorted_by_key(|(name, _)| (*name != "Arborio", *name != "Celeste", *name))
+            .collect::<Vec<_>>();
+        Self::new_core(&None, deps.into_iter(), language, emit_logs)
     }
 
     fn new_core<'a>(
         map_meta: &Option<CelesteMapMeta>,
         deps: impl Clone + Iterator<Item = (&'a str, &'a CelesteModule)>,
+        language: &str,
         emit_logs: bool,
     ) -> Self {
         let gameplay_atlas = MultiAtlas::from(build_palette_map(
@@ -82,12 +96,7 @@ impl ModuleAggregate {
             |module| module.gameplay_atlas.sprites_map.iter(),
             emit_logs,
         ));
-        let mut autotilers = build_palette_map(
-            "Tiler Config",
-            deps.clone(),
-            |module| module.tilers.iter(),
-            emit_logs,
-        );
+        let autotilers = build_autotilers(map_meta, deps.clone(), emit_logs);
         let entity_config = build_palette_map(
             "Entity Config",
             deps.clone(),
@@ -107,36 +116,12 @@ impl ModuleAggregate {
             emit_logs,
         );
 
-        if let Some(fg_xml) = map_meta
-            .as_ref()
-            .and_then(|meta| meta.bg_tiles.as_ref())
-            .map(|s| s.as_str())
-        {
-            if let Some(tiler) = lookup_tiler(fg_xml, deps.clone()) {
-                autotilers.insert("fg".into(), Arc::new(tiler));
-            }
-        }
-        if let Some(bg_xml) = map_meta
-            .as_ref()
-            .and_then(|meta| meta.bg_tiles.as_ref())
-            .map(|s| s.as_str())
-        {
-            if let Some(tiler) = lookup_tiler(bg_xml, deps.clone()) {
-                autotilers.insert("bg".into(), Arc::new(tiler));
-            }
-        }
-        if deps.clone().count() != 0 && !autotilers.contains_key("fg") {
-            autotilers.insert(
-                "fg".into(),
-                Arc::new(lookup_tiler("Graphics/ForegroundTiles.xml", deps.clone()).unwrap()),
-            );
-        }
-        if deps.clone().count() != 0 && !autotilers.contains_key("bg") {
-            autotilers.insert(
-                "bg".into(),
-                Arc::new(lookup_tiler("Graphics/BackgroundTiles.xml", deps.clone()).unwrap()),
-            );
-        }
+        let dialog = Arc::new(merge_dialog(language, deps.clone()));
+        let fallback_dialog = if language == DEFAULT_LANGUAGE {
+            dialog.clone()
+        } else {
+            Arc::new(merge_dialog(DEFAULT_LANGUAGE, deps.clone()))
+        };
 
         let fg_tiles_palette = autotilers
             .get("fg")
@@ -146,12 +131,11 @@ impl ModuleAggregate {
             .map_or_else(Vec::new, |tiler| extract_tiles_palette(tiler));
         let entities_palette = extract_entities_palette(&entity_config);
         let triggers_palette = extract_triggers_palette(&trigger_config);
-        let decals_palette = gameplay_atlas
-            .iter_paths()
-            .filter_map(|path| path.strip_prefix("decals/").map(intern_str))
-            .sorted()
-            .map(DecalSelectable)
-            .collect();
+        let decals_palette = extract_decals_palette(
+            gameplay_atlas
+                .iter_paths()
+                .filter_map(|path| path.strip_prefix("decals/")),
+        );
 
         let result = Self {
             gameplay_atlas,
@@ -159,6 +143,8 @@ impl ModuleAggregate {
             entity_config,
             trigger_config,
             styleground_config,
+            dialog,
+            fallback_dialog,
 
             fg_tiles_palette,
             bg_tiles_palette,
@@ -196,18 +182,112 @@ impl ModuleAggregate {
                 .unwrap_or_else(|| self.entity_config.get("default").unwrap())
         }
     }
+
+    /// Look up a dialog key in the selected language, falling back to English.
+    pub fn lookup_dialog(&self, key: &str) -> Option<&str> {
+        self.dialog
+            .get(key)
+            .or_else(|| self.fallback_dialog.get(key))
+            .map(String::as_str)
+    }
+
+    /// The text for a dialog key, or the key itself if no dialog defines it.
+    pub fn dialog_or_key<'a>(&'a self, key: &'a str) -> &'a str {
+        self.lookup_dialog(key).unwrap_or(key)
+    }
+
+    /// The name of a map as shown in game, if any of the dependencies' dialog gives it one.
+    pub fn map_display_name(&self, sid: &str) -> Option<&str> {
+        self.lookup_dialog(&dialog_keyify(sid))
+    }
+
+    /// The decal palette split up by the subfolder of `decals/` each decal is in.
+    pub fn decal_groups(&self) -> Vec<(&'static str, &[DecalSelectable])> {
+        let mut result = vec![];
+        let mut rest = self.decals_palette.as_slice();
+        while let Some(first) = rest.first() {
+            let len = rest
+                .iter()
+                .take_while(|decal| decal.group() == first.group())
+                .count();
+            let (group, next) = rest.split_at(len);
+            result.push((first.group(), group));
+            rest = next;
+        }
+        result
+    }
+
+    /// The numbered variants of a decal, e.g. `rocks/01` and `rocks/02` for `rocks/01`. Includes
+    /// the decal itself. Empty if the decal's name doesn't end in a number.
+    pub fn decal_variants(&self, decal: &str) -> Vec<DecalSelectable> {
+        let base = decal.trim_end_matches(|c: char| c.is_ascii_digit());
+        if base.len() == decal.len() {
+            return vec![];
+        }
+        self.decals_palette
+            .iter()
+            .filter(|d| {
+                d.0.strip_prefix(base).map_or(false, |rest| {
+                    !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit())
+                })
+            })
+            .copied()
+            .collect()
+    }
 }
 
+/// The tiler config from each dependency, plus the `fg` and `bg` autotilers. These are every
+/// dependency's `Graphics/ForegroundTiles.xml` or `Graphics/BackgroundTiles.xml`, then the XML named
+/// by the map meta, merged by tile char in that order, so a mod can add tilesets or replace vanilla
+/// ones and the last one to define a char wins.
+fn build_autotilers<'a>(
+    map_meta: &Option<CelesteMapMeta>,
+    deps: impl Clone + Iterator<Item = (&'a str, &'a CelesteModule)>,
+    emit_logs: bool,
+) -> InternedMap<Arc<Autotiler>> {
+    let mut autotilers = build_palette_map(
+        "Tiler Config",
+        deps.clone(),
+        |module| module.tilers.iter(),
+        emit_logs,
+    );
+    let meta = map_meta.as_ref();
+    for (name, default_xml, meta_xml) in [
+        (
+            "fg",
+            "Graphics/ForegroundTiles.xml",
+            meta.and_then(|meta| meta.fg_tiles.as_deref()),
+        ),
+        (
+            "bg",
+            "Graphics/BackgroundTiles.xml",
+            meta.and_then(|meta| meta.bg_tiles.as_deref()),
+        ),
+    ] {
+        let mut tiler = lookup_tiler(default_xml, deps.clone());
+        if let Some(meta_tiler) = meta_xml.and_then(|xml| lookup_tiler(xml, deps.clone())) {
+            tiler.get_or_insert_with(Autotiler::new).extend(meta_tiler);
+        }
+        if let Some(tiler) = tiler {
+            autotilers.insert(name.into(), Arc::new(tiler));
+        }
+    }
+    autotilers
+}
+
+/// Every dependency's copy of the tileset XML at `xml`, merged by tile char with later dependencies
+/// overriding earlier ones. `copy` can only refer to tilesets from the same file.
 fn lookup_tiler<'a>(
     xml: &str,
     deps: impl Clone + Iterator<Item = (&'a str, &'a CelesteModule)>,
 ) -> Option<Autotiler> {
+    let mut result: Option<Autotiler> = None;
     for (depname, dep) in deps {
         if let Some(root) = &dep.filesystem_root {
-            let mut config = open_module(root).unwrap();
+            let Some(mut config) = open_module(root) else { continue };
             if let Some(fp) = config.get_file(Path::new(xml)) {
                 match Tileset::new(fp, "tilesets/") {
-                    Ok(t) => return Some(t),
+                    Ok(t) => result.get_or_insert_with(Autotiler::new).extend(t),
                     Err(e) => {
                         log::error!("{}:{}: {}", depname, xml, e);
                     }
@@ -215,7 +295,20 @@ fn lookup_tiler<'a>(
             }
         }
     }
-    None
+    result
+}
+
+fn merge_dialog<'a>(
+    language: &str,
+    deps: impl Iterator<Item = (&'a str, &'a CelesteModule)>,
+) -> Dialog {
+    let mut dialog = Dialog::new();
+    for (_, module) in deps {
+        if let Some(module_dialog) = module.dialog.get(language) {
+            dialog.extend(module_dialog.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+    }
+    dialog
 }
 
 fn extract_tiles_palette(map: &HashMap<char, Tileset>) -> Vec<TileSelectable> {
@@ -233,6 +326,44 @@ fn extract_tiles_palette(map: &HashMap<char, Tileset>) -> Vec<TileSelectable> {
     vec
 }
 
+/// Build the decal palette from the paths of every decal texture, relative to `decals/`. The
+/// palette is sorted by subfolder so each one's decals are together. The frames of an animated
+/// decal, e.g. `flag00`, `flag01`... are collapsed into a single entry for the whole animation,
+/// `flag`.
+fn extract_decals_palette<'a>(paths: impl Iterator<Item = &'a str>) -> Vec<DecalSelectable> {
+    /// The name of the animation this could be a frame of, and the frame number's digits.
+    fn split_frame(path: &str) -> Option<(&str, &str)> {
+        let base = path.trim_end_matches(|c: char| c.is_ascii_digit());
+        (base.len() != path.len() && !base.is_empty() && !base.ends_with('/'))
+            .then(|| (base, &path[base.len()..]))
+    }
+
+    let paths = paths.collect::<HashSet<&str>>();
+    // an animation's frames count up from zero, and the game only animates a decal with more than
+    // one of them. the palette shows the animation in place of its frames.
+    let frame_counts = paths
+        .iter()
+        .filter_map(|path| split_frame(path))
+        .map(|(base, _)| base)
+        .counts();
+    let animations = paths
+        .iter()
+        .filter_map(|path| split_frame(path))
+        .filter(|(base, frame)| {
+            frame.chars().all(|c| c == '0') && frame_counts[base] > 1 && !paths.contains(base)
+        })
+        .map(|(base, _)| base)
+        .collect::<HashSet<&str>>();
+    paths
+        .iter()
+        .copied()
+        .filter(|path| split_frame(path).map_or(true, |(base, _)| !animations.contains(base)))
+        .chain(animations.iter().copied())
+        .map(|path| DecalSelectable(intern_str(path)))
+        .sorted_by_key(|decal| (decal.group(), *decal.0))
+        .collect()
+}
+
 fn extract_entities_palette(config: &InternedMap<Arc<EntityConfig>>) -> Vec<EntitySelectable> {
     config
         .iter()
@@ -332,3 +463,111 @@ fn build_palette_map<'a, T: 'a + Clone, I: 'a + Iterator<Item = (&'a Interned, &
     }
     result
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::everest_yaml::{EverestModuleVersion, EverestYaml};
+
+    #[test]
+    fn test_extract_decals_palette() {
+        let palette = extract_decals_palette(
+            [
+                "1-forsakencity/flag00",
+                "1-forsakencity/flag01",
+                "1-forsakencity/flag02",
+                "1-forsakencity/sign",
+                "0-prologue/rocks01",
+                "0-prologue/rocks02",
+                "generic/grass_a0",
+                "generic/grass_a1",
+                "generic/grass_a",
+                "generic/lone00",
+                "loose",
+            ]
+            .into_iter(),
+        );
+        assert_eq!(
+            palette.iter().map(|d| *d.0).collect::<Vec<_>>(),
+            vec![
+                "loose",
+                "0-prologue/rocks01",
+                "0-prologue/rocks02",
+                "1-forsakencity/flag",
+                "1-forsakencity/sign",
+                "generic/grass_a",
+                "generic/grass_a0",
+                "generic/grass_a1",
+                "generic/lone00",
+            ]
+        );
+        assert_eq!(palette[3].group(), "1-forsakencity");
+        assert_eq!(palette[0].group(), "");
+    }
+
+    #[test]
+    fn test_tileset_overrides() {
+        let root = std::env::temp_dir().join(format!("arborio-tilesets-{}", std::process::id()));
+        let fake_module = |name: &str, tilesets: &[(char, &str)]| {
+            let path = root.join(name);
+            std::fs::create_dir_all(path.join("Graphics")).unwrap();
+            let xml = tilesets
+                .iter()
+                .map(|(id, tex)| {
+                    format!(r#"<Tileset id="{id}" path="{tex}"><set mask="center" tiles="0,0"/></Tileset>"#)
+                })
+                .join("");
+            std::fs::write(
+                path.join("Graphics/ForegroundTiles.xml"),
+                format!("<Data>{xml}</Data>"),
+            )
+            .unwrap();
+            CelesteModule::new(
+                Some(path),
+                EverestYaml {
+                    name: name.to_owned(),
+                    version: EverestModuleVersion(vec![1, 0, 0]),
+                    dll: None,
+                    dependencies: vec![],
+                },
+            )
+        };
+        let vanilla = fake_module("Celeste", &[('1', "dirt"), ('3', "snow")]);
+        let mymod = fake_module("MyMod", &[('3', "mymod/snow"), ('z', "mymod/zebra")]);
+
+        let autotilers = build_autotilers(
+            &None,
+            [("Celeste", &vanilla), ("MyMod", &mymod)].into_iter(),
+            false,
+        );
+        let fg = autotilers.get("fg").unwrap();
+        assert_eq!(fg.keys().copied().sorted().collect::<String>(), "13z");
+        assert_eq!(*fg[&'1'].texture, "tilesets/dirt");
+        // the later dependency wins
+        assert_eq!(*fg[&'3'].texture, "tilesets/mymod/snow");
+
+        let autotilers = build_autotilers(
+            &None,
+            [("MyMod", &mymod), ("Celeste", &vanilla)].into_iter(),
+            false,
+        );
+        assert_eq!(
+            *autotilers.get("fg").unwrap()[&'3'].texture,
+            "tilesets/snow"
+        );
+        assert!(autotilers.get("bg").is_none());
+
+        // the omni palette has no dependency order to go by, so it goes by name after vanilla
+        let other = fake_module("AMod", &[('3', "amod/snow")]);
+        let modules = [mymod, vanilla, other]
+            .into_iter()
+            .map(|module| (ModuleID::new(), module))
+            .collect::<HashMap<_, _>>();
+        let omni = ModuleAggregate::new_omni(&modules, DEFAULT_LANGUAGE, false);
+        let fg = omni.autotilers.get("fg").unwrap();
+        assert_eq!(*fg[&'1'].texture, "tilesets/dirt");
+        assert_eq!(*fg[&'3'].texture, "tilesets/mymod/snow");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -11,6 +11,46 @@ pub struct EntityDraw {
     pub initial_draw: Vec<DrawElement>,
     #[serde(default)]
     pub node_draw: Vec<DrawElement>,
+    #[serde(default)]
+    pub node_line_render: Option<NodeLineRender>,
+}
+
+/// Lines joining the entity to its nodes, drawn before `node_draw`. The color is evaluated once
+/// per node with the node env.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Data)]
+pub struct NodeLineRender {
+    pub color: Color,
+    /// Connect body -> node 0 -> node 1 -> ... instead of the body to each node.
+    #[serde(default)]
+    pub chain: bool,
+    #[serde(default)]
+    pub arrowhead: bool,
+    #[serde(default = "one")]
+    pub thickness: u32,
+}
+
+impl NodeLineRender {
+    /// The line leading up to the current node, for evaluating in the node env.
+    pub fn line(&self) -> DrawElement {
+        let (start_x, start_y) = if self.chain {
+            ("prevnodexorbase", "prevnodeyorbase")
+        } else {
+            ("x", "y")
+        };
+        DrawElement::DrawLine {
+            start: Vec2 {
+                x: Expression::Atom(start_x.to_owned()),
+                y: Expression::Atom(start_y.to_owned()),
+            },
+            end: Vec2 {
+                x: Expression::Atom("nodex".to_owned()),
+                y: Expression::Atom("nodey".to_owned()),
+            },
+            color: self.color.clone(),
+            arrowhead: self.arrowhead,
+            thickness: self.thickness,
+        }
+    }
 }
 
 #[allow(clippy::large_enum_variant, clippy::enum_variant_names)]
@@ -58,6 +58,13 @@ pub struct EntityConfigV2 {
     pub keywords: Vec<String>,
     #[serde(default)]
     pub templates: Vec<EntityTemplate>,
+    /// The attribute naming the session flags which must be set for this entity to spawn, if any.
+    /// Used to preview different gameplay states in the editor.
+    #[serde(default)]
+    pub condition_attribute: Option<String>,
+    /// The fewest and most nodes this entity can have, if it's picky about it.
+    #[serde(default)]
+    pub node_limit: Option<(usize, usize)>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
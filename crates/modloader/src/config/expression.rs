@@ -7,11 +7,12 @@ use nom::combinator::{complete, eof, map, map_res, opt, recognize};
 use nom::error::{Error as NomError, ErrorKind};
 use nom::multi::{fold_many0, many0, separated_list0};
 use nom::number::complete;
-use nom::sequence::{delimited, pair, separated_pair, terminated, tuple};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 use nom::{IResult, InputTakeAtPosition, Parser};
 use serde::de::Error as SerdeError;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::Formatter;
 use std::hash::{Hash, Hasher};
@@ -31,6 +32,12 @@ pub enum Expression {
         func: BuiltinFunction,
         args: Vec<Expression>,
     },
+    /// `cond ? then : otherwise`. Only the chosen branch is evaluated.
+    Ternary {
+        cond: Box<Expression>,
+        then: Box<Expression>,
+        otherwise: Box<Expression>,
+    },
 }
 
 impl Data for Expression {
@@ -103,6 +110,7 @@ impl std::fmt::Display for UnOp {
 pub enum BuiltinFunction {
     Lower,
     Upper,
+    Len,
 }
 
 impl BuiltinFunction {
@@ -110,6 +118,7 @@ impl BuiltinFunction {
         match self {
             BuiltinFunction::Lower => "Lower",
             BuiltinFunction::Upper => "Upper",
+            BuiltinFunction::Len => "len",
         }
     }
 }
@@ -274,7 +283,7 @@ fn const_expression(input: &str) -> IResult<&str, Expression> {
 fn parenthetical(input: &str) -> IResult<&str, Expression> {
     delimited(
         tuple((space0, tag("("), space0)),
-        expression_4,
+        expression_5,
         tuple((space0, tag(")"), space0)),
     )(input)
 }
@@ -297,7 +306,7 @@ fn match_arm(input: &str) -> IResult<&str, (Option<Const>, Expression)> {
     separated_pair(
         match_case,
         delimited(space0, tag("=>"), space0),
-        expression_4,
+        expression_5,
     )(input)
 }
 
@@ -339,7 +348,7 @@ fn match_expr_with_errors(input: &str) -> IResult<&str, Result<Expression, ()>>
     pair(
         delimited(
             tuple((space0, pair(tag("match"), space0))),
-            expression_4,
+            expression_5,
             space0,
         ),
         delimited(
@@ -367,7 +376,7 @@ fn call_expr(input: &str) -> IResult<&str, Expression> {
             function,
             delimited(
                 tuple((space0, tag("("), space0)),
-                separated_list0(delimited(space0, tag(","), space0), expression_4),
+                separated_list0(delimited(space0, tag(","), space0), expression_5),
                 tuple((space0, tag(")"), space0)),
             ),
         ),
@@ -420,7 +429,26 @@ fn operator_4(input: &str) -> IResult<&str, BinOp> {
 
 fn function(input: &str) -> IResult<&str, BuiltinFunction> {
     use BuiltinFunction::*;
-    alt((func_name(Lower), func_name(Upper)))(input)
+    alt((func_name(Lower), func_name(Upper), func_name(Len)))(input)
+}
+
+fn expression_5(input: &str) -> IResult<&str, Expression> {
+    pair(
+        expression_4,
+        opt(complete(pair(
+            preceded(delimited(space0, tag("?"), space0), expression_5),
+            preceded(delimited(space0, tag(":"), space0), expression_5),
+        ))),
+    )
+    .map(|(cond, branches)| match branches {
+        Some((then, otherwise)) => Expression::Ternary {
+            cond: Box::new(cond),
+            then: Box::new(then),
+            otherwise: Box::new(otherwise),
+        },
+        None => cond,
+    })
+    .parse(input)
 }
 
 fn expression_4(input: &str) -> IResult<&str, Expression> {
@@ -463,7 +491,7 @@ fn expression_0(input: &str) -> IResult<&str, Expression> {
 }
 
 fn expression(input: &str) -> IResult<&str, Expression> {
-    terminated(expression_4, eof)(input)
+    terminated(expression_5, eof)(input)
 }
 
 impl<'de> Deserialize<'de> for Expression {
@@ -518,6 +546,11 @@ impl std::fmt::Display for Expression {
                 }
                 write!(f, ")")
             }
+            Expression::Ternary {
+                cond,
+                then,
+                otherwise,
+            } => write!(f, "({cond} ? {then} : {otherwise})"),
         }
     }
 }
@@ -555,51 +588,34 @@ impl Expression {
                     BinOp::Mul => Ok(Const::Number(Number(
                         child1val.as_number()?.0 * child2val.as_number()?.0,
                     ))),
-                    // division by zero can produce nan and that's okay (?)
-                    BinOp::Div => Ok(Const::Number(Number(
-                        child1val.as_number()?.0 / child2val.as_number()?.0,
-                    ))),
-                    BinOp::Mod => Ok(Const::Number(Number(
-                        child1val.as_number()?.0 % child2val.as_number()?.0,
-                    ))),
-                    BinOp::Lt => Ok(Const::Number(Number(
-                        if child1val.as_number()?.0 < child2val.as_number()?.0 {
-                            1.0
+                    BinOp::Div | BinOp::Mod => {
+                        let (n1, n2) = (child1val.as_number()?.0, child2val.as_number()?.0);
+                        if n2 == 0.0 {
+                            Err(format!("Division by zero in {self}"))
+                        } else if *op == BinOp::Div {
+                            Ok(Const::Number(Number(n1 / n2)))
                         } else {
-                            0.0
-                        },
+                            Ok(Const::Number(Number(n1 % n2)))
+                        }
+                    }
+                    BinOp::Lt => Ok(Const::from_bool(matches!(
+                        compare(&child1val, &child2val)?,
+                        Some(Ordering::Less)
                     ))),
-                    BinOp::Gt => Ok(Const::Number(Number(
-                        if child1val.as_number()?.0 > child2val.as_number()?.0 {
-                            1.0
-                        } else {
-                            0.0
-                        },
+                    BinOp::Gt => Ok(Const::from_bool(matches!(
+                        compare(&child1val, &child2val)?,
+                        Some(Ordering::Greater)
                     ))),
-                    BinOp::Le => Ok(Const::Number(Number(
-                        if child1val.as_number()?.0 <= child2val.as_number()?.0 {
-                            1.0
-                        } else {
-                            0.0
-                        },
+                    BinOp::Le => Ok(Const::from_bool(matches!(
+                        compare(&child1val, &child2val)?,
+                        Some(Ordering::Less | Ordering::Equal)
                     ))),
-                    BinOp::Ge => Ok(Const::Number(Number(
-                        if child1val.as_number()?.0 >= child2val.as_number()?.0 {
-                            1.0
-                        } else {
-                            0.0
-                        },
+                    BinOp::Ge => Ok(Const::from_bool(matches!(
+                        compare(&child1val, &child2val)?,
+                        Some(Ordering::Greater | Ordering::Equal)
                     ))),
-                    BinOp::Eq => Ok(Const::Number(Number(if child1val == child2val {
-                        1.0
-                    } else {
-                        0.0
-                    }))),
-                    BinOp::Ne => Ok(Const::Number(Number(if child1val != child2val {
-                        1.0
-                    } else {
-                        0.0
-                    }))),
+                    BinOp::Eq => Ok(Const::from_bool(child1val == child2val)),
+                    BinOp::Ne => Ok(Const::from_bool(child1val != child2val)),
                 }
             }
             Expression::UnOp(op, child) => {
@@ -638,12 +654,37 @@ impl Expression {
                         let Const::String(str_arg) = arg else { return Err(format!("Uppser: expected string argument, got {}", arg.ty().as_str()))};
                         Ok(Const::String(str_arg.to_uppercase()))
                     }
+                    BuiltinFunction::Len => {
+                        let [arg] = args_eval.as_slice() else { return Err(format!("len: expected 1 argument, got {}", args_eval.len()))};
+                        let Const::String(str_arg) = arg else { return Err(format!("len: expected string argument, got {}", arg.ty().as_str()))};
+                        Ok(Const::from_num(str_arg.chars().count() as u32))
+                    }
+                }
+            }
+            Expression::Ternary {
+                cond,
+                then,
+                otherwise,
+            } => {
+                if cond.evaluate(env)?.as_number()?.0 != 0.0 {
+                    then.evaluate(env)
+                } else {
+                    otherwise.evaluate(env)
                 }
             }
         }
     }
 }
 
+/// Order two values for the comparison operators. Numbers compare numerically and strings
+/// lexicographically, while comparing a number with a string is an error. Nan is unordered.
+fn compare(a: &Const, b: &Const) -> Result<Option<Ordering>, String> {
+    match (a, b) {
+        (Const::String(a), Const::String(b)) => Ok(Some(a.cmp(b))),
+        _ => Ok(a.as_number()?.0.partial_cmp(&b.as_number()?.0)),
+    }
+}
+
 impl Const {
     pub fn as_number(&self) -> Result<Number, String> {
         match self {
@@ -660,6 +701,10 @@ impl Const {
         }
     }
 
+    pub fn from_bool(b: bool) -> Const {
+        Const::from_num(i32::from(b))
+    }
+
     pub fn from_num<N>(i: N) -> Const
     where
         N: Into<f64>,
@@ -749,7 +794,7 @@ mod test {
         let mut env: HashMap<&str, Const> = HashMap::new();
         env.insert("x", Const::Number(Number(0f64)));
         let res = expr.evaluate(&env);
-        assert_eq!(res, Ok(Const::Number(Number(f64::NAN))));
+        assert!(res.is_err());
         env.insert("x", Const::Number(Number(1f64)));
         let res = expr.evaluate(&env);
         assert_eq!(res, Ok(Const::Number(Number(-1f64))));
@@ -798,6 +843,62 @@ mod test {
         assert_eq!(res, Ok(Const::Number(Number(1.0))));
     }
 
+    #[test]
+    fn test_ternary() {
+        let expr = expression("kind == 'big' ? 16 : x == 0 ? 0 : 8 / x")
+            .unwrap()
+            .1;
+        let mut env: HashMap<&str, Const> = HashMap::new();
+        env.insert("kind", Const::String("big".to_owned()));
+        env.insert("x", Const::from_num(0));
+        assert_eq!(expr.evaluate(&env), Ok(Const::from_num(16)));
+        env.insert("kind", Const::String("small".to_owned()));
+        assert_eq!(expr.evaluate(&env), Ok(Const::from_num(0)));
+        env.insert("x", Const::from_num(2));
+        assert_eq!(expr.evaluate(&env), Ok(Const::from_num(4)));
+
+        let expr2 = expression(format!("{expr}").as_str()).unwrap().1;
+        assert_eq!(expr, expr2);
+
+        let expr = expression("'yes' ? 1 : 0").unwrap().1;
+        assert!(expr.evaluate(&HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let eval = |s: &str| expression(s).unwrap().1.evaluate(&HashMap::new());
+        assert_eq!(eval("1 < 2"), Ok(Const::from_num(1)));
+        assert_eq!(eval("2 > 2"), Ok(Const::from_num(0)));
+        assert_eq!(eval("'a' < 'b'"), Ok(Const::from_num(1)));
+        assert_eq!(eval("'a' != 'a'"), Ok(Const::from_num(0)));
+        assert_eq!(eval("1 == '1'"), Ok(Const::from_num(0)));
+        assert!(eval("1 < 'b'").is_err());
+    }
+
+    #[test]
+    fn test_len() {
+        let eval = |s: &str| expression(s).unwrap().1.evaluate(&HashMap::new());
+        assert_eq!(eval("len('spikes') * 8"), Ok(Const::from_num(48)));
+        assert_eq!(eval("len('')"), Ok(Const::from_num(0)));
+        assert!(eval("len(3)").is_err());
+        assert!(eval("len('a', 'b')").is_err());
+
+        let mut env: HashMap<&str, Const> = HashMap::new();
+        env.insert("length", Const::from_num(3));
+        let expr = expression("length + 1").unwrap().1;
+        assert_eq!(expr.evaluate(&env), Ok(Const::from_num(4)));
+    }
+
+    #[test]
+    fn test_divide_by_zero() {
+        let eval = |s: &str| expression(s).unwrap().1.evaluate(&HashMap::new());
+        assert_eq!(eval("7 / 2"), Ok(Const::Number(Number(3.5))));
+        assert_eq!(eval("7 % 2"), Ok(Const::from_num(1)));
+        assert!(eval("7 / 0").is_err());
+        assert!(eval("7 % 0").is_err());
+        assert!(eval("0 / (1 - 1)").is_err());
+    }
+
     #[test]
     fn test_empty_string() {
         let expr = expression("''");
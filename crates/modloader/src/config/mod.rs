@@ -43,6 +43,12 @@ impl Default for PencilBehavior {
 pub struct AttributeInfo {
     #[serde(default, skip_serializing_if = "is_default")]
     pub display_name: Option<String>,
+    /// Help text shown when hovering the attribute in the inspector.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub description: Option<String>,
+    /// The collapsible inspector section this attribute is listed under.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub group: Option<String>,
     pub ty: AttributeType,
     pub default: AttributeValue,
     #[serde(default, skip_serializing_if = "is_default")]
@@ -250,6 +256,11 @@ impl EntityConfig {
             keywords: vec![],
         }
     }
+
+    /// The fewest and most nodes this entity can have.
+    pub fn node_range(&self) -> (usize, usize) {
+        self.node_limit.unwrap_or((0, usize::MAX))
+    }
 }
 
 impl TriggerConfig {
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+/// The language whose dialog is used for looking up names.
+pub const DEFAULT_LANGUAGE: &str = "English";
+
+/// The contents of one `Dialog/<Language>.txt` file, from dialog key to text.
+pub type Dialog = HashMap<String, String>;
+
+/// Parse a dialog file. Entries are `KEY=value` lines, and any following lines which don't start a
+/// new entry continue the value on a new line. Blank lines and lines starting with `#` are skipped.
+pub fn parse_dialog(text: &str) -> Dialog {
+    let mut result = Dialog::new();
+    let mut current_key: Option<String> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim();
+            if !key.is_empty() && key.chars().all(|ch| ch.is_alphanumeric() || ch == '_') {
+                result.insert(key.to_owned(), value.trim().to_owned());
+                current_key = Some(key.to_owned());
+                continue;
+            }
+        }
+        if let Some(value) = current_key.as_ref().and_then(|key| result.get_mut(key)) {
+            if !value.is_empty() {
+                value.push('\n');
+            }
+            value.push_str(line);
+        }
+    }
+    result
+}
+
+/// The dialog key a map's display name is stored under, e.g. `foo_bar` for `foo/bar`. This matches
+/// Everest's conversion.
+pub fn dialog_keyify(sid: &str) -> String {
+    sid.replace(['/', '-', '+', ' '], "_")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_dialog() {
+        let dialog = parse_dialog(
+            "# Maps\n\
+             foo_bar= Foo Bar \n\
+             \n\
+             foo_intro=\n\
+             \tFirst line\n\
+             # not part of it\n\
+             \tSecond line\n\
+             foo_eq=a=b\n",
+        );
+        assert_eq!(dialog.get("foo_bar").map(String::as_str), Some("Foo Bar"));
+        assert_eq!(
+            dialog.get("foo_intro").map(String::as_str),
+            Some("First line\nSecond line")
+        );
+        assert_eq!(dialog.get("foo_eq").map(String::as_str), Some("a=b"));
+        assert_eq!(dialog.len(), 3);
+    }
+
+    #[test]
+    fn test_dialog_keyify() {
+        assert_eq!(dialog_keyify("foo/bar"), "foo_bar");
+        assert_eq!(dialog_keyify("Author/Lobby-1 A+"), "Author_Lobby_1_A_");
+    }
+}
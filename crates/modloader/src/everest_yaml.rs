@@ -2,6 +2,7 @@ use arborio_utils::vizia::prelude::Data;
 use itertools::Itertools;
 use serde::de::{Error, Unexpected};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::io::Read;
 use std::path::Path;
@@ -42,9 +43,43 @@ pub struct EverestYamlDependency {
     pub version: EverestModuleVersion,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Clone, Debug, Data)]
+/// A dotted version number. Versions compare like semver, where missing trailing components
+/// count as zero, so `1.2` and `1.2.0` are the same version.
+#[derive(Clone, Debug, Data)]
 pub struct EverestModuleVersion(pub Vec<i32>);
 
+impl EverestModuleVersion {
+    /// The components with any trailing zeros dropped.
+    fn normalized(&self) -> &[i32] {
+        let len = self
+            .0
+            .iter()
+            .rposition(|x| *x != 0)
+            .map_or(0, |idx| idx + 1);
+        &self.0[..len]
+    }
+}
+
+impl PartialEq for EverestModuleVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl Eq for EverestModuleVersion {}
+
+impl PartialOrd for EverestModuleVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EverestModuleVersion {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.normalized().cmp(other.normalized())
+    }
+}
+
 impl<'de> Deserialize<'de> for EverestModuleVersion {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -83,6 +118,38 @@ impl FromStr for EverestModuleVersion {
     }
 }
 
+/// Something wrong with one of a module's dependencies, as found by
+/// [`EverestYaml::check_dependencies`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DependencyProblem {
+    /// No module of this name is loaded.
+    Missing { name: String },
+    /// The loaded module is older than the version depended on.
+    Outdated {
+        name: String,
+        required: EverestModuleVersion,
+        installed: EverestModuleVersion,
+    },
+}
+
+impl Display for DependencyProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DependencyProblem::Missing { name } => write!(f, "{name} is not installed"),
+            DependencyProblem::Outdated {
+                name,
+                required,
+                installed,
+            } => write!(
+                f,
+                "{name} {} is installed, but {} is required",
+                installed.to_string(),
+                required.to_string()
+            ),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum EverestYamlLoadError {
     ParseError(serde_yaml::Error),
@@ -126,6 +193,29 @@ impl EverestYaml {
         Ok(everest_yaml.into_iter().next().unwrap())
     }
 
+    /// Check each dependency against `available`, the version of every loaded module by name. The
+    /// `Everest` dependency is skipped since Everest isn't a module.
+    pub fn check_dependencies(
+        &self,
+        available: &HashMap<String, EverestModuleVersion>,
+    ) -> Vec<DependencyProblem> {
+        self.dependencies
+            .iter()
+            .filter(|dep| dep.name != "Everest")
+            .filter_map(|dep| match available.get(&dep.name) {
+                None => Some(DependencyProblem::Missing {
+                    name: dep.name.clone(),
+                }),
+                Some(installed) if *installed < dep.version => Some(DependencyProblem::Outdated {
+                    name: dep.name.clone(),
+                    required: dep.version.clone(),
+                    installed: installed.clone(),
+                }),
+                Some(_) => None,
+            })
+            .collect()
+    }
+
     pub fn save(&self, mod_path: &Path) {
         [self]
             .serialize(&mut serde_yaml::Serializer::new(
@@ -134,3 +224,67 @@ impl EverestYaml {
             .unwrap();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_dependencies() {
+        let dep = |name: &str, version: &str| EverestYamlDependency {
+            name: name.to_owned(),
+            version: version.parse().unwrap(),
+        };
+        let yaml = EverestYaml {
+            name: "MyMod".to_owned(),
+            version: "1.0.0".parse().unwrap(),
+            dll: None,
+            dependencies: vec![
+                dep("Everest", "1.3000.0"),
+                dep("Gone", "1.0.0"),
+                dep("Old", "1.2.0"),
+                dep("Fine", "1.2.0"),
+                dep("Short", "1.2.0"),
+                dep("Long", "2.1"),
+            ],
+        };
+        let available = [
+            ("Old", "1.1.9"),
+            ("Fine", "1.10.0"),
+            ("Short", "1.2"),
+            ("Long", "2.0.5"),
+        ]
+        .into_iter()
+        .map(|(name, version)| (name.to_owned(), version.parse().unwrap()))
+        .collect();
+        assert_eq!(
+            yaml.check_dependencies(&available),
+            vec![
+                DependencyProblem::Missing {
+                    name: "Gone".to_owned()
+                },
+                DependencyProblem::Outdated {
+                    name: "Old".to_owned(),
+                    required: "1.2.0".parse().unwrap(),
+                    installed: "1.1.9".parse().unwrap(),
+                },
+                DependencyProblem::Outdated {
+                    name: "Long".to_owned(),
+                    required: "2.1".parse().unwrap(),
+                    installed: "2.0.5".parse().unwrap(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_version_ordering() {
+        let v = |s: &str| s.parse::<EverestModuleVersion>().unwrap();
+        assert_eq!(v("1.2"), v("1.2.0"));
+        assert_eq!(v("1.2.0.0"), v("1.2"));
+        assert_eq!(v("0"), v("0.0.0"));
+        assert!(v("1.2") < v("1.2.1"));
+        assert!(v("1.10") > v("1.9.9"));
+        assert_eq!(v("1.2").to_string(), "1.2");
+    }
+}
@@ -1,5 +1,6 @@
 pub mod aggregate;
 pub mod config;
+pub mod dialog;
 pub mod discovery;
 pub mod everest_yaml;
 pub mod mapstruct_plus_config;
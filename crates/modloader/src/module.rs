@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
 use std::io;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -13,6 +15,7 @@ use arborio_walker::ConfigSourceTrait;
 use arborio_walker::{open_module, ConfigSource};
 
 use crate::config::{EntityConfig, StylegroundConfig, TriggerConfig};
+use crate::dialog::{parse_dialog, Dialog};
 use crate::everest_yaml::EverestYaml;
 
 #[derive(Debug, Clone)] // Clone should just increase the refcount on each arc, right?
@@ -25,6 +28,8 @@ pub struct CelesteModule {
     pub trigger_config: InternedMap<Arc<TriggerConfig>>,
     pub styleground_config: InternedMap<Arc<StylegroundConfig>>,
     pub maps: Vec<String>,
+    /// Dialog files by language, e.g. `English` for `Dialog/English.txt`.
+    pub dialog: HashMap<String, Arc<Dialog>>,
 }
 
 uuid_cls!(ModuleID);
@@ -51,6 +56,7 @@ impl CelesteModule {
             trigger_config: InternedMap::new(),
             styleground_config: InternedMap::new(),
             maps: vec![],
+            dialog: HashMap::new(),
         }
     }
 
@@ -134,6 +140,25 @@ impl CelesteModule {
             }
         }
 
+        for path in source.list_all_files(&PathBuf::from("Dialog")) {
+            if path.extension() != Some(OsStr::new("txt")) {
+                continue;
+            }
+            let Some(language) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+            let Some(mut f) = source.get_file(&path) else {
+                log::error!("Path disappeared from {}: {:?}", source, path);
+                continue;
+            };
+            let mut text = String::new();
+            match f.read_to_string(&mut text) {
+                Ok(_) => {
+                    self.dialog
+                        .insert(language.to_owned(), Arc::new(parse_dialog(&text)));
+                }
+                Err(e) => log::error!("Failed loading dialog {}: {}", path.display(), e),
+            }
+        }
+
         for path in source.list_all_files(&PathBuf::from("Maps")) {
             if path.extension() == Some(OsStr::new("bin")) {
                 if let Some(sid) = path
@@ -148,6 +173,11 @@ impl CelesteModule {
         }
     }
 
+    /// Look up a dialog key in one of this module's dialog files.
+    pub fn dialog(&self, language: &str, key: &str) -> Option<&str> {
+        self.dialog.get(language)?.get(key).map(String::as_str)
+    }
+
     pub fn module_kind(&self) -> CelesteModuleKind {
         if self.everest_metadata.name == "Celeste" {
             return CelesteModuleKind::Builtin;
@@ -195,7 +225,8 @@ impl CelesteModule {
         from_reader(reader)
     }
 
-    pub fn create_map(&mut self, sid: String) {
+    /// Save `map` as a new map of this module.
+    pub fn create_map(&mut self, sid: String, map: &CelesteMap) {
         let p = self
             .filesystem_root
             .as_ref()
@@ -203,7 +234,7 @@ impl CelesteModule {
             .join("Maps")
             .join(sid.clone() + ".bin");
         std::fs::create_dir_all(p.parent().unwrap()).expect("Failed to create directory for map");
-        save_as(&CelesteMap::default(), &sid, &p).expect("Could not save blank map");
+        save_as(map, &sid, &p).expect("Could not save new map");
         self.maps.push(sid);
     }
 }
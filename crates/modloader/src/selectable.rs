@@ -64,3 +64,12 @@ impl Default for DecalSelectable {
         Self("does not exist".into())
     }
 }
+
+impl DecalSelectable {
+    /// The subfolder of `decals/` this decal is in, which it is grouped under in the palette.
+    /// Empty for decals directly in `decals/`.
+    pub fn group(&self) -> &'static str {
+        let name: &'static str = *self.0;
+        name.split_once('/').map_or("", |(group, _)| group)
+    }
+}
@@ -11,6 +11,14 @@ impl<T> AutoSaver<T> {
     pub fn borrow_mut(&mut self) -> MutRef<T> {
         MutRef { auto_saver: self }
     }
+    /// Change the value without saving it, for changes too frequent to save each time. They're
+    /// saved along with the next change which is, or by `save`.
+    pub fn borrow_mut_unsaved(&mut self) -> &mut T {
+        &mut self.value
+    }
+    pub fn save(&mut self) {
+        (self.saver)(&mut self.value);
+    }
 }
 impl<T> Deref for AutoSaver<T> {
     type Target = T;
@@ -1,12 +1,12 @@
 use arborio_utils::units::{MapRectStrict, TileGrid, TilePoint, TileVector};
 use arborio_utils::uuid::next_uuid;
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use crate::data::project_map::{LevelState, MapState, MapStateUpdate};
+use crate::data::project_map::{LevelState, MapStateData, MapStateUpdate};
 use crate::data::selection::AppSelection;
 use arborio_maploader::map_struct::{
-    CelesteMapDecal, CelesteMapEntity, CelesteMapLevel, CelesteMapLevelUpdate,
+    CelesteMap, CelesteMapDecal, CelesteMapEntity, CelesteMapLevel, CelesteMapLevelUpdate,
     CelesteMapStyleground,
 };
 use arborio_utils::vizia::prelude::Data;
@@ -21,7 +21,7 @@ use arborio_utils::vizia::prelude::Data;
 // UGH THE LAST ONE IS BEING HACKED AROUND NOW
 
 pub fn apply_map_action(
-    map: &mut MapState,
+    map: &mut MapStateData,
     event: Vec<MapAction>,
     selection_option: Option<&mut HashSet<AppSelection>>,
 ) -> Result<Vec<MapAction>, String> {
@@ -74,22 +74,22 @@ pub fn apply_map_action(
                 }
             }
             MapAction::MetaUpdate { mut update } => {
-                map.data.apply(&mut update);
+                map.apply(&mut update);
                 Ok(MapAction::MetaUpdate { update })
             }
+            MapAction::SetNote { room, note } => {
+                let note = map.notes.set(room.as_deref(), note);
+                Ok(MapAction::SetNote { room, note })
+            }
             MapAction::AddRoom { idx, mut room } => {
-                let idx = idx.unwrap_or(map.data.levels.len());
+                let idx = idx.unwrap_or(map.levels.len());
                 if room.name.is_empty()
-                    || map
-                        .data
-                        .levels
-                        .iter()
-                        .any(|iroom| room.name == iroom.data.name)
+                    || map.levels.iter().any(|iroom| room.name == iroom.data.name)
                 {
                     room.name = pick_new_name(map);
                 }
-                if idx <= map.data.levels.len() {
-                    map.data.levels.insert(
+                if idx <= map.levels.len() {
+                    map.levels.insert(
                         idx,
                         LevelState {
                             data: *room,
@@ -103,8 +103,8 @@ pub fn apply_map_action(
                 }
             }
             MapAction::DeleteRoom { idx } => {
-                if idx <= map.data.levels.len() {
-                    let room = map.data.levels.remove(idx);
+                if idx < map.levels.len() {
+                    let room = map.levels.remove(idx);
                     Ok(MapAction::AddRoom {
                         idx: Some(idx),
                         room: Box::new(room.data),
@@ -114,12 +114,14 @@ pub fn apply_map_action(
                 }
             }
             MapAction::RoomAction { idx, event } => {
-                if let Some(room) = map.data.levels.get_mut(idx) {
+                if let Some(room) = map.levels.get_mut(idx) {
                     room.cache.borrow_mut().render_cache_valid = false;
-                    Ok(MapAction::RoomAction {
-                        idx,
-                        event: apply_room_event(room, event)?,
-                    })
+                    let old_name = room.data.name.clone();
+                    let event = apply_room_event(room, event)?;
+                    if room.data.name != old_name {
+                        map.notes.rename_room(&old_name, &room.data.name);
+                    }
+                    Ok(MapAction::RoomAction { idx, event })
                 } else {
                     Err("Out of range".to_owned())
                 }
@@ -167,6 +169,37 @@ pub fn apply_map_action(
     result
 }
 
+/// Apply a batch of actions to a map without any editor around it, e.g. from an external tool.
+/// Returns the batch which undoes it. As in the editor, the batch is applied in order and stops at
+/// the first failing action, leaving any previous actions applied.
+pub fn apply_actions(
+    map: &mut CelesteMap,
+    actions: Vec<MapAction>,
+) -> Result<Vec<MapAction>, String> {
+    let mut data = MapStateData::from(std::mem::take(map));
+    let result = apply_map_action(&mut data, actions, None);
+    *map = data.into();
+    result
+}
+
+/// Build a batch by running `f` over every room, for "do this to every room" automation. The
+/// result can be applied with [`apply_actions`] or sent to the editor as a single undoable
+/// `MapEvent::Action`.
+pub fn for_each_room<'a>(
+    rooms: impl IntoIterator<Item = &'a CelesteMapLevel>,
+    mut f: impl FnMut(&CelesteMapLevel) -> Vec<RoomAction>,
+) -> Vec<MapAction> {
+    rooms
+        .into_iter()
+        .enumerate()
+        .flat_map(|(idx, room)| {
+            f(room)
+                .into_iter()
+                .map(move |event| MapAction::RoomAction { idx, event })
+        })
+        .collect()
+}
+
 fn apply_room_event(room: &mut LevelState, event: RoomAction) -> Result<RoomAction, String> {
     match event {
         RoomAction::UpdateRoomMisc { mut update } => {
@@ -180,7 +213,9 @@ fn apply_room_event(room: &mut LevelState, event: RoomAction) -> Result<RoomActi
                 room.data
                     .object_tiles
                     .resize((bounds.size / 8).cast_unit(), -1);
-                room.cache.borrow_mut().render_cache = None;
+                let mut cache = room.cache.borrow_mut();
+                cache.render_cache = None;
+                cache.invalidate_tiles();
             }
             std::mem::swap(&mut room.data.bounds, &mut bounds);
             Ok(RoomAction::MoveRoom { bounds })
@@ -196,8 +231,43 @@ fn apply_room_event(room: &mut LevelState, event: RoomAction) -> Result<RoomActi
                 &mut room.data.bg
             };
             apply_tiles(&offset, &mut data, target, '\0');
+            room.cache.borrow_mut().invalidate_tiles();
             Ok(RoomAction::TileUpdate { fg, offset, data })
         }
+        RoomAction::RemapTiles { fg, mapping } => {
+            let target = if fg {
+                &mut room.data.solids
+            } else {
+                &mut room.data.bg
+            };
+            let reverse: HashMap<char, char> =
+                mapping.iter().map(|(&from, &to)| (to, from)).collect();
+            // the reverse mapping only works if nothing else ends up as one of the targets: either
+            // two tiles mapped to the same one, or a target which was already there untouched
+            let invertible = reverse.len() == mapping.len()
+                && !target
+                    .tiles
+                    .iter()
+                    .any(|tile| reverse.contains_key(tile) && !mapping.contains_key(tile));
+            let pre_image = (!invertible).then(|| target.clone());
+            for tile in target.tiles_mut() {
+                if let Some(&to) = mapping.get(tile) {
+                    *tile = to;
+                }
+            }
+            room.cache.borrow_mut().invalidate_tiles();
+            Ok(match pre_image {
+                None => RoomAction::RemapTiles {
+                    fg,
+                    mapping: reverse,
+                },
+                Some(data) => RoomAction::TileUpdate {
+                    fg,
+                    offset: TilePoint::zero(),
+                    data,
+                },
+            })
+        }
         RoomAction::ObjectTileUpdate { offset, mut data } => {
             apply_tiles(&offset, &mut data, &mut room.data.object_tiles, -2);
             Ok(RoomAction::ObjectTileUpdate { offset, data })
@@ -301,6 +371,44 @@ fn apply_room_event(room: &mut LevelState, event: RoomAction) -> Result<RoomActi
             }
             Err("No such decal".to_owned())
         }
+        RoomAction::ReplaceEntities {
+            trigger,
+            mut entities,
+        } => {
+            let target = if trigger {
+                &mut room.data.triggers
+            } else {
+                &mut room.data.entities
+            };
+            std::mem::swap(target, &mut entities);
+            Ok(RoomAction::ReplaceEntities { trigger, entities })
+        }
+        RoomAction::EntityReorder { id, trigger, idx } => {
+            let entities = if trigger {
+                &mut room.data.triggers
+            } else {
+                &mut room.data.entities
+            };
+            let old_idx = move_to_index(entities, |e| e.id == id, idx).ok_or("No such entity")?;
+            Ok(RoomAction::EntityReorder {
+                id,
+                trigger,
+                idx: old_idx,
+            })
+        }
+        RoomAction::DecalReorder { fg, id, idx } => {
+            let decals = if fg {
+                &mut room.data.fg_decals
+            } else {
+                &mut room.data.bg_decals
+            };
+            let old_idx = move_to_index(decals, |d| d.id == id, idx).ok_or("No such decal")?;
+            Ok(RoomAction::DecalReorder {
+                fg,
+                id,
+                idx: old_idx,
+            })
+        }
         RoomAction::TileFloatSet { fg, mut float } => {
             let target = if fg {
                 &mut room.floats.fg
@@ -317,12 +425,15 @@ fn apply_room_event(room: &mut LevelState, event: RoomAction) -> Result<RoomActi
     }
 }
 
+/// An undoable edit to a map. A `Vec<MapAction>` is applied in order as a single undo step.
 #[derive(Debug, Clone)]
 pub enum MapAction {
+    /// Insert a styleground at the given position.
     AddStyleground {
         loc: StylegroundSelection,
         style: Box<CelesteMapStyleground>,
     },
+    /// Replace the styleground at the given position.
     UpdateStyleground {
         loc: StylegroundSelection,
         style: Box<CelesteMapStyleground>,
@@ -330,10 +441,13 @@ pub enum MapAction {
     RemoveStyleground {
         loc: StylegroundSelection,
     },
+    /// Move a styleground, possibly between the foreground and background lists.
     MoveStyleground {
         loc: StylegroundSelection,
         target: StylegroundSelection,
     },
+    /// Insert a room, at the end if `idx` is `None`. Empty or clashing names are replaced with a
+    /// fresh one.
     AddRoom {
         idx: Option<usize>, // made absolute through mutation
         room: Box<CelesteMapLevel>,
@@ -341,37 +455,58 @@ pub enum MapAction {
     DeleteRoom {
         idx: usize,
     },
+    /// Apply a [`RoomAction`] to the room at index `idx`.
     RoomAction {
         idx: usize,
         event: RoomAction,
     },
+    /// Set the map metadata fields which are `Some` in the update.
     MetaUpdate {
         update: Box<MapStateUpdate>,
     },
+    /// Set the notes on the named room, or on the whole map for `None`.
+    SetNote {
+        room: Option<String>,
+        note: String,
+    },
 }
 
+/// An undoable edit to a single room. `fg` selects between the foreground and background layers,
+/// and `trigger` between the trigger and entity lists.
 #[derive(Debug, Clone)]
 pub enum RoomAction {
+    /// Set the room's position and size. Tile grids are resized to match.
     MoveRoom {
         bounds: MapRectStrict,
     },
+    /// Set the room properties which are `Some` in the update.
     UpdateRoomMisc {
         update: Box<CelesteMapLevelUpdate>,
     },
+    /// Paste tiles at the given offset. `'\0'` tiles are left unchanged.
     TileUpdate {
         fg: bool,
         offset: TilePoint,
         data: TileGrid<char>,
     },
+    /// Replace every tile in the layer according to `mapping`, e.g. when switching tilesets.
+    /// Undone by the reverse mapping if there is one, or else by a `TileUpdate` of the whole grid.
+    RemapTiles {
+        fg: bool,
+        mapping: HashMap<char, char>,
+    },
+    /// Paste object tiles at the given offset. `-2` tiles are left unchanged.
     ObjectTileUpdate {
         offset: TilePoint,
         data: TileGrid<i32>,
     },
+    /// Add an entity or trigger, giving it a fresh id if `genid` is set.
     EntityAdd {
         entity: Box<CelesteMapEntity>,
         trigger: bool,
         genid: bool,
     },
+    /// Replace the entity or trigger with the same id.
     EntityUpdate {
         entity: Box<CelesteMapEntity>,
         trigger: bool,
@@ -380,11 +515,13 @@ pub enum RoomAction {
         id: i32,
         trigger: bool,
     },
+    /// Add a decal, giving it a fresh id if `genid` is set.
     DecalAdd {
         fg: bool,
         decal: Box<CelesteMapDecal>,
         genid: bool,
     },
+    /// Replace the decal with the same id.
     DecalUpdate {
         fg: bool,
         decal: Box<CelesteMapDecal>,
@@ -393,25 +530,64 @@ pub enum RoomAction {
         fg: bool,
         id: u32,
     },
+    /// Replace the whole entity or trigger list.
+    ReplaceEntities {
+        trigger: bool,
+        entities: Vec<CelesteMapEntity>,
+    },
+    /// Move an entity or trigger to the given index in its list. Draw order within a layer is
+    /// list order.
+    EntityReorder {
+        id: i32,
+        trigger: bool,
+        idx: usize,
+    },
+    /// Move a decal to the given index in its list.
+    DecalReorder {
+        fg: bool,
+        id: u32,
+        idx: usize,
+    },
+    /// Set the floating tile selection. Editor-only state, not saved with the map.
     TileFloatSet {
         fg: bool,
         float: Option<(TilePoint, TileGrid<char>)>,
     },
+    /// Set the floating object tile selection. Editor-only state, not saved with the map.
     ObjFloatSet {
         float: Option<(TilePoint, TileGrid<i32>)>,
     },
 }
 
+// returns the index the item was moved from
+fn move_to_index<T>(vec: &mut Vec<T>, pred: impl Fn(&T) -> bool, idx: usize) -> Option<usize> {
+    let old_idx = vec.iter().position(pred)?;
+    let item = vec.remove(old_idx);
+    vec.insert(idx.min(vec.len()), item);
+    Some(old_idx)
+}
+
 pub fn apply_tiles<T: Copy + Eq>(
     offset: &TilePoint,
     data: &mut TileGrid<T>,
     target: &mut TileGrid<T>,
     ignore: T,
 ) -> bool {
+    // replacing the whole grid trades storage with it, so the undo entry keeps the old tiles
+    // shared with anything else still holding them instead of copying them out one by one
+    if *offset == TilePoint::zero()
+        && data.stride == target.stride
+        && data.tiles.len() == target.tiles.len()
+        && !data.tiles.contains(&ignore)
+    {
+        let dirty = data.tiles != target.tiles;
+        std::mem::swap(data, target);
+        return dirty;
+    }
     let mut dirty = false;
     let mut line_start = *offset;
     let mut cur = line_start;
-    for (idx, tile) in data.tiles.iter_mut().enumerate() {
+    for (idx, tile) in data.tiles_mut().iter_mut().enumerate() {
         if *tile != ignore {
             if let Some(tile_ref) = target.get_mut(cur) {
                 if *tile_ref != *tile {
@@ -430,9 +606,8 @@ pub fn apply_tiles<T: Copy + Eq>(
     dirty
 }
 
-pub fn pick_new_name(map: &MapState) -> String {
+pub fn pick_new_name(map: &MapStateData) -> String {
     let all_names = map
-        .data
         .levels
         .iter()
         .map(|room| &room.data.name)
@@ -464,8 +639,147 @@ pub fn pick_new_name(map: &MapState) -> String {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Data)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Data)]
 pub struct StylegroundSelection {
     pub fg: bool,
     pub idx: usize,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn delete_room_checks_range() {
+        let mut map = MapStateData::from(CelesteMap {
+            levels: vec![CelesteMapLevel::default()],
+            ..Default::default()
+        });
+        let delete = |idx| vec![MapAction::DeleteRoom { idx }];
+        assert!(apply_map_action(&mut map, delete(1), None).is_err());
+        assert_eq!(map.levels.len(), 1);
+
+        let undo = apply_map_action(&mut map, delete(0), None).unwrap();
+        assert!(map.levels.is_empty());
+        assert!(apply_map_action(&mut map, delete(0), None).is_err());
+        apply_map_action(&mut map, undo, None).unwrap();
+        assert_eq!(map.levels.len(), 1);
+    }
+
+    fn remap_room(tiles: &[char]) -> MapStateData {
+        let mut room = CelesteMapLevel::default();
+        for (x, &tile) in tiles.iter().enumerate() {
+            *room.solids.get_mut(TilePoint::new(x as i32, 0)).unwrap() = tile;
+        }
+        MapStateData::from(CelesteMap {
+            levels: vec![room],
+            ..Default::default()
+        })
+    }
+
+    fn remap(mapping: &[(char, char)]) -> Vec<MapAction> {
+        vec![MapAction::RoomAction {
+            idx: 0,
+            event: RoomAction::RemapTiles {
+                fg: true,
+                mapping: mapping.iter().copied().collect(),
+            },
+        }]
+    }
+
+    fn first_tiles(map: &MapStateData, n: usize) -> String {
+        map.levels[0].data.solids.tiles[..n].iter().collect()
+    }
+
+    #[test]
+    fn remap_tiles_undoes_by_inverse() {
+        let mut map = remap_room(&['1', '2', '3']);
+        let undo = apply_map_action(&mut map, remap(&[('1', '2'), ('2', '1')]), None).unwrap();
+        assert_eq!(first_tiles(&map, 4), "2130");
+        assert!(matches!(
+            undo[..],
+            [MapAction::RoomAction {
+                event: RoomAction::RemapTiles { .. },
+                ..
+            }]
+        ));
+        apply_map_action(&mut map, undo, None).unwrap();
+        assert_eq!(first_tiles(&map, 4), "1230");
+    }
+
+    #[test]
+    fn remap_tiles_undoes_merges_by_snapshot() {
+        // two tiles merged into one
+        let mut map = remap_room(&['1', '2', '3']);
+        let undo = apply_map_action(&mut map, remap(&[('1', '3'), ('2', '3')]), None).unwrap();
+        assert_eq!(first_tiles(&map, 4), "3330");
+        assert!(matches!(
+            undo[..],
+            [MapAction::RoomAction {
+                event: RoomAction::TileUpdate { .. },
+                ..
+            }]
+        ));
+        apply_map_action(&mut map, undo, None).unwrap();
+        assert_eq!(first_tiles(&map, 4), "1230");
+
+        // a tile mapped onto one which was already there
+        let undo = apply_map_action(&mut map, remap(&[('1', '3')]), None).unwrap();
+        assert_eq!(first_tiles(&map, 4), "3230");
+        apply_map_action(&mut map, undo, None).unwrap();
+        assert_eq!(first_tiles(&map, 4), "1230");
+    }
+
+    fn whole_grid_tiles(actions: &[MapAction]) -> &Arc<Vec<char>> {
+        match actions {
+            [MapAction::RoomAction {
+                event: RoomAction::TileUpdate { data, .. },
+                ..
+            }] => &data.tiles,
+            _ => panic!("expected a single tile update"),
+        }
+    }
+
+    #[test]
+    fn whole_grid_undo_shares_storage() {
+        let mut map = remap_room(&['1', '2', '3']);
+        let before = map.levels[0].data.solids.clone();
+        let undo = apply_map_action(&mut map, remap(&[('1', '3'), ('2', '3')]), None).unwrap();
+        assert!(Arc::ptr_eq(whole_grid_tiles(&undo), &before.tiles));
+
+        // undoing hands the same storage back rather than copying it into the room
+        let remapped = map.levels[0].data.solids.clone();
+        let redo = apply_map_action(&mut map, undo, None).unwrap();
+        assert!(Arc::ptr_eq(&map.levels[0].data.solids.tiles, &before.tiles));
+        assert!(Arc::ptr_eq(whole_grid_tiles(&redo), &remapped.tiles));
+    }
+
+    #[test]
+    fn tile_edits_forget_planned_draws() {
+        let plan = |map: &MapStateData| {
+            let mut cache = map.levels[0].cache.borrow_mut();
+            cache.fg_tile_draws = Some(vec![]);
+            cache.bg_tile_draws = Some(vec![]);
+        };
+        let mut map = remap_room(&['1']);
+        plan(&map);
+        apply_map_action(&mut map, remap(&[('1', '2')]), None).unwrap();
+        assert!(map.levels[0].cache.borrow().fg_tile_draws.is_none());
+
+        plan(&map);
+        let paint = vec![MapAction::RoomAction {
+            idx: 0,
+            event: RoomAction::TileUpdate {
+                fg: false,
+                offset: TilePoint::zero(),
+                data: TileGrid {
+                    tiles: vec!['3'].into(),
+                    stride: 1,
+                },
+            },
+        }];
+        apply_map_action(&mut map, paint, None).unwrap();
+        assert!(map.levels[0].cache.borrow().bg_tile_draws.is_none());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::data::action::{MapAction, RoomAction, StylegroundSelection};
 use arborio_maploader::map_struct::{CelesteMap, CelesteMapEntity};
 use arborio_modloader::aggregate::ModuleAggregate;
+use arborio_modloader::dialog::DEFAULT_LANGUAGE;
 use arborio_modloader::discovery::LoaderThreadMessage;
 use arborio_modloader::module::{CelesteModule, MapPath, ModuleID, CELESTE_MODULE_ID};
 use arborio_modloader::selectable::{
@@ -14,7 +15,9 @@ use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
 use std::time;
 
 use crate::auto_saver::AutoSaver;
@@ -45,6 +48,7 @@ pub struct AppState {
     pub poison_tab: usize,
 
     pub current_toolspec: ToolSpec,
+    pub previous_toolspec: ToolSpec,
     pub current_tool: RefCell<Option<Box<dyn Tool>>>,
     pub current_layer: Layer,
     pub current_fg_tile: TileSelectable,
@@ -59,6 +63,19 @@ pub struct AppState {
     pub current_decal_other: String,
     pub current_objtile: u32,
     pub objtiles_transform: MapToScreen,
+    /// Whether to preview entity flag conditions against `preview_flags`.
+    pub flag_preview: bool,
+    pub preview_flags: HashSet<String>,
+    /// A description of whatever was most recently eyedropped.
+    pub eyedropped: String,
+    /// The undo phase shared by every edit made in the entity tweaker until the session is ended.
+    pub tweaker_phase: EventPhase,
+    /// Set to stop the project batch operation which is currently running, if any.
+    pub batch_cancel: Arc<AtomicBool>,
+    /// Set while a project batch operation is running, so that only one runs at a time.
+    pub batch_running: Arc<AtomicBool>,
+    /// A room and selection to reveal once the map it's in finishes loading.
+    pub pending_reveal: Option<(MapPath, usize, HashSet<AppSelection>)>,
 
     pub last_draw: RefCell<time::Instant>, // mutable to draw
     pub progress: Progress,
@@ -77,9 +94,39 @@ pub enum AppEvent {
     SetClipboard {
         contents: String,
     },
+    /// An implicit change to the palette selection, e.g. from an eyedropper. `event` is only
+    /// applied if the palette isn't pinned.
+    Eyedrop {
+        event: Box<AppEvent>,
+        description: String,
+    },
+    SetFlagPreview {
+        enabled: bool,
+    },
+    SetPreviewFlag {
+        flag: String,
+        active: bool,
+    },
+    /// Start a new undo phase for edits made in the entity tweaker.
+    EndTweakerSession,
     EditSettings {
         setter: AppConfigSetter,
     },
+    /// The window was resized. This is only saved once the window closes, since it comes in a
+    /// flood while the user drags the window's edge.
+    WindowResized {
+        size: (f32, f32),
+    },
+    /// The window was moved. Like the size, this is only saved once the window closes.
+    WindowMoved {
+        position: (i32, i32),
+    },
+    /// Save anything that was held back from the config until now.
+    WindowClosing,
+    /// Switch which language's dialog is used, rebuilding the palettes to pick it up.
+    SetLanguage {
+        language: String,
+    },
     SetModules {
         modules: HashMap<ModuleID, CelesteModule>,
     },
@@ -92,10 +139,17 @@ pub enum AppEvent {
     OpenMap {
         path: MapPath,
     },
+    /// A map finished loading off of the main thread. `map` is `None` if it failed to load.
     LoadMap {
         path: MapPath,
         map: RefCell<Option<Box<CelesteMap>>>,
     },
+    /// Open a map if it isn't already, then show a room in it with `selection` selected.
+    RevealInMap {
+        path: MapPath,
+        room: usize,
+        selection: HashSet<AppSelection>,
+    },
     OpenInstallationTab,
     OpenConfigEditorTab,
     OpenLogsTab,
@@ -106,6 +160,10 @@ pub enum AppEvent {
         idx: usize,
     },
     NewMod,
+    /// Load a zipped mod from anywhere on disk for read-only viewing, then show its maps.
+    OpenZip {
+        path: PathBuf,
+    },
     MovePreview {
         tab: usize,
         pos: MapPointStrict,
@@ -157,6 +215,10 @@ pub enum AppEvent {
         tab: usize,
         config: Box<AnyConfig>,
     },
+    /// Write the config being edited into the mod folder the tab is scoped to.
+    SaveConfigToModule {
+        tab: usize,
+    },
     EditPreviewEntity {
         tab: usize,
         entity: CelesteMapEntity,
@@ -169,10 +231,31 @@ pub enum AppEvent {
         tab: usize,
         styleground: Option<StylegroundSelection>,
     },
+    SelectStylegrounds {
+        tab: usize,
+        stylegrounds: HashSet<StylegroundSelection>,
+    },
     SelectRoom {
         tab: usize,
         idx: usize,
     },
+    /// Save a render of a room, cropped to its bounds and transparent wherever nothing is drawn, as
+    /// a PNG. The editor does the rendering the next time it draws the tab.
+    ExportRoomImage {
+        tab: usize,
+        room: usize,
+        path: PathBuf,
+    },
+    /// Write the current map to `path`, even if its module is zipped. The map is only marked as
+    /// saved if `path` is where the module itself keeps it.
+    SaveMapAs {
+        path: PathBuf,
+    },
+    /// Put a render of just what's selected in the current room on the clipboard as an image. The
+    /// editor does the rendering the next time it draws the tab.
+    CopySelectionImage {
+        tab: usize,
+    },
     SelectLayer {
         layer: Layer,
     },
@@ -216,6 +299,17 @@ pub enum AppEvent {
         tab: usize,
         selection: HashSet<AppSelection>,
     },
+    /// Replace the selection with objects from any room of the map, by room index. See
+    /// `MapTab::map_selected`.
+    SelectAcrossMap {
+        tab: usize,
+        selection: HashMap<usize, HashSet<AppSelection>>,
+    },
+    /// Lock the given objects against selection, or unlock them if they are all locked already.
+    ToggleLock {
+        tab: usize,
+        selection: HashSet<AppSelection>,
+    },
     MapEvent {
         map: Option<MapID>,
         event: MapEvent,
@@ -244,7 +338,7 @@ impl Model for AppState {
 
 impl AppState {
     pub fn new(tx: Sender<LoaderThreadMessage>) -> AppState {
-        let mut cfg: AppConfig = confy::load("arborio", "arborio").unwrap_or_default();
+        let mut cfg = AppConfig::load();
         if !cfg
             .celeste_root
             .as_ref()
@@ -268,6 +362,7 @@ impl AppState {
             loaded_maps: HashMap::new(),
             loaded_maps_lookup: HashMap::new(),
             current_toolspec: ToolSpec::Selection,
+            previous_toolspec: ToolSpec::Pencil,
             current_tool: RefCell::new(None),
             current_fg_tile: TileSelectable::default(),
             current_fg_tile_other: "".to_owned(),
@@ -283,6 +378,13 @@ impl AppState {
             current_layer: Layer::FgTiles,
             current_objtile: 0,
             objtiles_transform: MapToScreen::identity(),
+            flag_preview: false,
+            preview_flags: HashSet::new(),
+            eyedropped: "".to_owned(),
+            tweaker_phase: EventPhase::new(),
+            batch_cancel: Arc::new(AtomicBool::new(false)),
+            batch_running: Arc::new(AtomicBool::new(false)),
+            pending_reveal: None,
 
             modules: HashMap::new(),
             modules_lookup: HashMap::new(),
@@ -292,6 +394,7 @@ impl AppState {
                 &HashMap::new(),
                 &None,
                 *CELESTE_MODULE_ID,
+                DEFAULT_LANGUAGE,
                 false,
             ),
             progress: Progress {
@@ -317,6 +420,17 @@ impl AppState {
         }
     }
 
+    /// Make every room of every open map render again from scratch on the next draw.
+    pub(crate) fn invalidate_render_caches(&self) {
+        for map in self.loaded_maps.values() {
+            for room in &map.data.levels {
+                let mut cache = room.cache.borrow_mut();
+                cache.render_cache_valid = false;
+                cache.invalidate_tiles();
+            }
+        }
+    }
+
     pub(crate) fn rebuild_modules_bookkeeping(&mut self) {
         // bump binding version
         self.modules_version += 1;
@@ -334,10 +448,12 @@ impl AppState {
                 &self.modules_lookup,
                 &Some(state.data.clone_meta()),
                 state.cache.path.module,
+                &self.config.language,
                 true,
             );
         }
-        self.omni_palette = ModuleAggregate::new_omni(&self.modules, false) // discard logs
+        // discard logs
+        self.omni_palette = ModuleAggregate::new_omni(&self.modules, &self.config.language, false);
     }
 
     pub fn current_project_id(&self) -> Option<ModuleID> {
@@ -369,7 +485,7 @@ impl AppState {
         if let Some(tab) = self.tabs.get(self.current_tab) {
             match tab {
                 AppTab::Map(maptab) => Some(maptab.id),
-                AppTab::MapMeta(mapid) => Some(*mapid),
+                AppTab::MapMeta(mapid) | AppTab::MapFlags(mapid) => Some(*mapid),
                 _ => None,
             }
         } else {
@@ -408,7 +524,7 @@ impl AppState {
 
                 match tab {
                     AppTab::ProjectOverview(project) => self.modules.contains_key(project),
-                    AppTab::Map(MapTab { id, .. }) | AppTab::MapMeta(id) => {
+                    AppTab::Map(MapTab { id, .. }) | AppTab::MapMeta(id) | AppTab::MapFlags(id) => {
                         if let Some(x) = self.loaded_maps.get(id) {
                             self.modules.contains_key(&x.cache.path.module)
                         } else {
@@ -436,7 +552,7 @@ impl AppState {
                 AppTab::Map(MapTab { id, .. }) => {
                     open_maps.insert(*id);
                 }
-                AppTab::MapMeta(id) => {
+                AppTab::MapMeta(id) | AppTab::MapFlags(id) => {
                     open_maps.insert(*id);
                 }
                 _ => {}
@@ -450,7 +566,11 @@ impl AppState {
     pub fn map_action(&self, event: Vec<MapAction>, merge_phase: EventPhase) -> AppEvent {
         AppEvent::MapEvent {
             map: Some(self.map_tab_unwrap().id),
-            event: MapEvent::Action { merge_phase, event },
+            event: MapEvent::Action {
+                merge_phase,
+                event,
+                select_added: true,
+            },
         }
     }
 
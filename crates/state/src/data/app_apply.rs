@@ -1,17 +1,25 @@
 use crate::data::app::{AppEvent, AppState};
-use crate::data::config_editor::ConfigSearchResult;
+use crate::data::config_editor::{AnyConfig, ConfigSearchResult, SearchScope};
+use crate::data::notes::{load_notes, notes_root, MapNotes};
 use crate::data::project_map::{MapEvent, MapState};
 use crate::data::tabs::{AppTab, ConfigEditorTab, MapTab};
-use crate::data::{load_map, AppConfigSetter, MapID};
+use crate::data::{
+    load_map, map_tile_count, AppConfigSetter, EventPhase, MapID, Progress, RecentMap,
+};
 use arborio_modloader::aggregate::ModuleAggregate;
-use arborio_modloader::discovery::LoaderThreadMessage;
+use arborio_modloader::discovery::{load_into, LoaderThreadMessage};
 use arborio_modloader::everest_yaml::{EverestModuleVersion, EverestYaml};
+use arborio_modloader::module::MapPath;
 use arborio_utils::units::*;
 use arborio_utils::uuid::next_uuid;
 use arborio_utils::vizia::prelude::*;
+use arborio_walker::{open_module, ConfigSource, ConfigSourceMutTrait, ZipSource};
 use log::Level;
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+const BIG_MAP_NOTICE: &str = "This is a large map, loading may take a moment";
 
 impl AppState {
     pub fn apply(&mut self, cx: &mut EventContext, event: AppEvent) {
@@ -26,6 +34,29 @@ impl AppState {
             AppEvent::Progress { progress } => {
                 self.progress = progress;
             }
+            AppEvent::Eyedrop { event, description } => {
+                self.eyedropped = description;
+                if !self.config.pin_palette {
+                    cx.emit(*event);
+                }
+            }
+            AppEvent::SetFlagPreview { enabled } => {
+                self.flag_preview = enabled;
+                self.invalidate_render_caches();
+            }
+            AppEvent::SetPreviewFlag { flag, active } => {
+                if active {
+                    self.preview_flags.insert(flag);
+                } else {
+                    self.preview_flags.remove(&flag);
+                }
+                if self.flag_preview {
+                    self.invalidate_render_caches();
+                }
+            }
+            AppEvent::EndTweakerSession => {
+                self.tweaker_phase = EventPhase::new();
+            }
             AppEvent::SetClipboard { contents } => {
                 cx.set_clipboard(contents)
                     .unwrap_or_else(|e| log::error!("Failed to copy: {}", e));
@@ -85,19 +116,36 @@ impl AppState {
                     }
                 }
                 if !found {
-                    if let Some(module) = self.modules.get(&path.module) {
-                        if let Some(module_root) = module.filesystem_root.clone() {
-                            let path = path.clone();
-                            cx.spawn(move |cx| {
-                                if let Some(map_struct) = load_map(&module_root, &path.sid) {
-                                    cx.emit(AppEvent::LoadMap {
-                                        path: path.clone(),
-                                        map: RefCell::new(Some(Box::new(map_struct))),
-                                    })
-                                    .unwrap();
-                                }
+                    let module_root = self
+                        .modules
+                        .get(&path.module)
+                        .and_then(|module| module.filesystem_root.clone());
+                    if let Some(module_root) = module_root {
+                        let path = path.clone();
+                        let big_map_tiles = self.config.big_map_tiles;
+                        cx.spawn(move |cx| {
+                            let map_struct = load_map(&module_root, &path.sid);
+                            if map_struct
+                                .as_ref()
+                                .map_or(false, |m| map_tile_count(m) > big_map_tiles)
+                            {
+                                cx.emit(AppEvent::Progress {
+                                    progress: Progress {
+                                        progress: 0,
+                                        status: BIG_MAP_NOTICE.to_owned(),
+                                    },
+                                })
+                                .unwrap();
+                            }
+                            cx.emit(AppEvent::LoadMap {
+                                path: path.clone(),
+                                map: RefCell::new(map_struct.map(Box::new)),
                             })
-                        }
+                            .unwrap();
+                        })
+                    } else {
+                        log::warn!("Could not open {}: its mod is not on disk", path.sid);
+                        self.drop_pending_reveal(&path);
                     }
                 }
             }
@@ -114,26 +162,75 @@ impl AppState {
                             id,
                             current_room: 0,
                             current_selected: HashSet::new(),
-                            styleground_selected: None,
+                            styleground_selected: HashSet::new(),
                             transform: MapToScreen::identity(),
                             preview_pos: MapPointStrict::zero(),
+                            locked: HashMap::new(),
+                            map_selected: HashMap::new(),
                         }));
                         cx.emit(AppEvent::SelectTab {
                             idx: self.tabs.len() - 1,
                         });
                     }
 
+                    let big = map_tile_count(&map) > self.config.big_map_tiles;
                     let palette = ModuleAggregate::new(
                         &self.modules,
                         &self.modules_lookup,
                         &map.meta,
                         path.module,
+                        &self.config.language,
                         true,
                     );
 
-                    self.loaded_maps
-                        .insert(id, MapState::new(*map, path.clone(), palette));
+                    let mut notes = MapNotes::default();
+                    if let Some(module) = self.modules.get(&path.module) {
+                        self.config.borrow_mut().push_recent_map(RecentMap {
+                            module: module.everest_metadata.name.clone(),
+                            sid: path.sid.clone(),
+                        });
+                        if let Some(root) = notes_root() {
+                            notes = load_notes(&root, &module.everest_metadata.name, &path.sid);
+                        }
+                    }
+
+                    let mut state = MapState::new(*map, path.clone(), palette);
+                    state.cache.big = big;
+                    state.data.notes = notes;
+                    self.loaded_maps.insert(id, state);
+                    if matches!(&self.pending_reveal, Some((pending, ..)) if *pending == path) {
+                        let (_, room, selection) = self.pending_reveal.take().unwrap();
+                        cx.emit(AppEvent::MapEvent {
+                            map: Some(id),
+                            event: MapEvent::Reveal { room, selection },
+                        });
+                    }
                     self.loaded_maps_lookup.insert(path, id);
+                    if big {
+                        cx.emit(AppEvent::Progress {
+                            progress: Progress {
+                                progress: 100,
+                                status: "".to_owned(),
+                            },
+                        });
+                    }
+                } else {
+                    self.drop_pending_reveal(&path);
+                }
+            }
+            AppEvent::RevealInMap {
+                path,
+                room,
+                selection,
+            } => {
+                if let Some(map) = self.loaded_maps_lookup.get(&path).copied() {
+                    self.apply_map_event(cx, Some(map), MapEvent::Reveal { room, selection });
+                } else if self.modules.contains_key(&path.module) {
+                    self.pending_reveal = Some((path.clone(), room, selection));
+                    cx.emit(AppEvent::OpenMap { path });
+                } else {
+                    log::warn!("Could not open {}: its mod is no longer loaded", path.sid);
+                    self.pending_reveal = None;
                 }
             }
             AppEvent::EditSettings { setter } => {
@@ -144,9 +241,25 @@ impl AppState {
                 }
                 setter.apply(&mut self.config.borrow_mut());
             }
+            AppEvent::WindowResized { size } => {
+                self.config.borrow_mut_unsaved().window_size = Some(size);
+            }
+            AppEvent::WindowMoved { position } => {
+                self.config.borrow_mut_unsaved().window_position = Some(position);
+            }
+            AppEvent::WindowClosing => {
+                self.config.save();
+            }
+            AppEvent::SetLanguage { language } => {
+                self.config.borrow_mut().language = language;
+                self.rebuild_modules_bookkeeping();
+            }
             AppEvent::SetModules { modules } => {
                 self.modules = modules;
                 self.rebuild_modules_bookkeeping();
+                if self.config.reopen_last_on_startup && self.loaded_maps.is_empty() {
+                    self.reopen_last_map(cx);
+                }
             }
             AppEvent::UpdateModules { modules } => {
                 for (id, module) in modules.into_iter() {
@@ -197,12 +310,58 @@ impl AppState {
                     break;
                 }
             }
+            AppEvent::OpenZip { path } => {
+                let existing = self
+                    .modules
+                    .iter()
+                    .find(|(_, module)| module.filesystem_root.as_ref() == Some(&path));
+                if let Some((id, _)) = existing {
+                    cx.emit(AppEvent::OpenModuleOverviewTab { module: *id });
+                    return;
+                }
+                self.sugar_mod = Some(path.clone());
+                cx.spawn(move |cx| {
+                    let Some(mut source) = ZipSource::new(&path).map(ConfigSource::from) else {
+                        log::error!("Could not open {} as a zip", path.display());
+                        return;
+                    };
+                    let mut modules = HashMap::new();
+                    match load_into(&mut source, &mut modules, &mut HashMap::new()) {
+                        Ok(id) => {
+                            let maps = modules[&id].maps.clone();
+                            cx.emit(AppEvent::UpdateModules {
+                                modules: modules
+                                    .into_iter()
+                                    .map(|(id, module)| (id, Some(module)))
+                                    .collect(),
+                            })
+                            .unwrap();
+                            // with only one map there's nothing to choose between
+                            if let [sid] = maps.as_slice() {
+                                cx.emit(AppEvent::OpenMap {
+                                    path: MapPath {
+                                        module: id,
+                                        sid: sid.clone(),
+                                    },
+                                })
+                                .unwrap();
+                            }
+                        }
+                        Err((_, e)) => {
+                            log::error!("Failed parsing everest.yaml for {}: {e}", path.display())
+                        }
+                    }
+                });
+            }
             AppEvent::SelectTool { spec } => {
                 if let Some(mut tool) = self.current_tool.borrow_mut().take() {
                     for event in tool.switch_off(self, cx) {
                         cx.emit(event);
                     }
                 }
+                if spec != self.current_toolspec {
+                    self.previous_toolspec = self.current_toolspec;
+                }
                 self.current_toolspec = spec;
                 *self.current_tool.borrow_mut() = Some(spec.switch_on(self));
             }
@@ -249,11 +408,7 @@ impl AppState {
                 self.objtiles_transform = self.objtiles_transform.pre_translate(delta);
             }
             AppEvent::ZoomObjectTiles { delta, focus } => {
-                self.objtiles_transform = self
-                    .objtiles_transform
-                    .pre_translate(focus.to_vector())
-                    .pre_scale(delta, delta)
-                    .pre_translate(-focus.to_vector());
+                self.objtiles_transform = zoom_transform(&self.objtiles_transform, delta, focus);
             }
 
             // tab events
@@ -265,9 +420,20 @@ impl AppState {
                         }
                     }
                     self.current_tab = idx;
-                    if let Some(AppTab::Map(_)) = self.tabs.get(idx) {
+                    if let Some(AppTab::Map(map_tab)) = self.tabs.get(idx) {
                         *self.current_tool.borrow_mut() =
                             Some(self.current_toolspec.switch_on(self));
+                        // reopen whichever map was looked at last, not just the last one opened
+                        let recent = self.loaded_maps.get(&map_tab.id).and_then(|map| {
+                            let module = self.modules.get(&map.cache.path.module)?;
+                            Some(RecentMap {
+                                module: module.everest_metadata.name.clone(),
+                                sid: map.cache.path.sid.clone(),
+                            })
+                        });
+                        if let Some(recent) = recent {
+                            self.config.borrow_mut_unsaved().push_recent_map(recent);
+                        }
                     }
                 }
             }
@@ -275,35 +441,44 @@ impl AppState {
                 self.poison_tab = idx;
                 self.garbage_collect();
             }
-            AppEvent::Pan { tab, delta } => {
-                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
-                    map_tab.transform = map_tab.transform.pre_translate(delta);
-                }
-            }
-            AppEvent::Zoom { tab, delta, focus } => {
-                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
-                    // TODO scale stepping, high and low limits
-                    map_tab.transform = map_tab
-                        .transform
-                        .pre_translate(focus.to_vector())
-                        .pre_scale(delta, delta)
-                        .pre_translate(-focus.to_vector());
-                }
-            }
-            AppEvent::MovePreview { tab, pos } => {
-                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
-                    map_tab.preview_pos = pos;
-                }
-            }
-            AppEvent::SelectRoom { tab, idx } => {
-                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
-                    map_tab.current_room = idx;
-                    if let Some(room) = self.current_room_ref() {
-                        room.cache.borrow_mut().render_cache_valid = false;
-                    }
-                }
+            AppEvent::Pan { .. }
+            | AppEvent::Zoom { .. }
+            | AppEvent::MovePreview { .. }
+            | AppEvent::SelectRoom { .. } => self.apply_view(event),
+            AppEvent::ExportRoomImage { tab, room, path } => {
+                let Some(AppTab::Map(map_tab)) = self.tabs.get(tab) else { return };
+                let Some(map) = self.loaded_maps.get(&map_tab.id) else { return };
+                let Some(level) = map.data.levels.get(room) else {
+                    log::error!("Internal error: export room image: no such room");
+                    return;
+                };
+                let mut cache = level.cache.borrow_mut();
+                cache.export_image_requested = Some(path);
+                cx.needs_redraw();
+            }
+            AppEvent::CopySelectionImage { tab } => {
+                let Some(AppTab::Map(map_tab)) = self.tabs.get(tab) else { return };
+                let Some(map) = self.loaded_maps.get(&map_tab.id) else { return };
+                let Some(level) = map.data.levels.get(map_tab.current_room) else { return };
+                if map_tab.current_selected.is_empty() {
+                    log::warn!("Nothing is selected to copy");
+                    return;
+                }
+                level.cache.borrow_mut().copy_selection_requested =
+                    Some(map_tab.current_selected.clone());
+                cx.needs_redraw();
+            }
+            AppEvent::SaveMapAs { path } => {
+                self.apply(
+                    cx,
+                    AppEvent::MapEvent {
+                        map: None,
+                        event: MapEvent::Export { path },
+                    },
+                );
             }
             AppEvent::SelectObjects { tab, selection } => {
+                self.tweaker_phase = EventPhase::new();
                 if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
                     for x in selection.into_iter() {
                         map_tab.current_selected.insert(x);
@@ -314,6 +489,7 @@ impl AppState {
                 }
             }
             AppEvent::DeselectObjects { tab, selection } => {
+                self.tweaker_phase = EventPhase::new();
                 if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
                     for x in selection.into_iter() {
                         map_tab.current_selected.remove(&x);
@@ -323,9 +499,29 @@ impl AppState {
                     }
                 }
             }
+            AppEvent::ToggleLock { tab, selection } => {
+                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
+                    map_tab.toggle_lock(&selection);
+                    cx.needs_redraw();
+                }
+            }
+            AppEvent::SelectAcrossMap { tab, mut selection } => {
+                self.tweaker_phase = EventPhase::new();
+                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
+                    map_tab.current_selected =
+                        selection.remove(&map_tab.current_room).unwrap_or_default();
+                    selection.retain(|_, selected| !selected.is_empty());
+                    map_tab.map_selected = selection;
+                    if let Some(room) = self.current_room_ref() {
+                        room.cache.borrow_mut().render_cache_valid = false;
+                    }
+                }
+            }
             AppEvent::ClearSelection { tab } => {
+                self.tweaker_phase = EventPhase::new();
                 if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
                     map_tab.current_selected.clear();
+                    map_tab.map_selected.clear();
                     if let Some(room) = self.current_room_ref() {
                         room.cache.borrow_mut().render_cache_valid = false;
                     }
@@ -333,7 +529,12 @@ impl AppState {
             }
             AppEvent::SelectStyleground { tab, styleground } => {
                 if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
-                    map_tab.styleground_selected = styleground;
+                    map_tab.styleground_selected = styleground.into_iter().collect();
+                }
+            }
+            AppEvent::SelectStylegrounds { tab, stylegrounds } => {
+                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
+                    map_tab.styleground_selected = stylegrounds;
                 }
             }
             AppEvent::SelectSearchScope { tab, scope } => {
@@ -397,14 +598,21 @@ impl AppState {
                     ctab.editing_config = Some(*config)
                 }
             }
+            AppEvent::SaveConfigToModule { tab } => {
+                if let Some(AppTab::ConfigEditor(ctab)) = self.tabs.get(tab) {
+                    self.save_config_to_module(ctab);
+                }
+            }
             AppEvent::SetConfigErrorMessage { tab, message } => {
                 if let Some(AppTab::ConfigEditor(ctab)) = self.tabs.get_mut(tab) {
                     ctab.error_message = message;
                 }
             }
             AppEvent::MapEvent { map, event } => {
-                let mut needs_tool_cycle =
-                    matches!(event, MapEvent::Undo | MapEvent::Redo | MapEvent::Save);
+                let mut needs_tool_cycle = matches!(
+                    event,
+                    MapEvent::Undo | MapEvent::Redo | MapEvent::Save | MapEvent::Export { .. }
+                );
                 if needs_tool_cycle {
                     let tool = self.current_tool.borrow_mut().take();
                     if let Some(mut tool) = tool {
@@ -436,4 +644,119 @@ impl AppState {
             }
         }
     }
+
+    /// Apply the events which only change how a map tab looks at its map. They never touch the
+    /// map itself, so they need no context.
+    pub(crate) fn apply_view(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::Pan { tab, delta } => {
+                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
+                    map_tab.transform = map_tab.transform.pre_translate(delta);
+                }
+            }
+            AppEvent::Zoom { tab, delta, focus } => {
+                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
+                    // TODO scale stepping
+                    map_tab.transform = zoom_transform(&map_tab.transform, delta, focus);
+                }
+            }
+            AppEvent::MovePreview { tab, pos } => {
+                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
+                    map_tab.preview_pos = pos;
+                }
+            }
+            AppEvent::SelectRoom { tab, idx } => {
+                if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(tab) {
+                    map_tab.current_room = idx;
+                    map_tab.map_selected.clear();
+                    if let Some(room) = self.current_room_ref() {
+                        room.cache.borrow_mut().render_cache_valid = false;
+                    }
+                }
+            }
+            _ => log::error!("Internal error: apply_view got a non-view event"),
+        }
+    }
+
+    fn save_config_to_module(&self, ctab: &ConfigEditorTab) {
+        let Some(config) = &ctab.editing_config else { return };
+        let module = match &ctab.search_scope {
+            SearchScope::Mod(module) => *module,
+            SearchScope::Map(map) => map.module,
+            _ => {
+                log::error!("Scope the search to a single mod to save configs into it");
+                return;
+            }
+        };
+        let root = self
+            .modules
+            .get(&module)
+            .and_then(|m| m.filesystem_root.as_ref());
+        let Some(root) = root else {
+            log::error!("This mod is not on disk");
+            return;
+        };
+        let (path, text) = match config {
+            AnyConfig::Entity(e) => (
+                format!("Arborio/entities/{}.yaml", e.entity_name),
+                e.to_string(),
+            ),
+            AnyConfig::Trigger(t) => (
+                format!("Arborio/triggers/{}.yaml", t.trigger_name),
+                t.to_string(),
+            ),
+            AnyConfig::Styleground(s) => (
+                format!("Arborio/stylegrounds/{}.yaml", s.styleground_name),
+                s.to_string(),
+            ),
+        };
+
+        let mut source = open_module(root);
+        let Some(dest) = source.as_mut().and_then(ConfigSource::as_mut_source) else {
+            log::error!(
+                "{} is not a folder and can't be edited in place",
+                root.display()
+            );
+            return;
+        };
+        match dest.put_file(Path::new(&path), text.as_bytes()) {
+            Ok(()) => log::info!("Saved {} into {}", path, root.display()),
+            Err(e) => log::error!("Could not save {}: {}", path, e),
+        }
+    }
+
+    /// Forget the objects waiting to be revealed once `path` opens, since it won't.
+    fn drop_pending_reveal(&mut self, path: &MapPath) {
+        if matches!(&self.pending_reveal, Some((pending, ..)) if pending == path) {
+            log::warn!(
+                "Could not show the usage in {}: the map did not open",
+                path.sid
+            );
+            self.pending_reveal = None;
+        }
+    }
+
+    /// Open the most recent map from the recents list, if it's still around.
+    fn reopen_last_map(&self, cx: &mut EventContext) {
+        let Some(recent) = self.config.recent_maps.first() else { return };
+        let found = self.modules_lookup.get(&recent.module).filter(|id| {
+            self.modules
+                .get(id)
+                .map_or(false, |module| module.maps.contains(&recent.sid))
+        });
+        if let Some(module) = found {
+            cx.emit(AppEvent::OpenMap {
+                path: MapPath {
+                    module: *module,
+                    sid: recent.sid.clone(),
+                },
+            });
+        } else {
+            log::warn!(
+                "Could not reopen {} from {}: it no longer exists",
+                recent.sid,
+                recent.module
+            );
+        }
+    }
 }
@@ -0,0 +1,134 @@
+use arborio_maploader::map_struct::{save_as, CelesteMap};
+use arborio_utils::vizia::prelude::*;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::data::action::apply_map_action;
+use crate::data::app::AppEvent;
+use crate::data::fixup::NodeRules;
+use crate::data::project_map::MapStateData;
+use crate::data::{load_map, Progress};
+
+/// An operation which can be run over every map in a project at once.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BatchOp {
+    /// Load and save each map unchanged, normalizing the file's layout.
+    Resave,
+    /// Apply the non-destructive part of Fix Map to each map and save the ones which changed.
+    Fix,
+    /// Report the problems Fix Map would find, without saving anything.
+    Validate,
+}
+
+impl BatchOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BatchOp::Resave => "Re-save",
+            BatchOp::Fix => "Fix",
+            BatchOp::Validate => "Validate",
+        }
+    }
+
+    pub fn saves(&self) -> bool {
+        !matches!(self, BatchOp::Validate)
+    }
+}
+
+/// Everything a batch needs, gathered on the main thread so it can run on another one.
+pub struct Batch {
+    pub op: BatchOp,
+    pub module_root: PathBuf,
+    pub sids: Vec<String>,
+    pub rules: NodeRules,
+    pub far_node_distance: i32,
+    pub cancel: Arc<AtomicBool>,
+    /// Cleared once the batch is finished, letting another one start.
+    pub running: Arc<AtomicBool>,
+}
+
+impl Batch {
+    /// Run the batch, reporting progress as it goes and each map's result to the log.
+    pub fn run(self, cx: &mut ContextProxy) {
+        let total = self.sids.len();
+        let mut failed = 0;
+        for (idx, sid) in self.sids.iter().enumerate() {
+            if self.cancel.load(Ordering::Relaxed) {
+                log::warn!(
+                    "{} all maps: cancelled after {} of {}",
+                    self.op.name(),
+                    idx,
+                    total
+                );
+                break;
+            }
+            cx.emit(AppEvent::Progress {
+                progress: Progress {
+                    progress: (idx * 100 / total) as i32,
+                    status: format!("{} all maps: {}", self.op.name(), sid),
+                },
+            })
+            .unwrap();
+            match self.run_one(sid) {
+                Ok(message) => log::info!("{} {}: {}", self.op.name(), sid, message),
+                Err(message) => {
+                    failed += 1;
+                    log::error!("{} {}: {}", self.op.name(), sid, message);
+                }
+            }
+        }
+        if failed != 0 {
+            log::error!(
+                "{} all maps: {} of {} failed",
+                self.op.name(),
+                failed,
+                total
+            );
+        }
+        self.running.store(false, Ordering::Relaxed);
+        cx.emit(AppEvent::Progress {
+            progress: Progress {
+                progress: 100,
+                status: "".to_owned(),
+            },
+        })
+        .unwrap();
+    }
+
+    fn run_one(&self, sid: &str) -> Result<String, String> {
+        let Some(map) = load_map(&self.module_root, sid) else { return Err("failed to load".to_owned()) };
+        let mut data: MapStateData = map.into();
+        let message = match self.op {
+            BatchOp::Resave => "saved".to_owned(),
+            BatchOp::Fix => {
+                let actions = data.fix_map_actions(&self.rules, false, None);
+                if actions.is_empty() {
+                    return Ok("nothing to fix".to_owned());
+                }
+                let count = actions.len();
+                apply_map_action(&mut data, actions, None)?;
+                format!("applied {count} fixes")
+            }
+            BatchOp::Validate => {
+                let outside = data.count_outside_entities();
+                let far = data.count_far_nodes(self.far_node_distance);
+                let fixes = data.fix_map_actions(&self.rules, false, None).len();
+                return if outside + far + fixes == 0 {
+                    Ok("no problems".to_owned())
+                } else {
+                    Err(format!(
+                        "{fixes} fixable problems, {outside} entities outside of their rooms, {far} far-flung nodes"
+                    ))
+                };
+            }
+        };
+        let map: CelesteMap = data.into();
+        let path = self
+            .module_root
+            .join("Maps")
+            .join(sid)
+            .with_extension("bin");
+        save_as(&map, sid, &path).map_err(|e| format!("failed to save: {e}"))?;
+        Ok(message)
+    }
+}
@@ -1,15 +1,17 @@
 use crate::data::action::StylegroundSelection;
+use crate::data::selection::AppSelection;
 use arborio_maploader::map_struct::{Attribute, CelesteMapEntity, CelesteMapStyleground};
 use arborio_modloader::aggregate::ModuleAggregate;
 use arborio_modloader::config::{EntityConfig, StylegroundConfig, TriggerConfig};
 use arborio_modloader::module::{MapPath, ModuleID};
 use arborio_utils::interned::Interned;
 use arborio_utils::vizia::prelude::*;
+use std::collections::HashSet;
 use std::fmt::Formatter;
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
-use crate::data::app::AppState;
+use crate::data::app::{AppEvent, AppState};
 
 #[derive(Debug, PartialEq, Eq, Clone, Data)]
 pub enum SearchScope {
@@ -53,11 +55,16 @@ pub enum ConfigSearchType {
     Entities,
     Triggers,
     Stylegrounds,
+    /// Attributes on placed entities and triggers which their config doesn't know about.
+    UnknownAttributes,
 }
 
 impl std::fmt::Display for ConfigSearchType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+        match self {
+            ConfigSearchType::UnknownAttributes => write!(f, "Unknown attributes"),
+            _ => write!(f, "{self:?}"),
+        }
     }
 }
 
@@ -87,6 +94,7 @@ pub enum ConfigSearchResult {
     Entity(EntityConfigSearchResult),
     Trigger(TriggerConfigSearchResult),
     Styleground(StylegroundConfigSearchResult),
+    UnknownAttribute(UnknownAttributeSearchResult),
 }
 
 #[derive(Debug, Clone, Lens)]
@@ -102,6 +110,7 @@ impl ConfigSearchResult {
             ConfigSearchResult::Entity(e) => &e.name,
             ConfigSearchResult::Trigger(t) => &t.name,
             ConfigSearchResult::Styleground(s) => &s.name,
+            ConfigSearchResult::UnknownAttribute(u) => &u.name,
         }
     }
 
@@ -110,6 +119,7 @@ impl ConfigSearchResult {
             ConfigSearchResult::Entity(e) => e.examples.lock().len(),
             ConfigSearchResult::Trigger(t) => t.examples.lock().len(),
             ConfigSearchResult::Styleground(s) => s.examples.lock().len(),
+            ConfigSearchResult::UnknownAttribute(_) => 1,
         }
     }
 
@@ -147,7 +157,49 @@ impl ConfigSearchResult {
     }
 
     pub fn display_list(&self) -> String {
-        format!("{} ({})", self.name(), self.examples_len())
+        match self {
+            ConfigSearchResult::UnknownAttribute(u) => {
+                format!("{} {}: {}.{}", u.map.sid, u.room_name, u.name, u.key)
+            }
+            _ => format!("{} ({})", self.name(), self.examples_len()),
+        }
+    }
+
+    /// The event which shows where this result is placed, opening its map if needed. Results
+    /// covering many placements show the first one. Stylegrounds don't belong to a room, so they
+    /// have nowhere to be shown.
+    pub fn usage(&self) -> Option<AppEvent> {
+        let (path, room, selected) = match self {
+            ConfigSearchResult::Entity(e) => {
+                let examples = e.examples.lock();
+                let (entity, path, room) = examples.first()?;
+                (
+                    path.clone(),
+                    *room,
+                    AppSelection::EntityBody(entity.id, false),
+                )
+            }
+            ConfigSearchResult::Trigger(t) => {
+                let examples = t.examples.lock();
+                let (trigger, path, room) = examples.first()?;
+                (
+                    path.clone(),
+                    *room,
+                    AppSelection::EntityBody(trigger.id, true),
+                )
+            }
+            ConfigSearchResult::Styleground(_) => return None,
+            ConfigSearchResult::UnknownAttribute(u) => (
+                u.map.clone(),
+                u.room,
+                AppSelection::EntityBody(u.id, u.trigger),
+            ),
+        };
+        Some(AppEvent::RevealInMap {
+            path,
+            room,
+            selection: HashSet::from([selected]),
+        })
     }
 
     pub fn get_config(&self, palette: &ModuleAggregate) -> AnyConfig {
@@ -173,6 +225,20 @@ impl ConfigSearchResult {
                     .map(|a| a.as_ref().clone())
                     .unwrap_or_else(|| StylegroundConfig::new(&e.name)),
             ),
+            ConfigSearchResult::UnknownAttribute(u) if u.trigger => AnyConfig::Trigger(
+                palette
+                    .trigger_config
+                    .get(&u.name)
+                    .map(|a| a.as_ref().clone())
+                    .unwrap_or_else(|| TriggerConfig::new(&u.name)),
+            ),
+            ConfigSearchResult::UnknownAttribute(u) => AnyConfig::Entity(
+                palette
+                    .entity_config
+                    .get(&u.name)
+                    .map(|a| a.as_ref().clone())
+                    .unwrap_or_else(|| EntityConfig::new(&u.name)),
+            ),
         }
     }
 }
@@ -196,6 +262,18 @@ pub struct StylegroundConfigSearchResult {
         Arc<parking_lot::Mutex<Vec<(CelesteMapStyleground, MapPath, StylegroundSelection)>>>,
 }
 
+/// One attribute on one placed entity or trigger.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UnknownAttributeSearchResult {
+    pub name: Interned,
+    pub key: String,
+    pub trigger: bool,
+    pub id: i32,
+    pub map: MapPath,
+    pub room: usize,
+    pub room_name: String,
+}
+
 impl EntityConfigSearchResult {
     pub fn new(name: Interned) -> Self {
         Self {
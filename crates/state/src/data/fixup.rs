@@ -0,0 +1,789 @@
+use arborio_maploader::map_struct::{Attribute, CelesteMapEntity, CelesteMapLevel, Node};
+use arborio_modloader::aggregate::ModuleAggregate;
+use arborio_modloader::config::{EntityConfig, TriggerConfig};
+use arborio_utils::interned::InternedMap;
+use arborio_utils::units::*;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::data::action::{MapAction, RoomAction};
+use crate::data::project_map::MapStateData;
+use crate::data::selection::AppSelection;
+
+/// Which entities and triggers may have nodes, taken from the palette's configs. Kept apart from
+/// the palette so that fixes can be computed off of the main thread.
+#[derive(Clone)]
+pub struct NodeRules {
+    entity_config: InternedMap<Arc<EntityConfig>>,
+    trigger_config: InternedMap<Arc<TriggerConfig>>,
+}
+
+impl NodeRules {
+    pub fn new(palette: &ModuleAggregate) -> Self {
+        Self {
+            entity_config: palette.entity_config.clone(),
+            trigger_config: palette.trigger_config.clone(),
+        }
+    }
+
+    /// The most nodes the entity's config allows. Entities we have no config for get the benefit
+    /// of the doubt. Triggers share the limit of the `trigger` entity config.
+    fn max_nodes(&self, name: &str, trigger: bool) -> usize {
+        let (takes_nodes, limit) = if trigger {
+            (
+                self.trigger_config.get(name).map_or(true, |c| c.nodes),
+                self.entity_config.get("trigger"),
+            )
+        } else {
+            let config = self.entity_config.get(name);
+            (config.map_or(true, |c| c.nodes), config)
+        };
+        if takes_nodes {
+            limit.map_or(usize::MAX, |c| c.node_range().1)
+        } else {
+            0
+        }
+    }
+}
+
+impl MapStateData {
+    /// Count the entities and triggers which lie entirely outside of their room.
+    pub fn count_outside_entities(&self) -> usize {
+        self.levels
+            .iter()
+            .map(|room| {
+                room.data
+                    .entities
+                    .iter()
+                    .chain(room.data.triggers.iter())
+                    .filter(|e| entity_outside(&room.data, e))
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Count the entity and trigger nodes which lie more than `distance` pixels outside of their
+    /// room.
+    pub fn count_far_nodes(&self, distance: i32) -> usize {
+        self.levels
+            .iter()
+            .map(|room| {
+                room.data
+                    .entities
+                    .iter()
+                    .chain(room.data.triggers.iter())
+                    .flat_map(|e| e.nodes.iter())
+                    .filter(|node| clamp_node(&room.data, node, distance) != **node)
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Find the entities, triggers, and decals whose position lies outside of their room, as the
+    /// index of the room and the selection which picks each one out. Unlike
+    /// [`Self::count_outside_entities`], sizes and nodes aren't considered.
+    pub fn find_outside_objects(&self) -> Vec<(usize, AppSelection)> {
+        let mut found = vec![];
+        for (idx, room) in self.levels.iter().enumerate() {
+            let room = &room.data;
+            for (entities, trigger) in [(&room.entities, false), (&room.triggers, true)] {
+                found.extend(
+                    entities
+                        .iter()
+                        .filter(|e| position_outside(room, e.x, e.y))
+                        .map(|e| (idx, AppSelection::EntityBody(e.id, trigger))),
+                );
+            }
+            for (decals, fg) in [(&room.fg_decals, true), (&room.bg_decals, false)] {
+                found.extend(
+                    decals
+                        .iter()
+                        .filter(|d| position_outside(room, d.x, d.y))
+                        .map(|d| (idx, AppSelection::Decal(d.id, fg))),
+                );
+            }
+        }
+        found
+    }
+
+    /// Compute a batch of actions which normalizes the problems commonly found in maps imported
+    /// from elsewhere. Every change is logged. Entities outside of their rooms are only removed if
+    /// `drop_outside` is set, and far-flung nodes are only clamped if `clamp_nodes` gives a
+    /// distance, since those are destructive.
+    pub fn fix_map_actions(
+        &self,
+        rules: &NodeRules,
+        drop_outside: bool,
+        clamp_nodes: Option<i32>,
+    ) -> Vec<MapAction> {
+        let mut result = vec![];
+        for (idx, room) in self.levels.iter().enumerate() {
+            let data = &room.data;
+            let size = data.bounds.size;
+            let trimmed = trimmed_size(data);
+            if trimmed != size {
+                log::info!(
+                    "Fix map: {}: resized from {}x{} to {}x{} to trim empty rows and columns",
+                    data.name,
+                    size.width,
+                    size.height,
+                    trimmed.width,
+                    trimmed.height
+                );
+                result.push(MapAction::RoomAction {
+                    idx,
+                    event: RoomAction::MoveRoom {
+                        bounds: MapRectStrict::new(data.bounds.origin, trimmed),
+                    },
+                });
+            }
+
+            // ids are shared between entities and triggers, see CelesteMapLevel::next_id
+            let mut seen_ids = HashSet::new();
+            let mut next_id = data.next_id();
+            for trigger in [false, true] {
+                let list = if trigger {
+                    &data.triggers
+                } else {
+                    &data.entities
+                };
+                let mut changed = false;
+                let mut fixed = Vec::with_capacity(list.len());
+                for entity in list {
+                    if drop_outside && entity_outside(data, entity) {
+                        log::info!(
+                            "Fix map: {}: removed {} #{} which was outside the room",
+                            data.name,
+                            entity.name,
+                            entity.id
+                        );
+                        changed = true;
+                        continue;
+                    }
+                    let mut entity = entity.clone();
+                    if !seen_ids.insert(entity.id) {
+                        log::info!(
+                            "Fix map: {}: reassigned duplicate id of {} #{} to #{}",
+                            data.name,
+                            entity.name,
+                            entity.id,
+                            next_id
+                        );
+                        entity.id = next_id;
+                        seen_ids.insert(next_id);
+                        next_id += 1;
+                        changed = true;
+                    }
+                    let max_nodes = rules.max_nodes(&entity.name, trigger);
+                    if entity.nodes.len() > max_nodes {
+                        log::info!(
+                            "Fix map: {}: removed {} orphan nodes from {} #{}",
+                            data.name,
+                            entity.nodes.len() - max_nodes,
+                            entity.name,
+                            entity.id
+                        );
+                        entity.nodes.truncate(max_nodes);
+                        changed = true;
+                    }
+                    if let Some(distance) = clamp_nodes {
+                        for node in entity.nodes.iter_mut() {
+                            let clamped = clamp_node(data, node, distance);
+                            if clamped != *node {
+                                log::info!(
+                                    "Fix map: {}: moved node of {} #{} from ({}, {}) to ({}, {})",
+                                    data.name,
+                                    entity.name,
+                                    entity.id,
+                                    node.x,
+                                    node.y,
+                                    clamped.x,
+                                    clamped.y
+                                );
+                                *node = clamped;
+                                changed = true;
+                            }
+                        }
+                    }
+                    fixed.push(entity);
+                }
+                if changed {
+                    result.push(MapAction::RoomAction {
+                        idx,
+                        event: RoomAction::ReplaceEntities {
+                            trigger,
+                            entities: fixed,
+                        },
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    /// Compute a batch of actions which sets `attribute` to `replace` on every entity and trigger
+    /// named `name` whose `attribute` currently equals `find`. The values are given as text and
+    /// compared against, or parsed into, the type the attribute already has.
+    pub fn replace_attribute_actions(
+        &self,
+        name: &str,
+        attribute: &str,
+        find: &str,
+        replace: &str,
+    ) -> Vec<MapAction> {
+        let mut result = vec![];
+        for (idx, room) in self.levels.iter().enumerate() {
+            let entities = room.data.entities.iter().map(|e| (e, false));
+            let triggers = room.data.triggers.iter().map(|e| (e, true));
+            for (entity, trigger) in entities.chain(triggers) {
+                if entity.name != name {
+                    continue;
+                }
+                let Some(value) = entity.attributes.get(attribute) else { continue };
+                if !attribute_equals(value, find) {
+                    continue;
+                }
+                let mut entity = entity.clone();
+                let replacement = parse_attribute_like(value, replace);
+                entity.attributes.insert(attribute.to_owned(), replacement);
+                result.push(MapAction::RoomAction {
+                    idx,
+                    event: RoomAction::EntityUpdate {
+                        entity: Box::new(entity),
+                        trigger,
+                    },
+                });
+            }
+        }
+        result
+    }
+}
+
+/// Whether an attribute's value is the one written as `text`.
+fn attribute_equals(attr: &Attribute, text: &str) -> bool {
+    match attr {
+        Attribute::Bool(b) => text.to_lowercase().parse() == Ok(*b),
+        Attribute::Int(i) => text.trim().parse() == Ok(*i),
+        Attribute::Float(f) => text.trim().parse() == Ok(*f),
+        Attribute::Text(s) => s == text,
+    }
+}
+
+/// Parse `text` as the same type of attribute as `like`, or as text if it doesn't fit that type.
+fn parse_attribute_like(like: &Attribute, text: &str) -> Attribute {
+    match like {
+        Attribute::Bool(_) => text.to_lowercase().parse().ok().map(Attribute::Bool),
+        Attribute::Int(_) => text.trim().parse().ok().map(Attribute::Int),
+        Attribute::Float(_) => text.trim().parse().ok().map(Attribute::Float),
+        Attribute::Text(_) => None,
+    }
+    .unwrap_or_else(|| Attribute::Text(text.to_owned()))
+}
+
+/// The size of the room with the empty tile rows and columns past its last content cropped off,
+/// on the tile grid. Entities, triggers, and decals count as content, so nothing is left outside.
+/// Rooms with no content at all are left at their size, rounded to the tile grid.
+fn trimmed_size(room: &CelesteMapLevel) -> MapSizeStrict {
+    let size = room.bounds.size;
+    let aligned = MapSizeStrict::new((size.width + 7) / 8 * 8, (size.height + 7) / 8 * 8);
+    let Some(content) = content_bounds(room) else { return aligned };
+    let end = content.max();
+    MapSizeStrict::new(
+        end.x.max(8).min(aligned.width),
+        end.y.max(8).min(aligned.height),
+    )
+}
+
+/// Pull a node back to within `distance` pixels of the room's bounds.
+fn clamp_node(room: &CelesteMapLevel, node: &Node, distance: i32) -> Node {
+    let size = room.bounds.size;
+    Node {
+        x: node.x.clamp(-distance, size.width + distance),
+        y: node.y.clamp(-distance, size.height + distance),
+    }
+}
+
+/// Whether a rect, relative to the room, lies entirely outside of it. Touching the room's edge
+/// still counts as inside.
+fn rect_outside(room: &CelesteMapLevel, x: i32, y: i32, width: i32, height: i32) -> bool {
+    let size = room.bounds.size;
+    x + width < 0 || y + height < 0 || x > size.width || y > size.height
+}
+
+fn entity_outside(room: &CelesteMapLevel, entity: &CelesteMapEntity) -> bool {
+    rect_outside(
+        room,
+        entity.x,
+        entity.y,
+        entity.width as i32,
+        entity.height as i32,
+    ) && entity
+        .nodes
+        .iter()
+        .all(|node| position_outside(room, node.x, node.y))
+}
+
+fn position_outside(room: &CelesteMapLevel, x: i32, y: i32) -> bool {
+    rect_outside(room, x, y, 0, 0)
+}
+
+/// The size of a screen of gameplay, rounded up to whole tiles. Rooms are usually built out of
+/// multiples of this.
+const SCREEN_SIZE: RoomSize = RoomSize::new(320, 184);
+
+impl MapStateData {
+    /// Compute a batch of actions which resizes room `idx` to tightly fit its tiles, entities,
+    /// triggers, and decals, moving them so they keep their place in the map when the room's
+    /// origin moves. If `screens` is set, the room is grown evenly around its content to a whole
+    /// number of screens instead. Only empty tiles are ever cropped, so undoing restores the room
+    /// exactly.
+    pub fn fit_room_actions(&self, idx: usize, screens: bool) -> Vec<MapAction> {
+        let Some(room) = self.levels.get(idx) else { return vec![] };
+        let data = &room.data;
+        let Some(content) = content_bounds(data) else { return vec![] };
+        let fitted = if screens {
+            let size = RoomSize::new(
+                (content.width().max(1) + SCREEN_SIZE.width - 1) / SCREEN_SIZE.width
+                    * SCREEN_SIZE.width,
+                (content.height().max(1) + SCREEN_SIZE.height - 1) / SCREEN_SIZE.height
+                    * SCREEN_SIZE.height,
+            );
+            let pad = RoomVector::new(
+                (size.width - content.width()) / 16 * 8,
+                (size.height - content.height()) / 16 * 8,
+            );
+            RoomRect::new(content.origin - pad, size)
+        } else {
+            content
+        };
+        let shift = fitted.origin.to_vector();
+        let bounds = MapRectStrict::new(
+            data.bounds.origin + shift.cast_unit(),
+            fitted.size.cast_unit(),
+        );
+        if bounds == data.bounds {
+            return vec![];
+        }
+
+        let action = |event| MapAction::RoomAction { idx, event };
+        let mut result = vec![];
+        // grow first so that the shifted tiles have somewhere to go, and crop at the very end
+        let union = data.bounds.size.max(bounds.size);
+        if union != data.bounds.size {
+            result.push(action(RoomAction::MoveRoom {
+                bounds: MapRectStrict::new(data.bounds.origin, union),
+            }));
+        }
+        let union = (union / 8).cast_unit();
+        let tile_shift = vector_room_to_tile(&shift);
+        let shifted = |grid: &TileGrid<char>| TileGrid {
+            tiles: rect_point_iter(TileRect::new(TilePoint::zero(), union), 1)
+                .map(|pt| *grid.get(pt + tile_shift).unwrap_or(&'0'))
+                .collect::<Vec<_>>()
+                .into(),
+            stride: union.width as usize,
+        };
+        for (fg, grid) in [(true, &data.solids), (false, &data.bg)] {
+            result.push(action(RoomAction::TileUpdate {
+                fg,
+                offset: TilePoint::zero(),
+                data: shifted(grid),
+            }));
+        }
+        result.push(action(RoomAction::ObjectTileUpdate {
+            offset: TilePoint::zero(),
+            data: TileGrid {
+                tiles: rect_point_iter(TileRect::new(TilePoint::zero(), union), 1)
+                    .map(|pt| *data.object_tiles.get(pt + tile_shift).unwrap_or(&-1))
+                    .collect::<Vec<_>>()
+                    .into(),
+                stride: union.width as usize,
+            },
+        }));
+
+        for (trigger, list) in [(false, &data.entities), (true, &data.triggers)] {
+            if list.is_empty() {
+                continue;
+            }
+            let entities = list
+                .iter()
+                .map(|entity| {
+                    let mut entity = entity.clone();
+                    entity.x -= shift.x;
+                    entity.y -= shift.y;
+                    for node in entity.nodes.iter_mut() {
+                        node.x -= shift.x;
+                        node.y -= shift.y;
+                    }
+                    entity
+                })
+                .collect();
+            result.push(action(RoomAction::ReplaceEntities { trigger, entities }));
+        }
+        for (fg, list) in [(true, &data.fg_decals), (false, &data.bg_decals)] {
+            for decal in list {
+                let mut decal = decal.clone();
+                decal.x -= shift.x;
+                decal.y -= shift.y;
+                result.push(action(RoomAction::DecalUpdate {
+                    fg,
+                    decal: Box::new(decal),
+                }));
+            }
+        }
+
+        result.push(action(RoomAction::MoveRoom { bounds }));
+        result
+    }
+}
+
+/// The smallest tile-aligned rect, relative to the room, which covers all of its content.
+fn content_bounds(room: &CelesteMapLevel) -> Option<RoomRect> {
+    let mut points = vec![];
+    let tiles = TileRect::new(TilePoint::zero(), (room.bounds.size / 8).cast_unit());
+    for pt in rect_point_iter(tiles, 1) {
+        let solid = room.solids.get(pt).map_or(false, |&ch| ch != '0');
+        let bg = room.bg.get(pt).map_or(false, |&ch| ch != '0');
+        let obj = room.object_tiles.get(pt).map_or(false, |&id| id != -1);
+        if solid || bg || obj {
+            let pt = point_tile_to_room(&pt);
+            points.push(pt);
+            points.push(pt + RoomVector::new(7, 7));
+        }
+    }
+    for entity in room.entities.iter().chain(room.triggers.iter()) {
+        points.push(RoomPoint::new(entity.x, entity.y));
+        points.push(RoomPoint::new(
+            entity.x + (entity.width as i32 - 1).max(0),
+            entity.y + (entity.height as i32 - 1).max(0),
+        ));
+        points.extend(
+            entity
+                .nodes
+                .iter()
+                .map(|node| RoomPoint::new(node.x, node.y)),
+        );
+    }
+    for decal in room.fg_decals.iter().chain(room.bg_decals.iter()) {
+        points.push(RoomPoint::new(decal.x, decal.y));
+    }
+
+    let min = points.iter().copied().reduce(|a, b| a.min(b))?;
+    let max = points.iter().copied().reduce(|a, b| a.max(b))?;
+    let min = RoomPoint::new(min.x.div_euclid(8) * 8, min.y.div_euclid(8) * 8);
+    let max = RoomPoint::new(max.x.div_euclid(8) * 8 + 8, max.y.div_euclid(8) * 8 + 8);
+    Some(RoomRect::new(min, (max - min).to_size()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::action::apply_map_action;
+    use arborio_maploader::map_struct::{CelesteMap, CelesteMapDecal};
+    use arborio_utils::interned::intern_str;
+
+    fn entity(id: i32, name: &str, x: i32, y: i32, nodes: usize) -> CelesteMapEntity {
+        CelesteMapEntity {
+            id,
+            name: name.to_owned(),
+            x,
+            y,
+            width: 0,
+            height: 0,
+            attributes: Default::default(),
+            nodes: (0..nodes as i32)
+                .map(|i| Node { x: x + i * 8, y })
+                .collect(),
+        }
+    }
+
+    fn node_counts(actions: &[MapAction], trigger: bool) -> Vec<usize> {
+        actions
+            .iter()
+            .find_map(|action| match action {
+                MapAction::RoomAction {
+                    event:
+                        RoomAction::ReplaceEntities {
+                            trigger: t,
+                            entities,
+                        },
+                    ..
+                } if *t == trigger => Some(entities.iter().map(|e| e.nodes.len()).collect()),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn trims_nodes_to_config() {
+        let entity_config = [
+            ("spikesUp", false, None),
+            ("zipMover", true, Some((1, 1))),
+            ("trigger", true, Some((0, 2))),
+        ]
+        .into_iter()
+        .map(|(name, nodes, node_limit)| {
+            let config = EntityConfig {
+                nodes,
+                node_limit,
+                ..Default::default()
+            };
+            (intern_str(name), Arc::new(config))
+        })
+        .collect();
+        let trigger_config = [("cameraOffsetTrigger", false), ("flagTrigger", true)]
+            .into_iter()
+            .map(|(name, nodes)| {
+                let config = TriggerConfig {
+                    trigger_name: name.to_owned(),
+                    nodes,
+                    ..Default::default()
+                };
+                (intern_str(name), Arc::new(config))
+            })
+            .collect();
+        let rules = NodeRules {
+            entity_config,
+            trigger_config,
+        };
+
+        let mut level = CelesteMapLevel::default();
+        level.entities = vec![
+            entity(1, "spikesUp", 16, 16, 2),
+            entity(2, "zipMover", 16, 32, 3),
+            entity(3, "someMod/unknown", 16, 48, 3),
+        ];
+        level.triggers = vec![
+            entity(4, "cameraOffsetTrigger", 64, 16, 1),
+            entity(5, "flagTrigger", 64, 32, 4),
+            entity(6, "someMod/unknownTrigger", 64, 48, 3),
+        ];
+        let mut data = MapStateData::from(CelesteMap {
+            levels: vec![level],
+            ..Default::default()
+        });
+
+        let actions = data.fix_map_actions(&rules, false, None);
+        assert_eq!(node_counts(&actions, false), vec![0, 1, 3]);
+        assert_eq!(node_counts(&actions, true), vec![0, 2, 2]);
+
+        apply_map_action(&mut data, actions, None).unwrap();
+        assert!(data.fix_map_actions(&rules, false, None).is_empty());
+    }
+
+    #[test]
+    fn trims_empty_rows_and_columns() {
+        let mut tiles = CelesteMapLevel::default();
+        *tiles.solids.get_mut(TilePoint::new(5, 3)).unwrap() = '1';
+        let mut objects = CelesteMapLevel::default();
+        *objects.bg.get_mut(TilePoint::new(2, 1)).unwrap() = '2';
+        objects.entities = vec![entity(1, "player", 100, 60, 0)];
+        let mut ragged = CelesteMapLevel::default();
+        ragged.bounds.size = MapSizeStrict::new(317, 180);
+        *ragged.solids.get_mut(TilePoint::new(38, 21)).unwrap() = '1';
+        let mut data = MapStateData::from(CelesteMap {
+            levels: vec![tiles, objects, ragged, CelesteMapLevel::default()],
+            ..Default::default()
+        });
+
+        let rules = NodeRules {
+            entity_config: Default::default(),
+            trigger_config: Default::default(),
+        };
+        let actions = data.fix_map_actions(&rules, false, None);
+        apply_map_action(&mut data, actions, None).unwrap();
+        let sizes = data
+            .levels
+            .iter()
+            .map(|room| room.data.bounds.size)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sizes,
+            vec![
+                MapSizeStrict::new(48, 32),
+                MapSizeStrict::new(104, 64),
+                MapSizeStrict::new(312, 176),
+                MapSizeStrict::new(320, 184),
+            ]
+        );
+        assert_eq!(data.levels[0].data.solids.size(), TileSize::new(6, 4));
+        assert!(data.fix_map_actions(&rules, false, None).is_empty());
+    }
+
+    fn decal(id: u32, x: i32, y: i32) -> CelesteMapDecal {
+        CelesteMapDecal {
+            id,
+            x,
+            y,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            texture: "decals/1-forsakencity/big_sign".to_owned(),
+            depth: None,
+            rotation: 0.0,
+        }
+    }
+
+    #[test]
+    fn finds_objects_outside_rooms() {
+        let mut level = CelesteMapLevel::default();
+        level.entities = vec![
+            entity(1, "inside", 16, 16, 0),
+            entity(2, "edge", 320, 184, 0),
+            entity(3, "outside", 321, 16, 0),
+            entity(4, "above", 16, -1, 0),
+        ];
+        let mut wide = entity(5, "wide", -40, 16, 0);
+        wide.width = 48;
+        level.triggers = vec![wide, entity(6, "outside", -8, 200, 0)];
+        level.fg_decals = vec![decal(7, 0, 0), decal(8, 400, 16)];
+        level.bg_decals = vec![decal(9, 160, 92), decal(10, 16, -30)];
+        let data = MapStateData::from(CelesteMap {
+            levels: vec![CelesteMapLevel::default(), level],
+            ..Default::default()
+        });
+
+        let found = data
+            .find_outside_objects()
+            .into_iter()
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            found,
+            HashSet::from([
+                (1, AppSelection::EntityBody(3, false)),
+                (1, AppSelection::EntityBody(4, false)),
+                (1, AppSelection::EntityBody(5, true)),
+                (1, AppSelection::EntityBody(6, true)),
+                (1, AppSelection::Decal(8, true)),
+                (1, AppSelection::Decal(10, false)),
+            ])
+        );
+        // unlike the map check, fixing the map only removes what has no part inside
+        assert_eq!(data.count_outside_entities(), 3);
+    }
+
+    #[test]
+    fn replaces_matching_attributes() {
+        let with = |id, name: &str, attribute: Attribute| {
+            let mut entity = entity(id, name, 16, 16, 0);
+            entity.attributes.insert("speed".to_owned(), attribute);
+            entity
+        };
+        let mut level = CelesteMapLevel::default();
+        level.entities = vec![
+            with(1, "zipMover", Attribute::Int(3)),
+            with(2, "zipMover", Attribute::Int(4)),
+            with(3, "dashBlock", Attribute::Int(3)),
+            with(4, "zipMover", Attribute::Text("3".to_owned())),
+            entity(5, "zipMover", 16, 16, 0),
+        ];
+        level.triggers = vec![with(6, "zipMover", Attribute::Int(3))];
+        let mut data = MapStateData::from(CelesteMap {
+            levels: vec![level],
+            ..Default::default()
+        });
+
+        let actions = data.replace_attribute_actions("zipMover", "speed", " 3", "5");
+        assert_eq!(actions.len(), 2);
+        apply_map_action(&mut data, actions, None).unwrap();
+        let room = &data.levels[0].data;
+        let speed = |entity: &CelesteMapEntity| entity.attributes.get("speed").cloned();
+        assert_eq!(speed(&room.entities[0]), Some(Attribute::Int(5)));
+        assert_eq!(speed(&room.entities[1]), Some(Attribute::Int(4)));
+        assert_eq!(speed(&room.entities[2]), Some(Attribute::Int(3)));
+        assert_eq!(
+            speed(&room.entities[3]),
+            Some(Attribute::Text("3".to_owned()))
+        );
+        assert_eq!(speed(&room.entities[4]), None);
+        assert_eq!(speed(&room.triggers[0]), Some(Attribute::Int(5)));
+
+        // a replacement which doesn't fit the attribute's type is kept as text
+        let actions = data.replace_attribute_actions("zipMover", "speed", "4", "fast");
+        apply_map_action(&mut data, actions, None).unwrap();
+        let speed = data.levels[0].data.entities[1].attributes.get("speed");
+        assert_eq!(speed, Some(&Attribute::Text("fast".to_owned())));
+
+        assert!(data
+            .replace_attribute_actions("zipMover", "speed", "7", "8")
+            .is_empty());
+    }
+
+    #[test]
+    fn compares_attributes_by_type() {
+        assert!(attribute_equals(&Attribute::Bool(true), "True"));
+        assert!(!attribute_equals(&Attribute::Bool(true), "1"));
+        assert!(attribute_equals(&Attribute::Float(0.5), "0.50"));
+        assert!(!attribute_equals(&Attribute::Text("a".to_owned()), "A"));
+        assert_eq!(
+            parse_attribute_like(&Attribute::Bool(false), "TRUE"),
+            Attribute::Bool(true)
+        );
+        assert_eq!(
+            parse_attribute_like(&Attribute::Float(1.0), "2.5"),
+            Attribute::Float(2.5)
+        );
+        assert_eq!(
+            parse_attribute_like(&Attribute::Text("1".to_owned()), "2"),
+            Attribute::Text("2".to_owned())
+        );
+    }
+
+    #[test]
+    fn fits_room_to_content() {
+        let mut level = CelesteMapLevel::default();
+        level.bounds.origin = MapPointStrict::new(100, 200);
+        *level.solids.get_mut(TilePoint::new(5, 3)).unwrap() = '1';
+        *level.bg.get_mut(TilePoint::new(9, 6)).unwrap() = '2';
+        level.entities = vec![entity(1, "player", 48, 40, 0)];
+        level.fg_decals = vec![decal(1, 60, 50)];
+        let mut data = MapStateData::from(CelesteMap {
+            levels: vec![level],
+            ..Default::default()
+        });
+
+        let actions = data.fit_room_actions(0, false);
+        let undo = apply_map_action(&mut data, actions, None).unwrap();
+        let room = &data.levels[0].data;
+        assert_eq!(
+            room.bounds,
+            MapRectStrict::new(MapPointStrict::new(140, 224), MapSizeStrict::new(40, 32))
+        );
+        assert_eq!(room.solids.size(), TileSize::new(5, 4));
+        assert_eq!(room.solids.get(TilePoint::new(0, 0)), Some(&'1'));
+        assert_eq!(room.bg.get(TilePoint::new(4, 3)), Some(&'2'));
+        assert_eq!((room.entities[0].x, room.entities[0].y), (8, 16));
+        assert_eq!((room.fg_decals[0].x, room.fg_decals[0].y), (20, 26));
+        assert!(data.fit_room_actions(0, false).is_empty());
+
+        apply_map_action(&mut data, undo, None).unwrap();
+        let room = &data.levels[0].data;
+        assert_eq!(
+            room.bounds,
+            MapRectStrict::new(MapPointStrict::new(100, 200), MapSizeStrict::new(320, 184))
+        );
+        assert_eq!(room.solids.get(TilePoint::new(5, 3)), Some(&'1'));
+        assert_eq!((room.entities[0].x, room.entities[0].y), (48, 40));
+
+        // growing to a screen pads evenly around the content, on the tile grid
+        let actions = data.fit_room_actions(0, true);
+        apply_map_action(&mut data, actions, None).unwrap();
+        let room = &data.levels[0].data;
+        assert_eq!(
+            room.bounds,
+            MapRectStrict::new(MapPointStrict::new(4, 152), MapSizeStrict::new(320, 184))
+        );
+        assert_eq!(room.solids.get(TilePoint::new(17, 9)), Some(&'1'));
+        assert_eq!((room.entities[0].x, room.entities[0].y), (144, 88));
+    }
+
+    #[test]
+    fn fitting_empty_room_does_nothing() {
+        let data = MapStateData::from(CelesteMap {
+            levels: vec![CelesteMapLevel::default()],
+            ..Default::default()
+        });
+        assert!(data.fit_room_actions(0, false).is_empty());
+        assert!(data.fit_room_actions(0, true).is_empty());
+        assert!(data.fit_room_actions(1, false).is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+use arborio_maploader::map_struct::{Attribute, CelesteMapEntity};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::data::project_map::MapStateData;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlagUsage {
+    pub room: usize,
+    pub id: i32,
+    pub trigger: bool,
+    pub attribute: String,
+}
+
+/// Is this an attribute which is likely to contain one or more session flag or counter names?
+/// There's no schema for this, so we go off the naming conventions used by vanilla and most mods.
+pub fn is_flag_attribute(key: &str) -> bool {
+    let key = key.to_lowercase();
+    key.contains("flag") || key.contains("counter")
+}
+
+/// Split a flag attribute value into the flag names it references.
+/// Handles comma-separated lists and `!`-inverted flags.
+pub fn parse_flag_names(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(|s| s.trim().trim_start_matches('!').trim())
+        .filter(|s| !s.is_empty())
+}
+
+/// Whether a flag condition attribute value is satisfied by the given set of active flags. Every
+/// listed flag must be set, or unset if it is `!`-inverted.
+pub fn flag_condition_met(value: &str, active: &HashSet<String>) -> bool {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .all(|flag| match flag.strip_prefix('!') {
+            Some(flag) => !active.contains(flag.trim()),
+            None => active.contains(flag),
+        })
+}
+
+impl MapStateData {
+    /// Aggregate every flag name referenced by any entity or trigger in the map, sorted by name.
+    pub fn flag_usages(&self) -> BTreeMap<String, Vec<FlagUsage>> {
+        let mut result: BTreeMap<String, Vec<FlagUsage>> = BTreeMap::new();
+        for (room_idx, room) in self.levels.iter().enumerate() {
+            let entities = room.data.entities.iter().map(|e| (e, false));
+            let triggers = room.data.triggers.iter().map(|e| (e, true));
+            for (entity, trigger) in entities.chain(triggers) {
+                collect_entity_flags(&mut result, room_idx, entity, trigger);
+            }
+        }
+        result
+    }
+}
+
+fn collect_entity_flags(
+    result: &mut BTreeMap<String, Vec<FlagUsage>>,
+    room: usize,
+    entity: &CelesteMapEntity,
+    trigger: bool,
+) {
+    for (key, value) in entity.attributes.iter() {
+        if !is_flag_attribute(key) {
+            continue;
+        }
+        let Attribute::Text(value) = value else { continue };
+        for name in parse_flag_names(value) {
+            result.entry(name.to_owned()).or_default().push(FlagUsage {
+                room,
+                id: entity.id,
+                trigger,
+                attribute: key.clone(),
+            });
+        }
+    }
+}
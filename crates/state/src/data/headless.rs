@@ -0,0 +1,150 @@
+use arborio_maploader::map_struct::{save_as, CelesteMap, CelesteMapLevel};
+use arborio_modloader::everest_yaml::EverestYaml;
+use arborio_modloader::module::CelesteModule;
+use arborio_walker::{open_module, ConfigSourceTrait};
+use std::io;
+use std::path::Path;
+
+use crate::data::action::{apply_map_action, MapAction};
+use crate::data::project_map::MapStateData;
+use crate::data::save;
+
+/// A map opened from a mod on disk, for scripting edits without the editor. Edits are made with
+/// the same [`MapAction`]s the editor uses, but there is no undo history: keep the returned
+/// inverse batches if you need it.
+///
+/// ```ignore
+/// let mut file = MapFile::open(Path::new("Mods/MyMod"), "MyMod/1-Forest")?;
+/// let batch = for_each_room(file.rooms(), |room| vec![/* ... */]);
+/// file.apply(batch)?;
+/// file.save()?;
+/// ```
+pub struct MapFile {
+    pub module: CelesteModule,
+    pub sid: String,
+    pub data: MapStateData,
+}
+
+impl MapFile {
+    /// Load the module at `module_root` (a folder or a zip) and the map `sid` from it.
+    pub fn open(module_root: &Path, sid: &str) -> Result<Self, io::Error> {
+        let mut source = open_module(module_root)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Not a folder or zip file"))?;
+        let yaml = EverestYaml::from_config(&mut source)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let mut module = CelesteModule::new(source.filesystem_root(), yaml);
+        module.load(&mut source);
+        let map = CelesteModule::load_map_static(module_root, sid)?;
+        Ok(Self {
+            module,
+            sid: sid.to_owned(),
+            data: map.into(),
+        })
+    }
+
+    pub fn rooms(&self) -> impl Iterator<Item = &CelesteMapLevel> {
+        self.data.levels.iter().map(|room| &room.data)
+    }
+
+    /// Apply a batch of actions. Returns the batch which undoes it.
+    pub fn apply(&mut self, actions: Vec<MapAction>) -> Result<Vec<MapAction>, String> {
+        apply_map_action(&mut self.data, actions, None)
+    }
+
+    pub fn to_map(&self) -> CelesteMap {
+        self.data.clone().into()
+    }
+
+    /// Save the map back where it came from. As in the editor, this only works for unpacked mods.
+    pub fn save(&self) -> Result<(), io::Error> {
+        save(&self.module, &self.sid, &self.to_map())
+    }
+
+    /// Save the map to an arbitrary file, e.g. to leave the original untouched.
+    pub fn save_as(&self, path: &Path) -> Result<(), io::Error> {
+        save_as(&self.to_map(), &self.sid, path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::action::RoomAction;
+    use arborio_maploader::map_struct::{from_reader, CelesteMapEntity, Node};
+    use arborio_modloader::everest_yaml::EverestModuleVersion;
+    use arborio_utils::units::{MapPointStrict, MapRectStrict, MapSizeStrict};
+
+    #[test]
+    fn edits_survive_saving_and_reopening() {
+        let root = std::env::temp_dir().join(format!("arborio-mapfile-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("Maps/MyMod")).unwrap();
+        EverestYaml {
+            name: "MyMod".to_owned(),
+            version: EverestModuleVersion(vec![1, 0, 0]),
+            dll: None,
+            dependencies: vec![],
+        }
+        .save(&root);
+        let map = CelesteMap {
+            levels: vec![CelesteMapLevel {
+                name: "a-00".to_owned(),
+                ..CelesteMapLevel::default()
+            }],
+            ..Default::default()
+        };
+        let sid = "MyMod/test";
+        save_as(&map, sid, &root.join("Maps/MyMod/test.bin")).unwrap();
+
+        let mut file = MapFile::open(&root, sid).unwrap();
+        assert_eq!(
+            file.rooms().map(|room| &room.name).collect::<Vec<_>>(),
+            ["a-00"]
+        );
+        let bounds = MapRectStrict::new(MapPointStrict::new(-64, 32), MapSizeStrict::new(40, 48));
+        let entity = CelesteMapEntity {
+            id: 0,
+            name: "player".to_owned(),
+            x: 16,
+            y: 24,
+            width: 0,
+            height: 0,
+            attributes: Default::default(),
+            nodes: vec![Node { x: 8, y: 8 }],
+        };
+        file.apply(vec![
+            MapAction::RoomAction {
+                idx: 0,
+                event: RoomAction::MoveRoom { bounds },
+            },
+            MapAction::RoomAction {
+                idx: 0,
+                event: RoomAction::EntityAdd {
+                    entity: Box::new(entity),
+                    trigger: false,
+                    genid: true,
+                },
+            },
+        ])
+        .unwrap();
+        file.save().unwrap();
+        let copy = root.join("copy.bin");
+        file.save_as(&copy).unwrap();
+
+        let reopened = MapFile::open(&root, sid).unwrap().to_map();
+        let copied = from_reader(std::fs::File::open(&copy).unwrap()).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        for map in [reopened, copied] {
+            let room = &map.levels[0];
+            assert_eq!(room.name, "a-00");
+            assert_eq!(room.bounds, bounds);
+            assert_eq!(room.solids.size().width, 5);
+            assert_eq!(room.entities.len(), 1);
+            let entity = &room.entities[0];
+            assert_eq!(
+                (entity.name.as_str(), entity.x, entity.y),
+                ("player", 16, 24)
+            );
+            assert_eq!(entity.nodes, vec![Node { x: 8, y: 8 }]);
+        }
+    }
+}
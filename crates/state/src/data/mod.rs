@@ -1,23 +1,35 @@
 pub mod action;
 pub mod app;
 pub mod app_apply;
+pub mod batch;
 pub mod config_editor;
+pub mod fixup;
+pub mod flags;
+pub mod headless;
+pub mod notes;
 pub mod project_map;
 pub mod selection;
 pub mod sid;
 pub mod tabs;
+pub mod tile_image;
+pub mod unused;
 
 use app::AppEvent;
 use log::Level;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::hash::Hash;
 use std::io;
 use std::path::{Path, PathBuf};
 
 use crate::data::action::{MapAction, RoomAction};
 use crate::data::project_map::MapEvent;
+use crate::data::selection::AppSelection;
 use arborio_maploader::map_struct::{save_as, CelesteMap};
+use arborio_modloader::config::PencilBehavior;
+use arborio_modloader::dialog::DEFAULT_LANGUAGE;
 use arborio_modloader::module::{CelesteModule, CelesteModuleKind, MapPath};
+use arborio_utils::units::RoomPoint;
 use arborio_utils::uuid_cls;
 use arborio_utils::vizia::prelude::*;
 
@@ -26,14 +38,72 @@ const UNDO_BUFFER_SIZE: usize = 1000;
 uuid_cls!(EventPhase);
 
 #[derive(Serialize, Deserialize, Lens, Debug, Setter)]
+#[serde(default)]
 pub struct AppConfig {
     pub celeste_root: Option<PathBuf>,
     pub last_filepath: PathBuf,
     pub user_name: String,
 
     pub draw_interval: f32,
-    pub snap: bool,
+    /// The grid that placed and dragged objects line up with. Configs from before this was a
+    /// choice stored a bool, which is read as `Tile` or `Off`.
+    #[serde(deserialize_with = "deserialize_snap_mode")]
+    pub snap: SnapMode,
     pub advanced: bool,
+    pub confirm_room_delete: bool,
+    /// After a pencil stroke places entities, triggers, or decals, select exactly what it placed.
+    /// When off, the selection is left as it was. On by default.
+    pub select_placed: bool,
+    /// How far, in pixels, an entity node may lie outside of its room before Fix Map considers it
+    /// corrupt and offers to clamp it back.
+    pub far_node_distance: i32,
+    /// Maps with more tiles than this get a notice while loading, and only have the rooms in view
+    /// rendered rather than every room at once.
+    pub big_map_tiles: usize,
+    /// Pan the view the other way when scrolling, matching "natural" trackpad scrolling.
+    pub natural_scroll: bool,
+    /// Keep the current palette selections when eyedropping. What was picked is only reported.
+    pub pin_palette: bool,
+    /// Have the bucket fill with a noise pattern mixing the current tile with `dither_tile`.
+    pub dither: bool,
+    /// The secondary tile char mixed in by dithered fills.
+    pub dither_tile: String,
+    /// The fraction of a dithered fill which uses the secondary tile, from 0 to 1.
+    pub dither_ratio: f32,
+    /// Dithered fills with the same seed produce the same pattern.
+    pub dither_seed: u64,
+    /// Reopen the map which was looked at last once modules finish loading.
+    pub reopen_last_on_startup: bool,
+    /// The most recently opened or switched to maps, most recent first.
+    pub recent_maps: Vec<RecentMap>,
+    /// The size of the window when it was last closed, restored on launch.
+    pub window_size: Option<(f32, f32)>,
+    /// The position of the window's top-left corner when it was last closed, restored on launch
+    /// if it is still on one of the monitors.
+    pub window_position: Option<(i32, i32)>,
+    /// Place a random numbered variant of the selected decal, e.g. any of `rocks/00` through
+    /// `rocks/05`, each time the pencil places a decal.
+    pub randomize_decal_variant: bool,
+    /// Darken everything outside of the current room, leaving its neighbors faintly visible.
+    pub focus_mode: bool,
+    /// The size, in screen pixels, of the names written over triggers. Zero hides them.
+    pub label_font_size: f32,
+    /// Hide trigger names which would overlap each other, and all of them when zoomed far out.
+    pub declutter_labels: bool,
+    /// How the pencil paints fg and bg tiles: freehand for `Line`, or filling a dragged out
+    /// rectangle for `Rect`.
+    pub tile_pencil: PencilBehavior,
+    /// Draw arrows between rooms which share an edge, showing where the player can transition.
+    pub show_transitions: bool,
+    /// Which `Dialog/<Language>.txt` files names are looked up in. Keys they're missing fall back
+    /// to English.
+    pub language: String,
+    /// The tile char each color stands for when importing tiles from an image, with colors written
+    /// as `rrggbb` hex. Pixels of any other color become air.
+    pub tile_image_colors: BTreeMap<String, char>,
+    /// Tile paints on the same room and layer which follow each other within this many
+    /// milliseconds are undone together, even if they were separate strokes. Zero turns this off.
+    pub undo_coalesce_ms: u64,
 }
 
 impl Default for AppConfig {
@@ -43,12 +113,150 @@ impl Default for AppConfig {
             last_filepath: Default::default(),
             user_name: "me".to_owned(),
             draw_interval: 8.0,
-            snap: true,
+            snap: SnapMode::Tile,
             advanced: false,
+            confirm_room_delete: true,
+            select_placed: true,
+            far_node_distance: 2048,
+            big_map_tiles: 1_000_000,
+            natural_scroll: false,
+            pin_palette: false,
+            dither: false,
+            dither_tile: "0".to_owned(),
+            dither_ratio: 0.3,
+            dither_seed: 0,
+            reopen_last_on_startup: false,
+            recent_maps: vec![],
+            window_size: None,
+            window_position: None,
+            randomize_decal_variant: false,
+            focus_mode: false,
+            label_font_size: 12.0,
+            declutter_labels: true,
+            tile_pencil: PencilBehavior::Line,
+            show_transitions: false,
+            language: DEFAULT_LANGUAGE.to_owned(),
+            tile_image_colors: [("000000".to_owned(), '1')].into_iter().collect(),
+            undo_coalesce_ms: 0,
         }
     }
 }
 
+#[derive(
+    Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Data, enum_iterator::Sequence,
+)]
+pub enum SnapMode {
+    Off,
+    HalfTile,
+    Tile,
+}
+
+impl SnapMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SnapMode::Off => "Off",
+            SnapMode::HalfTile => "Half Tile",
+            SnapMode::Tile => "Tile",
+        }
+    }
+
+    /// The spacing of the grid, in pixels.
+    pub fn grid(&self) -> i32 {
+        match self {
+            SnapMode::Off => 1,
+            SnapMode::HalfTile => 4,
+            SnapMode::Tile => 8,
+        }
+    }
+
+    pub fn snap(&self, pt: RoomPoint) -> RoomPoint {
+        let grid = self.grid();
+        pt / grid * grid
+    }
+}
+
+fn deserialize_snap_mode<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<SnapMode, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SnapSetting {
+        Legacy(bool),
+        Mode(SnapMode),
+    }
+
+    Ok(match SnapSetting::deserialize(deserializer)? {
+        SnapSetting::Legacy(true) => SnapMode::Tile,
+        SnapSetting::Legacy(false) => SnapMode::Off,
+        SnapSetting::Mode(mode) => mode,
+    })
+}
+
+const RECENT_MAPS_COUNT: usize = 10;
+
+/// A map remembered across sessions. Module ids are assigned fresh on every load, so the module is
+/// identified by its everest name instead.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecentMap {
+    pub module: String,
+    pub sid: String,
+}
+
+const MIN_WINDOW_SIZE: (u32, u32) = (640, 480);
+const MAX_WINDOW_SIZE: (u32, u32) = (16384, 16384);
+
+impl AppConfig {
+    pub fn load() -> Self {
+        confy::load("arborio", "arborio").unwrap_or_default()
+    }
+
+    /// The saved window size, clamped so a corrupt or outdated value can't make the window
+    /// unusably small or larger than any display.
+    pub fn initial_window_size(&self) -> Option<(u32, u32)> {
+        let (width, height) = self.window_size?;
+        if !width.is_finite() || !height.is_finite() {
+            return None;
+        }
+        Some((
+            (width as u32).clamp(MIN_WINDOW_SIZE.0, MAX_WINDOW_SIZE.0),
+            (height as u32).clamp(MIN_WINDOW_SIZE.1, MAX_WINDOW_SIZE.1),
+        ))
+    }
+
+    /// The saved window position, moved so that a window of `size` lies on the monitor it
+    /// overlaps most, or the nearest one if a monitor was unplugged since. `monitors` are the
+    /// bounds of the available displays as `(x, y, width, height)`.
+    pub fn initial_window_position(
+        &self,
+        size: (u32, u32),
+        monitors: &[(i32, i32, u32, u32)],
+    ) -> Option<(i32, i32)> {
+        let (x, y) = self.window_position?;
+        let (width, height) = (size.0 as i32, size.1 as i32);
+        let overlap = |&(mx, my, mw, mh): &(i32, i32, u32, u32)| {
+            let w = (x + width).min(mx + mw as i32) - x.max(mx);
+            let h = (y + height).min(my + mh as i32) - y.max(my);
+            w.max(0) as i64 * h.max(0) as i64
+        };
+        let distance = |&(mx, my, mw, mh): &(i32, i32, u32, u32)| {
+            let dx = (mx - x).max(x - (mx + mw as i32)).max(0) as i64;
+            let dy = (my - y).max(y - (my + mh as i32)).max(0) as i64;
+            dx * dx + dy * dy
+        };
+        let &(mx, my, mw, mh) = monitors.iter().max_by_key(|m| (overlap(m), -distance(m)))?;
+        Some((
+            x.min(mx + mw as i32 - width).max(mx),
+            y.min(my + mh as i32 - height).max(my),
+        ))
+    }
+
+    pub fn push_recent_map(&mut self, recent: RecentMap) {
+        self.recent_maps.retain(|r| r != &recent);
+        self.recent_maps.insert(0, recent);
+        self.recent_maps.truncate(RECENT_MAPS_COUNT);
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, enum_iterator::Sequence, Data)]
 pub enum Layer {
     FgTiles,
@@ -92,7 +300,16 @@ fn load_map(module_root: &Path, sid: &str) -> Option<CelesteMap> {
     }
 }
 
-fn save(module: &CelesteModule, path: &MapPath, map: &CelesteMap) -> Result<(), io::Error> {
+/// The number of tiles covered by every room in the map, a rough measure of how much work it is to
+/// load and render.
+fn map_tile_count(map: &CelesteMap) -> usize {
+    map.levels
+        .iter()
+        .map(|level| (level.bounds.size.width * level.bounds.size.height / 64) as usize)
+        .sum()
+}
+
+fn save(module: &CelesteModule, sid: &str, map: &CelesteMap) -> Result<(), io::Error> {
     if !matches!(module.module_kind(), CelesteModuleKind::Directory) {
         return Err(io::Error::new(
             io::ErrorKind::Other,
@@ -102,14 +319,7 @@ fn save(module: &CelesteModule, path: &MapPath, map: &CelesteMap) -> Result<(),
 
     if let Some(root) = &module.filesystem_root {
         if root.is_dir() {
-            return save_as(
-                map,
-                &path.sid,
-                &root
-                    .join("Maps")
-                    .join(path.sid.clone())
-                    .with_extension("bin"),
-            );
+            return save_as(map, sid, &root.join("Maps").join(sid).with_extension("bin"));
         }
     }
 
@@ -125,6 +335,7 @@ impl MapID {
             event: MapEvent::Action {
                 event: vec![action],
                 merge_phase: phase,
+                select_added: true,
             },
         }
     }
@@ -138,6 +349,7 @@ impl MapID {
                     event: action,
                 }],
                 merge_phase: phase,
+                select_added: true,
             },
         }
     }
@@ -147,4 +359,75 @@ impl MapID {
 pub struct ArborioRecord {
     pub level: Level,
     pub message: String,
+    /// An object in a map which the message is about. Clicking the message selects it.
+    pub reveal: Option<(MapPath, usize, AppSelection)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONITORS: [(i32, i32, u32, u32); 2] = [(0, 0, 1920, 1080), (1920, 0, 1280, 1024)];
+
+    fn positioned(x: i32, y: i32) -> AppConfig {
+        AppConfig {
+            window_position: Some((x, y)),
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn window_position_is_kept_on_screen() {
+        let size = (800, 600);
+        assert_eq!(
+            positioned(100, 100).initial_window_position(size, &MONITORS),
+            Some((100, 100))
+        );
+        // straddling two monitors, it moves onto the one it mostly covers
+        assert_eq!(
+            positioned(1800, 50).initial_window_position(size, &MONITORS),
+            Some((1920, 50))
+        );
+        // hanging off the bottom edge
+        assert_eq!(
+            positioned(100, 900).initial_window_position(size, &MONITORS),
+            Some((100, 480))
+        );
+        // the monitor it was on is gone
+        assert_eq!(
+            positioned(5000, 200).initial_window_position(size, &MONITORS),
+            Some((2400, 200))
+        );
+        assert_eq!(
+            positioned(-3000, -50).initial_window_position(size, &MONITORS),
+            Some((0, 0))
+        );
+    }
+
+    #[test]
+    fn legacy_snap_setting_still_loads() {
+        let snap = |text: &str| serde_yaml::from_str::<AppConfig>(text).unwrap().snap;
+        assert_eq!(snap("snap: true"), SnapMode::Tile);
+        assert_eq!(snap("snap: false"), SnapMode::Off);
+        assert_eq!(snap("snap: HalfTile"), SnapMode::HalfTile);
+
+        let config = AppConfig {
+            snap: SnapMode::HalfTile,
+            ..AppConfig::default()
+        };
+        let saved = serde_yaml::to_string(&config).unwrap();
+        assert_eq!(snap(&saved), SnapMode::HalfTile);
+    }
+
+    #[test]
+    fn window_position_needs_a_monitor() {
+        assert_eq!(
+            positioned(100, 100).initial_window_position((800, 600), &[]),
+            None
+        );
+        assert_eq!(
+            AppConfig::default().initial_window_position((800, 600), &MONITORS),
+            None
+        );
+    }
 }
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Design notes left on a map and its rooms. They are only for the editor: they're kept in
+/// arborio's own config folder rather than in the mod, so they never ship with it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapNotes {
+    /// Notes on the map as a whole.
+    pub map: String,
+    /// Notes on rooms, by room name.
+    pub rooms: BTreeMap<String, String>,
+}
+
+impl MapNotes {
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty() && self.rooms.is_empty()
+    }
+
+    /// The notes on a room, or on the map as a whole for `None`.
+    pub fn get(&self, room: Option<&str>) -> &str {
+        match room {
+            Some(room) => self.rooms.get(room).map_or("", String::as_str),
+            None => &self.map,
+        }
+    }
+
+    /// Set the notes on a room, or on the map as a whole for `None`, returning the old ones.
+    pub fn set(&mut self, room: Option<&str>, note: String) -> String {
+        match room {
+            Some(room) if note.is_empty() => self.rooms.remove(room).unwrap_or_default(),
+            Some(room) => self.rooms.insert(room.to_owned(), note).unwrap_or_default(),
+            None => std::mem::replace(&mut self.map, note),
+        }
+    }
+
+    /// Keep a room's notes attached to it when it is renamed.
+    pub fn rename_room(&mut self, old: &str, new: &str) {
+        if let Some(note) = self.rooms.remove(old) {
+            self.rooms.insert(new.to_owned(), note);
+        }
+    }
+}
+
+/// The folder all notes are kept under, beside arborio's config file.
+pub fn notes_root() -> Option<PathBuf> {
+    let config = confy::get_configuration_file_path("arborio", "arborio").ok()?;
+    Some(config.parent()?.join("notes"))
+}
+
+/// The notes file for a map, e.g. `<root>/MyMod/foo/bar.yaml` for `foo/bar` in the mod `MyMod`.
+/// Characters of the mod's name which don't belong in a folder name are replaced.
+pub fn notes_path(root: &Path, module: &str, sid: &str) -> PathBuf {
+    let module: String = module
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    root.join(module).join(format!("{sid}.yaml"))
+}
+
+/// Load a map's notes. A map without a notes file has no notes.
+pub fn load_notes(root: &Path, module: &str, sid: &str) -> MapNotes {
+    let path = notes_path(root, module, sid);
+    match fs::read_to_string(&path) {
+        Ok(text) => serde_yaml::from_str(&text).unwrap_or_else(|e| {
+            log::error!("Failed to load notes {}: {}", path.display(), e);
+            MapNotes::default()
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => MapNotes::default(),
+        Err(e) => {
+            log::error!("Failed to load notes {}: {}", path.display(), e);
+            MapNotes::default()
+        }
+    }
+}
+
+/// Save a map's notes, removing the notes file once there are none left.
+pub fn save_notes(root: &Path, module: &str, sid: &str, notes: &MapNotes) -> Result<(), io::Error> {
+    let path = notes_path(root, module, sid);
+    if notes.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let text = serde_yaml::to_string(notes).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("arborio-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+
+    #[test]
+    fn notes_are_keyed_by_mod_and_map() {
+        let root = temp_root("notes-keys");
+        assert_eq!(
+            notes_path(&root, "My Mod", "me/1-city"),
+            root.join("My Mod").join("me/1-city.yaml")
+        );
+        assert_eq!(
+            notes_path(&root, "../Evil:Mod", "a"),
+            root.join("___Evil_Mod").join("a.yaml")
+        );
+
+        let mut notes = MapNotes::default();
+        notes.set(None, "the whole map".to_owned());
+        notes.set(Some("a-00"), "first room".to_owned());
+        save_notes(&root, "ModA", "me/map", &notes).unwrap();
+        assert_eq!(load_notes(&root, "ModA", "me/map"), notes);
+        assert_eq!(load_notes(&root, "ModB", "me/map"), MapNotes::default());
+        assert_eq!(load_notes(&root, "ModA", "me/other"), MapNotes::default());
+
+        save_notes(&root, "ModA", "me/map", &MapNotes::default()).unwrap();
+        assert!(!notes_path(&root, "ModA", "me/map").exists());
+        save_notes(&root, "ModA", "me/map", &MapNotes::default()).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn notes_follow_renamed_rooms() {
+        let mut notes = MapNotes::default();
+        assert_eq!(notes.set(Some("a-00"), "hello".to_owned()), "");
+        notes.rename_room("a-00", "b-00");
+        assert_eq!(notes.get(Some("a-00")), "");
+        assert_eq!(notes.get(Some("b-00")), "hello");
+        assert_eq!(notes.set(Some("b-00"), "".to_owned()), "hello");
+        assert!(notes.is_empty());
+    }
+}
@@ -1,25 +1,38 @@
 use crate::data::action::{apply_map_action, MapAction, RoomAction};
 use crate::data::app::{step_modules_lookup, AppEvent, AppState};
+use crate::data::batch::{Batch, BatchOp};
+use crate::data::fixup::NodeRules;
+use crate::data::notes::{notes_path, notes_root, save_notes, MapNotes};
+use crate::data::selection::AppSelection;
 use crate::data::tabs::{AppTab, MapTab};
-use crate::data::{save, EventPhase, MapID, UNDO_BUFFER_SIZE};
+use crate::data::tile_image::{parse_tile_colors, tiles_from_image};
+use crate::data::unused::UnusedAssets;
+use crate::data::{save, ArborioRecord, EventPhase, MapID, UNDO_BUFFER_SIZE};
+use crate::rendering::TileDraw;
 use crate::tools::selection::{add_float_to_float, drop_float};
 use arborio_maploader::map_struct::{
-    CelesteMap, CelesteMapDecal, CelesteMapEntity, CelesteMapLevel, CelesteMapMeta,
+    save_as, CelesteMap, CelesteMapDecal, CelesteMapEntity, CelesteMapLevel, CelesteMapMeta,
     CelesteMapMetaAudioState, CelesteMapMetaMode, CelesteMapStyleground, FieldEntry,
 };
 use arborio_modloader::aggregate::ModuleAggregate;
+use arborio_modloader::dialog::DEFAULT_LANGUAGE;
 use arborio_modloader::discovery::LoaderThreadMessage;
 use arborio_modloader::everest_yaml::EverestModuleVersion;
 use arborio_modloader::module::CelesteModuleKind;
 use arborio_modloader::module::{MapPath, ModuleID};
 use arborio_utils::units::*;
+use arborio_utils::uuid::next_uuid;
 use arborio_utils::vizia::prelude::*;
 use arborio_utils::vizia::vg;
+use dialog::DialogBox;
+use log::Level;
 use std::cell::RefCell;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::fmt::{Debug, Formatter};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 
 #[derive(Lens)]
 pub struct MapState {
@@ -67,6 +80,9 @@ pub struct MapStateData {
 
     pub ambience: String,
     pub music: String,
+
+    /// Editor-only notes, saved apart from the map itself.
+    pub notes: MapNotes,
 }
 
 pub struct MapStateCache {
@@ -76,6 +92,11 @@ pub struct MapStateCache {
     pub redo_buffer: VecDeque<Vec<MapAction>>,
     pub event_phase: EventPhase,
     pub palette: ModuleAggregate,
+    /// Whether the map is over the big map threshold, see `AppConfig::big_map_tiles`.
+    pub big: bool,
+    /// When the last action was taken, and what it painted tiles on if that's all it did. See
+    /// `AppConfig::undo_coalesce_ms`.
+    pub last_action: Option<(Instant, Option<TilePaintTarget>)>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -131,6 +152,21 @@ impl MapStateData {
         }
     }
 
+    /// The number of entities, or triggers, with the given name across every room.
+    pub fn count_entities(&self, name: &str, trigger: bool) -> usize {
+        self.levels
+            .iter()
+            .map(|room| {
+                let list = if trigger {
+                    &room.data.triggers
+                } else {
+                    &room.data.entities
+                };
+                list.iter().filter(|e| e.name == name).count()
+            })
+            .sum()
+    }
+
     pub fn level_at(&self, pt: MapPointStrict) -> Option<usize> {
         for (idx, room) in self.levels.iter().enumerate() {
             if room.data.bounds.contains(pt) {
@@ -140,6 +176,29 @@ impl MapStateData {
         None
     }
 
+    /// The room to move to from room `current` going in `direction`: of the rooms whose centers
+    /// lie that way from its center, the nearest, with distance stretched the further a room is
+    /// off to the side.
+    pub fn level_in_direction(&self, current: usize, direction: MapVectorPrecise) -> Option<usize> {
+        let center = self.levels.get(current)?.data.bounds.cast::<f32>().center();
+        let direction = direction.normalize();
+        self.levels
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != current)
+            .filter_map(|(idx, room)| {
+                let offset = room.data.bounds.cast::<f32>().center() - center;
+                let along = offset.dot(direction);
+                if along <= 0.0 {
+                    return None;
+                }
+                let angle = offset.cross(direction).abs().atan2(along);
+                Some((idx, offset.length() * (1.0 + angle)))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(idx, _)| idx)
+    }
+
     pub fn apply(&mut self, patch: &mut MapStateUpdate) {
         if let Some(x) = patch.fg_tiles.as_mut() {
             std::mem::swap(&mut self.fg_tiles, x);
@@ -247,45 +306,125 @@ impl MapState {
         self.data.level_at(pt)
     }
 
+    /// Apply a batch of actions as a new undo step. The step is merged into the previous one if
+    /// it has the same phase, or if both paint tiles in the same place within `coalesce_window`
+    /// of each other. Returns the batch which undoes it. This is the only thing which clears the
+    /// redo buffer, and an empty batch doesn't even do that.
+    pub fn apply_action(
+        &mut self,
+        event: Vec<MapAction>,
+        merge_phase: EventPhase,
+        selection: Option<&mut HashSet<AppSelection>>,
+        coalesce_window: Duration,
+    ) -> Result<Vec<MapAction>, String> {
+        if event.is_empty() {
+            return Ok(vec![]);
+        }
+        let undo = apply_map_action(&mut self.data, event, selection)?;
+        self.cache.dirty = true;
+        if self.cache.undo_buffer.len() == UNDO_BUFFER_SIZE {
+            self.cache.undo_buffer.pop_front();
+        }
+        let now = Instant::now();
+        let target = tile_paint_target(&undo);
+        let coalesce = target.is_some()
+            && matches!(self.cache.last_action, Some((time, last))
+                if last == target && now.duration_since(time) < coalesce_window);
+        self.cache.last_action = Some((now, target));
+        if self.cache.undo_buffer.back().is_none()
+            || (self.cache.event_phase != merge_phase && !coalesce)
+        {
+            self.cache.undo_buffer.push_back(undo.clone());
+        } else if let Some(back) = self.cache.undo_buffer.back_mut() {
+            // irrefutable if the if fails
+            // breaking my own rules here: it's merge time
+            merge_events(back, undo.clone(), true);
+        }
+        self.cache.event_phase = merge_phase;
+        self.cache.redo_buffer.clear();
+        Ok(undo)
+    }
+
+    /// Undo the last undo step, if there is one, and make it available to redo. Returns the batch
+    /// which redoes it.
+    pub fn undo(
+        &mut self,
+        selection: Option<&mut HashSet<AppSelection>>,
+    ) -> Option<Result<Vec<MapAction>, String>> {
+        self.step_history(false, selection)
+    }
+
+    /// Redo the last undone step, if there is one. Returns the batch which undoes it again.
+    pub fn redo(
+        &mut self,
+        selection: Option<&mut HashSet<AppSelection>>,
+    ) -> Option<Result<Vec<MapAction>, String>> {
+        self.step_history(true, selection)
+    }
+
+    fn step_history(
+        &mut self,
+        redo: bool,
+        selection: Option<&mut HashSet<AppSelection>>,
+    ) -> Option<Result<Vec<MapAction>, String>> {
+        let (from, to) = if redo {
+            (&mut self.cache.redo_buffer, &mut self.cache.undo_buffer)
+        } else {
+            (&mut self.cache.undo_buffer, &mut self.cache.redo_buffer)
+        };
+        let event = from.pop_back()?;
+        let mut opposite = match apply_map_action(&mut self.data, event, selection) {
+            Ok(opposite) => opposite,
+            Err(e) => return Some(Err(e)),
+        };
+        for room_idx in opposite
+            .iter()
+            .filter_map(|act| {
+                if let MapAction::RoomAction { idx: room, .. } = act {
+                    Some(*room)
+                } else {
+                    None
+                }
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+        {
+            if let Some(room) = self.data.levels.get_mut(room_idx) {
+                let defloat = drop_float(&room.floats)
+                    .into_iter()
+                    .map(|ra| MapAction::RoomAction {
+                        idx: room_idx,
+                        event: ra,
+                    })
+                    .collect();
+                match apply_map_action(&mut self.data, defloat, None) {
+                    Ok(inverse) => {
+                        // NOTE: these true/false values are based on literally nothing. I'm not
+                        // even sure the calls are right.
+                        merge_events(&mut opposite, inverse.clone(), true);
+                        if let Some(next_back) = from.back_mut() {
+                            merge_events(next_back, inverse, true);
+                        }
+                    }
+                    Err(e) => {
+                        let verb = if redo { "redo" } else { "undo" };
+                        log::error!("Internal error: Failed to {}-defloat: {}", verb, e);
+                    }
+                }
+            }
+        }
+
+        self.cache.dirty = true;
+        to.push_back(opposite.clone());
+        self.cache.event_phase = EventPhase::null();
+        self.cache.last_action = None;
+        Some(Ok(opposite))
+    }
+
     pub fn new(x: CelesteMap, path: MapPath, palette: ModuleAggregate) -> Self {
         //let side = path.sid.parse::<SIDFields>().map(|f| f.mode).unwrap_or_default().idx();
-        let mut result = Self {
-            data: MapStateData {
-                filler: x.filler,
-                background_color: x.background_color,
-                foregrounds: x.foregrounds,
-                backgrounds: x.backgrounds,
-                levels: x.levels.into_iter().map(|x| x.into()).collect(),
-                fg_tiles: "".to_owned(),
-                bg_tiles: "".to_owned(),
-                animated_tiles: "".to_owned(),
-                sprites: "".to_owned(),
-                portraits: "".to_owned(),
-                cassette_note_color: "".to_owned(),
-                title_text_color: "".to_owned(),
-                title_base_color: "".to_owned(),
-                title_accent_color: "".to_owned(),
-                icon: "".to_owned(),
-                interlude: false,
-                wipe: "".to_string(),
-                cassette_song: "".to_string(),
-                postcard_sound_id: "".to_string(),
-                color_grade: "".to_string(),
-                dreaming: false,
-                intro_type: "".to_string(),
-                bloom_base: 0.0,
-                bloom_strength: 0.0,
-                darkness_alpha: 0.0,
-                core_mode: "".to_string(),
-                heart_is_end: false,
-                inventory: "".to_string(),
-                start_level: "".to_string(),
-                seeker_slowdown: false,
-                theo_in_bubble: false,
-                ignore_level_audio_layer_data: false,
-                ambience: "".to_string(),
-                music: "".to_string(),
-            },
+        Self {
+            data: x.into(),
             cache: MapStateCache {
                 dirty: false,
                 path,
@@ -293,10 +432,54 @@ impl MapState {
                 redo_buffer: Default::default(),
                 event_phase: EventPhase::null(),
                 palette,
+                big: false,
+                last_action: None,
             },
+        }
+    }
+}
+
+impl From<CelesteMap> for MapStateData {
+    fn from(x: CelesteMap) -> Self {
+        let mut result = MapStateData {
+            filler: x.filler,
+            background_color: x.background_color,
+            foregrounds: x.foregrounds,
+            backgrounds: x.backgrounds,
+            levels: x.levels.into_iter().map(|x| x.into()).collect(),
+            fg_tiles: "".to_owned(),
+            bg_tiles: "".to_owned(),
+            animated_tiles: "".to_owned(),
+            sprites: "".to_owned(),
+            portraits: "".to_owned(),
+            cassette_note_color: "".to_owned(),
+            title_text_color: "".to_owned(),
+            title_base_color: "".to_owned(),
+            title_accent_color: "".to_owned(),
+            icon: "".to_owned(),
+            interlude: false,
+            wipe: "".to_string(),
+            cassette_song: "".to_string(),
+            postcard_sound_id: "".to_string(),
+            color_grade: "".to_string(),
+            dreaming: false,
+            intro_type: "".to_string(),
+            bloom_base: 0.0,
+            bloom_strength: 0.0,
+            darkness_alpha: 0.0,
+            core_mode: "".to_string(),
+            heart_is_end: false,
+            inventory: "".to_string(),
+            start_level: "".to_string(),
+            seeker_slowdown: false,
+            theo_in_bubble: false,
+            ignore_level_audio_layer_data: false,
+            ambience: "".to_string(),
+            music: "".to_string(),
+            notes: MapNotes::default(),
         };
         if let Some(meta) = x.meta {
-            result.data.apply(&mut MapStateUpdate::new(meta, None))
+            result.apply(&mut MapStateUpdate::new(meta, None))
         }
         result
     }
@@ -450,166 +633,91 @@ impl AppState {
             log::error!("Internal error: loaded map referring to unloaded module");
             return
         };
-        let selection_option = if let Some(AppTab::Map(MapTab {
-            id,
-            current_selected,
-            ..
-        })) = self.tabs.get_mut(self.current_tab)
-        {
-            if *id == map {
-                Some(current_selected)
+        let mut map_tab = if let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(self.current_tab) {
+            if map_tab.id == map {
+                Some(map_tab)
             } else {
                 None
             }
         } else {
             None
         };
+        let selection_option = map_tab.as_deref_mut().map(|tab| &mut tab.current_selected);
 
         match event {
-            MapEvent::Action { event, merge_phase } => {
-                match apply_map_action(state, event, selection_option) {
+            MapEvent::Action {
+                event,
+                merge_phase,
+                select_added,
+            } => {
+                let window = Duration::from_millis(self.config.undo_coalesce_ms);
+                let selection_option = selection_option.filter(|_| select_added);
+                match state.apply_action(event, merge_phase, selection_option, window) {
                     Ok(undo) => {
-                        cx.needs_redraw();
-                        state.cache.dirty = true;
-                        if state.cache.undo_buffer.len() == UNDO_BUFFER_SIZE {
-                            state.cache.undo_buffer.pop_front();
-                        }
-                        if state.cache.undo_buffer.back().is_none()
-                            || state.cache.event_phase != merge_phase
-                        {
-                            state.cache.undo_buffer.push_back(undo);
-                        } else if let Some(back) = state.cache.undo_buffer.back_mut() {
-                            // irrefutable if the if fails
-                            // breaking my own rules here: it's merge time
-                            merge_events(back, undo, true);
+                        if let Some(map_tab) = map_tab {
+                            map_tab.forget_locks(&state.data, &undo);
                         }
-                        state.cache.event_phase = merge_phase;
-                        state.cache.redo_buffer.clear();
+                        cx.needs_redraw();
                     }
                     Err(e) => {
                         log::error!("Internal error: map event: {}", e);
                     }
                 }
             }
-            MapEvent::Undo => {
-                if let Some(event) = state.cache.undo_buffer.pop_back() {
-                    // if let Some(sels) = selection_option {
-                    //     sels.clear();
-                    // }
-                    match apply_map_action(state, event, selection_option) {
-                        Ok(mut opposite) => {
-                            for room_idx in opposite
-                                .iter()
-                                .filter_map(|act| {
-                                    if let MapAction::RoomAction { idx: room, .. } = act {
-                                        Some(*room)
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect::<HashSet<_>>()
-                                .into_iter()
-                            {
-                                if let Some(room) = state.data.levels.get_mut(room_idx) {
-                                    let defloat = drop_float(&room.floats)
-                                        .into_iter()
-                                        .map(|ra| MapAction::RoomAction {
-                                            idx: room_idx,
-                                            event: ra,
-                                        })
-                                        .collect();
-                                    match apply_map_action(state, defloat, None) {
-                                        Ok(inverse) => {
-                                            // ?????????????????????????
-                                            merge_events(&mut opposite, inverse.clone(), true);
-                                            if let Some(next_back) =
-                                                state.cache.undo_buffer.back_mut()
-                                            {
-                                                merge_events(next_back, inverse, true);
-                                            }
-                                        }
-                                        Err(e) => {
-                                            log::error!(
-                                                "Internal error: Failed to undo-defloat: {}",
-                                                e
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-
-                            cx.needs_redraw();
-                            state.cache.dirty = true;
-                            state.cache.redo_buffer.push_back(opposite);
-                            state.cache.event_phase = EventPhase::null();
-                        }
-                        Err(e) => {
-                            log::error!("Internal error: Failed to undo: {}", e);
-                        }
+            MapEvent::Undo => match state.undo(selection_option) {
+                Some(Ok(redo)) => {
+                    if let Some(map_tab) = map_tab {
+                        map_tab.forget_locks(&state.data, &redo);
                     }
+                    cx.needs_redraw();
                 }
+                Some(Err(e)) => log::error!("Internal error: Failed to undo: {}", e),
+                None => {}
+            },
+            MapEvent::Redo => match state.redo(selection_option) {
+                Some(Ok(undo)) => {
+                    if let Some(map_tab) = map_tab {
+                        map_tab.forget_locks(&state.data, &undo);
+                    }
+                    cx.needs_redraw();
+                }
+                Some(Err(e)) => log::error!("Internal error: Failed to redo: {}", e),
+                None => {}
+            },
+            MapEvent::Save => {
+                if let Err(e) = save(module, &state.cache.path.sid, &state.data.clone().into()) {
+                    log::error!("Failed to save: {}", e);
+                    return;
+                }
+                if let Some(root) = notes_root() {
+                    let name = &module.everest_metadata.name;
+                    if let Err(e) =
+                        save_notes(&root, name, &state.cache.path.sid, &state.data.notes)
+                    {
+                        log::error!("Failed to save notes: {}", e);
+                        return;
+                    }
+                }
+                state.cache.dirty = false;
             }
-            MapEvent::Redo => {
-                if let Some(event) = state.cache.redo_buffer.pop_back() {
-                    // if let Some(sels) = selection_option {
-                    //     sels.clear();
-                    // }
-                    match apply_map_action(state, event, selection_option) {
-                        Ok(mut opposite) => {
-                            for room_idx in opposite
-                                .iter()
-                                .filter_map(|act| {
-                                    if let MapAction::RoomAction { idx: room, .. } = act {
-                                        Some(*room)
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect::<HashSet<_>>()
-                                .into_iter()
-                            {
-                                if let Some(room) = state.data.levels.get_mut(room_idx) {
-                                    let defloat = drop_float(&room.floats)
-                                        .into_iter()
-                                        .map(|ra| MapAction::RoomAction {
-                                            idx: room_idx,
-                                            event: ra,
-                                        })
-                                        .collect();
-                                    match apply_map_action(state, defloat, None) {
-                                        Ok(inverse) => {
-                                            merge_events(&mut opposite, inverse.clone(), true); // NOTE: these true/false values are based on literally nothing. I'm not even sure the calls are right.
-                                            if let Some(next_back) =
-                                                state.cache.redo_buffer.back_mut()
-                                            {
-                                                merge_events(next_back, inverse, true);
-                                            }
-                                        }
-                                        Err(e) => {
-                                            log::error!(
-                                                "Internal error: Failed to redo-defloat: {}",
-                                                e
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-
-                            cx.needs_redraw();
-                            state.cache.dirty = true;
-                            state.cache.undo_buffer.push_back(opposite);
-                            state.cache.event_phase = EventPhase::null();
-                        }
-                        Err(e) => {
-                            log::error!("Internal error: Failed to redo: {}", e);
-                        }
+            MapEvent::Export { path } => {
+                if let Err(e) = save_as(&state.data.clone().into(), &state.cache.path.sid, &path) {
+                    log::error!("Failed to export: {}", e);
+                    return;
+                }
+                log::info!("Exported {} to {}", state.cache.path.sid, path.display());
+                // a copy elsewhere (e.g. out of a zip) leaves the map itself unsaved
+                let own_path = module.unpacked().map(|root| {
+                    root.join("Maps")
+                        .join(&state.cache.path.sid)
+                        .with_extension("bin")
+                });
+                if let Some(own_path) = own_path {
+                    if same_file(&own_path, &path) {
+                        state.cache.dirty = false;
                     }
                 }
             }
-            MapEvent::Save => match save(module, &state.cache.path, &state.data.clone().into()) {
-                Ok(_) => state.cache.dirty = false,
-                Err(e) => log::error!("Failed to save: {}", e),
-            },
             MapEvent::SetName { sid } => {
                 let current_sid = &state.cache.path.sid;
                 let Some(root) = module.unpacked() else {
@@ -638,6 +746,18 @@ impl AppState {
                     log::error!("Internal error: rename map: rename: {}", e);
                     return;
                 }
+                if let Some(notes) = notes_root() {
+                    let name = &module.everest_metadata.name;
+                    let old_notes = notes_path(&notes, name, current_sid);
+                    let new_notes = notes_path(&notes, name, &sid);
+                    if old_notes.exists() {
+                        let moved = std::fs::create_dir_all(new_notes.parent().unwrap())
+                            .and_then(|()| std::fs::rename(old_notes, new_notes));
+                        if let Err(e) = moved {
+                            log::error!("Failed to move notes along with map: {}", e);
+                        }
+                    }
+                }
 
                 module.maps[index] = sid.clone();
                 state.cache.path.sid = sid;
@@ -655,6 +775,221 @@ impl AppState {
                 });
                 self.tabs.push(AppTab::MapMeta(map));
             }
+            MapEvent::OpenFlags => {
+                for (idx, tab) in self.tabs.iter().enumerate() {
+                    if matches!(tab, AppTab::MapFlags(m) if *m == map) {
+                        cx.emit(AppEvent::SelectTab { idx });
+                        return;
+                    }
+                }
+                cx.emit(AppEvent::SelectTab {
+                    idx: self.tabs.len(),
+                });
+                self.tabs.push(AppTab::MapFlags(map));
+            }
+            MapEvent::OpenModule => {
+                cx.emit(AppEvent::OpenModuleOverviewTab {
+                    module: state.cache.path.module,
+                });
+            }
+            MapEvent::Duplicate => {
+                cx.emit(AppEvent::ProjectEvent {
+                    project: Some(state.cache.path.module),
+                    event: ProjectEvent::NewMap {
+                        template: MapTemplate::Copy(Box::new(state.data.clone().into())),
+                    },
+                });
+            }
+            MapEvent::FixMap => {
+                let outside = state.data.count_outside_entities();
+                let drop_outside = outside != 0
+                    && matches!(
+                        dialog::Question::new(format!(
+                            "{outside} entities and triggers lie entirely outside of their rooms. Remove them while fixing the map?"
+                        ))
+                        .title("Fix Map")
+                        .show(),
+                        Ok(dialog::Choice::Yes)
+                    );
+                let distance = self.config.far_node_distance;
+                let far = state.data.count_far_nodes(distance);
+                let clamp_nodes = (far != 0
+                    && matches!(
+                        dialog::Question::new(format!(
+                            "{far} entity nodes lie more than {distance} pixels outside of their rooms. Move them back in while fixing the map?"
+                        ))
+                        .title("Fix Map")
+                        .show(),
+                        Ok(dialog::Choice::Yes)
+                    ))
+                .then_some(distance);
+                let rules = NodeRules::new(&state.cache.palette);
+                let actions = state
+                    .data
+                    .fix_map_actions(&rules, drop_outside, clamp_nodes);
+                if actions.is_empty() {
+                    log::info!("Fix map: nothing to fix");
+                    return;
+                }
+                cx.emit(AppEvent::MapEvent {
+                    map: Some(map),
+                    event: MapEvent::Action {
+                        event: actions,
+                        merge_phase: EventPhase::new(),
+                        select_added: true,
+                    },
+                });
+            }
+            MapEvent::CheckOutsideObjects => {
+                let found = state.data.find_outside_objects();
+                if found.is_empty() {
+                    log::info!("Check map: everything lies inside of its room");
+                    return;
+                }
+                for &(room, selection) in &found {
+                    let level = &state.data.levels[room];
+                    let what = match selection {
+                        AppSelection::EntityBody(id, trigger) => level
+                            .entity(id, trigger)
+                            .map(|e| format!("{} {} at ({}, {})", e.name, e.id, e.x, e.y)),
+                        AppSelection::Decal(id, fg) => level
+                            .decal(id, fg)
+                            .map(|d| format!("Decal {} at ({}, {})", d.texture, d.x, d.y)),
+                        _ => None,
+                    };
+                    let Some(what) = what else { continue };
+                    cx.emit(AppEvent::Log {
+                        message: ArborioRecord {
+                            level: Level::Warn,
+                            message: format!("{what} lies outside of room {}", level.data.name),
+                            reveal: Some((state.cache.path.clone(), room, selection)),
+                        },
+                    });
+                }
+                log::warn!(
+                    "Check map: {} objects lie outside of their rooms. Click a warning to select one.",
+                    found.len()
+                );
+            }
+            MapEvent::ReplaceAttribute {
+                name,
+                attribute,
+                find,
+                replace,
+            } => {
+                let actions = state
+                    .data
+                    .replace_attribute_actions(&name, &attribute, &find, &replace);
+                if actions.is_empty() {
+                    log::warn!("Replace attribute: no {name} has {attribute} set to {find:?}");
+                    return;
+                }
+                log::info!(
+                    "Replace attribute: changed {attribute} of {} {name} from {find:?} to {replace:?}",
+                    actions.len()
+                );
+                cx.emit(AppEvent::MapEvent {
+                    map: Some(map),
+                    event: MapEvent::Action {
+                        event: actions,
+                        merge_phase: EventPhase::new(),
+                        select_added: true,
+                    },
+                });
+            }
+            MapEvent::CopyRoomImage => {
+                let Some(AppTab::Map(map_tab)) = self.tabs.get(self.current_tab) else { return };
+                let Some(level) = state.data.levels.get(map_tab.current_room) else { return };
+                // the editor does the actual rendering and copying next time it draws
+                level.cache.borrow_mut().copy_image_requested = true;
+                cx.needs_redraw();
+            }
+            MapEvent::ImportTileImage { path, fg } => {
+                let Some(AppTab::Map(map_tab)) = self.tabs.get(self.current_tab) else { return };
+                let room = map_tab.current_room;
+                let Some(level) = state.data.levels.get(room) else { return };
+                let image = match image::open(&path) {
+                    Ok(image) => image.into_rgba8(),
+                    Err(e) => {
+                        log::error!("Failed to open {}: {}", path.display(), e);
+                        return;
+                    }
+                };
+                let size = if fg {
+                    level.data.solids.size()
+                } else {
+                    level.data.bg.size()
+                };
+                if image.width() as i32 != size.width || image.height() as i32 != size.height {
+                    log::warn!(
+                        "Image is {}x{} but the room is {}x{} tiles; it will be cropped or padded with air",
+                        image.width(),
+                        image.height(),
+                        size.width,
+                        size.height
+                    );
+                }
+                let colors = parse_tile_colors(&self.config.tile_image_colors);
+                let (data, unmapped) = tiles_from_image(&image, &colors, size);
+                if unmapped != 0 {
+                    log::warn!("{unmapped} pixels had colors with no tile and became air");
+                }
+                cx.emit(AppEvent::MapEvent {
+                    map: Some(map),
+                    event: MapEvent::Action {
+                        event: vec![MapAction::RoomAction {
+                            idx: room,
+                            event: RoomAction::TileUpdate {
+                                fg,
+                                offset: TilePoint::zero(),
+                                data,
+                            },
+                        }],
+                        merge_phase: EventPhase::new(),
+                        select_added: true,
+                    },
+                });
+            }
+            MapEvent::Reveal { room, selection } => {
+                let Some(level) = state.data.levels.get(room) else {
+                    log::error!("Internal error: reveal: no such room");
+                    return;
+                };
+                level.cache.borrow_mut().render_cache_valid = false;
+                let origin = level.data.bounds.origin.cast::<f32>();
+
+                let idx = self
+                    .tabs
+                    .iter()
+                    .position(|tab| matches!(tab, AppTab::Map(t) if t.id == map))
+                    .unwrap_or_else(|| {
+                        self.tabs.push(AppTab::Map(MapTab {
+                            nonce: next_uuid(),
+                            id: map,
+                            current_room: room,
+                            current_selected: HashSet::new(),
+                            styleground_selected: HashSet::new(),
+                            transform: MapToScreen::identity(),
+                            preview_pos: MapPointStrict::zero(),
+                            locked: HashMap::new(),
+                            map_selected: HashMap::new(),
+                        }));
+                        self.tabs.len() - 1
+                    });
+                let Some(AppTab::Map(map_tab)) = self.tabs.get_mut(idx) else { unreachable!() };
+                map_tab.current_room = room;
+                map_tab.current_selected = selection;
+                map_tab.map_selected.clear();
+                // scroll so the room's corner is just inside the top left of the view
+                let screen_origin = map_tab.transform.transform_point(origin);
+                map_tab.transform = map_tab
+                    .transform
+                    .then_translate(ScreenVector::new(50.0, 50.0) - screen_origin.to_vector());
+                // switching tabs cycles the tool, which would clear the selection we just made
+                if idx != self.current_tab {
+                    cx.emit(AppEvent::SelectTab { idx });
+                }
+            }
             MapEvent::Delete => {
                 let Some(root) = module.unpacked() else {
                     log::error!("Internal error: tried to delete a packed map");
@@ -678,6 +1013,15 @@ impl AppState {
                     log::error!("Failed to delete map: {}", e);
                     return;
                 }
+                if let Some(notes) = notes_root() {
+                    let name = &module.everest_metadata.name;
+                    let old_notes = notes_path(&notes, name, &state.cache.path.sid);
+                    if old_notes.exists() {
+                        if let Err(e) = std::fs::remove_file(old_notes) {
+                            log::error!("Failed to delete notes along with map: {}", e);
+                        }
+                    }
+                }
                 module.maps.remove(idx);
                 self.loaded_maps.remove(&map);
                 self.modules_version += 1;
@@ -737,7 +1081,7 @@ impl AppState {
                     state.filesystem_root = Some(path);
                 }
             }
-            ProjectEvent::NewMap => {
+            ProjectEvent::NewMap { template } => {
                 if !matches!(state.module_kind(), CelesteModuleKind::Directory) {
                     log::error!(
                         "Cannot make a new map in {}: not a directory-loaded mod",
@@ -767,7 +1111,7 @@ impl AppState {
                     }
                     break new_sid;
                 };
-                state.create_map(new_sid.clone());
+                state.create_map(new_sid.clone(), &template.instantiate());
                 cx.emit(AppEvent::OpenMap {
                     path: MapPath {
                         module: project,
@@ -775,6 +1119,77 @@ impl AppState {
                     },
                 });
             }
+            ProjectEvent::RunBatch { op } => {
+                let Some(module_root) = state.filesystem_root.clone() else { return };
+                if self.batch_running.load(Ordering::Relaxed) {
+                    log::error!(
+                        "Cannot {} all maps: another batch is still running",
+                        op.name().to_lowercase()
+                    );
+                    return;
+                }
+                if op.saves() && !matches!(state.module_kind(), CelesteModuleKind::Directory) {
+                    log::error!(
+                        "Cannot {} the maps of {}: not a directory-loaded mod",
+                        op.name().to_lowercase(),
+                        &state.everest_metadata.name
+                    );
+                    return;
+                }
+                // saving over a map which is open would be clobbered by, or clobber, the editor
+                let sids = state
+                    .maps
+                    .iter()
+                    .filter(|sid| {
+                        let path = MapPath {
+                            module: project,
+                            sid: (*sid).clone(),
+                        };
+                        let open = op.saves() && self.loaded_maps_lookup.contains_key(&path);
+                        if open {
+                            log::warn!("{} {}: skipped since it is open", op.name(), sid);
+                        }
+                        !open
+                    })
+                    .cloned()
+                    .collect();
+                let palette = ModuleAggregate::new(
+                    &self.modules,
+                    &self.modules_lookup,
+                    &None,
+                    project,
+                    DEFAULT_LANGUAGE,
+                    false,
+                );
+                // the previous batch is done with the flag, so it can be reused
+                self.batch_cancel.store(false, Ordering::Relaxed);
+                self.batch_running.store(true, Ordering::Relaxed);
+                let batch = Batch {
+                    op,
+                    module_root,
+                    sids,
+                    rules: NodeRules::new(&palette),
+                    far_node_distance: self.config.far_node_distance,
+                    cancel: self.batch_cancel.clone(),
+                    running: self.batch_running.clone(),
+                };
+                cx.spawn(move |cx| batch.run(cx));
+            }
+            ProjectEvent::ReportUnusedAssets { output } => {
+                let Some(module_root) = state.filesystem_root.clone() else { return };
+                let report = UnusedAssets {
+                    project,
+                    module_root,
+                    sids: state.maps.clone(),
+                    modules: self.modules.clone(),
+                    modules_lookup: self.modules_lookup.clone(),
+                    output,
+                };
+                cx.spawn(move |cx| report.run(cx));
+            }
+            ProjectEvent::CancelBatch => {
+                self.batch_cancel.store(true, Ordering::Relaxed);
+            }
             ProjectEvent::Delete => {
                 if !matches!(state.module_kind(), CelesteModuleKind::Builtin) {
                     let module = self.modules.remove(&project).unwrap();
@@ -791,13 +1206,63 @@ impl AppState {
     }
 }
 
+/// What a new map starts out as.
+#[derive(Debug)]
+pub enum MapTemplate {
+    /// No rooms at all.
+    Empty,
+    /// A single screen with a floor and somewhere for the player to spawn.
+    Starter,
+    /// A copy of an existing map.
+    Copy(Box<CelesteMap>),
+}
+
+impl MapTemplate {
+    pub fn instantiate(self) -> CelesteMap {
+        match self {
+            MapTemplate::Empty => CelesteMap::default(),
+            MapTemplate::Starter => {
+                let mut room = CelesteMapLevel {
+                    name: "lvl_a-00".to_owned(),
+                    ..CelesteMapLevel::default()
+                };
+                let size: TileSize = (room.bounds.size / 8).cast_unit();
+                let floor_y = size.height - 2;
+                for x in 0..size.width {
+                    for y in floor_y..size.height {
+                        *room.solids.get_mut(TilePoint::new(x, y)).unwrap() = '1';
+                    }
+                }
+                room.entities.push(CelesteMapEntity {
+                    id: 1,
+                    name: "player".to_owned(),
+                    x: 32,
+                    y: floor_y * 8,
+                    width: 0,
+                    height: 0,
+                    attributes: Default::default(),
+                    nodes: vec![],
+                });
+                CelesteMap {
+                    levels: vec![room],
+                    ..CelesteMap::default()
+                }
+            }
+            MapTemplate::Copy(map) => *map,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ProjectEvent {
     SetName { name: String },
     SetVersion { version: EverestModuleVersion },
     SetPath { path: PathBuf },
-    NewMap,
+    NewMap { template: MapTemplate },
     Delete,
+    RunBatch { op: BatchOp },
+    CancelBatch,
+    ReportUnusedAssets { output: PathBuf },
 }
 
 #[derive(Debug)]
@@ -806,13 +1271,46 @@ pub enum MapEvent {
     Redo,
     Save,
     OpenMeta,
+    OpenFlags,
+    /// Open the overview of the module the map belongs to.
+    OpenModule,
+    /// Copy the map, including unsaved changes, into a new map of the same module.
+    Duplicate,
+    FixMap,
+    /// Log a warning for each entity, trigger, and decal whose position lies outside of its room.
+    /// Nothing is changed.
+    CheckOutsideObjects,
+    /// Set `attribute` to `replace` on every entity and trigger named `name` across the map where
+    /// it is currently `find`, as one undo step.
+    ReplaceAttribute {
+        name: String,
+        attribute: String,
+        find: String,
+        replace: String,
+    },
+    CopyRoomImage,
+    /// Replace the current room's fg or bg tiles with ones read from an image, one pixel per tile,
+    /// using `AppConfig::tile_image_colors`.
+    ImportTileImage {
+        path: PathBuf,
+        fg: bool,
+    },
+    Export {
+        path: PathBuf,
+    },
     Delete,
     SetName {
         sid: String,
     },
+    Reveal {
+        room: usize,
+        selection: HashSet<AppSelection>,
+    },
     Action {
         event: Vec<MapAction>,
         merge_phase: EventPhase,
+        /// Select whatever the actions add, such as pasted or placed objects.
+        select_added: bool,
     },
 }
 
@@ -835,10 +1333,30 @@ pub struct LevelFloatState {
 pub struct LevelStateCache {
     pub render_cache_valid: bool,
     pub render_cache: Option<vg::ImageId>,
+    /// Set to have the editor render this room without any selection and put it on the clipboard
+    /// as an image.
+    pub copy_image_requested: bool,
+    /// Set to have the editor render this room without any selection or triggers and save it to
+    /// this path as a PNG.
+    pub export_image_requested: Option<PathBuf>,
+    /// Set to have the editor render just these objects and put them on the clipboard as an image.
+    pub copy_selection_requested: Option<HashSet<AppSelection>>,
+    /// What drawing the fg and bg tiles takes, worked out the first time they're drawn and kept
+    /// until they change.
+    pub fg_tile_draws: Option<Vec<TileDraw>>,
+    pub bg_tile_draws: Option<Vec<TileDraw>>,
     pub last_entity_idx: usize,
     pub last_decal_idx: usize,
 }
 
+impl LevelStateCache {
+    /// Forget the planned tile draws, e.g. after the tiles are edited or the tilesets change.
+    pub fn invalidate_tiles(&mut self) {
+        self.fg_tile_draws = None;
+        self.bg_tile_draws = None;
+    }
+}
+
 impl From<CelesteMapLevel> for LevelState {
     fn from(data: CelesteMapLevel) -> Self {
         Self {
@@ -1002,6 +1520,14 @@ impl LevelState {
     }
 }
 
+/// Whether two paths name the same file, even if they are spelled differently.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
 // this function will forever be a pile of hacks
 // if I WANTED to justify this I would say that TileUpdates is special because it's necessarily working with big hunks of data
 // but I don't wanna justify this. it's just bad
@@ -1126,6 +1652,32 @@ fn merge_tile_events<T: Copy + PartialEq>(
     }
 }
 
+/// The room, and whether fg tiles, bg tiles or object tiles (`None`) were painted.
+pub type TilePaintTarget = (usize, Option<bool>);
+
+/// Where a batch of actions paints tiles, if that is all it does and it does it all in one place.
+fn tile_paint_target(actions: &[MapAction]) -> Option<TilePaintTarget> {
+    let mut result = None;
+    for action in actions {
+        let target = match action {
+            MapAction::RoomAction {
+                idx,
+                event: RoomAction::TileUpdate { fg, .. },
+            } => (*idx, Some(*fg)),
+            MapAction::RoomAction {
+                idx,
+                event: RoomAction::ObjectTileUpdate { .. },
+            } => (*idx, None),
+            _ => return None,
+        };
+        if result.map_or(false, |result| result != target) {
+            return None;
+        }
+        result = Some(target);
+    }
+    result
+}
+
 /*
 fn merge_events(old: &mut MapAction, new: MapAction) {
     // basically: we try to prove that new is already handled, and if not, add it to the batch I guess?
@@ -1154,3 +1706,144 @@ fn is_action_handled(old: &MapAction, new: &MapAction) -> bool {
     }
 }
  */
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_redo_survives_view_changes() {
+        let mut state = MapState::new(
+            CelesteMap {
+                levels: vec![CelesteMapLevel::default(), CelesteMapLevel::default()],
+                ..Default::default()
+            },
+            MapPath {
+                module: ModuleID::new(),
+                sid: "test".to_owned(),
+            },
+            ModuleAggregate::new_omni(&HashMap::new(), DEFAULT_LANGUAGE, false),
+        );
+        let original = state.data.levels[0].data.bounds;
+        let moved = MapRectStrict::new(original.origin + MapVectorStrict::new(8, 0), original.size);
+        let move_room = MapAction::RoomAction {
+            idx: 0,
+            event: RoomAction::MoveRoom { bounds: moved },
+        };
+
+        state
+            .apply_action(vec![move_room], EventPhase::new(), None, Duration::ZERO)
+            .unwrap();
+        assert_eq!(state.data.levels[0].data.bounds, moved);
+        state.undo(None).unwrap().unwrap();
+        assert_eq!(state.data.levels[0].data.bounds, original);
+
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut app = AppState::new(tx);
+        let id = MapID::new();
+        app.loaded_maps.insert(id, state);
+        app.tabs.push(AppTab::Map(MapTab {
+            nonce: next_uuid(),
+            id,
+            current_room: 0,
+            current_selected: HashSet::new(),
+            styleground_selected: HashSet::new(),
+            transform: MapToScreen::identity(),
+            preview_pos: MapPointStrict::zero(),
+            locked: HashMap::new(),
+            map_selected: HashMap::new(),
+        }));
+        let tab = app.tabs.len() - 1;
+        app.current_tab = tab;
+
+        app.apply_view(AppEvent::Pan {
+            tab,
+            delta: MapVectorPrecise::new(40.0, -16.0),
+        });
+        app.apply_view(AppEvent::Zoom {
+            tab,
+            delta: 2.0,
+            focus: MapPointPrecise::new(100.0, 100.0),
+        });
+        app.apply_view(AppEvent::SelectRoom { tab, idx: 1 });
+        app.apply_view(AppEvent::SelectRoom { tab, idx: 0 });
+        let AppTab::Map(map_tab) = &app.tabs[tab] else { unreachable!() };
+        assert_ne!(map_tab.transform, MapToScreen::identity());
+
+        // the tools may also send along an empty batch while the view changes
+        let state = app.loaded_maps.get_mut(&id).unwrap();
+        let mut selection = HashSet::new();
+        state
+            .apply_action(
+                vec![],
+                EventPhase::new(),
+                Some(&mut selection),
+                Duration::ZERO,
+            )
+            .unwrap();
+        state
+            .apply_action(vec![], EventPhase::null(), None, Duration::ZERO)
+            .unwrap();
+
+        state.redo(None).unwrap().unwrap();
+        assert_eq!(state.data.levels[0].data.bounds, moved);
+        assert!(state.redo(None).is_none());
+    }
+
+    #[test]
+    fn coalesced_paints_undo_to_the_first_pre_image() {
+        let mut state = MapState::new(
+            CelesteMap {
+                levels: vec![CelesteMapLevel::default()],
+                ..Default::default()
+            },
+            MapPath {
+                module: ModuleID::new(),
+                sid: "test".to_owned(),
+            },
+            ModuleAggregate::new_omni(&HashMap::new(), DEFAULT_LANGUAGE, false),
+        );
+        let paint = |x, ch| {
+            vec![MapAction::RoomAction {
+                idx: 0,
+                event: RoomAction::TileUpdate {
+                    fg: true,
+                    offset: TilePoint::new(x, 0),
+                    data: TileGrid {
+                        tiles: vec![ch].into(),
+                        stride: 1,
+                    },
+                },
+            }]
+        };
+        let tiles = |state: &MapState| -> String {
+            state.data.levels[0].data.solids.tiles[..2].iter().collect()
+        };
+        let window = Duration::from_secs(3600);
+
+        // two separate strokes, the second partly over the first
+        state
+            .apply_action(paint(0, '1'), EventPhase::new(), None, window)
+            .unwrap();
+        state
+            .apply_action(paint(0, '2'), EventPhase::new(), None, window)
+            .unwrap();
+        state
+            .apply_action(paint(1, '2'), EventPhase::new(), None, window)
+            .unwrap();
+        assert_eq!(tiles(&state), "22");
+        assert_eq!(state.cache.undo_buffer.len(), 1);
+
+        state.undo(None).unwrap().unwrap();
+        assert_eq!(tiles(&state), "00");
+        assert!(state.undo(None).is_none());
+
+        // without a window, each stroke is its own step
+        state.redo(None).unwrap().unwrap();
+        state
+            .apply_action(paint(0, '3'), EventPhase::new(), None, Duration::ZERO)
+            .unwrap();
+        state.undo(None).unwrap().unwrap();
+        assert_eq!(tiles(&state), "22");
+    }
+}
@@ -25,6 +25,26 @@ impl AppSelection {
             None
         }
     }
+
+    pub fn decal_info(&self) -> Option<(u32, bool)> {
+        if let AppSelection::Decal(decal_id, fg) = self {
+            Some((*decal_id, *fg))
+        } else {
+            None
+        }
+    }
+
+    /// What to record in `MapTab::locked` to lock this. Entity nodes lock along with their entity;
+    /// tiles can't be locked.
+    pub fn lock_key(&self) -> Option<AppSelection> {
+        match self {
+            AppSelection::EntityBody(id, trigger) | AppSelection::EntityNode(id, _, trigger) => {
+                Some(AppSelection::EntityBody(*id, *trigger))
+            }
+            AppSelection::Decal(..) => Some(*self),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
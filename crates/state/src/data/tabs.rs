@@ -1,15 +1,18 @@
-use crate::data::action::StylegroundSelection;
+use crate::data::action::{MapAction, RoomAction, StylegroundSelection};
 use crate::data::config_editor::{
     AnyConfig, ConfigSearchFilter, ConfigSearchResult, ConfigSearchType, SearchScope,
 };
+use crate::data::project_map::MapStateData;
 use crate::data::selection::AppSelection;
 use crate::data::MapID;
 use arborio_maploader::map_struct::CelesteMapEntity;
 use arborio_modloader::module::ModuleID;
-use arborio_utils::units::{MapPointStrict, MapToScreen};
+use arborio_utils::units::{
+    invert_transform, MapPointStrict, MapSpace, MapToScreen, ScreenSpace, Transform2D,
+};
 use arborio_utils::uuid::next_uuid;
 use arborio_utils::vizia::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 #[allow(clippy::large_enum_variant)] // this is very rarely passed around by value
 #[derive(PartialEq, Eq, Debug, Lens, Clone, Data)]
@@ -20,6 +23,7 @@ pub enum AppTab {
     ConfigEditor(ConfigEditorTab),
     Logs,
     MapMeta(MapID),
+    MapFlags(MapID),
 }
 
 #[derive(Debug, Lens, Clone)]
@@ -82,9 +86,117 @@ pub struct MapTab {
     pub nonce: u32,
     pub current_room: usize,
     pub current_selected: HashSet<AppSelection>,
-    pub styleground_selected: Option<StylegroundSelection>,
+    pub styleground_selected: HashSet<StylegroundSelection>,
     pub transform: MapToScreen,
     pub preview_pos: MapPointStrict,
+    /// Entities and decals which can't be selected without holding alt, by room index. See
+    /// `AppSelection::lock_key`.
+    pub locked: HashMap<usize, HashSet<AppSelection>>,
+    /// Objects selected in rooms other than the current one, by room index, from selecting across
+    /// the whole map. Tweaker edits apply to these along with `current_selected`. Dropped when the
+    /// selection is cleared, the current room changes, or rooms are added or removed.
+    pub map_selected: HashMap<usize, HashSet<AppSelection>>,
+}
+
+impl MapTab {
+    pub fn inverse_transform(&self) -> Transform2D<f32, ScreenSpace, MapSpace> {
+        invert_transform(&self.transform)
+    }
+
+    pub fn is_locked(&self, room: usize, sel: &AppSelection) -> bool {
+        match (self.locked.get(&room), sel.lock_key()) {
+            (Some(locked), Some(key)) => locked.contains(&key),
+            _ => false,
+        }
+    }
+
+    /// Lock everything in `selection` which is in the current room, or unlock it all if it is all
+    /// already locked. Anything newly locked is deselected.
+    pub fn toggle_lock(&mut self, selection: &HashSet<AppSelection>) {
+        let keys = selection
+            .iter()
+            .filter_map(AppSelection::lock_key)
+            .collect::<HashSet<_>>();
+        let locked = self.locked.entry(self.current_room).or_default();
+        if keys.is_subset(locked) {
+            locked.retain(|key| !keys.contains(key));
+        } else {
+            locked.extend(keys.iter().copied());
+            self.current_selected
+                .retain(|sel| !matches!(sel.lock_key(), Some(key) if keys.contains(&key)));
+        }
+    }
+
+    /// Forget the locks on anything removed by a batch of actions, given the batch which undoes
+    /// it and the map it was applied to, and follow rooms around as other rooms are added and
+    /// removed. Selections in other rooms are simply dropped then.
+    pub fn forget_locks(&mut self, map: &MapStateData, undo: &[MapAction]) {
+        // the undo batch is in reverse order
+        for action in undo.iter().rev() {
+            match action {
+                MapAction::AddRoom { idx: Some(idx), .. } => {
+                    self.map_selected.clear();
+                    self.locked.remove(idx);
+                    self.locked = std::mem::take(&mut self.locked)
+                        .into_iter()
+                        .map(|(room, locked)| (if room > *idx { room - 1 } else { room }, locked))
+                        .collect();
+                }
+                MapAction::DeleteRoom { idx } => {
+                    self.map_selected.clear();
+                    self.locked = std::mem::take(&mut self.locked)
+                        .into_iter()
+                        .map(|(room, locked)| (if room >= *idx { room + 1 } else { room }, locked))
+                        .collect();
+                }
+                MapAction::RoomAction {
+                    idx,
+                    event: RoomAction::ReplaceEntities { trigger, .. },
+                } => {
+                    // the replaced list is gone, so check what survived against the map instead
+                    let Some(room) = map.levels.get(*idx) else { continue };
+                    let list = if *trigger {
+                        &room.data.triggers
+                    } else {
+                        &room.data.entities
+                    };
+                    let exists = |sel: &AppSelection| match *sel {
+                        AppSelection::EntityBody(id, t) if t == *trigger => {
+                            list.iter().any(|e| e.id == id)
+                        }
+                        AppSelection::EntityNode(id, node, t) if t == *trigger => {
+                            list.iter().any(|e| e.id == id && node < e.nodes.len())
+                        }
+                        _ => true,
+                    };
+                    if let Some(locked) = self.locked.get_mut(idx) {
+                        locked.retain(exists);
+                    }
+                    if let Some(selected) = self.map_selected.get_mut(idx) {
+                        selected.retain(exists);
+                    }
+                    if *idx == self.current_room {
+                        self.current_selected.retain(exists);
+                    }
+                }
+                MapAction::RoomAction { idx, event } => {
+                    let removed = match event {
+                        RoomAction::EntityAdd {
+                            entity, trigger, ..
+                        } => AppSelection::EntityBody(entity.id, *trigger),
+                        RoomAction::DecalAdd { fg, decal, .. } => {
+                            AppSelection::Decal(decal.id, *fg)
+                        }
+                        _ => continue,
+                    };
+                    if let Some(locked) = self.locked.get_mut(idx) {
+                        locked.remove(&removed);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
 }
 
 impl PartialEq for MapTab {
@@ -100,3 +212,165 @@ impl Data for MapTab {
 }
 
 impl Eq for MapTab {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::action::apply_map_action;
+    use arborio_maploader::map_struct::{CelesteMap, CelesteMapLevel, Node};
+    use arborio_utils::units::MapToScreen;
+
+    fn map_tab() -> MapTab {
+        MapTab {
+            id: MapID::new(),
+            nonce: next_uuid(),
+            current_room: 0,
+            current_selected: HashSet::new(),
+            styleground_selected: HashSet::new(),
+            transform: MapToScreen::identity(),
+            preview_pos: MapPointStrict::zero(),
+            locked: HashMap::new(),
+            map_selected: HashMap::new(),
+        }
+    }
+
+    fn entity(id: i32, nodes: usize) -> CelesteMapEntity {
+        CelesteMapEntity {
+            id,
+            name: "zipMover".to_owned(),
+            x: 16,
+            y: 16,
+            width: 0,
+            height: 0,
+            attributes: Default::default(),
+            nodes: vec![Node { x: 32, y: 16 }; nodes],
+        }
+    }
+
+    #[test]
+    fn toggling_locks_and_unlocks() {
+        let mut tab = map_tab();
+        tab.current_room = 1;
+        tab.current_selected = HashSet::from([
+            AppSelection::EntityNode(4, 0, false),
+            AppSelection::Decal(7, true),
+            AppSelection::FgFloat,
+        ]);
+        let selection = tab.current_selected.clone();
+
+        tab.toggle_lock(&selection);
+        assert!(tab.is_locked(1, &AppSelection::EntityBody(4, false)));
+        assert!(tab.is_locked(1, &AppSelection::EntityNode(4, 2, false)));
+        assert!(tab.is_locked(1, &AppSelection::Decal(7, true)));
+        assert!(!tab.is_locked(0, &AppSelection::Decal(7, true)));
+        assert!(!tab.is_locked(1, &AppSelection::FgFloat));
+        // what was locked is deselected, the rest stays
+        assert_eq!(tab.current_selected, HashSet::from([AppSelection::FgFloat]));
+
+        // a partly locked selection locks the rest
+        tab.toggle_lock(&HashSet::from([
+            AppSelection::Decal(7, true),
+            AppSelection::Decal(8, false),
+        ]));
+        assert!(tab.is_locked(1, &AppSelection::Decal(8, false)));
+
+        tab.toggle_lock(&HashSet::from([
+            AppSelection::EntityBody(4, false),
+            AppSelection::Decal(7, true),
+        ]));
+        assert!(!tab.is_locked(1, &AppSelection::EntityBody(4, false)));
+        assert!(!tab.is_locked(1, &AppSelection::Decal(7, true)));
+        assert!(tab.is_locked(1, &AppSelection::Decal(8, false)));
+    }
+
+    #[test]
+    fn locks_follow_rooms_around() {
+        let level = |name: &str| CelesteMapLevel {
+            name: name.to_owned(),
+            ..CelesteMapLevel::default()
+        };
+        let mut map = MapStateData::from(CelesteMap {
+            levels: vec![level("a"), level("b"), level("c")],
+            ..Default::default()
+        });
+        let mut tab = map_tab();
+        for room in 0..3 {
+            tab.locked.insert(
+                room,
+                HashSet::from([AppSelection::Decal(room as u32, true)]),
+            );
+        }
+        tab.map_selected
+            .insert(2, HashSet::from([AppSelection::Decal(9, true)]));
+
+        let undo = apply_map_action(
+            &mut map,
+            vec![MapAction::AddRoom {
+                idx: Some(1),
+                room: Box::new(level("new")),
+            }],
+            None,
+        )
+        .unwrap();
+        tab.forget_locks(&map, &undo);
+        assert!(tab.map_selected.is_empty());
+        assert!(tab.is_locked(0, &AppSelection::Decal(0, true)));
+        assert!(tab.is_locked(2, &AppSelection::Decal(1, true)));
+        assert!(tab.is_locked(3, &AppSelection::Decal(2, true)));
+        assert!(!tab.locked.contains_key(&1));
+
+        let undo =
+            apply_map_action(&mut map, vec![MapAction::DeleteRoom { idx: 0 }], None).unwrap();
+        tab.forget_locks(&map, &undo);
+        assert_eq!(
+            tab.locked,
+            HashMap::from([
+                (1, HashSet::from([AppSelection::Decal(1, true)])),
+                (2, HashSet::from([AppSelection::Decal(2, true)])),
+            ])
+        );
+    }
+
+    #[test]
+    fn replacing_entities_forgets_what_is_gone() {
+        let mut level = CelesteMapLevel::default();
+        level.entities = vec![entity(1, 2), entity(2, 0), entity(3, 0)];
+        let mut map = MapStateData::from(CelesteMap {
+            levels: vec![level],
+            ..Default::default()
+        });
+        let mut tab = map_tab();
+        tab.current_selected = HashSet::from([
+            AppSelection::EntityBody(1, false),
+            AppSelection::EntityNode(1, 0, false),
+            AppSelection::EntityNode(1, 1, false),
+            AppSelection::EntityBody(3, false),
+            AppSelection::EntityBody(3, true),
+        ]);
+        tab.toggle_lock(&HashSet::from([AppSelection::EntityBody(2, false)]));
+
+        let undo = apply_map_action(
+            &mut map,
+            vec![MapAction::RoomAction {
+                idx: 0,
+                event: RoomAction::ReplaceEntities {
+                    trigger: false,
+                    entities: vec![entity(1, 1)],
+                },
+            }],
+            None,
+        )
+        .unwrap();
+        tab.forget_locks(&map, &undo);
+
+        assert!(!tab.is_locked(0, &AppSelection::EntityBody(2, false)));
+        assert_eq!(
+            tab.current_selected,
+            HashSet::from([
+                AppSelection::EntityBody(1, false),
+                AppSelection::EntityNode(1, 0, false),
+                AppSelection::EntityBody(3, true),
+            ])
+        );
+    }
+}
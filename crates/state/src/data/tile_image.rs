@@ -0,0 +1,98 @@
+use arborio_utils::units::{TileGrid, TilePoint, TileSize};
+use image::RgbaImage;
+use std::collections::{BTreeMap, HashMap};
+
+/// Parse the `rrggbb` keys of `AppConfig::tile_image_colors`. Malformed ones are logged and left
+/// out.
+pub fn parse_tile_colors(table: &BTreeMap<String, char>) -> HashMap<[u8; 3], char> {
+    let mut result = HashMap::new();
+    for (color, tile) in table {
+        let hex = color.trim_start_matches('#');
+        match u32::from_str_radix(hex, 16) {
+            Ok(rgb) if hex.len() == 6 => {
+                let [_, r, g, b] = rgb.to_be_bytes();
+                result.insert([r, g, b], *tile);
+            }
+            _ => log::error!("Bad color in tile image colors: {color:?}"),
+        }
+    }
+    result
+}
+
+/// Turn an image into a grid of tiles of the given size, one pixel per tile, along with how many
+/// pixels had a color missing from `colors`. Those pixels, transparent ones, and tiles beyond the
+/// edge of the image all become air.
+pub fn tiles_from_image(
+    image: &RgbaImage,
+    colors: &HashMap<[u8; 3], char>,
+    size: TileSize,
+) -> (TileGrid<char>, usize) {
+    let mut grid = TileGrid::new(size, '0');
+    let mut unmapped = 0;
+    for y in 0..size.height.min(image.height() as i32) {
+        for x in 0..size.width.min(image.width() as i32) {
+            let [r, g, b, a] = image.get_pixel(x as u32, y as u32).0;
+            if a == 0 {
+                continue;
+            }
+            match colors.get(&[r, g, b]) {
+                Some(tile) => *grid.get_mut(TilePoint::new(x, y)).unwrap() = *tile,
+                None => unmapped += 1,
+            }
+        }
+    }
+    (grid, unmapped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::Rgba;
+
+    fn colors() -> HashMap<[u8; 3], char> {
+        HashMap::from([([255, 0, 0], '1'), ([0, 0, 255], '3')])
+    }
+
+    #[test]
+    fn parses_tile_colors() {
+        let table = BTreeMap::from([
+            ("ff0000".to_owned(), '1'),
+            ("#0000FF".to_owned(), '3'),
+            ("00ff".to_owned(), '4'),
+            ("00ff00ff".to_owned(), '5'),
+            ("red".to_owned(), '6'),
+        ]);
+        assert_eq!(parse_tile_colors(&table), colors());
+    }
+
+    #[test]
+    fn unknown_colors_become_air() {
+        let image = RgbaImage::from_fn(3, 1, |x, _| match x {
+            0 => Rgba([255, 0, 0, 255]),
+            1 => Rgba([0, 255, 0, 255]),
+            _ => Rgba([0, 0, 255, 0]),
+        });
+        let (grid, unmapped) = tiles_from_image(&image, &colors(), TileSize::new(3, 1));
+        assert_eq!(unmapped, 1);
+        assert_eq!(grid.get(TilePoint::new(0, 0)), Some(&'1'));
+        // not in the table
+        assert_eq!(grid.get(TilePoint::new(1, 0)), Some(&'0'));
+        // transparent, so not counted either
+        assert_eq!(grid.get(TilePoint::new(2, 0)), Some(&'0'));
+    }
+
+    #[test]
+    fn image_is_cropped_or_padded_to_size() {
+        let image = RgbaImage::from_pixel(4, 2, Rgba([0, 0, 255, 255]));
+
+        let (grid, unmapped) = tiles_from_image(&image, &colors(), TileSize::new(2, 3));
+        assert_eq!(unmapped, 0);
+        assert_eq!(grid.size(), TileSize::new(2, 3));
+        assert_eq!(grid.get(TilePoint::new(1, 1)), Some(&'3'));
+        assert_eq!(grid.get(TilePoint::new(1, 2)), Some(&'0'));
+
+        let (grid, _) = tiles_from_image(&image, &colors(), TileSize::new(6, 1));
+        assert_eq!(grid.get(TilePoint::new(3, 0)), Some(&'3'));
+        assert_eq!(grid.get(TilePoint::new(4, 0)), Some(&'0'));
+    }
+}
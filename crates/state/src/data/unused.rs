@@ -0,0 +1,101 @@
+use arborio_modloader::aggregate::ModuleAggregate;
+use arborio_modloader::dialog::DEFAULT_LANGUAGE;
+use arborio_modloader::module::{CelesteModule, ModuleID};
+use arborio_utils::vizia::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::data::app::AppEvent;
+use crate::data::{load_map, Progress};
+use crate::rendering::decal_texture;
+
+/// Everything needed to find the tilesets and decals which a module provides but none of its maps
+/// use, gathered on the main thread so it can run on another one.
+pub struct UnusedAssets {
+    pub project: ModuleID,
+    pub module_root: PathBuf,
+    pub sids: Vec<String>,
+    pub modules: HashMap<ModuleID, CelesteModule>,
+    pub modules_lookup: HashMap<String, ModuleID>,
+    /// Where to write the report: one unused image per line, relative to the module root.
+    pub output: PathBuf,
+}
+
+impl UnusedAssets {
+    pub fn run(self, cx: &mut ContextProxy) {
+        let Some(module) = self.modules.get(&self.project) else { return };
+        let owned = |prefix: &str| -> BTreeSet<String> {
+            module
+                .gameplay_atlas
+                .iter_paths()
+                .filter(|path| path.starts_with(prefix))
+                .map(|path| path.to_string())
+                .collect()
+        };
+        let mut tilesets = owned("tilesets/");
+        let mut decals = owned("decals/");
+
+        let mut used_decals = HashSet::new();
+        let total = self.sids.len();
+        for (idx, sid) in self.sids.iter().enumerate() {
+            cx.emit(AppEvent::Progress {
+                progress: Progress {
+                    progress: (idx * 100 / total) as i32,
+                    status: format!("Finding unused assets: {sid}"),
+                },
+            })
+            .unwrap();
+            let Some(map) = load_map(&self.module_root, sid) else { continue };
+            // which tileset a char means depends on the map's tileset xml
+            let palette = ModuleAggregate::new(
+                &self.modules,
+                &self.modules_lookup,
+                &map.meta,
+                self.project,
+                DEFAULT_LANGUAGE,
+                false,
+            );
+            for room in &map.levels {
+                for (grid, tiler) in [(&room.solids, "fg"), (&room.bg, "bg")] {
+                    let Some(tiler) = palette.autotilers.get(tiler) else { continue };
+                    let chars: HashSet<char> = grid.tiles.iter().copied().collect();
+                    for ch in chars {
+                        if let Some(tileset) = tiler.get(&ch) {
+                            tilesets.remove(&*tileset.texture);
+                        }
+                    }
+                }
+                for decal in room.fg_decals.iter().chain(room.bg_decals.iter()) {
+                    used_decals.insert(decal_texture(decal));
+                }
+            }
+        }
+        // a decal without an exact match plays the numbered frames starting with its name
+        decals.retain(|path| {
+            !used_decals.contains(path)
+                && !used_decals.contains(path.trim_end_matches(|c: char| c.is_ascii_digit()))
+        });
+
+        let report = tilesets
+            .iter()
+            .chain(decals.iter())
+            .map(|path| format!("Graphics/Atlases/Gameplay/{path}.png\n"))
+            .collect::<String>();
+        match std::fs::write(&self.output, report) {
+            Ok(()) => log::info!(
+                "Found {} unused tilesets and {} unused decals, listed in {}",
+                tilesets.len(),
+                decals.len(),
+                self.output.display()
+            ),
+            Err(e) => log::error!("Could not write {}: {e}", self.output.display()),
+        }
+        cx.emit(AppEvent::Progress {
+            progress: Progress {
+                progress: 100,
+                status: "".to_owned(),
+            },
+        })
+        .unwrap();
+    }
+}
@@ -15,7 +15,7 @@ use crate::data::selection::AppSelection;
 use crate::data::tabs::{AppTab, MapTab};
 use crate::data::MapID;
 use arborio_maploader::map_struct::{
-    Attribute, CelesteMapEntity, CelesteMapLevel, CelesteMapStyleground,
+    Attribute, CelesteMapDecal, CelesteMapEntity, CelesteMapLevel, CelesteMapStyleground,
 };
 use arborio_modloader::aggregate::ModuleAggregate;
 use arborio_modloader::config::AttributeInfo;
@@ -63,7 +63,7 @@ where
 
 pub fn current_map_impl_lens() -> impl Lens<Source = AppState, Target = MapStateData> + Copy {
     ClosureLens::new(|source: &AppState| {
-        let (AppTab::Map(MapTab{id, ..}) | AppTab::MapMeta(id)) = source.tabs.get(source.current_tab)? else { return None };
+        let (AppTab::Map(MapTab{id, ..}) | AppTab::MapMeta(id) | AppTab::MapFlags(id)) = source.tabs.get(source.current_tab)? else { return None };
         Some(&source.loaded_maps.get(id).unwrap().data)
     })
 }
@@ -203,6 +203,75 @@ impl<T: 'static + PartialEq + Clone> Lens for CurrentSelectedEntitiesAllLens<T>
     }
 }
 
+/// Computes a value from the current room.
+#[derive(Clone)]
+pub struct CurrentRoomComputedLens<T> {
+    f: Arc<dyn 'static + Send + Sync + Fn(&AppState, &CelesteMapLevel) -> Option<T>>,
+}
+
+impl<T: 'static> CurrentRoomComputedLens<T> {
+    pub fn new<F: 'static + Send + Sync + Fn(&AppState, &CelesteMapLevel) -> Option<T>>(
+        f: F,
+    ) -> Self {
+        Self { f: Arc::new(f) }
+    }
+}
+
+impl<T: 'static> Lens for CurrentRoomComputedLens<T> {
+    type Source = AppState;
+    type Target = T;
+
+    fn view<O, F: FnOnce(Option<&Self::Target>) -> O>(&self, source: &Self::Source, map: F) -> O {
+        let result =
+            current_room_lens().view(source, |room| room.and_then(|room| (self.f)(source, room)));
+        map(result.as_ref())
+    }
+}
+
+/// Computes a value over every selected decal, failing unless they all agree. The closure also gets
+/// whether the decal is in the foreground.
+#[derive(Clone)]
+pub struct CurrentSelectedDecalsAllLens<T> {
+    f: Arc<dyn 'static + Send + Sync + Fn(&AppState, &CelesteMapDecal, bool) -> Option<T>>,
+}
+
+impl<T: 'static + PartialEq + Clone> CurrentSelectedDecalsAllLens<T> {
+    pub fn new_computed<
+        F: 'static + Send + Sync + Clone + Fn(&AppState, &CelesteMapDecal, bool) -> Option<T>,
+    >(
+        f: F,
+    ) -> Self {
+        Self { f: Arc::new(f) }
+    }
+}
+
+impl<T: 'static + PartialEq + Clone> Lens for CurrentSelectedDecalsAllLens<T> {
+    type Source = AppState;
+    type Target = T;
+
+    fn view<O, F: FnOnce(Option<&Self::Target>) -> O>(&self, source: &Self::Source, map: F) -> O {
+        let Some(AppTab::Map(MapTab {
+            id: map_id,
+            current_room,
+            current_selected,
+            ..
+        })) = source.tabs.get(source.current_tab) else { return map(None) };
+        let Some(room) = source.loaded_maps.get(map_id).and_then(|map| map.data.levels.get(*current_room)) else { return map(None) };
+
+        let mut decal_iter = current_selected
+            .iter()
+            .filter_map(AppSelection::decal_info)
+            .filter_map(|(id, fg)| room.decal(id, fg).map(|decal| (decal, fg)));
+        let Some((decal_first, fg_first)) = decal_iter.next() else { return map(None) };
+        let res_first = (self.f)(source, decal_first, fg_first);
+        if decal_iter.all(|(decal, fg)| res_first == (self.f)(source, decal, fg)) {
+            map(res_first.as_ref())
+        } else {
+            map(None)
+        }
+    }
+}
+
 pub enum CurrentSelectedEntitiesAttributesLens<T> {
     F1(Arc<dyn 'static + Send + Sync + Fn(&AppState, &HashSet<&String>) -> Option<T>>),
     F2(
@@ -798,23 +867,36 @@ impl Lens for StylegroundNameLens {
                     Attribute::Text(s) => s.to_owned(),
                 },
             )))
+        } else if source.is_effect() {
+            map(Some(&format!("{} (effect)", source.name)))
         } else {
             map(Some(&source.name))
         }
     }
 }
 
+/// The selected styleground, failing unless exactly one is selected.
 pub fn current_styleground_lens() -> impl Lens<Source = AppState, Target = StylegroundSelection> {
     ClosureLens::new(|source: &AppState| {
         let Some(AppTab::Map(maptab)) = source.tabs.get(source.current_tab) else { return None };
-        maptab.styleground_selected.as_ref()
+        let mut selected = maptab.styleground_selected.iter();
+        selected.next().filter(|_| selected.next().is_none())
+    })
+}
+
+pub fn current_styleground_set_lens(
+) -> impl Lens<Source = AppState, Target = HashSet<StylegroundSelection>> {
+    ClosureLens::new(|source: &AppState| {
+        let Some(AppTab::Map(maptab)) = source.tabs.get(source.current_tab) else { return None };
+        Some(&maptab.styleground_selected)
     })
 }
 
 pub fn current_styleground_impl_lens(
 ) -> impl Lens<Source = AppState, Target = CelesteMapStyleground> + Copy {
     ClosureLens::new(|source: &AppState| {
-        let AppTab::Map(MapTab { id, styleground_selected: Some(stysel),   ..}) = source.tabs.get(source.current_tab)? else { return None };
+        let stysel = current_styleground_lens().view(source, |sel| sel.copied())?;
+        let AppTab::Map(MapTab { id, .. }) = source.tabs.get(source.current_tab)? else { return None };
         source
             .loaded_maps
             .get(id)?
@@ -889,6 +971,14 @@ impl Lens for TabTextLens {
                     name.push_str(" - Meta");
                     name
                 }
+                AppTab::MapFlags(id) => {
+                    let mut name = source.loaded_maps.get(id).unwrap().cache.path.sid.clone();
+                    if source.loaded_maps.get(id).unwrap().cache.dirty {
+                        name.insert(0, '*');
+                    }
+                    name.push_str(" - Flags");
+                    name
+                }
             }))
         } else {
             map(None)
@@ -17,6 +17,15 @@ pub trait PaletteItem: 'static + Copy + Data + Debug + Send {
     const CAN_DRAW: bool = true;
     fn draw(&self, app: &AppState, canvas: &mut Canvas, other_name: &str);
     fn other() -> Self;
+    /// How many times this item is used in the current map, if that's meaningful for it.
+    fn usage_count(&self, _app: &AppState) -> Option<usize> {
+        None
+    }
+    /// The heading this item is listed under. Items of a group are expected to be next to each
+    /// other in the palette.
+    fn group(&self) -> Option<&'static str> {
+        None
+    }
 }
 
 impl PaletteItem for TileSelectable {
@@ -98,6 +107,7 @@ impl PaletteItem for EntitySelectable {
             &TileGrid::empty(),
             false,
             &TileGrid::empty(),
+            None,
         )
     }
 
@@ -107,6 +117,11 @@ impl PaletteItem for EntitySelectable {
             template: 0,
         }
     }
+
+    fn usage_count(&self, app: &AppState) -> Option<usize> {
+        let map = app.current_map_ref()?;
+        Some(map.data.count_entities(&self.entity, false))
+    }
 }
 
 impl PaletteItem for TriggerSelectable {
@@ -137,6 +152,11 @@ impl PaletteItem for TriggerSelectable {
             template: 0,
         }
     }
+
+    fn usage_count(&self, app: &AppState) -> Option<usize> {
+        let map = app.current_map_ref()?;
+        Some(map.data.count_entities(&self.trigger, true))
+    }
 }
 
 pub fn get_entity_config<'a>(this: &EntitySelectable, app: &'a AppState) -> &'a Arc<EntityConfig> {
@@ -198,6 +218,7 @@ pub fn instantiate_entity(
         height,
         nodes,
     };
+    fit_nodes(&mut entity, config.node_range());
     for (attr, info) in config.attribute_info.iter() {
         if !entity.attributes.contains_key(attr) {
             entity
@@ -209,6 +230,22 @@ pub fn instantiate_entity(
     entity
 }
 
+/// Give a new entity or trigger as many nodes as its config asks for, spaced out to the right of
+/// the last one, and drop any past the most it allows.
+fn fit_nodes(entity: &mut CelesteMapEntity, (min_nodes, max_nodes): (usize, usize)) {
+    entity.nodes.truncate(max_nodes);
+    while entity.nodes.len() < min_nodes {
+        let last = entity
+            .nodes
+            .last()
+            .map_or((entity.x, entity.y), |node| (node.x, node.y));
+        entity.nodes.push(Node {
+            x: last.0 + 16,
+            y: last.1,
+        });
+    }
+}
+
 pub fn get_trigger_config<'a>(
     this: &TriggerSelectable,
     app: &'a AppState,
@@ -264,6 +301,11 @@ pub fn instantiate_trigger(
         height,
         nodes,
     };
+    let palette = app.current_palette_unwrap();
+    fit_nodes(
+        &mut entity,
+        palette.get_entity_config(&entity.name, true).node_range(),
+    );
     for (attr, info) in config.attribute_info.iter() {
         if !entity.attributes.contains_key(attr) {
             entity
@@ -310,6 +352,10 @@ impl PaletteItem for DecalSelectable {
     fn other() -> Self {
         Self("arborio/other".into())
     }
+
+    fn group(&self) -> Option<&'static str> {
+        Some(DecalSelectable::group(self)).filter(|group| !group.is_empty())
+    }
 }
 
 pub fn instantiate_decal(
@@ -331,5 +377,7 @@ pub fn instantiate_decal(
         } else {
             this.0.to_string()
         },
+        depth: None,
+        rotation: 0.0,
     }
 }
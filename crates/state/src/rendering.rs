@@ -1,20 +1,28 @@
-use arborio_gfxloader::autotiler::{TextureTile, TileReference};
+use arborio_gfxloader::autotiler::{Autotiler, TextureTile, TileReference, Tileset};
 use arborio_maploader::map_struct::{
-    Attribute, CelesteMapDecal, CelesteMapEntity, CelesteMapLevel, CelesteMapStyleground,
-    FieldEntry,
+    Attribute, CelesteMap, CelesteMapDecal, CelesteMapEntity, CelesteMapLevel,
+    CelesteMapStyleground, FieldEntry,
 };
 use arborio_modloader::aggregate::ModuleAggregate;
 use arborio_modloader::config::{Const, DrawElement, EntityConfig, Number};
 use arborio_modloader::mapstruct_plus_config::{make_entity_env, make_node_env};
+use arborio_utils::interned::Interned;
 use arborio_utils::units::*;
 use arborio_utils::vizia::prelude::Canvas;
-use arborio_utils::vizia::vg::{Color, Paint, Path};
+use arborio_utils::vizia::vg::{
+    Align, Baseline, Color, ImageFlags, ImageId, Paint, Path, PixelFormat, RenderTarget,
+};
+use std::cell::RefCell;
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 
+use crate::data::flags::flag_condition_met;
+use crate::data::notes::MapNotes;
 use crate::data::project_map::LevelState;
 use crate::data::selection::AppSelection;
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_entity(
     config: &EntityConfig,
     palette: &ModuleAggregate,
@@ -23,40 +31,79 @@ pub fn draw_entity(
     field: &TileGrid<FieldEntry>,
     selected: bool,
     object_tiles: &TileGrid<i32>,
+    object_tileset: Option<Interned>,
 ) {
     let env = make_entity_env(entity);
 
+    let node_line = config
+        .standard_draw
+        .node_line_render
+        .as_ref()
+        .map(|render| render.line());
     for node_idx in 0..entity.nodes.len() {
-        for draw in &config.standard_draw.node_draw {
+        for draw in node_line.iter().chain(&config.standard_draw.node_draw) {
             let env = make_node_env(entity, env.clone(), node_idx);
-            if let Err(e) = draw_entity_directive(palette, canvas, draw, &env, field, object_tiles)
-            {
+            if let Err(e) = draw_entity_directive(
+                palette,
+                canvas,
+                draw,
+                &env,
+                field,
+                object_tiles,
+                object_tileset,
+            ) {
                 log::warn!("Error drawing {}: {}", &entity.name, e);
             }
         }
     }
 
     for draw in &config.standard_draw.initial_draw {
-        if let Err(e) = draw_entity_directive(palette, canvas, draw, &env, field, object_tiles) {
+        if let Err(e) = draw_entity_directive(
+            palette,
+            canvas,
+            draw,
+            &env,
+            field,
+            object_tiles,
+            object_tileset,
+        ) {
             log::warn!("Error drawing {}: {}", &entity.name, e);
         }
     }
 
     if selected {
+        let node_line = config
+            .selected_draw
+            .node_line_render
+            .as_ref()
+            .map(|render| render.line());
         for node_idx in 0..entity.nodes.len() {
-            for draw in &config.selected_draw.node_draw {
+            for draw in node_line.iter().chain(&config.selected_draw.node_draw) {
                 let env = make_node_env(entity, env.clone(), node_idx);
-                if let Err(e) =
-                    draw_entity_directive(palette, canvas, draw, &env, field, object_tiles)
-                {
+                if let Err(e) = draw_entity_directive(
+                    palette,
+                    canvas,
+                    draw,
+                    &env,
+                    field,
+                    object_tiles,
+                    object_tileset,
+                ) {
                     log::warn!("Error drawing {}: {}", &entity.name, e);
                 }
             }
         }
 
         for draw in &config.selected_draw.initial_draw {
-            if let Err(e) = draw_entity_directive(palette, canvas, draw, &env, field, object_tiles)
-            {
+            if let Err(e) = draw_entity_directive(
+                palette,
+                canvas,
+                draw,
+                &env,
+                field,
+                object_tiles,
+                object_tileset,
+            ) {
                 log::warn!("Error drawing {}: {}", &entity.name, e);
             }
         }
@@ -70,6 +117,7 @@ fn draw_entity_directive(
     env: &HashMap<&str, Const>,
     field: &TileGrid<FieldEntry>,
     object_tiles: &TileGrid<i32>,
+    object_tileset: Option<Interned>,
 ) -> Result<(), String> {
     match draw {
         DrawElement::DrawRect {
@@ -362,16 +410,11 @@ fn draw_entity_directive(
                             }
                         })
                     };
+                    let object_tileset = palette.gameplay_atlas.object_tileset(object_tileset);
                     for pt in rect_point_iter(tile_bounds, 1) {
                         let fp_pt = point_tile_to_room(&pt).cast::<f32>();
                         let tile_ref = if let Some(objtile_idx @ 1..) = object_tiles.get(pt) {
-                            TileReference {
-                                tile: TextureTile {
-                                    x: (*objtile_idx % 32) as u32,
-                                    y: (*objtile_idx / 32) as u32,
-                                },
-                                texture: "tilesets/scenery".into(), // TODO see similar TODO in selection.rs
-                            }
+                            object_tileset.tile(*objtile_idx)
                         } else {
                             let Some(tile) = tileset.tile(pt, &mut tiler) else { continue };
                             tile
@@ -402,6 +445,7 @@ fn draw_entity_directive(
                         &env2,
                         field,
                         object_tiles,
+                        object_tileset,
                     )?;
                 }
             }
@@ -466,15 +510,74 @@ where
     Ok(())
 }
 
-pub fn draw_decals(
-    palette: &ModuleAggregate,
-    canvas: &mut Canvas,
-    room: &CelesteMapLevel,
-    fg: bool,
-) {
-    let decals = if fg { &room.fg_decals } else { &room.bg_decals };
-    for decal in decals {
-        draw_decal(palette, canvas, decal);
+/// The depth the game draws the background tiles at. Higher depths are further back.
+pub const BG_TILES_DEPTH: i32 = 10000;
+/// The depth the game draws the foreground tiles at.
+pub const FG_TILES_DEPTH: i32 = -10000;
+/// Entity configs don't say what depth to draw at, so entities and triggers are all drawn as if
+/// they were at the player's.
+pub const ENTITIES_DEPTH: i32 = 0;
+
+/// Every decal in a room, fg and bg together, in the order they're drawn: deepest first. The sort
+/// is stable, so ties keep bg decals before fg ones and list order within each.
+pub fn decals_by_depth(room: &CelesteMapLevel) -> Vec<(&CelesteMapDecal, bool)> {
+    let mut decals = room
+        .bg_decals
+        .iter()
+        .map(|decal| (decal, false))
+        .chain(room.fg_decals.iter().map(|decal| (decal, true)))
+        .collect::<Vec<_>>();
+    decals.sort_by_key(|(decal, fg)| Reverse(decal.effective_depth(*fg)));
+    decals
+}
+
+/// Decals waiting to be drawn in between the other layers of a room.
+struct DecalQueue<'a>(std::iter::Peekable<std::vec::IntoIter<(&'a CelesteMapDecal, bool)>>);
+
+impl<'a> DecalQueue<'a> {
+    fn new(decals: Vec<(&'a CelesteMapDecal, bool)>) -> Self {
+        Self(decals.into_iter().peekable())
+    }
+
+    /// Draw the decals which are further back than `depth`. Decals at exactly a layer's depth go in
+    /// front of it.
+    fn draw_behind(&mut self, palette: &ModuleAggregate, canvas: &mut Canvas, depth: i32) {
+        let behind = |(decal, fg): &(&CelesteMapDecal, bool)| decal.effective_depth(*fg) > depth;
+        while let Some((decal, _)) = self.0.next_if(behind) {
+            draw_decal(palette, canvas, decal);
+        }
+    }
+}
+
+/// Where a selectable comes in the order a room is drawn, so of two overlapping selectables, the
+/// greater is drawn on top.
+pub fn draw_order(room: &CelesteMapLevel, sel: AppSelection) -> (Reverse<i32>, u8, usize) {
+    let entity_order = |id: i32, trigger: bool| {
+        let list = if trigger {
+            &room.triggers
+        } else {
+            &room.entities
+        };
+        let idx = list.iter().position(|e| e.id == id).unwrap_or_default();
+        (Reverse(ENTITIES_DEPTH), 1 + u8::from(!trigger), idx)
+    };
+    match sel {
+        AppSelection::BgTile(_) => (Reverse(BG_TILES_DEPTH), 0, 0),
+        AppSelection::BgFloat => (Reverse(BG_TILES_DEPTH), 0, 1),
+        AppSelection::FgTile(_) | AppSelection::ObjectTile(_) => (Reverse(FG_TILES_DEPTH), 0, 0),
+        AppSelection::FgFloat => (Reverse(FG_TILES_DEPTH), 0, 1),
+        AppSelection::ObjFloat => (Reverse(i32::MIN), u8::MAX, 0),
+        AppSelection::EntityBody(id, trigger) | AppSelection::EntityNode(id, _, trigger) => {
+            entity_order(id, trigger)
+        }
+        AppSelection::Decal(id, fg) => {
+            let decals = decals_by_depth(room);
+            let idx = decals
+                .iter()
+                .position(|(decal, decal_fg)| decal.id == id && *decal_fg == fg);
+            let depth = idx.map_or(0, |idx| decals[idx].0.effective_depth(fg));
+            (Reverse(depth), 3, idx.unwrap_or_default())
+        }
     }
 }
 
@@ -489,7 +592,7 @@ pub fn draw_decal(palette: &ModuleAggregate, canvas: &mut Canvas, decal: &Celest
         None,
         Some(scale),
         None,
-        0.0,
+        decal.rotation,
     ) {
         log::warn!("Failed drawing decal: {}", e);
         palette
@@ -502,7 +605,7 @@ pub fn draw_decal(palette: &ModuleAggregate, canvas: &mut Canvas, decal: &Celest
                 None,
                 Some(scale),
                 None,
-                0.0,
+                decal.rotation,
             )
             .unwrap();
     }
@@ -514,35 +617,301 @@ pub fn decal_texture(decal: &CelesteMapDecal) -> String {
     path.to_str().unwrap().to_owned()
 }
 
+/// One draw of a room's tile layer: either a single tile, or a run of center tiles of the same
+/// tileset along a row, which can be filled from that tileset's repeating pattern in one go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileDraw {
+    Tile(TilePoint, TileReference),
+    CenterRun {
+        tileset: char,
+        start: TilePoint,
+        len: i32,
+    },
+}
+
+/// Work out what to draw for one tile layer of a room. Center tiles are only merged into runs for
+/// the tileset characters `patterned` says have a pattern to fill them from.
+pub fn plan_tile_draws(
+    tiles_asset: &Autotiler,
+    room: &CelesteMapLevel,
+    fg: bool,
+    patterned: impl Fn(char) -> bool,
+) -> Vec<TileDraw> {
+    let tiles = if fg { &room.solids } else { &room.bg };
+    let mut draws = vec![];
+
+    // Most of a room is long runs of air or of a single tileset, so skip air outright and only
+    // look up the tileset when the character changes.
+    let mut last_tileset = None;
+    for ty in 0..room.bounds.height() / 8 {
+        let mut run: Option<(char, TilePoint, i32)> = None;
+        for tx in 0..room.bounds.width() / 8 {
+            let pt = TilePoint::new(tx, ty);
+            let tile = *tiles.get(pt).unwrap();
+            let drawn = if tile == '0' {
+                None
+            } else {
+                let tileset = match last_tileset {
+                    Some((ch, tileset)) if ch == tile => tileset,
+                    _ => {
+                        let tileset = tiles_asset.get(&tile).map(|t| (t, patterned(tile)));
+                        last_tileset = Some((tile, tileset));
+                        tileset
+                    }
+                };
+                tileset.and_then(|(tileset, patterned)| {
+                    tileset
+                        .tile_or_center(pt, &mut |pt| room.tile(pt, fg))
+                        .map(|(tile, center)| (tile, center && patterned))
+                })
+            };
+
+            match (drawn, &mut run) {
+                (Some((_, true)), Some((ch, _, len))) if *ch == tile => *len += 1,
+                (drawn, run) => {
+                    if let Some((tileset, start, len)) = run.take() {
+                        draws.push(TileDraw::CenterRun {
+                            tileset,
+                            start,
+                            len,
+                        });
+                    }
+                    match drawn {
+                        Some((_, true)) => *run = Some((tile, pt, 1)),
+                        Some((tile, false)) => draws.push(TileDraw::Tile(pt, tile)),
+                        None => {}
+                    }
+                }
+            }
+        }
+        if let Some((tileset, start, len)) = run {
+            draws.push(TileDraw::CenterRun {
+                tileset,
+                start,
+                len,
+            });
+        }
+    }
+
+    draws
+}
+
+struct CenterPattern {
+    atlas: ImageId,
+    center: Vec<TextureTile>,
+    image: ImageId,
+    size: f32,
+}
+
+thread_local! {
+    // keyed by texture as well as character since the autotilers are rebuilt whenever the palette
+    // is, and the fg and bg tilesets share characters
+    static CENTER_PATTERNS: RefCell<HashMap<(Interned, char), CenterPattern>> =
+        RefCell::new(HashMap::new());
+}
+
+/// The pattern image for a tileset's center tiles and how big it is, if one is ready.
+fn cached_center_pattern(
+    palette: &ModuleAggregate,
+    canvas: &mut Canvas,
+    ch: char,
+    tileset: &Tileset,
+) -> Option<(ImageId, f32)> {
+    let atlas = palette
+        .gameplay_atlas
+        .sprite_image(canvas, &tileset.texture)?;
+    CENTER_PATTERNS.with(|patterns| {
+        patterns
+            .borrow()
+            .get(&(tileset.texture, ch))
+            .filter(|pattern| pattern.atlas == atlas && pattern.center == tileset.center)
+            .map(|pattern| (pattern.image, pattern.size))
+    })
+}
+
+/// Render a repeating image of the center tiles of every tileset whose center tiles repeat, so the
+/// insides of solid areas can be filled in one draw instead of one per tile. Patterns which are
+/// already up to date are kept, and those of tilesets the palette doesn't have are deleted. This
+/// switches the render target, so it must be called while drawing to the screen, before any rooms
+/// are rendered.
+pub fn prepare_tile_patterns(palette: &ModuleAggregate, canvas: &mut Canvas) {
+    let mut used = HashSet::new();
+    for layer in ["fg", "bg"] {
+        let Some(tiles_asset) = palette.autotilers.get(layer) else {
+            continue;
+        };
+        for (ch, tileset) in tiles_asset.iter() {
+            let Some(period) = tileset.center_period() else {
+                continue;
+            };
+            used.insert((tileset.texture, *ch));
+            if cached_center_pattern(palette, canvas, *ch, tileset).is_some() {
+                continue;
+            }
+            let Some(atlas) = palette
+                .gameplay_atlas
+                .sprite_image(canvas, &tileset.texture)
+            else {
+                continue;
+            };
+            let size = period * 8;
+            let image = match canvas.create_image_empty(
+                size as usize,
+                size as usize,
+                PixelFormat::Rgba8,
+                ImageFlags::FLIP_Y
+                    | ImageFlags::NEAREST
+                    | ImageFlags::REPEAT_X
+                    | ImageFlags::REPEAT_Y,
+            ) {
+                Ok(image) => image,
+                Err(e) => {
+                    log::error!("Could not allocate tile pattern: {e}");
+                    continue;
+                }
+            };
+
+            canvas.save();
+            canvas.reset();
+            canvas.set_render_target(RenderTarget::Image(image));
+            canvas.clear_rect(0, 0, size as u32, size as u32, Color::rgba(0, 0, 0, 0));
+            for y in 0..period {
+                for x in 0..period {
+                    let pt = TilePoint::new(x, y);
+                    let Some(tile) = tileset.center_tile(pt) else {
+                        continue;
+                    };
+                    let tile = TileReference {
+                        tile,
+                        texture: tileset.texture,
+                    };
+                    if let Err(e) = palette.gameplay_atlas.draw_tile(
+                        canvas,
+                        tile,
+                        (x * 8) as f32,
+                        (y * 8) as f32,
+                        Color::white(),
+                    ) {
+                        log::error!("Failed drawing tile: {}", e);
+                    }
+                }
+            }
+            canvas.restore();
+            canvas.set_render_target(RenderTarget::Screen);
+
+            let old = CENTER_PATTERNS.with(|patterns| {
+                patterns.borrow_mut().insert(
+                    (tileset.texture, *ch),
+                    CenterPattern {
+                        atlas,
+                        center: tileset.center.clone(),
+                        image,
+                        size: size as f32,
+                    },
+                )
+            });
+            if let Some(old) = old {
+                canvas.delete_image(old.image);
+            }
+        }
+    }
+
+    // drop the patterns of tilesets which are gone, e.g. after switching to a map with other mods
+    let unused = CENTER_PATTERNS.with(|patterns| {
+        let mut patterns = patterns.borrow_mut();
+        let unused = patterns
+            .keys()
+            .filter(|key| !used.contains(*key))
+            .copied()
+            .collect::<Vec<_>>();
+        unused
+            .into_iter()
+            .filter_map(|key| patterns.remove(&key))
+            .collect::<Vec<_>>()
+    });
+    for pattern in unused {
+        canvas.delete_image(pattern.image);
+    }
+}
+
 pub fn draw_tiles(palette: &ModuleAggregate, canvas: &mut Canvas, room: &LevelState, fg: bool) {
-    let (tiles, tiles_asset) = if fg {
-        (&room.data.solids, palette.autotilers.get("fg").unwrap())
+    let tiles_asset = palette
+        .autotilers
+        .get(if fg { "fg" } else { "bg" })
+        .unwrap();
+
+    let patterns = tiles_asset
+        .iter()
+        .filter_map(|(ch, tileset)| {
+            cached_center_pattern(palette, canvas, *ch, tileset).map(|pattern| (*ch, pattern))
+        })
+        .collect::<HashMap<_, _>>();
+    let mut cache = room.cache.borrow_mut();
+    let planned = if fg {
+        &mut cache.fg_tile_draws
     } else {
-        (&room.data.bg, palette.autotilers.get("bg").unwrap())
+        &mut cache.bg_tile_draws
     };
+    // the plan only goes stale when the tiles change, or if a pattern it fills runs from is gone
+    let stale = planned.as_ref().map_or(true, |draws| {
+        draws.iter().any(|draw| {
+            matches!(draw, TileDraw::CenterRun { tileset, .. } if !patterns.contains_key(tileset))
+        })
+    });
+    if stale {
+        *planned = Some(plan_tile_draws(tiles_asset, &room.data, fg, |ch| {
+            patterns.contains_key(&ch)
+        }));
+    }
+    let draws = planned.as_deref().unwrap_or_default();
 
-    // TODO use point_iter
-    for ty in 0..room.data.bounds.height() / 8 {
-        for tx in 0..room.data.bounds.width() / 8 {
-            let pt = TilePoint::new(tx, ty);
-            let rx = (tx * 8) as f32;
-            let ry = (ty * 8) as f32;
-            let tile = tiles.get(pt).unwrap();
-            if let Some(tile) = tiles_asset
-                .get(tile)
-                .and_then(|tileset| tileset.tile(pt, &mut |pt| room.data.tile(pt, fg)))
-            {
-                if let Err(e) =
-                    palette
-                        .gameplay_atlas
-                        .draw_tile(canvas, tile, rx, ry, Color::white())
-                {
+    let mut runs: HashMap<char, Path> = HashMap::new();
+    for &draw in draws {
+        match draw {
+            TileDraw::Tile(pt, tile) => {
+                if let Err(e) = palette.gameplay_atlas.draw_tile(
+                    canvas,
+                    tile,
+                    (pt.x * 8) as f32,
+                    (pt.y * 8) as f32,
+                    Color::white(),
+                ) {
                     log::error!("Failed drawing tile: {}", e);
                 }
             }
+            TileDraw::CenterRun {
+                tileset,
+                start,
+                len,
+            } => {
+                runs.entry(tileset).or_default().rect(
+                    (start.x * 8) as f32,
+                    (start.y * 8) as f32,
+                    (len * 8) as f32,
+                    8.0,
+                );
+            }
         }
     }
+    for (ch, mut path) in runs {
+        let (image, size) = patterns[&ch];
+        canvas.fill_path(
+            &mut path,
+            &Paint::image(image, 0.0, 0.0, size, size, 0.0, 1.0),
+        );
+    }
+    drop(cache);
+
+    draw_tiles_float(palette, canvas, room, fg);
+}
 
+/// Draw the floating selection of a tile layer, if it has one.
+pub fn draw_tiles_float(
+    palette: &ModuleAggregate,
+    canvas: &mut Canvas,
+    room: &LevelState,
+    fg: bool,
+) {
     let float = if fg { &room.floats.fg } else { &room.floats.bg };
 
     if let Some((float_pos, float_dat)) = float {
@@ -575,26 +944,52 @@ pub fn draw_tiles(palette: &ModuleAggregate, canvas: &mut Canvas, room: &LevelSt
     }
 }
 
+/// If `active_flags` is given, entities whose config declares a flag condition which isn't met are
+/// dimmed.
 pub fn draw_entities(
     palette: &ModuleAggregate,
     canvas: &mut Canvas,
     room: &CelesteMapLevel,
     selection: &HashSet<AppSelection>,
+    active_flags: Option<&HashSet<String>>,
 ) {
     let field = room.occupancy_field();
     for entity in &room.entities {
         let selected = selection.contains(&AppSelection::EntityBody(entity.id, false))
             || (0..entity.nodes.len())
                 .any(|i| selection.contains(&AppSelection::EntityNode(entity.id, i, false)));
+        let config = palette.get_entity_config(&entity.name, false);
+        let hidden =
+            active_flags.map_or(false, |flags| !entity_condition_met(config, entity, flags));
+        if hidden {
+            canvas.save();
+            canvas.set_global_alpha(0.25);
+        }
         draw_entity(
-            palette.get_entity_config(&entity.name, false),
+            config,
             palette,
             canvas,
             entity,
             &field,
             selected,
             &room.object_tiles,
+            room.object_tileset,
         );
+        if hidden {
+            canvas.restore();
+        }
+    }
+}
+
+fn entity_condition_met(
+    config: &EntityConfig,
+    entity: &CelesteMapEntity,
+    flags: &HashSet<String>,
+) -> bool {
+    let Some(attr) = &config.condition_attribute else { return true };
+    match entity.attributes.get(attr) {
+        Some(Attribute::Text(value)) => flag_condition_met(value, flags),
+        _ => true,
     }
 }
 
@@ -616,10 +1011,141 @@ pub fn draw_triggers(
             &TileGrid::empty(),
             selected,
             &TileGrid::empty(),
+            None,
         );
     }
 }
 
+/// Draw arrows across the middle of each stretch of edge shared by two rooms, since the player can
+/// transition between those. Downwards transitions out of rooms which disable them are drawn in
+/// red. `scale` is the current zoom, so the arrows stay the same size on screen.
+pub fn draw_transitions(canvas: &mut Canvas, rooms: &[LevelState], scale: f32) {
+    let len = 10.0 / scale;
+    let gap = 4.0 / scale;
+    let allowed = Paint::color(Color::rgb(255, 255, 255)).with_line_width(2.0 / scale);
+    let blocked = Paint::color(Color::rgb(255, 64, 64)).with_line_width(2.0 / scale);
+    for (idx, first) in rooms.iter().enumerate() {
+        for second in &rooms[idx + 1..] {
+            let (first, second) = (&first.data, &second.data);
+            for (left, right) in [(first, second), (second, first)] {
+                let top = left.bounds.min_y().max(right.bounds.min_y());
+                let bottom = left.bounds.max_y().min(right.bounds.max_y());
+                if left.bounds.max_x() == right.bounds.min_x() && top < bottom {
+                    let x = left.bounds.max_x() as f32;
+                    let y = (top + bottom) as f32 / 2.0;
+                    draw_arrow(
+                        canvas,
+                        (x - len, y - gap),
+                        (x + len, y - gap),
+                        gap,
+                        &allowed,
+                    );
+                    draw_arrow(
+                        canvas,
+                        (x + len, y + gap),
+                        (x - len, y + gap),
+                        gap,
+                        &allowed,
+                    );
+                }
+            }
+            for (upper, lower) in [(first, second), (second, first)] {
+                let left = upper.bounds.min_x().max(lower.bounds.min_x());
+                let right = upper.bounds.max_x().min(lower.bounds.max_x());
+                if upper.bounds.max_y() == lower.bounds.min_y() && left < right {
+                    let x = (left + right) as f32 / 2.0;
+                    let y = upper.bounds.max_y() as f32;
+                    let down = if upper.disable_down_transition {
+                        &blocked
+                    } else {
+                        &allowed
+                    };
+                    draw_arrow(canvas, (x - gap, y - len), (x - gap, y + len), gap, down);
+                    draw_arrow(
+                        canvas,
+                        (x + gap, y + len),
+                        (x + gap, y - len),
+                        gap,
+                        &allowed,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Draw a line from `from` to `to` with an arrowhead, `head` long, at the `to` end.
+fn draw_arrow(canvas: &mut Canvas, from: (f32, f32), to: (f32, f32), head: f32, paint: &Paint) {
+    let dir = Vector2D::<f32, UnknownUnit>::new(to.0 - from.0, to.1 - from.1).normalize();
+    let head = dir * head;
+    let mut path = Path::new();
+    path.move_to(from.0, from.1);
+    path.line_to(to.0, to.1);
+    path.move_to(to.0 - head.x - head.y, to.1 - head.y + head.x);
+    path.line_to(to.0, to.1);
+    path.line_to(to.0 - head.x + head.y, to.1 - head.y - head.x);
+    canvas.stroke_path(&mut path, paint);
+}
+
+/// Mark the rooms which have notes with a triangle in their top right corner. `scale` is the current
+/// zoom, so the marks stay the same size on screen.
+pub fn draw_note_markers(canvas: &mut Canvas, rooms: &[LevelState], notes: &MapNotes, scale: f32) {
+    let size = 16.0 / scale;
+    let mut path = Path::new();
+    for room in rooms {
+        if notes.get(Some(&room.data.name)).is_empty() {
+            continue;
+        }
+        let bounds = room.data.bounds.cast::<f32>();
+        let size = size.min(bounds.width()).min(bounds.height());
+        path.move_to(bounds.max_x() - size, bounds.min_y());
+        path.line_to(bounds.max_x(), bounds.min_y());
+        path.line_to(bounds.max_x(), bounds.min_y() + size);
+        path.close();
+    }
+    canvas.fill_path(&mut path, &Paint::color(Color::rgb(255, 220, 64)));
+}
+
+/// When decluttering, labels are hidden entirely while zoomed out further than this.
+const LABEL_MIN_ZOOM: f32 = 0.5;
+
+/// Write the name of each trigger across its middle. `font_size` is in screen pixels and `scale` is
+/// the current zoom, so labels stay the same size on screen. When decluttering, labels which would
+/// overlap one already written are skipped.
+pub fn draw_trigger_labels(
+    canvas: &mut Canvas,
+    room: &CelesteMapLevel,
+    scale: f32,
+    font_size: f32,
+    declutter: bool,
+) {
+    if font_size <= 0.0 || (declutter && scale < LABEL_MIN_ZOOM) {
+        return;
+    }
+    let mut paint = Paint::color(Color::white());
+    paint.set_font_size(font_size / scale);
+    paint.set_text_align(Align::Center);
+    paint.set_text_baseline(Baseline::Middle);
+    let mut placed: Vec<Rect<f32, RoomSpace>> = vec![];
+    for trigger in &room.triggers {
+        let name = trigger.name.rsplit('/').next().unwrap_or(&trigger.name);
+        let x = trigger.x as f32 + trigger.width as f32 / 2.0;
+        let y = trigger.y as f32 + trigger.height as f32 / 2.0;
+        if declutter {
+            let Ok(metrics) = canvas.measure_text(x, y, name, &paint) else { continue };
+            let bounds = Rect::new(
+                Point2D::new(metrics.x, metrics.y),
+                Size2D::new(metrics.width(), metrics.height()),
+            );
+            if placed.iter().any(|other| other.intersects(&bounds)) {
+                continue;
+            }
+            placed.push(bounds);
+        }
+        let _ = canvas.fill_text(x, y, name, &paint);
+    }
+}
+
 pub fn draw_stylegrounds(
     palette: &ModuleAggregate,
     canvas: &mut Canvas,
@@ -636,7 +1162,13 @@ pub fn draw_stylegrounds(
         let mut color = parse_color(&bg.color).unwrap_or_else(Color::white);
         color.a = bg.alpha;
 
-        if bg.name == "parallax" {
+        if bg.is_effect() {
+            // we can't simulate these, so just tint the view to show that something is there
+            let mut path = Path::new();
+            path.rect(preview.x as f32, preview.y as f32, 320.0, 180.0);
+            color.a *= 0.1;
+            canvas.fill_path(&mut path, &Paint::color(color));
+        } else if bg.name == "parallax" {
             let posx = bg.x + preview.x as f32 * (1.0 - bg.scroll_x);
             let posy = bg.y + preview.y as f32 * (1.0 - bg.scroll_y);
             let texture = bg
@@ -710,19 +1242,16 @@ fn parse_color(color: &str) -> Option<Color> {
 pub fn draw_objtiles_float(palette: &ModuleAggregate, canvas: &mut Canvas, room: &LevelState) {
     let Some((float_pos, float_dat)) = &room.floats.obj else { return };
     let rect = TileRect::new(*float_pos, float_dat.size());
+    let tileset = palette
+        .gameplay_atlas
+        .object_tileset(room.data.object_tileset);
     for pt in rect_point_iter(rect, 1) {
         let float_pt = pt - float_pos.to_vector();
         let ch = float_dat.get_or_default(float_pt);
         if ch < 0 {
             continue;
         }
-        let tile = TileReference {
-            tile: TextureTile {
-                x: (ch % 32) as u32,
-                y: (ch / 32) as u32,
-            },
-            texture: "tilesets/scenery".into(), // TODO we shouldn't be doing this lookup during draw. cache this string statically?
-        };
+        let tile = tileset.tile(ch);
         let room_pos = point_tile_to_room(&pt);
         if let Err(e) = palette.gameplay_atlas.draw_tile(
             canvas,
@@ -735,3 +1264,418 @@ pub fn draw_objtiles_float(palette: &ModuleAggregate, canvas: &mut Canvas, room:
         }
     }
 }
+
+/// Draw every layer of a room, back to front, the same way the editor does. Decals are drawn by
+/// depth in between the tile layers and entities. Triggers can be left out since they're invisible
+/// in game.
+pub fn draw_room(
+    palette: &ModuleAggregate,
+    canvas: &mut Canvas,
+    room: &LevelState,
+    selected: &HashSet<AppSelection>,
+    triggers: bool,
+    active_flags: Option<&HashSet<String>>,
+) {
+    let mut decals = DecalQueue::new(decals_by_depth(&room.data));
+    decals.draw_behind(palette, canvas, BG_TILES_DEPTH);
+    draw_tiles(palette, canvas, room, false);
+    decals.draw_behind(palette, canvas, ENTITIES_DEPTH);
+    if triggers {
+        draw_triggers(palette, canvas, &room.data, selected);
+    }
+    draw_entities(palette, canvas, &room.data, selected, active_flags);
+    decals.draw_behind(palette, canvas, FG_TILES_DEPTH);
+    draw_tiles(palette, canvas, room, true);
+    decals.draw_behind(palette, canvas, i32::MIN);
+    draw_objtiles_float(palette, canvas, room);
+}
+
+/// Run `draw` against a transparent offscreen image of the given size and read back what it drew.
+///
+/// femtovg can only draw through OpenGL, so the caller supplies a canvas over any GL context (a
+/// hidden window or a surfaceless EGL context both work). Nothing is drawn to its screen, but it
+/// must be drawing to the screen when this is called.
+pub fn render_to_image(
+    canvas: &mut Canvas,
+    palette: &ModuleAggregate,
+    width: usize,
+    height: usize,
+    draw: impl FnOnce(&mut Canvas),
+) -> Result<image::RgbaImage, String> {
+    prepare_tile_patterns(palette, canvas);
+    let target = canvas
+        .create_image_empty(
+            width.max(1),
+            height.max(1),
+            PixelFormat::Rgba8,
+            ImageFlags::NEAREST | ImageFlags::FLIP_Y,
+        )
+        .map_err(|e| e.to_string())?;
+
+    canvas.save();
+    canvas.reset();
+    canvas.set_render_target(RenderTarget::Image(target));
+    canvas.clear_rect(0, 0, width as u32, height as u32, Color::rgba(0, 0, 0, 0));
+    draw(canvas);
+
+    let result = canvas.screenshot().map_err(|e| e.to_string());
+    canvas.restore();
+    canvas.set_render_target(RenderTarget::Screen);
+    canvas.delete_image(target);
+
+    let shot = result?;
+    let (w, h) = (shot.width() as u32, shot.height() as u32);
+    let pixels = shot
+        .pixels()
+        .flat_map(|px| [px.r, px.g, px.b, px.a])
+        .collect();
+    image::RgbaImage::from_raw(w, h, pixels).ok_or_else(|| "Bad screenshot size".to_owned())
+}
+
+/// Render a room to an image cropped to its bounds, with nothing selected. See [`render_to_image`]
+/// for what `canvas` needs to be.
+pub fn render_level_to_image(
+    canvas: &mut Canvas,
+    palette: &ModuleAggregate,
+    room: &LevelState,
+    scale: f32,
+    triggers: bool,
+    active_flags: Option<&HashSet<String>>,
+) -> Result<image::RgbaImage, String> {
+    let width = (room.data.bounds.width() as f32 * scale).ceil() as usize;
+    let height = (room.data.bounds.height() as f32 * scale).ceil() as usize;
+    let nil = HashSet::new();
+    render_to_image(canvas, palette, width, height, |canvas| {
+        canvas.scale(scale, scale);
+        draw_room(palette, canvas, room, &nil, triggers, active_flags);
+    })
+}
+
+/// Render one room of `map` to an image the same way the editor draws it, minus any selection.
+/// See [`render_to_image`] for what `canvas` needs to be.
+pub fn render_room_to_image(
+    canvas: &mut Canvas,
+    map: &CelesteMap,
+    palette: &ModuleAggregate,
+    room: usize,
+    scale: f32,
+) -> Result<image::RgbaImage, String> {
+    let level = map
+        .levels
+        .get(room)
+        .ok_or_else(|| format!("Map has no room {room}"))?;
+    let room = LevelState::from(level.clone());
+    render_level_to_image(canvas, palette, &room, scale, true, None)
+}
+
+/// Render just the selected objects of a room to a transparent image. It's cropped to `rects`, the
+/// areas they cover, grown to take in anything drawn past them such as sprites which overhang their
+/// hitboxes. Selected tiles come out autotiled the way they are in the room.
+pub fn render_selection_to_image(
+    canvas: &mut Canvas,
+    palette: &ModuleAggregate,
+    room: &LevelState,
+    selection: &HashSet<AppSelection>,
+    rects: &[RoomRect],
+) -> Result<image::RgbaImage, String> {
+    let room_rect = RoomRect::new(RoomPoint::zero(), room.data.bounds.size.cast_unit());
+    let crop = rects
+        .iter()
+        .copied()
+        .reduce(|a, b| a.union(&b))
+        .and_then(|rect| rect.intersection(&room_rect))
+        .ok_or_else(|| "Nothing selected to render".to_owned())?;
+    let full = render_to_image(
+        canvas,
+        palette,
+        room_rect.width() as usize,
+        room_rect.height() as usize,
+        |canvas| draw_selection(palette, canvas, room, selection),
+    )?;
+
+    // only the selection was drawn, so everything that isn't transparent belongs in the image
+    let crop = full
+        .enumerate_pixels()
+        .filter(|(_, _, px)| px.0[3] != 0)
+        .fold(crop, |crop, (x, y, _)| {
+            crop.union(&RoomRect::new(
+                RoomPoint::new(x as i32, y as i32),
+                RoomSize::new(1, 1),
+            ))
+        });
+    Ok(image::imageops::crop_imm(
+        &full,
+        crop.min_x() as u32,
+        crop.min_y() as u32,
+        crop.width() as u32,
+        crop.height() as u32,
+    )
+    .to_image())
+}
+
+/// Draw just the selected objects of a room, back to front.
+fn draw_selection(
+    palette: &ModuleAggregate,
+    canvas: &mut Canvas,
+    room: &LevelState,
+    selection: &HashSet<AppSelection>,
+) {
+    let entity_selected = |entity: &CelesteMapEntity, trigger: bool| {
+        selection.contains(&AppSelection::EntityBody(entity.id, trigger))
+            || (0..entity.nodes.len())
+                .any(|i| selection.contains(&AppSelection::EntityNode(entity.id, i, trigger)))
+    };
+    let mut decals = DecalQueue::new(
+        decals_by_depth(&room.data)
+            .into_iter()
+            .filter(|(decal, fg)| selection.contains(&AppSelection::Decal(decal.id, *fg)))
+            .collect(),
+    );
+
+    decals.draw_behind(palette, canvas, BG_TILES_DEPTH);
+    draw_selected_tiles(palette, canvas, room, selection, false);
+    decals.draw_behind(palette, canvas, ENTITIES_DEPTH);
+    let triggers = room.data.triggers.iter();
+    for trigger in triggers.filter(|trigger| entity_selected(trigger, true)) {
+        draw_entity(
+            palette.get_entity_config(&trigger.name, true),
+            palette,
+            canvas,
+            trigger,
+            &TileGrid::empty(),
+            false,
+            &TileGrid::empty(),
+            None,
+        );
+    }
+    let field = room.data.occupancy_field();
+    let entities = room.data.entities.iter();
+    for entity in entities.filter(|entity| entity_selected(entity, false)) {
+        draw_entity(
+            palette.get_entity_config(&entity.name, false),
+            palette,
+            canvas,
+            entity,
+            &field,
+            false,
+            &room.data.object_tiles,
+            room.data.object_tileset,
+        );
+    }
+    decals.draw_behind(palette, canvas, FG_TILES_DEPTH);
+    draw_selected_tiles(palette, canvas, room, selection, true);
+    let object_tileset = palette
+        .gameplay_atlas
+        .object_tileset(room.data.object_tileset);
+    for sel in selection {
+        let AppSelection::ObjectTile(pt) = sel else {
+            continue;
+        };
+        let Some(idx @ 0..) = room.data.object_tiles.get(*pt).copied() else {
+            continue;
+        };
+        let room_pos = point_tile_to_room(pt);
+        if let Err(e) = palette.gameplay_atlas.draw_tile(
+            canvas,
+            object_tileset.tile(idx),
+            room_pos.x as f32,
+            room_pos.y as f32,
+            Color::white(),
+        ) {
+            log::error!("{}", e)
+        }
+    }
+    decals.draw_behind(palette, canvas, i32::MIN);
+    if selection.contains(&AppSelection::ObjFloat) {
+        draw_objtiles_float(palette, canvas, room);
+    }
+}
+
+/// Draw the selected tiles of one layer, autotiled against the rest of the room, and its floating
+/// selection if that's selected.
+fn draw_selected_tiles(
+    palette: &ModuleAggregate,
+    canvas: &mut Canvas,
+    room: &LevelState,
+    selection: &HashSet<AppSelection>,
+    fg: bool,
+) {
+    let tiles_asset = palette
+        .autotilers
+        .get(if fg { "fg" } else { "bg" })
+        .unwrap();
+    for sel in selection {
+        let pt = match (sel, fg) {
+            (AppSelection::FgTile(pt), true) | (AppSelection::BgTile(pt), false) => *pt,
+            _ => continue,
+        };
+        let Some(tile) = room.data.tile(pt, fg).and_then(|ch| {
+            tiles_asset
+                .get(&ch)?
+                .tile(pt, &mut |pt| room.data.tile(pt, fg))
+        }) else {
+            continue;
+        };
+        let room_pos = point_tile_to_room(&pt);
+        if let Err(e) = palette.gameplay_atlas.draw_tile(
+            canvas,
+            tile,
+            room_pos.x as f32,
+            room_pos.y as f32,
+            Color::white(),
+        ) {
+            log::error!("Failed drawing tile: {}", e);
+        }
+    }
+
+    let float = if fg {
+        AppSelection::FgFloat
+    } else {
+        AppSelection::BgFloat
+    };
+    if selection.contains(&float) {
+        draw_tiles_float(palette, canvas, room, fg);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A 200x200 room of one tileset with a 20x20 hole in it, and the tileset.
+    fn stress_room() -> (Autotiler, CelesteMapLevel) {
+        let xml = r#"<Data><Tileset id="a" path="dirt">
+            <set mask="padding" tiles="0,1"/>
+            <set mask="center" tiles="0,0;1,0;2,0;3,0"/>
+            <set mask="xxx-xxx-xxx" tiles="4,0"/>
+        </Tileset></Data>"#;
+        let tiler = Tileset::new(xml.as_bytes(), "").unwrap();
+
+        let size = TileSize::new(200, 200);
+        let mut room = CelesteMapLevel {
+            bounds: MapRectStrict::new(
+                MapPointStrict::new(0, 0),
+                MapSizeStrict::new(size.width * 8, size.height * 8),
+            ),
+            solids: TileGrid::new(size, 'a'),
+            ..Default::default()
+        };
+        let hole = TileRect::new(TilePoint::new(50, 50), TileSize::new(20, 20));
+        for pt in rect_point_iter(hole, 1) {
+            *room.solids.get_mut(pt).unwrap() = '0';
+        }
+        (tiler, room)
+    }
+
+    #[test]
+    fn center_runs_match_tiles() {
+        let (tiler, room) = stress_room();
+        let tileset = &tiler[&'a'];
+        let period = tileset.center_period().unwrap();
+
+        let single = plan_tile_draws(&tiler, &room, true, |_| false);
+        let batched = plan_tile_draws(&tiler, &room, true, |_| true);
+        // the measurement: one draw per tile against about one per row
+        assert_eq!(single.len(), 200 * 200 - 20 * 20);
+        assert!(batched.len() < single.len() / 50, "{}", batched.len());
+
+        let drawn = |draws: &[TileDraw]| {
+            let mut drawn = HashMap::new();
+            for draw in draws {
+                match *draw {
+                    TileDraw::Tile(pt, tile) => {
+                        drawn.insert(pt, tile.tile);
+                    }
+                    TileDraw::CenterRun {
+                        tileset: ch,
+                        start,
+                        len,
+                    } => {
+                        assert_eq!(ch, 'a');
+                        for x in start.x..start.x + len {
+                            // what the repeating pattern has there
+                            let pattern = TilePoint::new(x % period, start.y % period);
+                            drawn.insert(
+                                TilePoint::new(x, start.y),
+                                tileset.center_tile(pattern).unwrap(),
+                            );
+                        }
+                    }
+                }
+            }
+            drawn
+        };
+        assert_eq!(drawn(&single), drawn(&batched));
+    }
+
+    /// Not run by default since it only prints timings. Run it with
+    /// `cargo test --release -p arborio-state bench_tile_draws -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_tile_draws() {
+        let (tiler, room) = stress_room();
+        let runs = 100;
+
+        // what redrawing a room used to pay even when its tiles hadn't changed
+        let start = std::time::Instant::now();
+        for _ in 0..runs {
+            std::hint::black_box(plan_tile_draws(&tiler, &room, true, |_| true));
+        }
+        let planning = start.elapsed() / runs;
+
+        // what it pays now: checking the cached plan still has its patterns
+        let cached = plan_tile_draws(&tiler, &room, true, |_| true);
+        let patterns = HashSet::from(['a']);
+        let start = std::time::Instant::now();
+        for _ in 0..runs {
+            std::hint::black_box(cached.iter().any(|draw| {
+                matches!(draw, TileDraw::CenterRun { tileset, .. } if !patterns.contains(tileset))
+            }));
+        }
+        let checking = start.elapsed() / runs;
+
+        println!("200x200 room: planning {planning:?}, reusing the cached plan {checking:?}");
+    }
+
+    #[test]
+    fn decals_are_ordered_across_layers() {
+        let decal = |id, depth| CelesteMapDecal {
+            id,
+            x: 0,
+            y: 0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            texture: String::new(),
+            depth,
+            rotation: 0.0,
+        };
+        let room = CelesteMapLevel {
+            bg_decals: vec![decal(1, None), decal(4, Some(-11000))],
+            fg_decals: vec![decal(2, Some(9500)), decal(3, None)],
+            ..Default::default()
+        };
+        let ids = decals_by_depth(&room)
+            .iter()
+            .map(|(decal, _)| decal.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![2, 1, 3, 4]);
+
+        // from the back to the front, as drawn
+        let order = [
+            AppSelection::BgTile(TilePoint::zero()),
+            AppSelection::Decal(2, true),
+            AppSelection::Decal(1, false),
+            AppSelection::FgTile(TilePoint::zero()),
+            AppSelection::Decal(3, true),
+            AppSelection::Decal(4, false),
+        ];
+        for pair in order.windows(2) {
+            assert!(
+                draw_order(&room, pair[0]) < draw_order(&room, pair[1]),
+                "{:?} should be behind {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+}
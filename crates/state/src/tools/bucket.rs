@@ -10,7 +10,10 @@ use arborio_utils::vizia::vg::{Color, Paint, Path};
 use std::collections::{HashSet, VecDeque};
 
 #[derive(Default)]
-pub struct BucketTool {}
+pub struct BucketTool {
+    /// Whether shift was held as of the last event, making the fill spread diagonally too.
+    diagonal: bool,
+}
 
 impl Tool for BucketTool {
     fn event(&mut self, event: &WindowEvent, cx: &mut EventContext) -> Vec<AppEvent> {
@@ -24,26 +27,36 @@ impl Tool for BucketTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos = app
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos)
             .cast();
         let room_pos = (map_pos - room.data.bounds.origin).to_point().cast_unit();
         let tile_pos = point_room_to_tile(&room_pos);
+        self.diagonal = cx.modifiers.contains(Modifiers::SHIFT);
 
         if matches!(event, WindowEvent::MouseDown(MouseButton::Left))
             && matches!(app.current_layer, Layer::FgTiles | Layer::BgTiles)
         {
             let fg = matches!(app.current_layer, Layer::FgTiles);
-            let tiles = bucket_it(room, fg, tile_pos);
+            let tiles = bucket_it(room, fg, tile_pos, self.diagonal);
             let mut result_float = None;
             let ch = if fg {
                 app.current_fg_tile.id
             } else {
                 app.current_bg_tile.id
             };
+            let config = &app.config;
+            let second = config
+                .dither
+                .then(|| config.dither_tile.chars().next())
+                .flatten();
             for tile in tiles {
+                let ch = match second {
+                    Some(second) if dither_pick(config.dither_seed, tile, config.dither_ratio) => {
+                        second
+                    }
+                    _ => ch,
+                };
                 add_to_float(&mut result_float, tile, Some(&ch), '\0');
             }
             if let Some((offset, data)) = result_float {
@@ -77,9 +90,7 @@ impl Tool for BucketTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos = state
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos)
             .cast();
         let room_pos = (map_pos - room.data.bounds.origin).to_point().cast_unit();
@@ -93,6 +104,7 @@ impl Tool for BucketTool {
             room,
             matches!(state.current_layer, Layer::FgTiles),
             tile_pos,
+            self.diagonal,
         );
         let mut path = Path::new();
         for tile in tiles {
@@ -102,6 +114,10 @@ impl Tool for BucketTool {
         canvas.fill_path(&mut path, &Paint::color(Color::rgba(255, 0, 255, 128)));
         canvas.restore();
     }
+
+    fn cursor(&self, _cx: &mut EventContext) -> CursorIcon {
+        CursorIcon::Crosshair
+    }
 }
 
 impl BucketTool {
@@ -110,12 +126,20 @@ impl BucketTool {
     }
 }
 
-fn bucket_it(room: &LevelState, fg: bool, tile_pos: TilePoint) -> HashSet<TilePoint> {
+/// Find the region of tiles matching the one at `tile_pos` which are connected to it, either only
+/// orthogonally or, if `diagonal` is set, through corners as well.
+pub fn bucket_it(
+    room: &LevelState,
+    fg: bool,
+    tile_pos: TilePoint,
+    diagonal: bool,
+) -> HashSet<TilePoint> {
     let Some(desired_char) = room.tile(tile_pos, fg) else { return HashSet::new() };
 
     let mut result = HashSet::new();
     let mut queue = VecDeque::from([tile_pos]);
     while let Some(pt) = queue.pop_front() {
+        // tile() has no tiles past the room's edges, so even the diagonal fill stops there
         if room.tile(pt, fg) != Some(desired_char) {
             continue;
         }
@@ -129,6 +153,28 @@ fn bucket_it(room: &LevelState, fg: bool, tile_pos: TilePoint) -> HashSet<TilePo
             pt + TileVector::new(0, -1),
         ];
         queue.extend(children.into_iter());
+        if diagonal {
+            let corners = [
+                pt + TileVector::new(1, 1),
+                pt + TileVector::new(-1, 1),
+                pt + TileVector::new(-1, -1),
+                pt + TileVector::new(1, -1),
+            ];
+            queue.extend(corners.into_iter());
+        }
     }
     result
 }
+
+/// Whether the tile at `pt` should take the secondary tile of a dithered fill. This is a hash of
+/// the seed and position rather than a running rng so that the pattern doesn't depend on the order
+/// tiles are visited in, and so that filling adjacent regions with the same seed lines up.
+pub fn dither_pick(seed: u64, pt: TilePoint, ratio: f32) -> bool {
+    // splitmix64
+    let pos = ((pt.x as u32 as u64) << 32) | pt.y as u32 as u64;
+    let mut x = seed.wrapping_add(0x9e3779b97f4a7c15) ^ pos;
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^= x >> 31;
+    ((x >> 40) as f32 / (1u64 << 24) as f32) < ratio
+}
@@ -38,9 +38,7 @@ impl Tool for HandTool {
                     let screen_delta = screen_pt - self.last_pos.unwrap();
                     let map_pan = state
                         .map_tab_unwrap()
-                        .transform
-                        .inverse()
-                        .unwrap()
+                        .inverse_transform()
                         .transform_vector(screen_delta);
                     self.last_pos = Some(screen_pt);
                     vec![AppEvent::Pan {
@@ -55,4 +53,12 @@ impl Tool for HandTool {
             _ => vec![],
         }
     }
+
+    fn cursor(&self, _cx: &mut EventContext) -> CursorIcon {
+        if self.last_pos.is_some() {
+            CursorIcon::Grabbing
+        } else {
+            CursorIcon::Grab
+        }
+    }
 }
@@ -9,6 +9,8 @@ use arborio_utils::vizia::prelude::*;
 use enum_iterator::Sequence;
 
 use crate::data::app::{AppEvent, AppInternalEvent, AppState};
+use crate::data::selection::AppSelection;
+use crate::data::AppConfig;
 use arborio_utils::units::*;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Sequence, Data)]
@@ -33,6 +35,20 @@ impl ToolSpec {
         }
     }
 
+    // the number keys select tools in toolbar order
+    pub fn from_hotkey(code: Code) -> Option<ToolSpec> {
+        let idx = match code {
+            Code::Digit1 => 0,
+            Code::Digit2 => 1,
+            Code::Digit3 => 2,
+            Code::Digit4 => 3,
+            Code::Digit5 => 4,
+            Code::Digit6 => 5,
+            _ => return None,
+        };
+        enum_iterator::all::<ToolSpec>().nth(idx)
+    }
+
     pub fn switch_on(&self, app: &AppState) -> Box<dyn Tool> {
         match self {
             ToolSpec::Hand => Box::new(hand::HandTool::new()),
@@ -61,10 +77,69 @@ pub trait Tool {
     fn cursor(&self, cx: &mut EventContext) -> CursorIcon {
         CursorIcon::Default
     }
+
+    /// Called on right click. Returns the events to apply right away and the actions to offer in
+    /// a context menu at the cursor. Tools which return None get the click as a normal event.
+    fn context_menu(
+        &mut self,
+        cx: &mut EventContext,
+    ) -> Option<(Vec<AppEvent>, Vec<ContextAction>)> {
+        None
+    }
+
+    fn context_action(&mut self, action: ContextAction, cx: &mut EventContext) -> Vec<AppEvent> {
+        vec![]
+    }
+}
+
+/// An entry in the editor's right click menu.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ContextAction {
+    Copy,
+    Delete,
+    /// Select just this entity or decal so that the tweaker shows its properties alone.
+    Properties(AppSelection),
+    SendToBack,
+    ToggleLock,
+    /// Remove one node of an entity or trigger, by id, node index and whether it's a trigger.
+    RemoveNode(i32, usize, bool),
+    PasteHere(RoomPoint),
+    NewRoom(MapPointStrict),
+}
+
+impl ContextAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContextAction::Copy => "Copy",
+            ContextAction::Delete => "Delete",
+            ContextAction::Properties(_) => "Properties",
+            ContextAction::SendToBack => "Send to back",
+            ContextAction::ToggleLock => "Lock / unlock",
+            ContextAction::RemoveNode(..) => "Remove node",
+            ContextAction::PasteHere(_) => "Paste here",
+            ContextAction::NewRoom(_) => "New room",
+        }
+    }
 }
 
 pub const SCROLL_SENSITIVITY: f32 = 35.0;
 
+/// Turn a scroll wheel event into the amount to pan the view by, in screen space. Every view which
+/// pans on scroll should go through this so they all agree on direction.
+pub fn scroll_vector(x: f32, y: f32, modifiers: &Modifiers, config: &AppConfig) -> ScreenVector {
+    let (x, y) = if modifiers.contains(Modifiers::SHIFT) {
+        (y, x)
+    } else {
+        (x, y)
+    };
+    let vec = ScreenVector::new(x, y) * SCROLL_SENSITIVITY;
+    if config.natural_scroll {
+        -vec
+    } else {
+        vec
+    }
+}
+
 pub fn generic_nav(
     event: &WindowEvent,
     state: &AppState,
@@ -79,38 +154,49 @@ pub fn generic_nav(
                 delta: y.exp(),
                 focus: state
                     .map_tab_unwrap()
-                    .transform
-                    .inverse()
-                    .unwrap()
+                    .inverse_transform()
                     .transform_point(screen_pt),
             }]
         }
         WindowEvent::MouseScroll(x, y) if !cx.modifiers.contains(Modifiers::CTRL) => {
-            let (x, y) = if cx.modifiers.contains(Modifiers::SHIFT) {
-                (y, x)
-            } else {
-                (x, y)
-            };
-            let screen_vec = ScreenVector::new(*x, *y) * SCROLL_SENSITIVITY;
+            let screen_vec = scroll_vector(*x, *y, cx.modifiers, &state.config);
             let map_vec = state
                 .map_tab_unwrap()
-                .transform
-                .inverse()
-                .unwrap()
+                .inverse_transform()
                 .transform_vector(screen_vec);
             vec![AppEvent::Pan {
                 tab: state.current_tab,
                 delta: map_vec,
             }]
         }
+        WindowEvent::KeyDown(
+            code @ (Code::ArrowUp | Code::ArrowDown | Code::ArrowLeft | Code::ArrowRight),
+            _,
+        ) if room && cx.modifiers == &Modifiers::CTRL => {
+            let direction = match code {
+                Code::ArrowUp => MapVectorPrecise::new(0.0, -1.0),
+                Code::ArrowDown => MapVectorPrecise::new(0.0, 1.0),
+                Code::ArrowLeft => MapVectorPrecise::new(-1.0, 0.0),
+                _ => MapVectorPrecise::new(1.0, 0.0),
+            };
+            let map_tab = state.map_tab_unwrap();
+            state
+                .loaded_maps
+                .get(&map_tab.id)
+                .and_then(|map| map.data.level_in_direction(map_tab.current_room, direction))
+                .map(|idx| AppEvent::SelectRoom {
+                    tab: state.current_tab,
+                    idx,
+                })
+                .into_iter()
+                .collect()
+        }
         WindowEvent::MouseDown(btn) if *btn == MouseButton::Left => {
             if room {
                 if let Some(map) = state.loaded_maps.get(&state.map_tab_unwrap().id) {
                     let map_pt = state
                         .map_tab_unwrap()
-                        .transform
-                        .inverse()
-                        .unwrap()
+                        .inverse_transform()
                         .transform_point(screen_pt)
                         .cast();
                     if let Some(idx) = map.level_at(map_pt) {
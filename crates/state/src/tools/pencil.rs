@@ -1,9 +1,10 @@
 use arborio_utils::vizia::prelude::*;
-use arborio_utils::vizia::vg::{Color, Paint, Path};
+use arborio_utils::vizia::vg::{Align, Baseline, Color, Paint, Path};
 
 use crate::data::action::RoomAction;
 use crate::data::app::{AppEvent, AppState};
-use crate::data::{EventPhase, Layer};
+use crate::data::project_map::MapEvent;
+use crate::data::{EventPhase, Layer, SnapMode};
 use crate::palette_item::{
     get_entity_config, instantiate_decal, instantiate_entity, instantiate_trigger,
 };
@@ -12,11 +13,24 @@ use crate::rendering::draw_decal;
 use crate::tools::{generic_nav, Tool};
 use arborio_maploader::map_struct::{CelesteMapEntity, Node};
 use arborio_modloader::config::PencilBehavior;
-use arborio_modloader::selectable::{EntitySelectable, TriggerSelectable};
+use arborio_modloader::selectable::{DecalSelectable, EntitySelectable, TriggerSelectable};
 use arborio_utils::units::*;
 
+/// The size of a trigger placed by clicking without dragging out a rectangle.
+const DEFAULT_TRIGGER_SIZE: i32 = 16;
+/// The largest brush for painting tiles, in tiles across. Brushes are always an odd size so they
+/// can be centered on the cursor.
+const MAX_BRUSH_SIZE: i32 = 5;
+
 pub struct PencilTool {
     reference_point: Option<RoomPoint>,
+    stroke_start: Option<TilePoint>,
+    straight_line: bool,
+    /// The width and height in tiles of the block painted on tile layers.
+    brush_size: i32,
+    /// The mouse went down to eyedrop rather than to draw, so drags and releases are ignored
+    /// until it next goes down without alt.
+    eyedropping: bool,
     draw_phase: EventPhase,
 }
 
@@ -24,6 +38,10 @@ impl PencilTool {
     pub fn new() -> Self {
         Self {
             reference_point: None,
+            stroke_start: None,
+            straight_line: false,
+            brush_size: 1,
+            eyedropping: false,
             draw_phase: EventPhase::null(),
         }
     }
@@ -37,6 +55,19 @@ impl Default for PencilTool {
 
 impl Tool for PencilTool {
     fn event(&mut self, event: &WindowEvent, cx: &mut EventContext) -> Vec<AppEvent> {
+        if let WindowEvent::KeyDown(code @ (Code::BracketLeft | Code::BracketRight), _) = event {
+            let layer = cx.data::<AppState>().unwrap().current_layer;
+            if is_tile_layer(layer) {
+                self.brush_size = if *code == Code::BracketLeft {
+                    (self.brush_size - 2).max(1)
+                } else {
+                    (self.brush_size + 2).min(MAX_BRUSH_SIZE)
+                };
+                cx.needs_redraw();
+                return vec![];
+            }
+        }
+
         let app = cx.data::<AppState>().unwrap();
         let events = generic_nav(event, app, cx, true);
         if !events.is_empty() {
@@ -47,23 +78,51 @@ impl Tool for PencilTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos = app
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos)
             .cast();
         let room_pos = (map_pos - room.data.bounds.origin).to_point().cast_unit();
-        match event {
+        self.straight_line = cx.modifiers.contains(Modifiers::SHIFT);
+        let mut events = match event {
+            WindowEvent::MouseDown(MouseButton::Left)
+                if cx.modifiers.contains(Modifiers::ALT) && is_tile_layer(app.current_layer) =>
+            {
+                self.eyedropping = true;
+                return eyedrop_tile(app, point_room_to_tile(&room_pos));
+            }
             WindowEvent::MouseDown(MouseButton::Left) => {
+                self.eyedropping = false;
                 self.do_draw_start(app, room_pos);
-                self.do_draw(app, room_pos)
+                let mut events = vec![];
+                if app.config.select_placed && places_objects(app.current_layer) {
+                    // start the stroke from an empty selection so we end up with just what it placed
+                    events.push(AppEvent::ClearSelection {
+                        tab: app.current_tab,
+                    });
+                }
+                events.extend(self.do_draw(app, room_pos));
+                events
             }
+            WindowEvent::MouseMove(..) | WindowEvent::MouseUp(_) if self.eyedropping => vec![],
             WindowEvent::MouseMove(..) if cx.mouse.left.state == MouseButtonState::Pressed => {
                 self.do_draw(app, room_pos)
             }
             WindowEvent::MouseUp(MouseButton::Left) => self.do_draw_finish(app, room_pos),
             _ => vec![],
+        };
+        if !app.config.select_placed {
+            // leave the selection as it was instead of adding what was placed to it
+            for event in events.iter_mut() {
+                if let AppEvent::MapEvent {
+                    event: MapEvent::Action { select_added, .. },
+                    ..
+                } = event
+                {
+                    *select_added = false;
+                }
+            }
         }
+        events
     }
 
     fn switch_off(&mut self, app: &AppState, cx: &EventContext) -> Vec<AppEvent> {
@@ -71,9 +130,7 @@ impl Tool for PencilTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos = app
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos)
             .cast();
         let room_pos = (map_pos - room.data.bounds.origin).to_point().cast_unit();
@@ -98,30 +155,51 @@ impl Tool for PencilTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos = state
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos)
             .cast();
         let room_pos = (map_pos - room.data.bounds.origin).to_point().cast_unit();
         let tile_pos = point_room_to_tile(&room_pos);
+        let tile_pos = if is_tile_layer(state.current_layer) {
+            self.constrain_tile(tile_pos)
+        } else {
+            tile_pos
+        };
         let room_pos_snapped = point_tile_to_room(&tile_pos);
-        let room_pos = if state.config.snap {
+        let room_pos = if is_tile_layer(state.current_layer) {
             room_pos_snapped
         } else {
-            room_pos
+            state.config.snap.snap(room_pos)
         };
 
         match state.current_layer {
             Layer::FgTiles | Layer::BgTiles | Layer::ObjectTiles => {
-                let mut path = Path::new();
-                path.rect(
-                    room_pos_snapped.x as f32,
-                    room_pos_snapped.y as f32,
-                    8.0,
-                    8.0,
-                );
-                canvas.fill_path(&mut path, &Paint::color(Color::rgba(255, 0, 255, 128)));
+                if let Some(brush) = self.brush_rect(state, tile_pos) {
+                    let brush = rect_tile_to_room(&brush);
+                    let mut path = Path::new();
+                    path.rect(
+                        brush.min_x() as f32,
+                        brush.min_y() as f32,
+                        brush.width() as f32,
+                        brush.height() as f32,
+                    );
+                    canvas.fill_path(&mut path, &Paint::color(Color::rgba(255, 0, 255, 128)));
+                }
+                if let Some(rect) = self.tile_rect(state, room_pos_snapped) {
+                    let rect = rect_tile_to_room(&rect);
+                    let mut path = Path::new();
+                    path.rect(
+                        rect.min_x() as f32,
+                        rect.min_y() as f32,
+                        rect.width() as f32,
+                        rect.height() as f32,
+                    );
+                    canvas.stroke_path(
+                        &mut path,
+                        &Paint::color(Color::rgb(255, 0, 255))
+                            .with_line_width(2.0 / state.map_tab_unwrap().transform.m11),
+                    );
+                }
             }
             Layer::Entities => {
                 let tmp_entity = self.get_terminal_entity(state, state.current_entity, room_pos);
@@ -136,6 +214,7 @@ impl Tool for PencilTool {
                     &TileGrid::empty(),
                     false,
                     &room.data.object_tiles,
+                    room.data.object_tileset,
                 );
             }
             Layer::Triggers => {
@@ -151,7 +230,22 @@ impl Tool for PencilTool {
                     &TileGrid::empty(),
                     false,
                     &TileGrid::empty(),
+                    None,
                 );
+                if self.reference_point.is_some() {
+                    canvas.set_global_alpha(1.0);
+                    let scale = state.map_tab_unwrap().transform.m11;
+                    let mut paint = Paint::color(Color::white());
+                    paint.set_font_size(12.0 / scale);
+                    paint.set_text_align(Align::Left);
+                    paint.set_text_baseline(Baseline::Top);
+                    let _ = canvas.fill_text(
+                        (tmp_trigger.x + tmp_trigger.width as i32) as f32 + 2.0 / scale,
+                        (tmp_trigger.y + tmp_trigger.height as i32) as f32 + 2.0 / scale,
+                        format!("{}x{}", tmp_trigger.width, tmp_trigger.height),
+                        &paint,
+                    );
+                }
             }
             Layer::FgDecals | Layer::BgDecals => {
                 if cx.mouse.left.state == MouseButtonState::Released {
@@ -172,12 +266,88 @@ impl Tool for PencilTool {
 
         canvas.restore();
     }
+
+    fn cursor(&self, _cx: &mut EventContext) -> CursorIcon {
+        CursorIcon::Crosshair
+    }
+}
+
+fn is_tile_layer(layer: Layer) -> bool {
+    matches!(layer, Layer::FgTiles | Layer::BgTiles | Layer::ObjectTiles)
+}
+
+/// Select the tile under the cursor in the palette of the current tile layer. Empty tiles, and
+/// tiles the palette doesn't know about, are ignored.
+fn eyedrop_tile(app: &AppState, tile_pos: TilePoint) -> Vec<AppEvent> {
+    let Some(room) = app.current_room_ref() else { return vec![] };
+    let event = match app.current_layer {
+        Layer::FgTiles | Layer::BgTiles => {
+            let fg = app.current_layer == Layer::FgTiles;
+            let Some(ch) = room.tile(tile_pos, fg).filter(|&ch| ch != '0') else { return vec![] };
+            let palette = app.current_palette_unwrap();
+            let tiles = if fg {
+                &palette.fg_tiles_palette
+            } else {
+                &palette.bg_tiles_palette
+            };
+            let Some(&tile) = tiles.iter().find(|tile| tile.id == ch) else { return vec![] };
+            AppEvent::Eyedrop {
+                event: Box::new(AppEvent::SelectPaletteTile { fg, tile }),
+                description: format!("Picked {}", tile.name),
+            }
+        }
+        Layer::ObjectTiles => {
+            let id = room.data.object_tiles.get(tile_pos).copied().unwrap_or(-1);
+            if id < 0 {
+                return vec![];
+            }
+            AppEvent::Eyedrop {
+                event: Box::new(AppEvent::SelectPaletteObjectTile { tile: id as u32 }),
+                description: format!("Picked object tile {id}"),
+            }
+        }
+        _ => return vec![],
+    };
+    vec![event]
+}
+
+/// The tile the pencil paints with on the given tile layer.
+fn current_tile(app: &AppState, fg: bool) -> char {
+    let ch = if fg {
+        app.current_fg_tile
+    } else {
+        app.current_bg_tile
+    };
+    let other = if fg {
+        &app.current_fg_tile_other
+    } else {
+        &app.current_bg_tile_other
+    }
+    .chars()
+    .next()
+    .unwrap_or('0');
+    if ch.id == '\0' {
+        other
+    } else {
+        ch.id
+    }
+}
+
+fn places_objects(layer: Layer) -> bool {
+    matches!(
+        layer,
+        Layer::Entities | Layer::Triggers | Layer::FgDecals | Layer::BgDecals
+    )
 }
 
 impl PencilTool {
     fn do_draw_start(&mut self, app: &AppState, room_pos: RoomPoint) {
         self.draw_phase = EventPhase::new();
+        self.stroke_start = Some(point_room_to_tile(&room_pos));
         match app.current_layer {
+            Layer::FgTiles | Layer::BgTiles if app.config.tile_pencil == PencilBehavior::Rect => {
+                self.reference_point = Some(point_tile_to_room(&point_room_to_tile(&room_pos)));
+            }
             Layer::Entities | Layer::Triggers => {
                 let pencil = if app.current_layer == Layer::Triggers {
                     PencilBehavior::Rect
@@ -187,13 +357,7 @@ impl PencilTool {
                 match pencil {
                     PencilBehavior::Line => {}
                     PencilBehavior::Node | PencilBehavior::Rect => {
-                        let room_pos = if app.config.snap {
-                            let tile_pos = point_room_to_tile(&room_pos);
-                            point_tile_to_room(&tile_pos)
-                        } else {
-                            room_pos
-                        };
-                        self.reference_point = Some(room_pos);
+                        self.reference_point = Some(app.config.snap.snap(room_pos));
                     }
                 }
             }
@@ -204,72 +368,42 @@ impl PencilTool {
     // TODO test to see if the diff would do anything before sending an event
     fn do_draw(&mut self, app: &AppState, room_pos: RoomPoint) -> Vec<AppEvent> {
         let tile_pos = point_room_to_tile(&room_pos);
-        let room_pos = if app.config.snap {
+        let tile_pos = if is_tile_layer(app.current_layer) {
+            self.constrain_tile(tile_pos)
+        } else {
+            tile_pos
+        };
+        let room_pos = if is_tile_layer(app.current_layer) {
             point_tile_to_room(&tile_pos)
         } else {
-            room_pos
+            app.config.snap.snap(room_pos)
         };
 
         match app.current_layer {
             Layer::ObjectTiles => {
+                let tile = app.current_objtile as i32;
+                let Some((offset, data)) = self.stamp(app, &[tile_pos], tile, -2) else { return vec![] };
                 vec![app.room_action(
-                    RoomAction::ObjectTileUpdate {
-                        offset: tile_pos,
-                        data: TileGrid {
-                            tiles: vec![app.current_objtile as i32],
-                            stride: 1,
-                        },
-                    },
+                    RoomAction::ObjectTileUpdate { offset, data },
                     self.draw_phase,
                 )]
             }
+            // rectangles are only filled in once the drag finishes
+            Layer::FgTiles | Layer::BgTiles if app.config.tile_pencil == PencilBehavior::Rect => {
+                vec![]
+            }
             Layer::FgTiles | Layer::BgTiles => {
                 let fg = app.current_layer == Layer::FgTiles;
-                let ch = if fg {
-                    app.current_fg_tile
-                } else {
-                    app.current_bg_tile
+                let centers = match self.reference_point {
+                    Some(start) => steps(point_room_to_tile(&start), tile_pos, 1),
+                    None => vec![tile_pos],
                 };
-                let other = if fg {
-                    &app.current_fg_tile_other
-                } else {
-                    &app.current_bg_tile_other
-                }
-                .chars()
-                .next()
-                .unwrap_or('0');
-                let ch_id = if ch.id == '\0' { other } else { ch.id };
-                if let Some(start) = self.reference_point {
-                    let mut result = vec![];
-                    for step in steps(point_room_to_tile(&start), tile_pos, 1) {
-                        result.push(app.room_action(
-                            RoomAction::TileUpdate {
-                                fg,
-                                offset: step,
-                                data: TileGrid {
-                                    tiles: vec![ch_id],
-                                    stride: 1,
-                                },
-                            },
-                            self.draw_phase,
-                        ));
-                        self.reference_point = Some(point_tile_to_room(&step));
-                    }
-                    result
-                } else {
-                    self.reference_point = Some(room_pos);
-                    vec![app.room_action(
-                        RoomAction::TileUpdate {
-                            fg,
-                            offset: tile_pos,
-                            data: TileGrid {
-                                tiles: vec![ch_id],
-                                stride: 1,
-                            },
-                        },
-                        self.draw_phase,
-                    )]
+                if let Some(last) = centers.last() {
+                    self.reference_point = Some(point_tile_to_room(last));
                 }
+                let ch_id = current_tile(app, fg);
+                let Some((offset, data)) = self.stamp(app, &centers, ch_id, '\0') else { return vec![] };
+                vec![app.room_action(RoomAction::TileUpdate { fg, offset, data }, self.draw_phase)]
             }
             Layer::Entities
                 if get_entity_config(&app.current_entity, app).pencil == PencilBehavior::Line =>
@@ -279,11 +413,7 @@ impl PencilTool {
                         let mut result = vec![];
                         let mut last_step = None;
                         for step in steps(last_draw, room_pos, app.config.draw_interval as i32) {
-                            let step = if app.config.snap {
-                                point_tile_to_room(&point_room_to_tile(&step))
-                            } else {
-                                step
-                            };
+                            let step = app.config.snap.snap(step);
                             if last_step == Some(step) {
                                 continue;
                             }
@@ -326,12 +456,24 @@ impl PencilTool {
     }
 
     fn do_draw_finish(&mut self, app: &AppState, room_pos: RoomPoint) -> Vec<AppEvent> {
-        let room_pos = if app.config.snap {
-            point_tile_to_room(&point_room_to_tile(&room_pos))
-        } else {
-            room_pos
-        };
+        let room_pos = app.config.snap.snap(room_pos);
         let result = match app.current_layer {
+            Layer::FgTiles | Layer::BgTiles if app.config.tile_pencil == PencilBehavior::Rect => {
+                match self.tile_rect(app, room_pos) {
+                    Some(rect) => {
+                        let fg = app.current_layer == Layer::FgTiles;
+                        vec![app.room_action(
+                            RoomAction::TileUpdate {
+                                fg,
+                                offset: rect.origin,
+                                data: TileGrid::new(rect.size, current_tile(app, fg)),
+                            },
+                            self.draw_phase,
+                        )]
+                    }
+                    None => vec![],
+                }
+            }
             Layer::Entities | Layer::Triggers => {
                 let pencil = if app.current_layer == Layer::Triggers {
                     PencilBehavior::Rect
@@ -366,7 +508,7 @@ impl PencilTool {
                     RoomAction::DecalAdd {
                         fg: app.current_layer == Layer::FgDecals,
                         decal: Box::new(instantiate_decal(
-                            &app.current_decal,
+                            &self.decal_to_place(app),
                             &app.current_decal_other,
                             room_pos.x,
                             room_pos.y,
@@ -381,9 +523,105 @@ impl PencilTool {
             _ => vec![],
         };
         self.reference_point = None;
+        self.stroke_start = None;
         result
     }
 
+    /// While shift is held, snap a tile stroke to the nearest horizontal, vertical, or 45 degree
+    /// line through the tile the stroke started on.
+    fn constrain_tile(&self, tile_pos: TilePoint) -> TilePoint {
+        let Some(start) = self.stroke_start.filter(|_| self.straight_line) else { return tile_pos };
+        let diff = tile_pos - start;
+        let (dx, dy) = (diff.x.abs(), diff.y.abs());
+        if dx > dy * 2 {
+            TilePoint::new(tile_pos.x, start.y)
+        } else if dy > dx * 2 {
+            TilePoint::new(start.x, tile_pos.y)
+        } else {
+            let len = dx.max(dy);
+            start + TileVector::new(len * diff.x.signum(), len * diff.y.signum())
+        }
+    }
+
+    /// The tiles covered by the rectangle being dragged out from `reference_point` to `room_pos`,
+    /// in whichever direction, cropped to the room. `None` if no rectangle fill is in progress or
+    /// it lies entirely outside of the room.
+    fn tile_rect(&self, app: &AppState, room_pos: RoomPoint) -> Option<TileRect> {
+        if app.config.tile_pencil != PencilBehavior::Rect
+            || !matches!(app.current_layer, Layer::FgTiles | Layer::BgTiles)
+        {
+            return None;
+        }
+        let start = point_room_to_tile(&self.reference_point?);
+        let end = point_room_to_tile(&room_pos);
+        let min = start.min(end);
+        let rect = TileRect::new(
+            min,
+            (start.max(end) - min + TileVector::new(1, 1)).to_size(),
+        );
+        let room = rect_room_to_tile(&app.current_room_ref()?.data.room_bounds());
+        rect.intersection(&room)
+    }
+
+    /// The tiles painted with the cursor over `center`, cropped to the room. Filling rectangles
+    /// doesn't use the brush.
+    fn brush_rect(&self, app: &AppState, center: TilePoint) -> Option<TileRect> {
+        let size = if app.config.tile_pencil == PencilBehavior::Rect
+            && matches!(app.current_layer, Layer::FgTiles | Layer::BgTiles)
+        {
+            1
+        } else {
+            self.brush_size
+        };
+        let rect = TileRect::new(
+            center - TileVector::new(size / 2, size / 2),
+            TileSize::new(size, size),
+        );
+        let room = rect_room_to_tile(&app.current_room_ref()?.data.room_bounds());
+        rect.intersection(&room)
+    }
+
+    /// Paint the brush at each of `centers` in a single grid, which is `blank` wherever the brush
+    /// didn't reach. Returns the grid along with where it goes in the room.
+    fn stamp<T: Copy>(
+        &self,
+        app: &AppState,
+        centers: &[TilePoint],
+        tile: T,
+        blank: T,
+    ) -> Option<(TilePoint, TileGrid<T>)> {
+        let rects: Vec<TileRect> = centers
+            .iter()
+            .filter_map(|center| self.brush_rect(app, *center))
+            .collect();
+        let bounds = rects.iter().copied().reduce(|a, b| a.union(&b))?;
+        let mut data = TileGrid::new(bounds.size, blank);
+        for rect in rects {
+            for y in rect.min_y()..rect.max_y() {
+                for x in rect.min_x()..rect.max_x() {
+                    if let Some(slot) =
+                        data.get_mut(TilePoint::new(x, y) - bounds.origin.to_vector())
+                    {
+                        *slot = tile;
+                    }
+                }
+            }
+        }
+        Some((bounds.origin, data))
+    }
+
+    fn decal_to_place(&self, app: &AppState) -> DecalSelectable {
+        if app.config.randomize_decal_variant {
+            let variants = app
+                .current_palette_unwrap()
+                .decal_variants(&app.current_decal.0);
+            if !variants.is_empty() {
+                return variants[rand::random::<usize>() % variants.len()];
+            }
+        }
+        app.current_decal
+    }
+
     fn get_terminal_entity(
         &self,
         app: &AppState,
@@ -443,15 +681,30 @@ impl PencilTool {
         room_pos: RoomPoint,
     ) -> CelesteMapEntity {
         let ref_pos = self.reference_point.unwrap_or(room_pos);
-        let diff = room_pos - ref_pos;
+        let (origin, size) = if ref_pos == room_pos {
+            // a click without a drag, or hovering before one
+            (
+                ref_pos,
+                RoomVector::new(DEFAULT_TRIGGER_SIZE, DEFAULT_TRIGGER_SIZE),
+            )
+        } else {
+            // when snapping, cover both the cell the drag started in and the one it's over now
+            let pad = match app.config.snap {
+                SnapMode::Off => 0,
+                mode => mode.grid(),
+            };
+            let min = ref_pos.min(room_pos);
+            let max = ref_pos.max(room_pos) + RoomVector::new(pad, pad);
+            (min, max - min)
+        };
         instantiate_trigger(
             &selectable,
             &app.current_trigger_other,
             app,
-            ref_pos.x,
-            ref_pos.y,
-            diff.x,
-            diff.y,
+            origin.x,
+            origin.y,
+            size.x,
+            size.y,
             vec![],
         )
     }
@@ -2,7 +2,7 @@ use arborio_utils::vizia::prelude::*;
 use arborio_utils::vizia::vg::{Color, Paint, Path};
 use std::collections::{HashMap, HashSet};
 
-use crate::data::action::{MapAction, RoomAction};
+use crate::data::action::{pick_new_name, MapAction, RoomAction};
 use crate::data::app::{AppEvent, AppInternalEvent, AppState};
 use crate::data::project_map::MapState;
 use crate::data::selection::AppSelectable;
@@ -11,6 +11,9 @@ use crate::tools::selection::ResizeSide;
 use crate::tools::{generic_nav, Tool};
 use arborio_maploader::map_struct::CelesteMapLevel;
 use arborio_utils::units::*;
+use arborio_utils::uuid::next_uuid;
+use dialog::DialogBox;
+use itertools::Itertools;
 
 pub struct RoomTool {
     pending_selection: HashSet<usize>,
@@ -43,8 +46,16 @@ struct ResizingStatus {
 
 impl RoomTool {
     pub fn new(app: &AppState) -> Self {
+        let current_room = app.map_tab_unwrap().current_room;
+        let room_exists = app
+            .current_map_ref()
+            .map_or(false, |map| current_room < map.data.levels.len());
         RoomTool {
-            current_selection: HashSet::from([app.map_tab_unwrap().current_room]),
+            current_selection: if room_exists {
+                HashSet::from([current_room])
+            } else {
+                HashSet::new()
+            },
             pending_selection: HashSet::new(),
             status: SelectionStatus::None,
             draw_phase: EventPhase::null(),
@@ -63,9 +74,7 @@ impl Tool for RoomTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos_precise = app
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos);
         let map_pos_unsnapped = point_lose_precision(&map_pos_precise);
         let map_pos = (map_pos_unsnapped / 8) * 8;
@@ -180,6 +189,7 @@ impl Tool for RoomTool {
                         Code::KeyC if cx.modifiers == &Modifiers::CTRL => {
                             self.clipboard_copy(app, mapid)
                         }
+                        Code::KeyD if cx.modifiers == &Modifiers::CTRL => self.duplicate(app, map),
                         Code::KeyX if cx.modifiers == &Modifiers::CTRL => {
                             let mut result = self.clipboard_copy(app, mapid);
                             result.extend(self.delete_all(app));
@@ -188,12 +198,18 @@ impl Tool for RoomTool {
                         Code::KeyV if cx.modifiers == &Modifiers::CTRL => {
                             if let Ok(s) = cx.get_clipboard() {
                                 let app = cx.data().unwrap();
-                                self.clipboard_paste(app, s)
+                                self.clipboard_paste(app, s, map_pos)
+                            } else {
+                                vec![]
+                            }
+                        }
+                        Code::Backspace | Code::Delete => {
+                            if !app.config.confirm_room_delete || self.confirm_delete(app) {
+                                self.delete_all(app)
                             } else {
                                 vec![]
                             }
                         }
-                        Code::Backspace | Code::Delete => self.delete_all(app),
                         _ => vec![],
                     }
                 } else {
@@ -221,9 +237,7 @@ impl Tool for RoomTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos_precise = state
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos);
         let map_pos_unsnapped = point_lose_precision(&map_pos_precise);
         let map_pos = (map_pos_unsnapped / 8) * 8;
@@ -319,9 +333,7 @@ impl Tool for RoomTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos_precise = app
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos);
         let map_pos_unsnapped = point_lose_precision(&map_pos_precise);
 
@@ -331,7 +343,8 @@ impl Tool for RoomTool {
             SelectionStatus::CouldStartDragging(_, _) | SelectionStatus::None => {
                 self.can_resize(map, map_pos_unsnapped).to_cursor_icon()
             }
-            SelectionStatus::Dragging(_) | SelectionStatus::Selecting(_) => CursorIcon::Default,
+            SelectionStatus::Dragging(_) => CursorIcon::Move,
+            SelectionStatus::Selecting(_) => CursorIcon::Default,
             SelectionStatus::Resizing(info) => info.side.to_cursor_icon(),
         }
     }
@@ -371,20 +384,24 @@ impl RoomTool {
         let mut events = vec![];
 
         for room in self.current_selection.iter() {
+            let Some(level) = map.data.levels.get(*room) else { continue };
             let base = dragging
-                .map(|d| d.selection_reference_points[room])
-                .unwrap_or_else(|| map.data.levels[*room].data.bounds.origin);
+                .and_then(|d| d.selection_reference_points.get(room).copied())
+                .unwrap_or(level.data.bounds.origin);
+            let bounds = MapRectStrict::new(base + nudge, level.data.bounds.size);
+            if bounds == level.data.bounds {
+                // a no-op move would still push an undo step and throw away the redo buffer
+                continue;
+            }
             events.push(MapAction::RoomAction {
-                event: RoomAction::MoveRoom {
-                    bounds: MapRectStrict::new(
-                        base + nudge,
-                        map.data.levels[*room].data.bounds.size,
-                    ),
-                },
+                event: RoomAction::MoveRoom { bounds },
                 idx: *room,
             });
         }
 
+        if events.is_empty() {
+            return vec![];
+        }
         vec![app.batch_action(events, self.draw_phase)]
     }
 
@@ -423,9 +440,10 @@ impl RoomTool {
         let mut events = vec![];
 
         for room in self.current_selection.iter() {
+            let Some(level) = map.data.levels.get(*room) else { continue };
             let start_rect = dragging
-                .map(|d| d.selection_reference_sizes[room])
-                .unwrap_or_else(|| map.data.levels[*room].data.bounds);
+                .and_then(|d| d.selection_reference_sizes.get(room).copied())
+                .unwrap_or(level.data.bounds);
             let mut new_rect = MapRectStrict::new(
                 start_rect.origin + pos_vec,
                 start_rect.size + size_vec.to_size(),
@@ -490,6 +508,43 @@ impl RoomTool {
             .collect()
     }
 
+    fn confirm_delete(&self, app: &AppState) -> bool {
+        let Some(map) = app.current_map_ref() else { return false };
+        let summary = self
+            .current_selection
+            .iter()
+            .filter_map(|idx| map.data.levels.get(*idx))
+            .map(|room| {
+                let tiles = room
+                    .data
+                    .solids
+                    .tiles
+                    .iter()
+                    .chain(room.data.bg.tiles.iter())
+                    .filter(|&&ch| ch != '0')
+                    .count();
+                format!(
+                    "{}: {} entities, {} triggers, {} decals, {} tiles",
+                    room.data.name,
+                    room.data.entities.len(),
+                    room.data.triggers.len(),
+                    room.data.fg_decals.len() + room.data.bg_decals.len(),
+                    tiles
+                )
+            })
+            .join("\n");
+        if summary.is_empty() {
+            return true;
+        }
+        let message = format!(
+            "Delete the following?\n\n{summary}\n\nThis can be undone. You can turn off this prompt in the Rooms tool settings."
+        );
+        matches!(
+            dialog::Question::new(message).title("Delete Room").show(),
+            Ok(dialog::Choice::Yes)
+        )
+    }
+
     fn clipboard_copy(&self, app: &AppState, mapid: MapID) -> Vec<AppEvent> {
         if self.current_selection.is_empty() {
             return vec![];
@@ -499,14 +554,50 @@ impl RoomTool {
             contents: serde_yaml::to_string(&AppSelectable::Rooms(
                 self.current_selection
                     .iter()
-                    .map(|roomid| map.data.levels.get(*roomid).unwrap().data.clone())
+                    .filter_map(|roomid| map.data.levels.get(*roomid))
+                    .map(|room| room.data.clone())
                     .collect(),
             ))
             .unwrap(),
         }]
     }
 
-    fn clipboard_paste(&mut self, app: &AppState, data: String) -> Vec<AppEvent> {
+    /// Add a copy of the current room to the right of it, or below that spot if it is taken, and
+    /// select the copy.
+    fn duplicate(&mut self, app: &AppState, map: &MapState) -> Vec<AppEvent> {
+        let Some(original) = app.current_room_ref() else { return vec![] };
+        let mut room = original.data.clone();
+        room.name = pick_new_name(&map.data);
+        room.bounds.origin.x += room.bounds.width();
+        while map
+            .data
+            .levels
+            .iter()
+            .any(|other| other.data.bounds.intersects(&room.bounds))
+        {
+            room.bounds.origin.y += 8;
+        }
+        for decal in room.fg_decals.iter_mut().chain(room.bg_decals.iter_mut()) {
+            decal.id = next_uuid();
+        }
+
+        self.current_selection = HashSet::from([map.data.levels.len()]);
+        let mut events = self.notify_selection(app);
+        events.push(app.map_action_unique(vec![MapAction::AddRoom {
+            idx: None,
+            room: Box::new(room),
+        }]));
+        events
+    }
+
+    /// Paste rooms from the clipboard, centered on `at` and keeping their positions relative to
+    /// each other.
+    fn clipboard_paste(
+        &mut self,
+        app: &AppState,
+        data: String,
+        at: MapPointStrict,
+    ) -> Vec<AppEvent> {
         let mut result = self.clear_selection(app);
         let Ok(AppSelectable::Rooms(clipboard_data)) = serde_yaml::from_str(&data) else { return result };
         if clipboard_data.is_empty() {
@@ -518,17 +609,9 @@ impl RoomTool {
             min_pt = min_pt.min(room.bounds.min());
             max_pt = max_pt.max(room.bounds.max());
         }
-        let center = ((min_pt.to_vector() + max_pt.to_vector()) / 2).to_point();
-        let real_center = point_lose_precision(
-            &app.map_tab_unwrap()
-                .transform
-                .inverse()
-                .unwrap()
-                .transform_point(ScreenPoint::new(100., 100.)),
-        );
-        let real_center =
-            point_tile_to_room(&point_room_to_tile(&real_center.cast_unit())).cast_unit();
-        let offset = real_center - center;
+        // keep the rooms on the tile grid, wherever they were copied from
+        let center = ((min_pt.to_vector() + max_pt.to_vector()) / 16 * 8).to_point();
+        let offset = at - center;
         result.push(
             app.batch_action_unique(clipboard_data.into_iter().map(|mut room| {
                 MapAction::AddRoom {
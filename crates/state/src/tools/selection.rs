@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
-use arborio_maploader::map_struct::Node;
+use arborio_maploader::map_struct::{CelesteMapLevel, Node};
 use arborio_modloader::mapstruct_plus_config::{make_entity_env, make_node_env};
 use arborio_utils::units::*;
 use arborio_utils::vizia::prelude::*;
@@ -12,23 +13,44 @@ use crate::data::project_map::{LevelFloatState, LevelState};
 use crate::data::selection::{AppInRoomSelectable, AppSelectable, AppSelection};
 use crate::data::tabs::MapTab;
 use crate::data::{EventPhase, Layer};
-use crate::rendering::decal_texture;
-use crate::tools::{generic_nav, Tool};
+use crate::rendering::{self, decal_texture};
+use crate::tools::bucket::bucket_it;
+use crate::tools::{generic_nav, ContextAction, Tool};
+
+/// How soon an arrow key nudge has to follow the last one to be undone along with it.
+const NUDGE_BURST: Duration = Duration::from_secs(1);
 
 pub struct SelectionTool {
     pending_selection: HashSet<AppSelection>,
 
     status: SelectionStatus,
     draw_phase: EventPhase,
+    /// While a quick filter key is held on the All layer, only this layer is selectable.
+    quick_filter: Option<Layer>,
+    /// Whether the selection is a fresh paste which follows the cursor until a click places it.
+    placing: bool,
+    /// Whether alt is held, making locked objects selectable.
+    include_locked: bool,
+    /// Whether the lasso being traced adds to the selection rather than replacing it. Replacing
+    /// only clears the selection once the mouse moves, so that a ctrl+click without a drag can
+    /// toggle what's under the cursor instead.
+    lasso_adds: bool,
+    /// When the last arrow key nudge happened and the phase it used. Nudges in quick succession
+    /// share a phase so a burst of them is undone at once.
+    last_nudge: Option<(Instant, EventPhase)>,
 }
 
 #[derive(Eq, PartialEq, Debug)]
 enum SelectionStatus {
     None,
     Selecting(RoomPoint),
+    /// A freeform selection, traced through these points while ctrl-dragging.
+    Lasso(Vec<RoomPoint>),
     CouldStartDragging(RoomPoint, RoomPoint),
     Dragging(DraggingStatus),
     Resizing(ResizingStatus),
+    /// A paste was just centered on this point and will start following the cursor when it moves.
+    Placing(RoomPoint),
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -155,12 +177,57 @@ impl AppEventStaging {
     }
 }
 
+/// The region swept out by a drag-selection.
+enum SelectionShape {
+    Rect(RoomRect),
+    /// A closed polygon. Where it crosses over itself, the even-odd rule decides what is inside.
+    Lasso(Vec<RoomPoint>),
+}
+
+impl SelectionShape {
+    fn bounds(&self) -> RoomRect {
+        match self {
+            SelectionShape::Rect(rect) => rect_normalize(rect),
+            SelectionShape::Lasso(points) => {
+                let rect = RoomRect::from_points(points);
+                RoomRect::new(rect.origin, rect.size + RoomSize::new(1, 1))
+            }
+        }
+    }
+
+    fn intersects(&self, rect: &RoomRect) -> bool {
+        match self {
+            SelectionShape::Rect(shape) => rect_normalize(shape).intersects(rect),
+            SelectionShape::Lasso(points) => polygon_intersects_rect(points, rect),
+        }
+    }
+
+    fn intersects_tile(&self, tile: TilePoint) -> bool {
+        self.intersects(&rect_tile_to_room(&TileRect::new(
+            tile,
+            TileSize::new(1, 1),
+        )))
+    }
+}
+
+fn everywhere() -> RoomRect {
+    RoomRect::new(
+        RoomPoint::new(-1000000, -1000000),
+        RoomSize::new(2000000, 2000000),
+    )
+}
+
 impl SelectionTool {
     pub fn new() -> Self {
         Self {
             pending_selection: HashSet::new(),
             status: SelectionStatus::None,
             draw_phase: EventPhase::null(),
+            quick_filter: None,
+            placing: false,
+            include_locked: false,
+            lasso_adds: false,
+            last_nudge: None,
         }
     }
 }
@@ -180,32 +247,76 @@ impl Tool for SelectionTool {
         }
 
         let Some(room) = app.current_room_ref() else { return vec![] };
+        self.include_locked = cx.modifiers.contains(Modifiers::ALT);
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos_precise = app
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos);
         let map_pos = point_lose_precision(&map_pos_precise);
         let room_pos_unsnapped = (map_pos - room.data.bounds.origin).to_point().cast_unit();
-        let room_pos = if app.config.snap {
-            let tile_pos = point_room_to_tile(&room_pos_unsnapped);
-            point_tile_to_room(&tile_pos)
-        } else {
-            room_pos_unsnapped
-        };
+        let room_pos = app.config.snap.snap(room_pos_unsnapped);
 
         match event {
             WindowEvent::MouseUp(MouseButton::Left) => {
-                let events = if let SelectionStatus::Selecting(_) = self.status {
-                    self.confirm_selection(app)
-                } else {
-                    AppEventStaging::default()
+                let events = match &self.status {
+                    // a ctrl+click which never moved toggles whatever is under it
+                    SelectionStatus::Lasso(points) if points.len() == 1 => {
+                        let start = points[0];
+                        self.pending_selection.clear();
+                        let mut events = AppEventStaging::default();
+                        if let Some(got) = self.selectable_at(app, room, app.current_layer, start) {
+                            let tab = app.current_tab;
+                            let selection = HashSet::from([got]);
+                            let event = if app.map_tab_unwrap().current_selected.contains(&got) {
+                                AppEvent::DeselectObjects { tab, selection }
+                            } else {
+                                AppEvent::SelectObjects { tab, selection }
+                            };
+                            events.push_ind(event);
+                        }
+                        events
+                    }
+                    SelectionStatus::Selecting(_) | SelectionStatus::Lasso(_) => {
+                        self.confirm_selection(app)
+                    }
+                    _ => AppEventStaging::default(),
                 };
                 self.status = SelectionStatus::None;
+                self.placing = false;
                 events
             }
+            WindowEvent::MouseDown(MouseButton::Left)
+                if self.status == SelectionStatus::None
+                    && cx.modifiers.contains(Modifiers::ALT)
+                    && matches!(app.current_layer, Layer::FgTiles | Layer::BgTiles) =>
+            {
+                self.magic_wand(
+                    app,
+                    room,
+                    point_room_to_tile(&room_pos_unsnapped),
+                    cx.modifiers.contains(Modifiers::SHIFT),
+                )
+            }
+            WindowEvent::MouseDown(MouseButton::Left)
+                if self.status == SelectionStatus::None
+                    && cx.modifiers.contains(Modifiers::CTRL) =>
+            {
+                // ctrl+click on an entity pulls a new node out of it, anywhere else starts a lasso
+                if !cx.modifiers.contains(Modifiers::SHIFT) {
+                    if let Some(AppSelection::EntityBody(id, trigger)) =
+                        self.selectable_at(app, room, app.current_layer, room_pos_unsnapped)
+                    {
+                        return self
+                            .append_node(app, room, id, trigger, room_pos, room_pos_unsnapped)
+                            .finalize(app, self.draw_phase);
+                    }
+                }
+                self.status = SelectionStatus::Lasso(vec![room_pos_unsnapped]);
+                self.pending_selection.clear();
+                self.lasso_adds = cx.modifiers.contains(Modifiers::SHIFT);
+                AppEventStaging::default()
+            }
             WindowEvent::MouseDown(MouseButton::Left) => {
                 if self.status == SelectionStatus::None {
                     let got = self.selectable_at(app, room, app.current_layer, room_pos_unsnapped);
@@ -230,26 +341,52 @@ impl Tool for SelectionTool {
                 }
             }
             WindowEvent::MouseMove(..) => {
-                let (mut events, floats) =
-                    if let SelectionStatus::CouldStartDragging(pt, unsn) = self.status {
-                        let (events, floats) = self.begin_dragging(app, room, pt, unsn); // sets self.status = Dragging | Resizing
+                let (mut events, floats) = match self.status {
+                    SelectionStatus::CouldStartDragging(pt, unsn) => {
+                        let (events, floats) = self.begin_dragging(app, room, pt, Some(unsn)); // sets self.status = Dragging | Resizing
                         (events, Some(floats))
-                    } else {
-                        (AppEventStaging::default(), None)
-                    };
+                    }
+                    SelectionStatus::Placing(pt) => {
+                        let (events, floats) = self.begin_dragging(app, room, pt, None); // sets self.status = Dragging
+                        (events, Some(floats))
+                    }
+                    SelectionStatus::Lasso(_) if !self.lasso_adds => {
+                        // only now is this a drag rather than a click, so replace the selection
+                        self.lasso_adds = true;
+                        (self.clear_selection(app, &room.floats), None)
+                    }
+                    _ => (AppEventStaging::default(), None),
+                };
 
                 events.accumulate(match self.status {
                     SelectionStatus::None => AppEventStaging::default(),
-                    SelectionStatus::CouldStartDragging(_, _) => unreachable!(),
+                    SelectionStatus::CouldStartDragging(_, _) | SelectionStatus::Placing(_) => {
+                        unreachable!()
+                    }
                     SelectionStatus::Selecting(ref_pos) => {
                         self.pending_selection = self.selectables_in(
                             app,
                             room,
                             app.current_layer,
-                            RoomRect::new(ref_pos, (room_pos - ref_pos).to_size()),
+                            &SelectionShape::Rect(RoomRect::new(
+                                ref_pos,
+                                (room_pos - ref_pos).to_size(),
+                            )),
                         );
                         AppEventStaging::default()
                     }
+                    SelectionStatus::Lasso(ref mut points) => {
+                        if points.last() != Some(&room_pos_unsnapped) {
+                            points.push(room_pos_unsnapped);
+                        }
+                        let shape = SelectionShape::Lasso(std::mem::take(points));
+                        self.pending_selection =
+                            self.selectables_in(app, room, app.current_layer, &shape);
+                        if let SelectionShape::Lasso(points) = shape {
+                            self.status = SelectionStatus::Lasso(points);
+                        }
+                        AppEventStaging::default()
+                    }
                     SelectionStatus::Dragging(DraggingStatus {
                         pointer_reference_point,
                         ..
@@ -267,47 +404,92 @@ impl Tool for SelectionTool {
 
                 events
             }
+            WindowEvent::KeyDown(code @ (Code::KeyE | Code::KeyT), _)
+                if cx.modifiers.is_empty() =>
+            {
+                self.quick_filter = Some(if *code == Code::KeyE {
+                    Layer::Entities
+                } else {
+                    Layer::Triggers
+                });
+                AppEventStaging::default()
+            }
+            WindowEvent::KeyUp(Code::KeyE | Code::KeyT, _) => {
+                self.quick_filter = None;
+                AppEventStaging::default()
+            }
+            WindowEvent::KeyDown(Code::Escape, _) if self.placing => {
+                // take the paste back out. this shares the paste's phase, so it leaves no undo step
+                self.status = SelectionStatus::None;
+                self.placing = false;
+                self.delete_all(app, room)
+            }
+            // ctrl+arrows move between rooms, even where there's no room to move to
+            WindowEvent::KeyDown(
+                Code::ArrowUp | Code::ArrowDown | Code::ArrowLeft | Code::ArrowRight,
+                _,
+            ) if cx.modifiers == &Modifiers::CTRL => AppEventStaging::default(),
             WindowEvent::KeyDown(code, _) => {
                 if self.status == SelectionStatus::None {
-                    let mut old_draw_phase = EventPhase::new();
-                    std::mem::swap(&mut old_draw_phase, &mut self.draw_phase);
+                    let arrow = matches!(
+                        code,
+                        Code::ArrowUp | Code::ArrowDown | Code::ArrowLeft | Code::ArrowRight
+                    );
+                    let burst = arrow
+                        && matches!(self.last_nudge, Some((time, phase))
+                            if phase == self.draw_phase && time.elapsed() < NUDGE_BURST);
+                    let old_draw_phase = self.draw_phase;
+                    if !burst {
+                        self.draw_phase = EventPhase::new();
+                    }
+                    // alt nudges by a pixel, unless tiles are selected, which have to stay on the grid
+                    let step = if cx.modifiers == &Modifiers::ALT && !self.any_tiles(app) {
+                        1
+                    } else {
+                        8
+                    };
                     let events = match code {
                         Code::ArrowDown => {
-                            self.nudge(app, room, RoomVector::new(0, 8), room.floats.clone())
+                            self.nudge(app, room, RoomVector::new(0, step), room.floats.clone())
                         }
                         Code::ArrowUp => {
-                            self.nudge(app, room, RoomVector::new(0, -8), room.floats.clone())
+                            self.nudge(app, room, RoomVector::new(0, -step), room.floats.clone())
                         }
                         Code::ArrowRight => {
-                            self.nudge(app, room, RoomVector::new(8, 0), room.floats.clone())
+                            self.nudge(app, room, RoomVector::new(step, 0), room.floats.clone())
                         }
                         Code::ArrowLeft => {
-                            self.nudge(app, room, RoomVector::new(-8, 0), room.floats.clone())
+                            self.nudge(app, room, RoomVector::new(-step, 0), room.floats.clone())
                         }
                         Code::KeyA if cx.modifiers == &Modifiers::CTRL => {
                             self.pending_selection = self.selectables_in(
                                 app,
                                 room,
                                 app.current_layer,
-                                RoomRect::new(
-                                    RoomPoint::new(-1000000, -1000000),
-                                    RoomSize::new(2000000, 2000000),
-                                ),
+                                &SelectionShape::Rect(everywhere()),
                             );
                             self.confirm_selection(app)
                         }
+                        Code::KeyI if cx.modifiers == &Modifiers::CTRL => {
+                            self.invert_selection(app, room)
+                        }
+                        Code::PageUp if cx.modifiers == &Modifiers::CTRL => {
+                            self.reorder(app, room, true)
+                        }
+                        Code::PageDown if cx.modifiers == &Modifiers::CTRL => {
+                            self.reorder(app, room, false)
+                        }
                         Code::KeyC if cx.modifiers == &Modifiers::CTRL => {
                             self.clipboard_copy(app, room)
                         }
+                        Code::KeyL if cx.modifiers == &Modifiers::CTRL => self.toggle_lock(app),
                         Code::KeyX if cx.modifiers == &Modifiers::CTRL => {
-                            let mut result = self.clipboard_copy(app, room);
-                            result.accumulate(self.delete_all(app, room));
-                            result
+                            self.clipboard_cut(app, room)
                         }
                         Code::KeyV if cx.modifiers == &Modifiers::CTRL => {
                             if let Ok(s) = cx.get_clipboard() {
                                 let app = cx.data().unwrap();
-                                self.clipboard_paste(app, s)
+                                self.clipboard_paste(app, s, Some(point_room_to_tile(&room_pos)))
                             } else {
                                 AppEventStaging::default()
                             }
@@ -317,6 +499,8 @@ impl Tool for SelectionTool {
                     };
                     if events.is_empty() {
                         self.draw_phase = old_draw_phase;
+                    } else if arrow {
+                        self.last_nudge = Some((Instant::now(), self.draw_phase));
                     }
                     events
                 } else {
@@ -356,6 +540,99 @@ impl Tool for SelectionTool {
             .finalize(app, self.draw_phase)
     }
 
+    fn context_menu(
+        &mut self,
+        cx: &mut EventContext,
+    ) -> Option<(Vec<AppEvent>, Vec<ContextAction>)> {
+        let app = cx.data::<AppState>().unwrap();
+        let room = app.current_room_ref()?;
+        if self.status != SelectionStatus::None {
+            return None;
+        }
+        let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
+        let map_pos = point_lose_precision(
+            &app.map_tab_unwrap()
+                .inverse_transform()
+                .transform_point(screen_pos),
+        );
+        let room_pos = (map_pos - room.data.bounds.origin).to_point().cast_unit();
+
+        if let Some(got) = self.selectable_at(app, room, app.current_layer, room_pos) {
+            // act on what was clicked, like a left click would select it
+            let mut events = AppEventStaging::default();
+            if !app.map_tab_unwrap().current_selected.contains(&got) {
+                events.accumulate(self.clear_selection(app, &room.floats));
+                events.push_ind(AppEvent::SelectObjects {
+                    tab: app.current_tab,
+                    selection: HashSet::from([got]),
+                });
+            }
+            let mut actions = vec![ContextAction::Copy, ContextAction::Delete];
+            if let Some(target) = got.lock_key() {
+                actions.push(ContextAction::Properties(target));
+            }
+            if let AppSelection::EntityNode(id, node_idx, trigger) = got {
+                actions.push(ContextAction::RemoveNode(id, node_idx, trigger));
+            }
+            actions.extend([ContextAction::SendToBack, ContextAction::ToggleLock]);
+            Some((events.finalize_unique(app), actions))
+        } else {
+            let actions = vec![
+                ContextAction::PasteHere(room_pos),
+                ContextAction::NewRoom(map_pos),
+            ];
+            Some((vec![], actions))
+        }
+    }
+
+    fn context_action(&mut self, action: ContextAction, cx: &mut EventContext) -> Vec<AppEvent> {
+        let app = cx.data::<AppState>().unwrap();
+        let Some(room) = app.current_room_ref() else { return vec![] };
+        self.draw_phase = EventPhase::new();
+        let events = match action {
+            ContextAction::Copy => self.clipboard_copy(app, room),
+            ContextAction::Delete => self.delete_all(app, room),
+            ContextAction::Properties(target) => {
+                let mut events = self.clear_selection(app, &room.floats);
+                events.push_ind(AppEvent::SelectObjects {
+                    tab: app.current_tab,
+                    selection: HashSet::from([target]),
+                });
+                events
+            }
+            ContextAction::SendToBack => self.reorder(app, room, false),
+            ContextAction::ToggleLock => self.toggle_lock(app),
+            ContextAction::RemoveNode(id, node_idx, trigger) => {
+                self.remove_node(app, room, id, node_idx, trigger)
+            }
+            ContextAction::PasteHere(pos) => {
+                let Ok(s) = cx.get_clipboard() else { return vec![] };
+                let app = cx.data().unwrap();
+                self.clipboard_paste(app, s, Some(point_room_to_tile(&pos)))
+            }
+            ContextAction::NewRoom(pos) => {
+                let mut result = CelesteMapLevel::default();
+                result.bounds.origin =
+                    point_tile_to_room(&point_room_to_tile(&pos.cast_unit())).cast_unit();
+                // like duplicating a room, step down until it is clear of the others
+                let Some(map) = app.current_map_ref() else { return vec![] };
+                while map
+                    .data
+                    .levels
+                    .iter()
+                    .any(|other| other.data.bounds.intersects(&result.bounds))
+                {
+                    result.bounds.origin.y += 8;
+                }
+                return vec![app.map_action_unique(vec![MapAction::AddRoom {
+                    idx: None,
+                    room: Box::new(result),
+                }])];
+            }
+        };
+        events.finalize(cx.data::<AppState>().unwrap(), self.draw_phase)
+    }
+
     fn draw(&mut self, canvas: &mut Canvas, state: &AppState, cx: &DrawContext) {
         let Some(room) = state.current_room_ref() else { return };
         canvas.save();
@@ -368,19 +645,12 @@ impl Tool for SelectionTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos_precise = state
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos)
             .cast();
         let map_pos = point_lose_precision(&map_pos_precise);
         let room_pos = (map_pos - room.data.bounds.origin).to_point().cast_unit();
-        let room_pos = if state.config.snap {
-            let tile_pos = point_room_to_tile(&room_pos);
-            point_tile_to_room(&tile_pos)
-        } else {
-            room_pos
-        };
+        let room_pos = state.config.snap.snap(room_pos);
 
         if let SelectionStatus::Selecting(ref_pos) = &self.status {
             let selection =
@@ -397,6 +667,18 @@ impl Tool for SelectionTool {
                 &vg::Paint::color(vg::Color::rgb(0, 0, 0)).with_line_width(1.5),
             );
         }
+        if let SelectionStatus::Lasso(points) = &self.status {
+            let mut path = vg::Path::new();
+            path.move_to(points[0].x as f32, points[0].y as f32);
+            for point in &points[1..] {
+                path.line_to(point.x as f32, point.y as f32);
+            }
+            path.close();
+            canvas.stroke_path(
+                &mut path,
+                &vg::Paint::color(vg::Color::rgb(0, 0, 0)).with_line_width(1.5),
+            );
+        }
 
         let mut path = vg::Path::new();
         for selectable in self
@@ -404,7 +686,7 @@ impl Tool for SelectionTool {
             .iter()
             .chain(state.map_tab_unwrap().current_selected.iter())
         {
-            for rect in self.rects_of(state, room, *selectable) {
+            for rect in rects_of(state, room, *selectable) {
                 path.rect(
                     rect.min_x() as f32,
                     rect.min_y() as f32,
@@ -419,11 +701,30 @@ impl Tool for SelectionTool {
             &vg::Paint::color(vg::Color::rgba(255, 255, 0, 128)),
         );
 
+        let map_tab = state.map_tab_unwrap();
+        if let Some(locked) = map_tab.locked.get(&map_tab.current_room) {
+            let mut path = vg::Path::new();
+            for sel in locked {
+                for rect in rects_of(state, room, *sel) {
+                    path.rect(
+                        rect.min_x() as f32,
+                        rect.min_y() as f32,
+                        rect.width() as f32,
+                        rect.height() as f32,
+                    );
+                }
+            }
+            canvas.fill_path(
+                &mut path,
+                &vg::Paint::color(vg::Color::rgba(128, 128, 128, 64)),
+            );
+        }
+
         if self.status == SelectionStatus::None {
             if let Some(sel) = self.selectable_at(state, room, state.current_layer, room_pos) {
                 if !state.map_tab_unwrap().current_selected.contains(&sel) {
                     let mut path = vg::Path::new();
-                    for rect in self.rects_of(state, room, sel) {
+                    for rect in rects_of(state, room, sel) {
                         path.rect(
                             rect.min_x() as f32,
                             rect.min_y() as f32,
@@ -448,9 +749,7 @@ impl Tool for SelectionTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos_precise = app
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos)
             .cast();
         let map_pos = point_lose_precision(&map_pos_precise);
@@ -463,111 +762,130 @@ impl Tool for SelectionTool {
             SelectionStatus::CouldStartDragging(_, _) | SelectionStatus::None => {
                 self.can_resize(app, room, room_pos).to_cursor_icon()
             }
-            SelectionStatus::Dragging(_) | SelectionStatus::Selecting(_) => CursorIcon::Default,
+            SelectionStatus::Dragging(_) | SelectionStatus::Placing(_) => CursorIcon::Move,
+            SelectionStatus::Selecting(_) | SelectionStatus::Lasso(_) => CursorIcon::Default,
             SelectionStatus::Resizing(info) => info.side.to_cursor_icon(),
         }
     }
 }
 
 impl SelectionTool {
+    /// Select everything on the current layer which isn't selected, and deselect everything which
+    /// is. Selections on other layers are left alone.
     #[must_use]
-    fn confirm_selection(&mut self, app: &AppState) -> AppEventStaging {
-        let mut result = AppEventStaging::default();
-        result.push_ind(AppEvent::SelectObjects {
-            tab: app.current_tab,
-            selection: self.pending_selection.drain().collect(),
-        });
+    fn invert_selection(&mut self, app: &AppState, room: &LevelState) -> AppEventStaging {
+        let everything = self.selectables_in(
+            app,
+            room,
+            app.current_layer,
+            &SelectionShape::Rect(everywhere()),
+        );
+        let current = app
+            .map_tab_unwrap()
+            .current_selected
+            .iter()
+            .filter(|sel| {
+                !matches!(
+                    sel,
+                    AppSelection::FgFloat | AppSelection::BgFloat | AppSelection::ObjFloat
+                )
+            })
+            .copied()
+            .collect::<HashSet<_>>();
+        let mut result = self.clear_selection(app, &room.floats);
+        self.pending_selection = everything.symmetric_difference(&current).copied().collect();
+        result.accumulate(self.confirm_selection(app));
         result
     }
 
-    fn rects_of(
-        &self,
-        app: &AppState,
-        room: &LevelState,
-        selectable: AppSelection,
-    ) -> Vec<RoomRect> {
-        fn rects_of_layer<T: Copy + Eq>(
-            layer: Option<&(Point2D<i32, TileSpace>, TileGrid<T>)>,
-            nil: T,
-        ) -> Vec<RoomRect> {
-            if let Some((origin, grid)) = layer {
-                rect_point_iter(TileRect::new(TilePoint::zero(), grid.size()), 1)
-                    .filter_map(|pt| {
-                        let tile = grid.get(pt);
-                        if *tile.unwrap() != nil {
-                            Some(RoomRect::new(
-                                point_tile_to_room(&(*origin + pt.to_vector())),
-                                RoomSize::new(8, 8),
-                            ))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect()
+    /// Move the selected entities, triggers, and decals to the front (end) or back (start) of
+    /// their lists, keeping their order relative to each other.
+    #[must_use]
+    fn reorder(&self, app: &AppState, room: &LevelState, front: bool) -> AppEventStaging {
+        let selected = &app.map_tab_unwrap().current_selected;
+        let idx = if front { usize::MAX } else { 0 };
+        let mut result = AppEventStaging::default();
+        for trigger in [false, true] {
+            let list = if trigger {
+                &room.data.triggers
             } else {
-                vec![]
+                &room.data.entities
+            };
+            let mut ids = list
+                .iter()
+                .filter(|e| selected.contains(&AppSelection::EntityBody(e.id, trigger)))
+                .map(|e| e.id)
+                .collect::<Vec<_>>();
+            if !front {
+                ids.reverse();
             }
-        }
-        match selectable {
-            AppSelection::FgTile(pt) | AppSelection::BgTile(pt) | AppSelection::ObjectTile(pt) => {
-                vec![RoomRect::new(point_tile_to_room(&pt), RoomSize::new(8, 8))]
+            for id in ids {
+                result.push_room(RoomAction::EntityReorder { id, trigger, idx });
             }
-            AppSelection::FgFloat => rects_of_layer(room.floats.fg.as_ref(), '\0'),
-            AppSelection::BgFloat => rects_of_layer(room.floats.bg.as_ref(), '\0'),
-            AppSelection::ObjFloat => rects_of_layer(room.floats.obj.as_ref(), -2),
-            AppSelection::EntityBody(id, trigger) => {
-                if let Some(entity) = room.entity(id, trigger) {
-                    let config = app
-                        .current_palette_unwrap()
-                        .get_entity_config(&entity.name, trigger);
-                    let env = make_entity_env(entity);
-                    config
-                        .hitboxes
-                        .initial_rects
-                        .iter()
-                        .filter_map(|r| r.evaluate_int(&env).ok())
-                        .collect()
-                } else {
-                    vec![]
-                }
+        }
+        for fg in [false, true] {
+            let list = if fg {
+                &room.data.fg_decals
+            } else {
+                &room.data.bg_decals
+            };
+            let mut ids = list
+                .iter()
+                .filter(|d| selected.contains(&AppSelection::Decal(d.id, fg)))
+                .map(|d| d.id)
+                .collect::<Vec<_>>();
+            if !front {
+                ids.reverse();
             }
-            AppSelection::EntityNode(id, node_idx, trigger) => {
-                if let Some(entity) = room.entity(id, trigger) {
-                    let config = app
-                        .current_palette_unwrap()
-                        .get_entity_config(&entity.name, trigger);
-                    let env = make_node_env(entity, make_entity_env(entity), node_idx);
-                    config
-                        .hitboxes
-                        .node_rects
-                        .iter()
-                        .filter_map(|r| r.evaluate_int(&env).ok())
-                        .collect()
-                } else {
-                    vec![]
-                }
-            }
-            AppSelection::Decal(id, fg) => {
-                if let Some(decal) = room.decal(id, fg) {
-                    let dim = app
-                        .current_palette_unwrap()
-                        .gameplay_atlas
-                        .sprite_dimensions(&decal_texture(decal))
-                        .unwrap_or(Size2D::new(16, 16));
-                    let size = dim
-                        .cast()
-                        .cast_unit()
-                        .to_vector()
-                        .component_mul(Vector2D::new(decal.scale_x, decal.scale_y))
-                        .cast()
-                        .to_size()
-                        .abs();
-                    vec![Rect::new(RoomPoint::new(decal.x, decal.y) - size / 2, size)]
-                } else {
-                    vec![]
-                }
+            for id in ids {
+                result.push_room(RoomAction::DecalReorder { fg, id, idx });
             }
         }
+        result
+    }
+
+    /// Select the connected region of identical tiles under the cursor, the same region the bucket
+    /// tool would fill.
+    fn magic_wand(
+        &mut self,
+        app: &AppState,
+        room: &LevelState,
+        tile_pos: TilePoint,
+        add: bool,
+    ) -> AppEventStaging {
+        let fg = matches!(app.current_layer, Layer::FgTiles);
+        let mut result = if add {
+            AppEventStaging::default()
+        } else {
+            self.clear_selection(app, &room.floats)
+        };
+        if room.tile(tile_pos, fg).unwrap_or('0') == '0' {
+            return result;
+        }
+        result.push_ind(AppEvent::SelectObjects {
+            tab: app.current_tab,
+            selection: bucket_it(room, fg, tile_pos, false)
+                .into_iter()
+                .map(|pt| {
+                    if fg {
+                        AppSelection::FgTile(pt)
+                    } else {
+                        AppSelection::BgTile(pt)
+                    }
+                })
+                .collect(),
+        });
+        result
+    }
+
+    #[must_use]
+    fn confirm_selection(&mut self, app: &AppState) -> AppEventStaging {
+        let mut result = AppEventStaging::default();
+        result.push_ind(AppEvent::SelectObjects {
+            tab: app.current_tab,
+            selection: self.pending_selection.drain().collect(),
+        });
+        result
     }
 
     fn selectable_at(
@@ -581,11 +899,11 @@ impl SelectionTool {
             app,
             room,
             layer,
-            RoomRect::new(room_pos, RoomSize::new(1, 1)),
+            &SelectionShape::Rect(RoomRect::new(room_pos, RoomSize::new(1, 1))),
         )
-        .iter()
-        .next()
-        .cloned()
+        .into_iter()
+        // pick whatever is drawn on top
+        .max_by_key(|sel| rendering::draw_order(&room.data, *sel))
     }
 
     fn selectables_in(
@@ -593,9 +911,17 @@ impl SelectionTool {
         app: &AppState,
         room: &LevelState,
         layer: Layer,
-        room_rect: RoomRect,
+        shape: &SelectionShape,
     ) -> HashSet<AppSelection> {
-        let room_rect = rect_normalize(&room_rect);
+        let layer = match (layer, self.quick_filter) {
+            (Layer::All, Some(filter)) => filter,
+            _ => layer,
+        };
+        let map_tab = app.map_tab_unwrap();
+        let locked = |sel: &AppSelection| {
+            !self.include_locked && map_tab.is_locked(map_tab.current_room, sel)
+        };
+        let room_rect = shape.bounds();
         let mut result = HashSet::new();
         let room_rect_cropped = room_rect.intersection(&RoomRect::new(
             RoomPoint::zero(),
@@ -606,7 +932,7 @@ impl SelectionTool {
             for (idx, decal) in room.data.fg_decals.iter().enumerate().rev() {
                 room.cache_decal_idx(idx);
                 let sel = AppSelection::Decal(decal.id, true);
-                if intersects_any(&self.rects_of(app, room, sel), &room_rect) {
+                if !locked(&sel) && intersects_any(&rects_of(app, room, sel), shape) {
                     result.insert(sel);
                 }
             }
@@ -615,11 +941,12 @@ impl SelectionTool {
             if let Some(room_rect_cropped) = room_rect_cropped {
                 for tile_pos_unaligned in rect_point_iter(room_rect_cropped, 8) {
                     let tile_pos = point_room_to_tile(&tile_pos_unaligned);
-                    if room
-                        .data
-                        .object_tiles
-                        .get(tile_pos)
-                        .map_or(false, |&tile| tile != -1)
+                    if shape.intersects_tile(tile_pos)
+                        && room
+                            .data
+                            .object_tiles
+                            .get(tile_pos)
+                            .map_or(false, |&tile| tile != -1)
                     {
                         result.insert(AppSelection::ObjectTile(tile_pos));
                     }
@@ -630,7 +957,9 @@ impl SelectionTool {
             if let Some(room_rect_cropped) = room_rect_cropped {
                 for tile_pos_unaligned in rect_point_iter(room_rect_cropped, 8) {
                     let tile_pos = point_room_to_tile(&tile_pos_unaligned);
-                    if room.tile(tile_pos, true).unwrap_or('0') != '0' {
+                    if shape.intersects_tile(tile_pos)
+                        && room.tile(tile_pos, true).unwrap_or('0') != '0'
+                    {
                         result.insert(AppSelection::FgTile(tile_pos));
                     }
                 }
@@ -641,12 +970,23 @@ impl SelectionTool {
                 room.cache_entity_idx(idx);
                 for node_idx in 0..entity.nodes.len() {
                     let sel = AppSelection::EntityNode(entity.id, node_idx, false);
-                    if intersects_any(&self.rects_of(app, room, sel), &room_rect) {
+                    let node = &entity.nodes[node_idx];
+                    if !locked(&sel)
+                        && intersects_any(
+                            &with_hit_area(rects_of(app, room, sel), node.x, node.y),
+                            shape,
+                        )
+                    {
                         result.insert(sel);
                     }
                 }
                 let sel = AppSelection::EntityBody(entity.id, false);
-                if intersects_any(&self.rects_of(app, room, sel), &room_rect) {
+                if !locked(&sel)
+                    && intersects_any(
+                        &with_hit_area(rects_of(app, room, sel), entity.x, entity.y),
+                        shape,
+                    )
+                {
                     result.insert(sel);
                 }
             }
@@ -656,12 +996,23 @@ impl SelectionTool {
                 room.cache_entity_idx(idx);
                 for node_idx in 0..entity.nodes.len() {
                     let sel = AppSelection::EntityNode(entity.id, node_idx, true);
-                    if intersects_any(&self.rects_of(app, room, sel), &room_rect) {
+                    let node = &entity.nodes[node_idx];
+                    if !locked(&sel)
+                        && intersects_any(
+                            &with_hit_area(rects_of(app, room, sel), node.x, node.y),
+                            shape,
+                        )
+                    {
                         result.insert(sel);
                     }
                 }
                 let sel = AppSelection::EntityBody(entity.id, true);
-                if intersects_any(&self.rects_of(app, room, sel), &room_rect) {
+                if !locked(&sel)
+                    && intersects_any(
+                        &with_hit_area(rects_of(app, room, sel), entity.x, entity.y),
+                        shape,
+                    )
+                {
                     result.insert(sel);
                 }
             }
@@ -670,7 +1021,7 @@ impl SelectionTool {
             for (idx, decal) in room.data.bg_decals.iter().enumerate().rev() {
                 room.cache_decal_idx(idx);
                 let sel = AppSelection::Decal(decal.id, false);
-                if intersects_any(&self.rects_of(app, room, sel), &room_rect) {
+                if !locked(&sel) && intersects_any(&rects_of(app, room, sel), shape) {
                     result.insert(sel);
                 }
             }
@@ -679,7 +1030,9 @@ impl SelectionTool {
             if let Some(room_rect_cropped) = room_rect_cropped {
                 for tile_pos_unaligned in rect_point_iter(room_rect_cropped, 8) {
                     let tile_pos = point_room_to_tile(&tile_pos_unaligned);
-                    if room.tile(tile_pos, false).unwrap_or('0') != '0' {
+                    if shape.intersects_tile(tile_pos)
+                        && room.tile(tile_pos, false).unwrap_or('0') != '0'
+                    {
                         result.insert(AppSelection::BgTile(tile_pos));
                     }
                 }
@@ -692,7 +1045,9 @@ impl SelectionTool {
                 for tile_pos in
                     rect_point_iter(float_rect_cropped, 8).map(|point| point_room_to_tile(&point))
                 {
-                    if grid.get(tile_pos - pt.to_vector()).copied().unwrap_or('\0') != '\0' {
+                    if shape.intersects_tile(tile_pos)
+                        && grid.get(tile_pos - pt.to_vector()).copied().unwrap_or('\0') != '\0'
+                    {
                         result.insert(AppSelection::BgFloat);
                         break;
                     }
@@ -703,6 +1058,105 @@ impl SelectionTool {
         result
     }
 
+    /// Add a node to the end of an entity's nodes and select it, ready to be dragged into place.
+    #[must_use]
+    fn append_node(
+        &mut self,
+        app: &AppState,
+        room: &LevelState,
+        id: i32,
+        trigger: bool,
+        pos: RoomPoint,
+        pos_unsnapped: RoomPoint,
+    ) -> AppEventStaging {
+        let Some(entity) = room.entity(id, trigger) else { return AppEventStaging::default() };
+        let (_, max_nodes) = app
+            .current_palette_unwrap()
+            .get_entity_config(&entity.name, trigger)
+            .node_range();
+        if entity.nodes.len() >= max_nodes {
+            log::warn!("{} can't have more than {} nodes", entity.name, max_nodes);
+            return AppEventStaging::default();
+        }
+        let mut entity = entity.clone();
+        entity.nodes.push(Node { x: pos.x, y: pos.y });
+        let node_idx = entity.nodes.len() - 1;
+
+        // the drag which follows is part of the same undo step
+        self.draw_phase = EventPhase::new();
+        self.status = SelectionStatus::CouldStartDragging(pos, pos_unsnapped);
+        let mut result = self.clear_selection(app, &room.floats);
+        result.push_room(RoomAction::EntityUpdate {
+            entity: Box::new(entity),
+            trigger,
+        });
+        result.push_ind(AppEvent::SelectObjects {
+            tab: app.current_tab,
+            selection: HashSet::from([AppSelection::EntityNode(id, node_idx, trigger)]),
+        });
+        result
+    }
+
+    /// Remove a single node, leaving the rest of the selection alone.
+    #[must_use]
+    fn remove_node(
+        &mut self,
+        app: &AppState,
+        room: &LevelState,
+        id: i32,
+        node_idx: usize,
+        trigger: bool,
+    ) -> AppEventStaging {
+        let mut result = AppEventStaging::default();
+        let Some(entity) = room.entity(id, trigger) else { return result };
+        if node_idx >= entity.nodes.len() {
+            return result;
+        }
+        let (min_nodes, _) = app
+            .current_palette_unwrap()
+            .get_entity_config(&entity.name, trigger)
+            .node_range();
+        if entity.nodes.len() <= min_nodes {
+            log::warn!("{} needs at least {} nodes", entity.name, min_nodes);
+            return result;
+        }
+        let mut entity = entity.clone();
+        entity.nodes.remove(node_idx);
+        result.push_room(RoomAction::EntityUpdate {
+            entity: Box::new(entity),
+            trigger,
+        });
+        // the later nodes' indices have all shifted, so their selections no longer mean anything
+        let selection = app
+            .map_tab_unwrap()
+            .current_selected
+            .iter()
+            .filter(|sel| {
+                !matches!(sel, AppSelection::EntityNode(sel_id, idx, sel_trigger)
+                    if *sel_id == id && *sel_trigger == trigger && *idx >= node_idx)
+            })
+            .copied()
+            .collect();
+        result.push_ind(AppEvent::ClearSelection {
+            tab: app.current_tab,
+        });
+        result.push_ind(AppEvent::SelectObjects {
+            tab: app.current_tab,
+            selection,
+        });
+        result
+    }
+
+    #[must_use]
+    fn toggle_lock(&mut self, app: &AppState) -> AppEventStaging {
+        let mut result = AppEventStaging::default();
+        result.push_ind(AppEvent::ToggleLock {
+            tab: app.current_tab,
+            selection: app.map_tab_unwrap().current_selected.clone(),
+        });
+        result
+    }
+
     #[must_use]
     fn clear_selection(&mut self, app: &AppState, floats: &LevelFloatState) -> AppEventStaging {
         let mut result = AppEventStaging::default();
@@ -715,6 +1169,22 @@ impl SelectionTool {
         result
     }
 
+    /// Whether any tiles are selected. Tiles can only move by whole tiles, so nothing else in the
+    /// selection may move by less without drifting away from them.
+    fn any_tiles(&self, app: &AppState) -> bool {
+        app.map_tab_unwrap().current_selected.iter().any(|sel| {
+            matches!(
+                sel,
+                AppSelection::FgTile(_)
+                    | AppSelection::BgTile(_)
+                    | AppSelection::ObjectTile(_)
+                    | AppSelection::FgFloat
+                    | AppSelection::BgFloat
+                    | AppSelection::ObjFloat
+            )
+        })
+    }
+
     /// This function interprets nudge as relative to the reference positions in Dragging mode and
     /// relative to the current position in other modes.
     #[must_use]
@@ -966,7 +1436,7 @@ impl SelectionTool {
                         fg: true,
                         offset: pt,
                         data: TileGrid {
-                            tiles: vec!['0'],
+                            tiles: vec!['0'].into(),
                             stride: 1,
                         },
                     });
@@ -979,7 +1449,7 @@ impl SelectionTool {
                         fg: false,
                         offset: pt,
                         data: TileGrid {
-                            tiles: vec!['0'],
+                            tiles: vec!['0'].into(),
                             stride: 1,
                         },
                     });
@@ -991,7 +1461,7 @@ impl SelectionTool {
                     result.push_room(RoomAction::ObjectTileUpdate {
                         offset: pt,
                         data: TileGrid {
-                            tiles: vec![-1],
+                            tiles: vec![-1].into(),
                             stride: 1,
                         },
                     });
@@ -1009,36 +1479,11 @@ impl SelectionTool {
         (result, floats)
     }
 
-    fn elaborate_nodes(&mut self, app: &AppState, room: &LevelState) -> AppEventStaging {
-        let mut result = AppEventStaging::default();
-        let mut select = HashSet::new();
-        for sel in app
-            .map_tab_unwrap()
-            .current_selected
-            .iter()
-            .cloned()
-            .collect::<Vec<AppSelection>>()
-        // TODO why did I collect here & elsewhere
-        {
-            if let AppSelection::EntityNode(id, _, trigger) = sel {
-                select.insert(AppSelection::EntityBody(id, trigger));
-                for (idx, _) in room.entity(id, trigger).unwrap().nodes.iter().enumerate() {
-                    select.insert(AppSelection::EntityNode(id, idx, trigger));
-                }
-            }
-        }
-        result.push_ind(AppEvent::SelectObjects {
-            tab: app.current_tab,
-            selection: select,
-        });
-        result
-    }
-
     fn can_resize(&self, app: &AppState, room: &LevelState, pointer: RoomPoint) -> ResizeSide {
         // get which side of the rectangle we're on
         let mut side = ResizeSide::None;
         'outer: for sel in app.map_tab_unwrap().current_selected.iter() {
-            for rect in self.rects_of(app, room, *sel) {
+            for rect in rects_of(app, room, *sel) {
                 if rect.contains(pointer) {
                     let smaller_rect = rect.inflate(-2, -2);
                     let at_top = pointer.y < smaller_rect.min_y();
@@ -1095,14 +1540,16 @@ impl SelectionTool {
         app: &AppState,
         room: &LevelState,
         pointer_reference_point: RoomPoint,
-        pointer_reference_point_unsnapped: RoomPoint,
+        pointer_reference_point_unsnapped: Option<RoomPoint>,
     ) -> (AppEventStaging, LevelFloatState) {
         // Offload all fg/bg selections into the floats
         let (result, floats) = self.float_tiles(room, app.current_tab, app.map_tab_unwrap());
         let mut result_floats = room.floats.clone();
         add_floats_to_floats(&mut result_floats, &floats);
 
-        let side = self.can_resize(app, room, pointer_reference_point_unsnapped);
+        // without an unsnapped point to check against the handles, only move
+        let side = pointer_reference_point_unsnapped
+            .map_or(ResizeSide::None, |pt| self.can_resize(app, room, pt));
         if side != ResizeSide::None {
             // collect reference sizes
             let selection_reference_sizes = app
@@ -1264,6 +1711,14 @@ impl SelectionTool {
         for (id, indices) in entity_nodes_removed {
             if !entities_removed.contains(&id) {
                 if let Some(entity) = room.entity(*id, false) {
+                    let (min_nodes, _) = app
+                        .current_palette_unwrap()
+                        .get_entity_config(&entity.name, false)
+                        .node_range();
+                    if entity.nodes.len().saturating_sub(indices.len()) < min_nodes {
+                        log::warn!("{} needs at least {} nodes", entity.name, min_nodes);
+                        continue;
+                    }
                     let mut entity = entity.clone();
                     for idx in (0..entity.nodes.len()).rev() {
                         if indices.contains(&idx) {
@@ -1281,6 +1736,14 @@ impl SelectionTool {
         for (id, indices) in trigger_nodes_removed {
             if !triggers_removed.contains(&id) {
                 if let Some(entity) = room.entity(*id, true) {
+                    let (min_nodes, _) = app
+                        .current_palette_unwrap()
+                        .get_entity_config(&entity.name, true)
+                        .node_range();
+                    if entity.nodes.len().saturating_sub(indices.len()) < min_nodes {
+                        log::warn!("{} needs at least {} nodes", entity.name, min_nodes);
+                        continue;
+                    }
                     let mut entity = entity.clone();
                     for idx in (0..entity.nodes.len()).rev() {
                         if indices.contains(&idx) {
@@ -1301,25 +1764,52 @@ impl SelectionTool {
         result
     }
 
+    /// Copy the selection and delete it. A node on its own can't be pasted, so copying it copies
+    /// its whole entity; cutting it would then leave a copy of the entity in the room and another
+    /// on the clipboard. Nodes are only cut along with their entity, and the cut is refused if any
+    /// are selected without it.
+    #[must_use]
+    fn clipboard_cut(&mut self, app: &AppState, room: &LevelState) -> AppEventStaging {
+        let selected = &app.map_tab_unwrap().current_selected;
+        let lone_node = selected.iter().any(|sel| match sel {
+            AppSelection::EntityNode(id, _, trigger) => {
+                !selected.contains(&AppSelection::EntityBody(*id, *trigger))
+            }
+            _ => false,
+        });
+        if lone_node {
+            log::warn!("Can't cut nodes without their entity. Copy or delete them instead.");
+            return AppEventStaging::default();
+        }
+        let mut result = self.clipboard_copy(app, room);
+        result.accumulate(self.delete_all(app, room));
+        result
+    }
+
     pub fn clipboard_copy(&mut self, app: &AppState, room: &LevelState) -> AppEventStaging {
-        let (mut result, float) = self.float_tiles(room, app.current_tab, app.map_tab_unwrap());
+        // copying must leave the room and the selection exactly as they were, so the selected
+        // tiles are gathered into a scratch float and the events which would float them for real
+        // are thrown away
+        let (_, float) = self.float_tiles(room, app.current_tab, app.map_tab_unwrap());
         let mut result_float = room.floats.clone();
         add_floats_to_floats(&mut result_float, &float);
 
-        result.accumulate(self.elaborate_nodes(app, room));
-
         let mut clipboard_data: Vec<AppInRoomSelectable> = vec![];
+        let mut entities_seen = HashSet::new();
         for sel in app.map_tab_unwrap().current_selected.iter() {
             match sel {
                 AppSelection::FgTile(_) | AppSelection::BgTile(_) | AppSelection::ObjectTile(_) => {
                 }
                 AppSelection::FgFloat | AppSelection::BgFloat | AppSelection::ObjFloat => {}
-                AppSelection::EntityNode(_, _, _) => {}
-                AppSelection::EntityBody(id, trigger) => {
-                    clipboard_data.push(AppInRoomSelectable::Entity(
-                        room.entity(*id, *trigger).unwrap().clone(),
-                        *trigger,
-                    ));
+                // a selected node copies its whole entity
+                AppSelection::EntityBody(id, trigger)
+                | AppSelection::EntityNode(id, _, trigger) => {
+                    if entities_seen.insert((*id, *trigger)) {
+                        clipboard_data.push(AppInRoomSelectable::Entity(
+                            room.entity(*id, *trigger).unwrap().clone(),
+                            *trigger,
+                        ));
+                    }
                 }
                 AppSelection::Decal(id, fg) => {
                     clipboard_data.push(AppInRoomSelectable::Decal(
@@ -1340,17 +1830,25 @@ impl SelectionTool {
         }
         let s = serde_yaml::to_string(&AppSelectable::InRoom(clipboard_data))
             .expect("Failed to serialize copied data");
+        let mut result = AppEventStaging::default();
         result.push_ind(AppEvent::SetClipboard { contents: s });
-        result.accumulate(floats_to_events(result_float));
         result
     }
 
-    pub fn clipboard_paste(&mut self, app: &AppState, data: String) -> AppEventStaging {
-        let mut result = self.clear_selection(app, &app.current_room_ref().unwrap().floats);
+    /// Paste the clipboard contents centered on `at`, or on the middle of the room. The pasted
+    /// content is left selected and follows the cursor until a click places it, or escape takes it
+    /// back out.
+    pub fn clipboard_paste(
+        &mut self,
+        app: &AppState,
+        data: String,
+        at: Option<TilePoint>,
+    ) -> AppEventStaging {
+        let Some(room) = app.current_room_ref() else { return AppEventStaging::default() };
+        let mut result = self.clear_selection(app, &room.floats);
         let mut result_float = LevelFloatState::default();
 
         let Ok(AppSelectable::InRoom(clipboard_data)) = serde_yaml::from_str(&data) else { return result };
-        let Some(room) = app.current_room_ref() else { return result };
         if clipboard_data.is_empty() {
             return result;
         }
@@ -1403,8 +1901,10 @@ impl SelectionTool {
             }
         }
         let center = (min_tile.to_vector() + max_tile.to_vector()) / 2;
-        let real_center =
-            (size_room_to_tile(&room.data.bounds.size.cast_unit::<RoomSpace>()) / 2).to_vector();
+        let real_center = at.map_or_else(
+            || (size_room_to_tile(&room.data.bounds.size.cast_unit::<RoomSpace>()) / 2).to_vector(),
+            |at| at.to_vector(),
+        );
         let offset = real_center - center;
         for obj in clipboard_data {
             match obj {
@@ -1442,19 +1942,133 @@ impl SelectionTool {
             }
         }
         result.accumulate(floats_to_events(result_float));
+        self.status = SelectionStatus::Placing(point_tile_to_room(&real_center.to_point()));
+        self.placing = true;
         result
     }
 }
 
+/// The areas of the room covered by `selectable`, which are what it is hit tested against.
+pub fn rects_of(app: &AppState, room: &LevelState, selectable: AppSelection) -> Vec<RoomRect> {
+    fn rects_of_layer<T: Copy + Eq>(
+        layer: Option<&(Point2D<i32, TileSpace>, TileGrid<T>)>,
+        nil: T,
+    ) -> Vec<RoomRect> {
+        if let Some((origin, grid)) = layer {
+            rect_point_iter(TileRect::new(TilePoint::zero(), grid.size()), 1)
+                .filter_map(|pt| {
+                    let tile = grid.get(pt);
+                    if *tile.unwrap() != nil {
+                        Some(RoomRect::new(
+                            point_tile_to_room(&(*origin + pt.to_vector())),
+                            RoomSize::new(8, 8),
+                        ))
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+    match selectable {
+        AppSelection::FgTile(pt) | AppSelection::BgTile(pt) | AppSelection::ObjectTile(pt) => {
+            vec![RoomRect::new(point_tile_to_room(&pt), RoomSize::new(8, 8))]
+        }
+        AppSelection::FgFloat => rects_of_layer(room.floats.fg.as_ref(), '\0'),
+        AppSelection::BgFloat => rects_of_layer(room.floats.bg.as_ref(), '\0'),
+        AppSelection::ObjFloat => rects_of_layer(room.floats.obj.as_ref(), -2),
+        AppSelection::EntityBody(id, trigger) => {
+            if let Some(entity) = room.entity(id, trigger) {
+                let config = app
+                    .current_palette_unwrap()
+                    .get_entity_config(&entity.name, trigger);
+                let env = make_entity_env(entity);
+                config
+                    .hitboxes
+                    .initial_rects
+                    .iter()
+                    .filter_map(|r| r.evaluate_int(&env).ok())
+                    .collect()
+            } else {
+                vec![]
+            }
+        }
+        AppSelection::EntityNode(id, node_idx, trigger) => {
+            if let Some(entity) = room.entity(id, trigger) {
+                let config = app
+                    .current_palette_unwrap()
+                    .get_entity_config(&entity.name, trigger);
+                let env = make_node_env(entity, make_entity_env(entity), node_idx);
+                config
+                    .hitboxes
+                    .node_rects
+                    .iter()
+                    .filter_map(|r| r.evaluate_int(&env).ok())
+                    .collect()
+            } else {
+                vec![]
+            }
+        }
+        AppSelection::Decal(id, fg) => {
+            if let Some(decal) = room.decal(id, fg) {
+                let dim = app
+                    .current_palette_unwrap()
+                    .gameplay_atlas
+                    .sprite_dimensions(&decal_texture(decal))
+                    .unwrap_or(Size2D::new(16, 16));
+                let size: Vector2D<f32, RoomSpace> = dim
+                    .cast()
+                    .cast_unit()
+                    .to_vector()
+                    .component_mul(Vector2D::new(decal.scale_x, decal.scale_y));
+                // hit test rotated decals by the box enclosing them
+                let (sin, cos) = decal.rotation.to_radians().sin_cos();
+                let size: RoomSize = Size2D::new(
+                    (size.x * cos).abs() + (size.y * sin).abs(),
+                    (size.x * sin).abs() + (size.y * cos).abs(),
+                )
+                .round()
+                .cast();
+                vec![Rect::new(RoomPoint::new(decal.x, decal.y) - size / 2, size)]
+            } else {
+                vec![]
+            }
+        }
+    }
+}
+
 // oh would it were that rust iterators weren't a fucking pain to write
-fn intersects_any(haystack: &[RoomRect], needle: &RoomRect) -> bool {
+fn intersects_any(haystack: &[RoomRect], needle: &SelectionShape) -> bool {
     for hay in haystack {
-        if hay.intersects(needle) {
+        if needle.intersects(hay) {
             return true;
         }
     }
     false
 }
+
+/// The minimum distance from an entity or node's origin at which it can be clicked on, so that
+/// entities with tiny or empty hitboxes can still be picked.
+const MIN_HIT_RADIUS: i32 = 4;
+
+/// Add a small hit area around the given origin to a set of hitboxes if they are all too thin to
+/// reliably click. Only for picking - the hitboxes drawn are left as they are.
+fn with_hit_area(mut rects: Vec<RoomRect>, x: i32, y: i32) -> Vec<RoomRect> {
+    let min_size = MIN_HIT_RADIUS * 2;
+    if rects
+        .iter()
+        .all(|r| r.width() < min_size || r.height() < min_size)
+    {
+        rects.push(RoomRect::new(
+            RoomPoint::new(x - MIN_HIT_RADIUS, y - MIN_HIT_RADIUS),
+            RoomSize::new(min_size, min_size),
+        ));
+    }
+    rects
+}
+
 pub(crate) fn add_to_float<T: Copy>(
     float: &mut Option<(TilePoint, TileGrid<T>)>,
     pt: TilePoint,
@@ -1465,7 +2079,7 @@ pub(crate) fn add_to_float<T: Copy>(
         let mut default = (
             pt,
             TileGrid {
-                tiles: vec![],
+                tiles: vec![].into(),
                 stride: 1,
             },
         );
@@ -1482,14 +2096,14 @@ pub(crate) fn add_to_float<T: Copy>(
 
         let new_dat = if new_size != old_size {
             let mut new_dat = TileGrid {
-                tiles: vec![filler; (new_size.x * new_size.y) as usize],
+                tiles: vec![filler; (new_size.x * new_size.y) as usize].into(),
                 stride: new_size.x as usize,
             };
             let movement = *old_origin - new_origin;
             let dest_start_offset = movement.x + movement.y * new_size.x;
             for line in 0..old_size.y {
                 let src = &old_dat.tiles[(line * old_size.x) as usize..][..old_size.x as usize];
-                new_dat.tiles[(dest_start_offset + line * new_size.x) as usize..]
+                new_dat.tiles_mut()[(dest_start_offset + line * new_size.x) as usize..]
                     [..old_size.x as usize]
                     .clone_from_slice(src);
             }
@@ -1500,7 +2114,7 @@ pub(crate) fn add_to_float<T: Copy>(
         };
 
         let movement = pt - new_origin;
-        new_dat.tiles[(movement.x + movement.y * new_size.x) as usize] = *ch;
+        new_dat.tiles_mut()[(movement.x + movement.y * new_size.x) as usize] = *ch;
     }
 }
 
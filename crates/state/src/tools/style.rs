@@ -25,9 +25,7 @@ impl Tool for StyleTool {
         let screen_pos = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let map_pos_precise = app
             .map_tab_unwrap()
-            .transform
-            .inverse()
-            .unwrap()
+            .inverse_transform()
             .transform_point(screen_pos);
         let map_pos_unsnapped = point_lose_precision(&map_pos_precise);
         //let map_pos = (map_pos_unsnapped / 8) * 8;
@@ -1,5 +1,6 @@
 pub use euclid::{Angle, Point2D, Rect, Size2D, Transform2D, UnknownUnit, Vector2D};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 pub struct TileSpace;
 pub struct RoomSpace;
@@ -61,6 +62,41 @@ pub fn point_lose_precision(pt: &MapPointPrecise) -> MapPointStrict {
     MapPointStrict::new(pt.x.floor() as i32, pt.y.floor() as i32)
 }
 
+/// The furthest out a view may zoom, in screen pixels per map pixel.
+pub const MIN_ZOOM: f32 = 1.0 / 256.0;
+/// The furthest in a view may zoom, in screen pixels per map pixel.
+pub const MAX_ZOOM: f32 = 256.0;
+
+/// Zoom a view transform by `delta` around `focus`, clamping the resulting scale to
+/// `MIN_ZOOM..=MAX_ZOOM`. This keeps the transform invertible no matter how far the user scrolls.
+pub fn zoom_transform<Src, Dst>(
+    transform: &Transform2D<f32, Src, Dst>,
+    delta: f32,
+    focus: Point2D<f32, Src>,
+) -> Transform2D<f32, Src, Dst> {
+    let scale = transform.m11;
+    if !scale.is_normal() || scale < 0.0 {
+        // already broken somehow. start over rather than compound it
+        return Transform2D::identity();
+    }
+    if !delta.is_normal() || delta < 0.0 {
+        return *transform;
+    }
+    let delta = (scale * delta).clamp(MIN_ZOOM, MAX_ZOOM) / scale;
+    transform
+        .pre_translate(focus.to_vector())
+        .pre_scale(delta, delta)
+        .pre_translate(-focus.to_vector())
+}
+
+/// Invert a view transform. Zooming is clamped by `zoom_transform` so this should never fail, but
+/// fall back to the identity rather than panic in case it does.
+pub fn invert_transform<Src, Dst>(
+    transform: &Transform2D<f32, Src, Dst>,
+) -> Transform2D<f32, Dst, Src> {
+    transform.inverse().unwrap_or_else(Transform2D::identity)
+}
+
 pub struct RectPointIter<T, U> {
     rect: Rect<T, U>,
     step: Vector2D<T, U>,
@@ -155,16 +191,88 @@ where
     }
 }
 
+/// Whether the point lies inside the closed polygon, by the even-odd rule: wherever a loop
+/// crosses over itself, the overlap counts as outside.
+pub fn polygon_contains<U>(polygon: &[Point2D<i32, U>], x: f32, y: f32) -> bool {
+    let mut inside = false;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = &polygon[(i + 1) % polygon.len()];
+        let (ax, ay, bx, by) = (a.x as f32, a.y as f32, b.x as f32, b.y as f32);
+        if (ay > y) != (by > y) && x < ax + (y - ay) / (by - ay) * (bx - ax) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Whether any part of the rect is inside the closed polygon, by the even-odd rule.
+pub fn polygon_intersects_rect<U>(polygon: &[Point2D<i32, U>], rect: &Rect<i32, U>) -> bool {
+    if polygon.is_empty() {
+        return false;
+    }
+    for (i, a) in polygon.iter().enumerate() {
+        let b = &polygon[(i + 1) % polygon.len()];
+        if segment_intersects_rect(a, b, rect) {
+            return true;
+        }
+    }
+    // no edge touches the rect, so it is either entirely inside or entirely outside
+    polygon_contains(
+        polygon,
+        rect.origin.x as f32 + rect.size.width as f32 / 2.,
+        rect.origin.y as f32 + rect.size.height as f32 / 2.,
+    )
+}
+
+fn segment_intersects_rect<U>(
+    a: &Point2D<i32, U>,
+    b: &Point2D<i32, U>,
+    rect: &Rect<i32, U>,
+) -> bool {
+    let (ax, ay) = (a.x as f32, a.y as f32);
+    let (dx, dy) = ((b.x - a.x) as f32, (b.y - a.y) as f32);
+    let (mut t0, mut t1) = (0f32, 1f32);
+    for (p, q) in [
+        (-dx, ax - rect.min_x() as f32),
+        (dx, rect.max_x() as f32 - ax),
+        (-dy, ay - rect.min_y() as f32),
+        (dy, rect.max_y() as f32 - ay),
+    ] {
+        if p == 0. {
+            if q < 0. {
+                return false;
+            }
+        } else {
+            let r = q / p;
+            if p < 0. {
+                if r > t1 {
+                    return false;
+                }
+                t0 = t0.max(r);
+            } else {
+                if r < t0 {
+                    return false;
+                }
+                t1 = t1.min(r);
+            }
+        }
+    }
+    true
+}
+
+/// The tile data is copy-on-write, so clones of a grid (e.g. room copies, the floats, or undo
+/// entries which replace a whole grid) share storage until one of them is modified. Undo entries
+/// for smaller paints still hold their own copy of the tiles they replaced.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TileGrid<T> {
-    pub tiles: Vec<T>,
+    pub tiles: Arc<Vec<T>>,
     pub stride: usize,
 }
 
 impl<T: Sized> TileGrid<T> {
     pub fn empty() -> Self {
         TileGrid {
-            tiles: vec![],
+            tiles: vec![].into(),
             stride: 1,
         }
     }
@@ -174,7 +282,7 @@ impl<T: Sized> TileGrid<T> {
         T: Clone,
     {
         Self {
-            tiles: vec![fill; (size.width * size.height) as usize],
+            tiles: vec![fill; (size.width * size.height) as usize].into(),
             stride: size.width as usize,
         }
     }
@@ -187,15 +295,26 @@ impl<T: Sized> TileGrid<T> {
         }
     }
 
-    pub fn get_mut(&mut self, pt: TilePoint) -> Option<&mut T> {
+    pub fn get_mut(&mut self, pt: TilePoint) -> Option<&mut T>
+    where
+        T: Clone,
+    {
         if pt.x < 0 || pt.x >= self.stride as i32 || pt.y < 0 {
             None
         } else {
-            self.tiles
+            self.tiles_mut()
                 .get_mut((pt.x + pt.y * self.stride as i32) as usize)
         }
     }
 
+    /// Mutable access to the tile data. Copies it first if it is shared with another grid.
+    pub fn tiles_mut(&mut self) -> &mut Vec<T>
+    where
+        T: Clone,
+    {
+        Arc::make_mut(&mut self.tiles)
+    }
+
     pub fn size(&self) -> TileSize {
         TileSize::new(self.stride as i32, (self.tiles.len() / self.stride) as i32)
     }
@@ -216,14 +335,11 @@ impl<T: Sized> TileGrid<T> {
 
         for line in (0..min_lines).rev() {
             for idx in (0..min_stride).rev() {
-                std::mem::swap(
-                    &mut self.tiles[line * old_stride + idx],
-                    &mut result[line * new_stride + idx],
-                );
+                result[line * new_stride + idx] = self.tiles[line * old_stride + idx].clone();
             }
         }
 
-        self.tiles = result;
+        self.tiles = result.into();
         self.stride = new_stride;
     }
 }
@@ -235,8 +351,124 @@ impl<T: Clone + Default + Sized> TileGrid<T> {
 
     pub fn new_default(size: TileSize) -> Self {
         Self {
-            tiles: vec![T::default(); (size.width * size.height) as usize],
+            tiles: vec![T::default(); (size.width * size.height) as usize].into(),
             stride: size.width as usize,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zoom_many(delta: f32, times: usize) -> MapToScreen {
+        let focus = MapPointPrecise::new(123456.0, -98765.0);
+        (0..times).fold(MapToScreen::identity(), |t, _| {
+            zoom_transform(&t, delta, focus)
+        })
+    }
+
+    #[test]
+    fn zoom_clamps_out() {
+        let t = zoom_many(0.5, 1000);
+        assert!((t.m11 - MIN_ZOOM).abs() < MIN_ZOOM * 1e-3);
+        assert!(t.inverse().is_some());
+    }
+
+    #[test]
+    fn zoom_clamps_in() {
+        let t = zoom_many(2.0, 1000);
+        assert!((t.m11 - MAX_ZOOM).abs() < MAX_ZOOM * 1e-3);
+        assert!(t.inverse().is_some());
+    }
+
+    #[test]
+    fn zoom_ignores_bad_deltas() {
+        let t = zoom_many(0.5, 3);
+        for delta in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert_eq!(zoom_transform(&t, delta, MapPointPrecise::zero()), t);
+        }
+    }
+
+    #[test]
+    fn extreme_zoom_round_trips() {
+        for t in [zoom_many(0.5, 1000), zoom_many(2.0, 1000)] {
+            let t = t.then_translate(ScreenVector::new(640.0, 360.0));
+            let inv = invert_transform(&t);
+            for pt in [
+                MapPointPrecise::new(0.0, 0.0),
+                MapPointPrecise::new(-50000.0, 80000.0),
+                MapPointPrecise::new(250000.0, 250000.0),
+            ] {
+                let back = inv.transform_point(t.transform_point(pt));
+                assert!((back - pt).length() < 1.0, "{pt:?} came back as {back:?}");
+            }
+        }
+    }
+
+    /// The bytes of tile data actually held by `grids`, counting shared storage once.
+    fn tile_bytes(grids: &[TileGrid<char>]) -> usize {
+        let mut seen = Vec::new();
+        for grid in grids {
+            if !seen.iter().any(|other| Arc::ptr_eq(other, &grid.tiles)) {
+                seen.push(grid.tiles.clone());
+            }
+        }
+        seen.iter()
+            .map(|tiles| tiles.len() * std::mem::size_of::<char>())
+            .sum()
+    }
+
+    #[test]
+    fn tile_grid_clones_share_storage() {
+        // a stress-sized room, snapshotted into a long undo history
+        let grid = TileGrid::new(TileSize::new(1000, 1000), '0');
+        let one = 1000 * 1000 * std::mem::size_of::<char>();
+        let mut history = vec![grid; 100];
+        assert_eq!(tile_bytes(&history), one);
+
+        // writing to one snapshot copies only that one
+        *history[0].get_mut(TilePoint::new(5, 5)).unwrap() = '1';
+        assert_eq!(tile_bytes(&history), 2 * one);
+        assert_eq!(history[1].get_or_default(TilePoint::new(5, 5)), '0');
+    }
+
+    #[test]
+    fn polygon_hit_testing() {
+        let rect = |x, y, w, h| RoomRect::new(RoomPoint::new(x, y), RoomSize::new(w, h));
+        let triangle = [
+            RoomPoint::new(0, 0),
+            RoomPoint::new(100, 0),
+            RoomPoint::new(0, 100),
+        ];
+        assert!(polygon_intersects_rect(&triangle, &rect(10, 10, 5, 5)));
+        assert!(polygon_intersects_rect(&triangle, &rect(45, 45, 20, 20)));
+        assert!(polygon_intersects_rect(
+            &triangle,
+            &rect(-50, -50, 200, 200)
+        ));
+        assert!(!polygon_intersects_rect(&triangle, &rect(60, 60, 10, 10)));
+        assert!(!polygon_intersects_rect(&[], &rect(0, 0, 10, 10)));
+
+        // a square traced twice around overlaps itself, so even-odd puts its middle outside
+        let twice = [
+            RoomPoint::new(0, 0),
+            RoomPoint::new(100, 0),
+            RoomPoint::new(100, 100),
+            RoomPoint::new(0, 100),
+            RoomPoint::new(0, 0),
+            RoomPoint::new(100, 0),
+            RoomPoint::new(100, 100),
+            RoomPoint::new(0, 100),
+        ];
+        assert!(!polygon_contains(&twice, 50., 50.));
+        assert!(!polygon_intersects_rect(&twice, &rect(40, 40, 20, 20)));
+        assert!(polygon_intersects_rect(&twice, &rect(90, 40, 20, 20)));
+    }
+
+    #[test]
+    fn invert_degenerate_transform() {
+        let t = MapToScreen::scale(0.0, 0.0);
+        assert_eq!(invert_transform(&t), Transform2D::identity());
+    }
+}
@@ -1,11 +1,12 @@
+use std::ffi::OsString;
 use std::fmt::Formatter;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Write};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
 use super::ReadSeek;
-use crate::ConfigSourceTrait;
+use crate::{ConfigSourceMutTrait, ConfigSourceTrait};
 
 pub struct FolderSource(PathBuf);
 
@@ -72,3 +73,32 @@ impl ConfigSourceTrait for FolderSource {
         Some(Box::new(BufReader::new(file)))
     }
 }
+
+impl ConfigSourceMutTrait for FolderSource {
+    fn put_file(&mut self, path: &Path, data: &[u8]) -> std::io::Result<()> {
+        let dest = self.0.join(path);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // write next to the destination so the rename can't cross filesystems
+        let mut tmp_name = dest
+            .file_name()
+            .map_or_else(OsString::new, |n| n.to_owned());
+        tmp_name.push(".tmp");
+        let tmp = dest.with_file_name(tmp_name);
+
+        let result = File::create(&tmp).and_then(|mut f| {
+            f.write_all(data)?;
+            f.sync_all()
+        });
+        if let Err(e) = result.and_then(|_| std::fs::rename(&tmp, &dest)) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn delete_file(&mut self, path: &Path) -> std::io::Result<()> {
+        std::fs::remove_file(self.0.join(path))
+    }
+}
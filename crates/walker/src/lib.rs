@@ -18,6 +18,16 @@ pub enum ConfigSource {
     Zip(ZipSource),
 }
 
+impl ConfigSource {
+    /// The writable side of this source. Only plain folders can be edited in place.
+    pub fn as_mut_source(&mut self) -> Option<&mut dyn ConfigSourceMutTrait> {
+        match self {
+            ConfigSource::Dir(s) => Some(s),
+            ConfigSource::Embedded(_) | ConfigSource::Zip(_) => None,
+        }
+    }
+}
+
 impl Display for ConfigSource {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -40,6 +50,13 @@ pub trait ConfigSourceTrait {
     fn get_file(&mut self, path: &Path) -> Option<Box<dyn ReadSeek>>;
 }
 
+pub trait ConfigSourceMutTrait {
+    /// Replace the contents of `path`, creating it and its parents if needed. Readers never see a
+    /// partially written file.
+    fn put_file(&mut self, path: &Path, data: &[u8]) -> std::io::Result<()>;
+    fn delete_file(&mut self, path: &Path) -> std::io::Result<()>;
+}
+
 pub fn open_module(path: &Path) -> Option<ConfigSource> {
     FolderSource::new(path)
         .map(FolderSource::into)
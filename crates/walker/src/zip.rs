@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 use std::fmt::Formatter;
 use std::fs::File;
-use std::io::{Cursor, Read};
+use std::io::{Cursor, Read, Seek};
 use std::path::{Path, PathBuf};
 use zip::ZipArchive;
 
@@ -12,6 +12,8 @@ use super::ReadSeek;
 pub struct ZipSource {
     path: PathBuf,
     archive: ZipArchive<File>,
+    /// A single directory wrapping the whole mod, which is hidden from all paths.
+    prefix: PathBuf,
 }
 
 impl ZipSource {
@@ -19,13 +21,52 @@ impl ZipSource {
         File::open(path)
             .ok()
             .and_then(|f| ZipArchive::new(f).ok())
-            .map(|a| ZipSource {
+            .map(|mut a| ZipSource {
                 path: path.to_path_buf(),
+                prefix: find_prefix(&mut a),
                 archive: a,
             })
     }
 }
 
+/// Many downloads wrap the mod in one top-level folder. If there's no everest.yaml at the root, but
+/// every entry lives under exactly one directory and that directory has one, it is the real root.
+fn find_prefix<R: Read + Seek>(archive: &mut ZipArchive<R>) -> PathBuf {
+    let has_yaml = |archive: &mut ZipArchive<R>, dir: &Path| {
+        ["everest.yaml", "everest.yml"].iter().any(|name| {
+            dir.join(name)
+                .to_str()
+                .map_or(false, |name| archive.by_name(name).is_ok())
+        })
+    };
+    if has_yaml(archive, Path::new("")) {
+        return PathBuf::new();
+    }
+
+    let mut top = None;
+    for idx in 0..archive.len() {
+        let Ok(f) = archive.by_index(idx) else { continue };
+        let name = f.mangled_name();
+        let mut components = name.components();
+        let Some(first) = components.next() else { continue };
+        if components.next().is_none() && !f.is_dir() {
+            // a file at the root
+            return PathBuf::new();
+        }
+        let first = PathBuf::from(first.as_os_str());
+        match &top {
+            None => top = Some(first),
+            Some(top) if *top == first => {}
+            Some(_) => return PathBuf::new(),
+        }
+    }
+
+    match top {
+        Some(top) if has_yaml(archive, &top) => top,
+        _ => PathBuf::new(),
+    }
+}
+
 impl std::fmt::Display for ZipSource {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -49,10 +90,11 @@ impl ConfigSourceTrait for ZipSource {
         for idx in 0..self.archive.len() {
             if let Ok(f) = self.archive.by_index(idx) {
                 if f.is_dir() {
-                    let name = f.mangled_name();
+                    let full = f.mangled_name();
+                    let Ok(name) = full.strip_prefix(&self.prefix) else { continue };
                     if let Ok(rest) = name.strip_prefix(path) {
                         if rest.components().count() == 1 {
-                            seen.insert(name);
+                            seen.insert(name.to_path_buf());
                         }
                     }
                 }
@@ -67,9 +109,10 @@ impl ConfigSourceTrait for ZipSource {
 
         for idx in 0..self.archive.len() {
             if let Ok(f) = self.archive.by_index(idx) {
-                let name = f.mangled_name();
+                let full = f.mangled_name();
+                let Ok(name) = full.strip_prefix(&self.prefix) else { continue };
                 if name.starts_with(path) && f.is_file() {
-                    seen.push(name);
+                    seen.push(name.to_path_buf());
                 }
             }
         }
@@ -78,6 +121,7 @@ impl ConfigSourceTrait for ZipSource {
     }
 
     fn get_file(&mut self, path: &Path) -> Option<Box<dyn ReadSeek>> {
+        let path = self.prefix.join(path);
         self.archive
             .by_name(
                 path.to_str()
@@ -92,3 +136,55 @@ impl ConfigSourceTrait for ZipSource {
             })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    fn archive(files: &[&str]) -> ZipArchive<Cursor<Vec<u8>>> {
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        for name in files {
+            if name.ends_with('/') {
+                zip.add_directory(*name, FileOptions::default()).unwrap();
+            } else {
+                zip.start_file(*name, FileOptions::default()).unwrap();
+            }
+        }
+        ZipArchive::new(zip.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn strips_single_mod_directory() {
+        let mut zip = archive(&[
+            "MyMod/",
+            "MyMod/everest.yaml",
+            "MyMod/Maps/",
+            "MyMod/Maps/a.bin",
+        ]);
+        assert_eq!(find_prefix(&mut zip), PathBuf::from("MyMod"));
+
+        let mut zip = archive(&["MyMod/everest.yml", "MyMod/Maps/a.bin"]);
+        assert_eq!(find_prefix(&mut zip), PathBuf::from("MyMod"));
+    }
+
+    #[test]
+    fn keeps_root_mods() {
+        let mut zip = archive(&["everest.yaml", "Maps/", "Maps/a.bin"]);
+        assert_eq!(find_prefix(&mut zip), PathBuf::new());
+
+        // a single directory without an everest.yaml isn't a wrapped mod
+        let mut zip = archive(&["Maps/", "Maps/a.bin"]);
+        assert_eq!(find_prefix(&mut zip), PathBuf::new());
+    }
+
+    #[test]
+    fn keeps_several_top_level_entries() {
+        let mut zip = archive(&["MyMod/everest.yaml", "Other/everest.yaml"]);
+        assert_eq!(find_prefix(&mut zip), PathBuf::new());
+
+        let mut zip = archive(&["MyMod/everest.yaml", "readme.txt"]);
+        assert_eq!(find_prefix(&mut zip), PathBuf::new());
+    }
+}
@@ -1,10 +1,14 @@
 use std::str::FromStr;
+use std::sync::Arc;
 
+use crate::drag_number::DragNumber;
+use crate::stepper::{Steppable, Stepper};
 use crate::textedit_dropdown::TextboxDropdown;
 use crate::validator_box;
 use crate::validator_box::validator_box;
 use arborio_maploader::map_struct::Attribute;
 use arborio_modloader::config::AttributeType;
+use arborio_state::data::EventPhase;
 use arborio_state::lenses::IsFailedLens;
 use arborio_utils::vizia::fonts::icons_names::{CANCEL, DOWN, PLUS};
 use arborio_utils::vizia::prelude::*;
@@ -43,6 +47,40 @@ where
     });
 }
 
+/// A [`tweak_attr_text`] for a number which can also be changed by dragging its label sideways.
+/// Typed edits each get their own phase; a drag reuses one for its whole duration.
+pub fn tweak_attr_drag<L, F>(cx: &mut Context, name: &'static str, lens: L, speed: f32, setter: F)
+where
+    L: Lens<Target = f32>,
+    F: 'static + Send + Sync + Fn(&mut EventContext, f32, EventPhase),
+{
+    let setter = Arc::new(setter);
+    let drag_setter = setter.clone();
+    let drag_lens = lens.clone();
+    HStack::new(cx, move |cx| {
+        DragNumber::new(
+            cx,
+            drag_lens,
+            speed,
+            |cx| {
+                Label::new(cx, name).class("label");
+            },
+            move |cx, value, phase| drag_setter(cx, value, phase),
+        );
+        validator_box::validator_box(
+            cx,
+            lens,
+            move |cx, value| {
+                setter(cx, value, EventPhase::new());
+                true
+            },
+            |cx, valid| {
+                cx.toggle_class("validation_error", !valid);
+            },
+        );
+    });
+}
+
 pub fn tweak_attr_text_dropdown<L, LL, F>(
     cx: &mut Context,
     name: &'static str,
@@ -112,7 +150,7 @@ pub fn advanced_attrs_editor<
                     false,
                 );
                 let setter2 = setter.clone();
-                attr_editor(
+                attr_stepper(
                     cx,
                     i_value_lens,
                     key_lens.clone(),
@@ -120,7 +158,7 @@ pub fn advanced_attrs_editor<
                     false,
                 );
                 let setter2 = setter.clone();
-                attr_editor(
+                attr_stepper(
                     cx,
                     f_value_lens,
                     key_lens.clone(),
@@ -230,6 +268,46 @@ pub fn attr_editor<T: ToString + FromStr + Data>(
     }
 }
 
+/// An [`attr_editor`] for a number, which can also be stepped with the arrow keys or scroll wheel.
+pub fn attr_stepper<T: ToString + FromStr + Data + Steppable>(
+    cx: &mut Context,
+    lens: impl Lens<Target = T>,
+    key: impl Send + Sync + Lens<Target = String>,
+    setter: impl 'static + Clone + Send + Sync + Fn(&mut EventContext, String, T),
+    force: bool,
+) {
+    if force {
+        attr_stepper_inner(cx, lens, key, setter);
+    } else {
+        Binding::new(cx, IsFailedLens::new(lens.clone()), move |cx, failed| {
+            if !failed.get(cx) {
+                let key = key.clone();
+                let setter = setter.clone();
+                let lens = lens.clone();
+                attr_stepper_inner(cx, lens, key, setter);
+            }
+        });
+    }
+}
+
+fn attr_stepper_inner<T: ToString + FromStr + Data + Steppable>(
+    cx: &mut Context,
+    lens: impl Lens<Target = T>,
+    key: impl Send + Sync + Lens<Target = String>,
+    setter: impl 'static + Clone + Send + Sync + Fn(&mut EventContext, String, T),
+) {
+    let (step_lens, step_key, step_setter) = (lens.clone(), key.clone(), setter.clone());
+    Stepper::new(
+        cx,
+        move |cx| attr_editor_inner(cx, lens, key, setter),
+        move |cx, amount| {
+            if let Some(value) = step_lens.get_fallible(cx) {
+                step_setter(cx, step_key.get(cx), value.step(amount));
+            }
+        },
+    );
+}
+
 pub fn attr_editor_inner<T: ToString + FromStr + Data>(
     cx: &mut Context,
     lens: impl Lens<Target = T>,
@@ -1,8 +1,40 @@
-use crate::advanced_tweaker::attr_editor;
+use crate::advanced_tweaker::{attr_editor, attr_stepper};
 use arborio_maploader::map_struct::Attribute;
 use arborio_modloader::config::{AttributeInfo, AttributeType};
-use arborio_utils::vizia::fonts::icons_names::DOWN;
+use arborio_utils::vizia::fonts::icons_names::{DOWN, UP};
 use arborio_utils::vizia::prelude::*;
+use std::collections::HashSet;
+
+#[derive(Debug, Lens)]
+struct AttrGroupsState {
+    collapsed: HashSet<String>,
+}
+
+#[derive(Debug)]
+enum AttrGroupsEvent {
+    Toggle(String),
+    CollapseAll(Vec<String>),
+    ExpandAll,
+}
+
+impl Model for AttrGroupsState {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|msg, _| match msg {
+            AttrGroupsEvent::Toggle(name) => {
+                if !self.collapsed.remove(name) {
+                    self.collapsed.insert(name.clone());
+                }
+            }
+            AttrGroupsEvent::CollapseAll(names) => self.collapsed.extend(names.iter().cloned()),
+            AttrGroupsEvent::ExpandAll => self.collapsed.clear(),
+        });
+    }
+}
+
+/// Attributes without a group all land in one section.
+fn group_name(group: &Option<String>) -> String {
+    group.clone().unwrap_or_else(|| "General".to_owned())
+}
 
 pub fn basic_attrs_editor<LN, F, LK, LA, LC, FS>(
     cx: &mut Context,
@@ -17,147 +49,80 @@ pub fn basic_attrs_editor<LN, F, LK, LA, LC, FS>(
     LC: Send + Sync + Lens<Target = AttributeInfo>,
     FS: 'static + Clone + Send + Sync + Fn(&mut EventContext, String, Attribute),
 {
+    AttrGroupsState {
+        collapsed: HashSet::new(),
+    }
+    .build(cx);
+
     Binding::new(cx, lens_count, move |cx, info_len| {
         let info_len = info_len.get_fallible(cx).unwrap_or_default();
 
+        let mut groups: Vec<(Option<String>, Vec<usize>)> = vec![(None, vec![])];
         for idx in 0..info_len {
-            let (lens_attr_key, lens_attr_val, lens_attr_info) = index_func(idx);
-            let lens_attr_type = lens_attr_info.clone().then(AttributeInfo::ty);
-            let lens_attr_name = lens_attr_info.clone().then(AttributeInfo::display_name);
-            let lens_attr_opts = lens_attr_info.clone().then(AttributeInfo::options);
-
-            if lens_attr_info.then(AttributeInfo::ignore).get(cx) {
+            let (_, _, lens_attr_info) = index_func(idx);
+            if lens_attr_info.clone().then(AttributeInfo::ignore).get(cx) {
                 continue;
             }
+            let group = lens_attr_info
+                .then(AttributeInfo::group)
+                .get_fallible(cx)
+                .flatten();
+            match groups.iter_mut().find(|(g, _)| *g == group) {
+                Some((_, members)) => members.push(idx),
+                None => groups.push((group, vec![idx])),
+            }
+        }
+
+        // entities which don't group their attributes get the plain list
+        if groups.len() == 1 {
+            for idx in 0..info_len {
+                attr_row(cx, idx, &index_func, setter.clone());
+            }
+            return;
+        }
 
+        let names = groups
+            .iter()
+            .map(|(group, _)| group_name(group))
+            .collect::<Vec<_>>();
+        HStack::new(cx, move |cx| {
+            Button::new(
+                cx,
+                move |cx| cx.emit(AttrGroupsEvent::CollapseAll(names.clone())),
+                |cx| Label::new(cx, "Collapse all"),
+            );
+            Button::new(
+                cx,
+                |cx| cx.emit(AttrGroupsEvent::ExpandAll),
+                |cx| Label::new(cx, "Expand all"),
+            );
+        });
+        for (group, members) in groups {
+            if members.is_empty() {
+                continue;
+            }
+            let name = group_name(&group);
+            let collapsed_lens = AttrGroupsState::collapsed.map({
+                let name = name.clone();
+                move |collapsed| collapsed.contains(&name)
+            });
+            let index_func = index_func.clone();
             let setter = setter.clone();
-            HStack::new(cx, move |cx| {
-                {
-                    let lens_attr_key = lens_attr_key.clone();
-                    let lens_attr_name = lens_attr_name.clone();
-                    Binding::new(cx, lens_attr_key, move |cx, lens_attr_key| {
-                        let attr_key = lens_attr_key.get(cx);
-                        let lens_attr_name = lens_attr_name.clone();
-                        Binding::new(cx, lens_attr_name, move |cx, lens_attr_name| {
-                            let attr_name = lens_attr_name.get(cx);
-                            let name = attr_name.as_ref().unwrap_or(&attr_key);
-                            Label::new(cx, name);
-                        });
-                    });
-                }
-                let lens_attr_val = lens_attr_val.clone();
-                Binding::new(cx, lens_attr_opts, move |cx, lens_attr_opts| {
-                    let lens_attr_val = lens_attr_val.clone();
-                    let lens_attr_key = lens_attr_key.clone();
-                    let setter = setter.clone();
-                    let opts_len =
-                        lens_attr_opts.view(cx.data().unwrap(), |opts| opts.map_or(0, |x| x.len()));
-                    if opts_len != 0 {
-                        // ugh... the placement of this binding is nontrivial
-                        Binding::new(cx, lens_attr_val, move |cx, lens_attr_val| {
-                            let attr_val = lens_attr_val.get_fallible(cx);
-                            let (found_idx, found_lbl) =
-                                lens_attr_opts.view(cx.data().unwrap(), |opts| {
-                                    for (idx, opt) in opts.unwrap().iter().enumerate() {
-                                        if let Some(attr_val) = &attr_val {
-                                            if opt.value.to_binel().eq_insensitive(attr_val) {
-                                                return (Some(idx), Some(opt.name.clone()));
-                                            }
-                                        }
-                                    }
-                                    (None, None)
-                                });
-                            let lens_attr_opts = lens_attr_opts.clone();
-                            let lens_attr_key = lens_attr_key.clone();
-                            let setter = setter.clone();
-                            Dropdown::new(
-                                cx,
-                                move |cx| {
-                                    let found_lbl = found_lbl.clone();
-                                    HStack::new(cx, move |cx| {
-                                        Label::new(
-                                            cx,
-                                            found_lbl.as_ref().map_or("weh", |a| a.as_str()),
-                                        );
-                                        Label::new(cx, DOWN).font_family(vec![FamilyOwned::Name(
-                                            "Entypo".to_owned(),
-                                        )]);
-                                    })
-                                    .width(Stretch(1.0))
-                                },
-                                move |cx| {
-                                    for idx in 0..opts_len {
-                                        let opt = lens_attr_opts.clone().index(idx).get(cx);
-                                        let lens_attr_key = lens_attr_key.clone();
-                                        let setter = setter.clone();
-                                        Label::new(cx, &opt.name)
-                                            .class("dropdown_element")
-                                            .toggle_class("checked", Some(idx) == found_idx)
-                                            .on_press(move |cx| {
-                                                let key = lens_attr_key.get(cx);
-                                                setter(cx.as_mut(), key, opt.value.to_binel());
-                                                cx.emit(PopupEvent::Close);
-                                            });
-                                    }
-                                },
-                            );
-                        });
-                    } else {
-                        Binding::new(cx, lens_attr_type.clone(), move |cx, attr_type| {
-                            let lens_attr_val = lens_attr_val.clone();
-                            let lens_attr_key = lens_attr_key.clone();
-                            let setter = setter.clone();
-                            let attr_type = attr_type.get(cx);
-                            match attr_type {
-                                AttributeType::String => {
-                                    attr_editor(
-                                        cx,
-                                        lens_attr_val.then(Attribute::text),
-                                        lens_attr_key,
-                                        move |cx, key, val| {
-                                            setter(cx, key, Attribute::Text(val));
-                                        },
-                                        true,
-                                    );
-                                }
-                                AttributeType::Int => {
-                                    attr_editor(
-                                        cx,
-                                        lens_attr_val.then(Attribute::int),
-                                        lens_attr_key,
-                                        move |cx, key, val| {
-                                            setter(cx, key, Attribute::Int(val));
-                                        },
-                                        true,
-                                    );
-                                }
-                                AttributeType::Float => {
-                                    attr_editor(
-                                        cx,
-                                        lens_attr_val.then(Attribute::float),
-                                        lens_attr_key,
-                                        move |cx, key, val| {
-                                            setter(cx, key, Attribute::Float(val));
-                                        },
-                                        true,
-                                    );
-                                }
-                                AttributeType::Bool => {
-                                    Checkbox::new(cx, lens_attr_val.clone().then(Attribute::bool))
-                                        .on_toggle(move |cx| {
-                                            let key = lens_attr_key.get(cx);
-                                            let val = !lens_attr_val
-                                                .clone()
-                                                .then(Attribute::bool)
-                                                .get_fallible(cx)
-                                                .unwrap_or(false);
-                                            setter(cx, key, Attribute::Bool(val));
-                                        });
-                                }
-                            }
-                        });
+            Binding::new(cx, collapsed_lens, move |cx, collapsed| {
+                let collapsed = collapsed.get(cx);
+                let header_name = name.clone();
+                let toggle_name = name.clone();
+                HStack::new(cx, move |cx| {
+                    Label::new(cx, if collapsed { DOWN } else { UP }).class("icon");
+                    Label::new(cx, &header_name);
+                })
+                .class("attr_group_header")
+                .on_press(move |cx| cx.emit(AttrGroupsEvent::Toggle(toggle_name.clone())));
+                if !collapsed {
+                    for &idx in &members {
+                        attr_row(cx, idx, &index_func, setter.clone());
                     }
-                });
+                }
             });
         }
 
@@ -188,3 +153,173 @@ pub fn basic_attrs_editor<LN, F, LK, LA, LC, FS>(
         // })
     })
 }
+
+fn attr_row<F, LK, LA, LC, FS>(cx: &mut Context, idx: usize, index_func: &F, setter: FS)
+where
+    F: Fn(usize) -> (LK, LA, LC),
+    LK: Send + Sync + Lens<Target = String>,
+    LA: Send + Sync + Lens<Target = Attribute>,
+    LC: Send + Sync + Lens<Target = AttributeInfo>,
+    FS: 'static + Clone + Send + Sync + Fn(&mut EventContext, String, Attribute),
+{
+    let (lens_attr_key, lens_attr_val, lens_attr_info) = index_func(idx);
+    let lens_attr_type = lens_attr_info.clone().then(AttributeInfo::ty);
+    let lens_attr_name = lens_attr_info.clone().then(AttributeInfo::display_name);
+    let lens_attr_desc = lens_attr_info.clone().then(AttributeInfo::description);
+    let lens_attr_opts = lens_attr_info.clone().then(AttributeInfo::options);
+
+    let lens_attr_default = lens_attr_info.clone().then(AttributeInfo::default);
+    if lens_attr_info.then(AttributeInfo::ignore).get(cx) {
+        return;
+    }
+
+    HStack::new(cx, move |cx| {
+        let reset_key = lens_attr_key.clone();
+        let reset_setter = setter.clone();
+        {
+            let lens_attr_key = lens_attr_key.clone();
+            let lens_attr_name = lens_attr_name.clone();
+            Binding::new(cx, lens_attr_key, move |cx, lens_attr_key| {
+                let attr_key = lens_attr_key.get(cx);
+                let lens_attr_name = lens_attr_name.clone();
+                let lens_attr_desc = lens_attr_desc.clone();
+                Binding::new(cx, lens_attr_name, move |cx, lens_attr_name| {
+                    let attr_name = lens_attr_name.get(cx);
+                    let name = attr_name.unwrap_or_else(|| attr_key.clone());
+                    Binding::new(cx, lens_attr_desc.clone(), move |cx, lens_attr_desc| {
+                        let desc = lens_attr_desc.get(cx);
+                        let name = name.clone();
+                        // the description only shows while the row is hovered
+                        VStack::new(cx, move |cx| {
+                            Label::new(cx, &name);
+                            if let Some(desc) = &desc {
+                                Label::new(cx, desc).class("attr_description");
+                            }
+                        })
+                        .width(Auto)
+                        .height(Auto);
+                    });
+                });
+            });
+        }
+        let lens_attr_val = lens_attr_val.clone();
+        Binding::new(cx, lens_attr_opts, move |cx, lens_attr_opts| {
+            let lens_attr_val = lens_attr_val.clone();
+            let lens_attr_key = lens_attr_key.clone();
+            let setter = setter.clone();
+            let opts_len =
+                lens_attr_opts.view(cx.data().unwrap(), |opts| opts.map_or(0, |x| x.len()));
+            if opts_len != 0 {
+                // ugh... the placement of this binding is nontrivial
+                Binding::new(cx, lens_attr_val, move |cx, lens_attr_val| {
+                    let attr_val = lens_attr_val.get_fallible(cx);
+                    let (found_idx, found_lbl) = lens_attr_opts.view(cx.data().unwrap(), |opts| {
+                        for (idx, opt) in opts.unwrap().iter().enumerate() {
+                            if let Some(attr_val) = &attr_val {
+                                if opt.value.to_binel().eq_insensitive(attr_val) {
+                                    return (Some(idx), Some(opt.name.clone()));
+                                }
+                            }
+                        }
+                        (None, None)
+                    });
+                    let lens_attr_opts = lens_attr_opts.clone();
+                    let lens_attr_key = lens_attr_key.clone();
+                    let setter = setter.clone();
+                    Dropdown::new(
+                        cx,
+                        move |cx| {
+                            let found_lbl = found_lbl.clone();
+                            HStack::new(cx, move |cx| {
+                                Label::new(cx, found_lbl.as_ref().map_or("weh", |a| a.as_str()));
+                                Label::new(cx, DOWN)
+                                    .font_family(vec![FamilyOwned::Name("Entypo".to_owned())]);
+                            })
+                            .width(Stretch(1.0))
+                        },
+                        move |cx| {
+                            for idx in 0..opts_len {
+                                let opt = lens_attr_opts.clone().index(idx).get(cx);
+                                let lens_attr_key = lens_attr_key.clone();
+                                let setter = setter.clone();
+                                Label::new(cx, &opt.name)
+                                    .class("dropdown_element")
+                                    .toggle_class("checked", Some(idx) == found_idx)
+                                    .on_press(move |cx| {
+                                        let key = lens_attr_key.get(cx);
+                                        setter(cx.as_mut(), key, opt.value.to_binel());
+                                        cx.emit(PopupEvent::Close);
+                                    });
+                            }
+                        },
+                    );
+                });
+            } else {
+                Binding::new(cx, lens_attr_type.clone(), move |cx, attr_type| {
+                    let lens_attr_val = lens_attr_val.clone();
+                    let lens_attr_key = lens_attr_key.clone();
+                    let setter = setter.clone();
+                    let attr_type = attr_type.get(cx);
+                    match attr_type {
+                        AttributeType::String => {
+                            attr_editor(
+                                cx,
+                                lens_attr_val.then(Attribute::text),
+                                lens_attr_key,
+                                move |cx, key, val| {
+                                    setter(cx, key, Attribute::Text(val));
+                                },
+                                true,
+                            );
+                        }
+                        AttributeType::Int => {
+                            attr_stepper(
+                                cx,
+                                lens_attr_val.then(Attribute::int),
+                                lens_attr_key,
+                                move |cx, key, val| {
+                                    setter(cx, key, Attribute::Int(val));
+                                },
+                                true,
+                            );
+                        }
+                        AttributeType::Float => {
+                            attr_stepper(
+                                cx,
+                                lens_attr_val.then(Attribute::float),
+                                lens_attr_key,
+                                move |cx, key, val| {
+                                    setter(cx, key, Attribute::Float(val));
+                                },
+                                true,
+                            );
+                        }
+                        AttributeType::Bool => {
+                            Checkbox::new(cx, lens_attr_val.clone().then(Attribute::bool))
+                                .on_toggle(move |cx| {
+                                    let key = lens_attr_key.get(cx);
+                                    let val = !lens_attr_val
+                                        .clone()
+                                        .then(Attribute::bool)
+                                        .get_fallible(cx)
+                                        .unwrap_or(false);
+                                    setter(cx, key, Attribute::Bool(val));
+                                });
+                        }
+                    }
+                });
+            }
+        });
+        Button::new(
+            cx,
+            move |cx| {
+                let key = reset_key.get(cx);
+                let default = lens_attr_default.get(cx);
+                reset_setter(cx, key, default.to_binel());
+            },
+            |cx| Label::new(cx, "Reset"),
+        )
+        .class("reset_btn");
+    })
+    .class("attr_row");
+}
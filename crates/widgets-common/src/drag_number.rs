@@ -0,0 +1,64 @@
+use arborio_state::data::EventPhase;
+use arborio_utils::vizia::prelude::*;
+
+/// Wraps a label so that dragging it sideways changes a number, by `speed` per pixel or a tenth
+/// of that with shift held. Every change made during one drag shares an [`EventPhase`], so the
+/// whole drag is a single undo step.
+pub struct DragNumber<L> {
+    lens: L,
+    speed: f32,
+    /// The cursor x, the value, and the phase when the drag started.
+    drag: Option<(f32, f32, EventPhase)>,
+    on_drag: Box<dyn Fn(&mut EventContext, f32, EventPhase) + Send + Sync>,
+}
+
+impl<L: Lens<Target = f32>> DragNumber<L> {
+    pub fn new<'a>(
+        cx: &'a mut Context,
+        lens: L,
+        speed: f32,
+        content: impl FnOnce(&mut Context),
+        on_drag: impl 'static + Send + Sync + Fn(&mut EventContext, f32, EventPhase),
+    ) -> Handle<'a, Self> {
+        Self {
+            lens,
+            speed,
+            drag: None,
+            on_drag: Box::new(on_drag),
+        }
+        .build(cx, content)
+    }
+}
+
+impl<L: Lens<Target = f32>> View for DragNumber<L> {
+    fn element(&self) -> Option<&'static str> {
+        Some("drag_number")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                let Some(value) = self.lens.get_fallible(cx) else { return };
+                self.drag = Some((cx.mouse.cursorx, value, EventPhase::new()));
+                cx.capture();
+                meta.consume();
+            }
+            WindowEvent::MouseMove(x, _) => {
+                let Some((start_x, start_value, phase)) = self.drag else { return };
+                let speed = if cx.modifiers.contains(Modifiers::SHIFT) {
+                    self.speed / 10.0
+                } else {
+                    self.speed
+                };
+                (self.on_drag)(cx, start_value + (x - start_x).round() * speed, phase);
+                meta.consume();
+            }
+            WindowEvent::MouseUp(MouseButton::Left) if self.drag.is_some() => {
+                self.drag = None;
+                cx.release();
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+}
@@ -2,7 +2,10 @@ pub mod advanced_tweaker;
 pub mod basic_tweaker;
 pub mod confirm_delete;
 pub mod container_model;
+pub mod drag_number;
 pub mod label_with_pencil;
 pub mod list_palette;
+pub mod options;
+pub mod stepper;
 pub mod textedit_dropdown;
 pub mod validator_box;
@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
 use arborio_state::data::app::AppState;
-use arborio_state::lenses::ClosureLens;
+use arborio_state::lenses::{current_map_lens, ClosureLens};
 use arborio_state::palette_item::PaletteItem;
 use arborio_utils::vizia::prelude::*;
 use arborio_utils::vizia::vg::{Paint, Path};
@@ -47,16 +47,61 @@ where
         .build(cx, move |cx| {
             ScrollView::new(cx, 0.0, 0.0, false, true, move |cx| {
                 let selected2 = selected.clone();
-                List::new(cx, items, move |cx, _, item| {
+                let items2 = items.clone();
+                List::new(cx, items, move |cx, index, item| {
+                    // a heading above the first item of each group. the groups get chopped up by
+                    // filtering, so leave them out then
+                    let items3 = items2.clone();
+                    let heading = move |cx: &Context| {
+                        if !palette_widget_filter_lens::<T, LI, LO>().get(cx).is_empty() {
+                            return None;
+                        }
+                        let model = cx.data::<<LL as Lens>::Source>().unwrap();
+                        items3.view(model, |items| {
+                            let items = items?;
+                            let group = items.get(index)?.group()?;
+                            let prev = index.checked_sub(1).and_then(|i| items.get(i));
+                            (prev.and_then(|prev| prev.group()) != Some(group)).then_some(group)
+                        })
+                    };
+                    let heading2 = heading.clone();
+                    Label::new(cx, "")
+                        .class("palette_group")
+                        .bind(items2.clone(), move |handle, _| {
+                            let group = heading(handle.cx);
+                            handle
+                                .text(group.unwrap_or_default())
+                                .display(group.is_some());
+                        })
+                        .bind(
+                            palette_widget_filter_lens::<T, LI, LO>(),
+                            move |handle, _| {
+                                let group = heading2(handle.cx);
+                                handle
+                                    .text(group.unwrap_or_default())
+                                    .display(group.is_some());
+                            },
+                        );
                     let item2 = item.clone();
                     let item3 = item.clone();
                     let item4 = item.clone();
+                    let item5 = item.clone();
                     HStack::new(cx, move |cx| {
                         Label::new(cx, "").bind(item2, |handle, item| {
                             let app = handle.cx.data::<AppState>().unwrap();
                             let text = item.get(handle.cx).display_name(app);
                             handle.text(&text);
                         });
+                        // counted when the current map changes rather than on every edit
+                        Label::new(cx, "").class("usage_badge").bind(
+                            current_map_lens(),
+                            move |handle, _| {
+                                let app = handle.cx.data::<AppState>().unwrap();
+                                let count = item5.get(handle.cx).usage_count(app);
+                                handle.text(&count.map_or(String::new(), |c| c.to_string()));
+                                handle.display(count.map_or(false, |c| c > 0));
+                            },
+                        );
                     })
                     .class("palette_item")
                     .class("list_highlight")
@@ -0,0 +1,130 @@
+//! Values the game knows for some free-form text attributes, to offer as suggestions.
+
+pub const MUSIC_OPTIONS: [&str; 82] = [
+    "event:/music/menu/level_select",
+    "event:/music/menu/credits",
+    "event:/music/menu/complete_area",
+    "event:/music/menu/complete_summit",
+    "event:/music/menu/complete_bside",
+    "event:/game/00_prologue/intro_vignette",
+    "event:/music/lvl0/intro",
+    "event:/music/lvl0/bridge",
+    "event:/music/lvl0/title_ping",
+    "event:/music/lvl1/main",
+    "event:/music/lvl1/theo",
+    "event:/music/lvl2/beginning",
+    "event:/music/lvl2/mirror",
+    "event:/music/lvl2/dreamblock_sting_pt1",
+    "event:/music/lvl2/dreamblock_sting_pt2",
+    "event:/music/lvl2/evil_madeline",
+    "event:/music/lvl2/chase",
+    "event:/music/lvl2/phone_loop",
+    "event:/music/lvl2/phone_end",
+    "event:/music/lvl2/awake",
+    "event:/music/lvl3/intro",
+    "event:/music/lvl3/explore",
+    "event:/music/lvl3/clean",
+    "event:/music/lvl3/clean_extended",
+    "event:/music/lvl3/oshiro_theme",
+    "event:/music/lvl3/oshiro_chase",
+    "event:/music/lvl4/main",
+    "event:/music/lvl4/heavy_winds",
+    "event:/music/lvl4/minigame",
+    "event:/music/lvl5/normal",
+    "event:/music/lvl5/middle_temple",
+    "event:/music/lvl5/mirror",
+    "event:/music/lvl5/mirror_cutscene",
+    "event:/music/lvl6/madeline_and_theo",
+    "event:/music/lvl6/starjump",
+    "event:/music/lvl6/the_fall",
+    "event:/music/lvl6/badeline_fight",
+    "event:/music/lvl6/badeline_glitch",
+    "event:/music/lvl6/badeline_acoustic",
+    "event:/music/lvl6/main",
+    "event:/music/lvl6/secret_room",
+    "event:/music/lvl7/main",
+    "event:/music/lvl7/final_ascent",
+    "event:/music/lvl8/main",
+    "event:/music/lvl9/main",
+    "event:/classic/pico8_mus_00",
+    "event:/classic/pico8_mus_01",
+    "event:/classic/pico8_mus_02",
+    "event:/classic/pico8_mus_03",
+    "event:/classic/sfx61",
+    "event:/classic/sfx62",
+    "event:/classic/pico8_boot",
+    "event:/music/remix/01_forsaken_city",
+    "event:/music/remix/02_old_site",
+    "event:/music/remix/03_resort",
+    "event:/music/remix/04_cliffside",
+    "event:/music/remix/05_mirror_temple",
+    "event:/music/remix/06_reflection",
+    "event:/music/remix/07_summit",
+    "event:/music/remix/09_core",
+    "event:/music/cassette/01_forsaken_city",
+    "event:/music/cassette/02_old_site",
+    "event:/music/cassette/03_resort",
+    "event:/music/cassette/04_cliffside",
+    "event:/music/cassette/05_mirror_temple",
+    "event:/music/cassette/06_reflection",
+    "event:/music/cassette/07_summit",
+    "event:/music/cassette/09_core",
+    "event:/new_content/music/lvl10/part01",
+    "event:/new_content/music/lvl10/part02",
+    "event:/new_content/music/lvl10/part03",
+    "event:/new_content/music/lvl10/intermission_heartgroove",
+    "event:/new_content/music/lvl10/intermission_powerpoint",
+    "event:/new_content/music/lvl10/reconciliation",
+    "event:/new_content/music/lvl10/cassette_rooms",
+    "event:/new_content/music/lvl10/final_run",
+    "event:/new_content/music/lvl10/cinematic/end",
+    "event:/new_content/music/lvl10/cinematic/end_intro",
+    "event:/new_content/music/lvl10/cinematic/bird_crash_first",
+    "event:/new_content/music/lvl10/cinematic/bird_crash_second",
+    "event:/new_content/music/lvl10/granny_farewell",
+    "event:/new_content/music/lvl10/golden_room",
+];
+
+pub const AMBIENCE_OPTIONS: [&str; 23] = [
+    "event:/env/amb/00_prologue",
+    "event:/env/amb/01_main",
+    "event:/env/amb/02_awake",
+    "event:/env/amb/02_dream",
+    "event:/env/amb/03_exterior",
+    "event:/env/amb/03_interior",
+    "event:/env/amb/03_pico8_closeup",
+    "event:/env/amb/04_main",
+    "event:/env/amb/05_interior_dark",
+    "event:/env/amb/05_interior_main",
+    "event:/env/amb/05_mirror_sequence",
+    "event:/env/amb/06_lake",
+    "event:/env/amb/06_main",
+    "event:/env/amb/06_prehug",
+    "event:/env/amb/09_main",
+    "event:/env/amb/worldmap",
+    "event:/new_content/env/10_rain",
+    "event:/new_content/env/10_electricity",
+    "event:/new_content/env/10_endscene",
+    "event:/new_content/env/10_rushingvoid",
+    "event:/new_content/env/10_space_underwater",
+    "event:/new_content/env/10_voidspiral",
+    "event:/new_content/env/10_grannyclouds",
+];
+
+pub const WIND_PATTERN_OPTIONS: [&str; 15] = [
+    "None",
+    "Left",
+    "Right",
+    "LeftStrong",
+    "RightStrong",
+    "LeftOnOff",
+    "RightOnOff",
+    "LeftOnOffFast",
+    "RightOnOffFast",
+    "Alternating",
+    "LeftGemsOnly",
+    "RightCrazy",
+    "Down",
+    "Up",
+    "Space",
+];
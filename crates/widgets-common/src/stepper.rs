@@ -0,0 +1,71 @@
+use arborio_utils::vizia::prelude::*;
+
+/// Numbers which a [`Stepper`] can nudge up or down.
+pub trait Steppable: Sized {
+    fn step(&self, amount: i32) -> Self;
+}
+
+impl Steppable for i32 {
+    fn step(&self, amount: i32) -> Self {
+        self.saturating_add(amount)
+    }
+}
+
+impl Steppable for f32 {
+    fn step(&self, amount: i32) -> Self {
+        self + amount as f32
+    }
+}
+
+/// Wraps a numeric field so that the arrow keys step its value while it is focused, and the scroll
+/// wheel while it is hovered. Steps are by 1, or by 10 with shift held.
+pub struct Stepper {
+    on_step: Box<dyn Fn(&mut EventContext, i32) + Send + Sync>,
+}
+
+impl Stepper {
+    pub fn new<'a>(
+        cx: &'a mut Context,
+        content: impl FnOnce(&mut Context),
+        on_step: impl 'static + Send + Sync + Fn(&mut EventContext, i32),
+    ) -> Handle<'a, Self> {
+        Self {
+            on_step: Box::new(on_step),
+        }
+        .build(cx, content)
+    }
+}
+
+impl View for Stepper {
+    fn element(&self) -> Option<&'static str> {
+        Some("stepper")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| {
+            let direction = match window_event {
+                WindowEvent::KeyDown(Code::ArrowUp, _) => 1,
+                WindowEvent::KeyDown(Code::ArrowDown, _) => -1,
+                // some platforms turn shift+scroll into horizontal scrolling
+                WindowEvent::MouseScroll(x, y) => {
+                    let delta = if *y != 0.0 { *y } else { *x };
+                    if delta > 0.0 {
+                        1
+                    } else if delta < 0.0 {
+                        -1
+                    } else {
+                        return;
+                    }
+                }
+                _ => return,
+            };
+            let amount = if cx.modifiers.contains(Modifiers::SHIFT) {
+                10
+            } else {
+                1
+            };
+            (self.on_step)(cx, direction * amount);
+            meta.consume();
+        });
+    }
+}
@@ -0,0 +1,189 @@
+use arborio_maploader::binel::BinElAttr;
+use arborio_maploader::from_binel::TryFromBinEl;
+use arborio_maploader::map_struct::{Attribute, CelesteMapDecal};
+use arborio_modloader::config::AttributeType;
+use arborio_state::data::action::RoomAction;
+use arborio_state::data::app::AppState;
+use arborio_state::data::tabs::AppTab;
+use arborio_state::data::{AppConfig, EventPhase};
+use arborio_state::lenses::{AutoSaverLens, CurrentSelectedDecalsAllLens};
+use arborio_utils::vizia::fonts::icons_names::DOWN;
+use arborio_utils::vizia::prelude::*;
+use arborio_widgets_common::advanced_tweaker::advanced_attrs_editor;
+use std::collections::HashMap;
+
+/// Celeste's conventional depths for decals, from its Depths class. Higher depths are further back.
+const DEPTH_PRESETS: &[(&str, i32)] = &[
+    ("Below BG tiles", 10500),
+    ("Above BG tiles (bg default)", 9000),
+    ("Below solids", 5000),
+    ("Behind player", 2000),
+    ("In front of player", -8500),
+    ("Below FG tiles", -9000),
+    ("Above FG tiles (fg default)", -10500),
+];
+
+pub fn build_decal_tweaker(cx: &mut Context) {
+    let depth_lens =
+        CurrentSelectedDecalsAllLens::new_computed(|_, decal, fg| Some(decal.effective_depth(fg)));
+    Label::new(cx, "Decals");
+    HStack::new(cx, {
+        let depth_lens = depth_lens.clone();
+        move |cx| {
+            Label::new(cx, "depth");
+            Textbox::new(cx, depth_lens).on_edit(|cx, value| {
+                if let Ok(value) = value.parse() {
+                    set_depth(cx, value);
+                }
+            });
+        }
+    });
+    Binding::new(cx, depth_lens, |cx, depth| {
+        let depth = depth.get_fallible(cx);
+        let found = DEPTH_PRESETS
+            .iter()
+            .find(|(_, value)| Some(*value) == depth)
+            .map_or("Custom", |(name, _)| name);
+        Dropdown::new(
+            cx,
+            move |cx| {
+                HStack::new(cx, move |cx| {
+                    Label::new(cx, found);
+                    Label::new(cx, DOWN).font_family(vec![FamilyOwned::Name("Entypo".to_owned())]);
+                })
+                .width(Stretch(1.0))
+            },
+            move |cx| {
+                for &(name, value) in DEPTH_PRESETS {
+                    Label::new(cx, name)
+                        .class("dropdown_element")
+                        .toggle_class("checked", Some(value) == depth)
+                        .on_press(move |cx| {
+                            set_depth(cx.as_mut(), value);
+                            cx.emit(PopupEvent::Close);
+                        });
+                }
+            },
+        );
+    });
+
+    HStack::new(cx, |cx| {
+        Label::new(cx, "rotation");
+        let rotation_lens =
+            CurrentSelectedDecalsAllLens::new_computed(|_, decal, _| Some(decal.rotation));
+        Textbox::new(cx, rotation_lens).on_edit(|cx, value| {
+            if let Ok(value) = value.parse::<f32>() {
+                if value.is_finite() {
+                    edit_decal(cx, |decal, _| decal.rotation = value);
+                }
+            }
+        });
+    });
+
+    let advanced_lens = AppState::config
+        .then(AutoSaverLens::new())
+        .then(AppConfig::advanced);
+    Binding::new(cx, advanced_lens, |cx, advanced| {
+        if advanced.get(cx) {
+            Label::new(cx, "Raw attributes");
+            advanced_attrs_editor(
+                cx,
+                CurrentSelectedDecalsAllLens::new_computed(|_, decal, _| {
+                    Some(decal.to_binel().attributes.len())
+                }),
+                |index| {
+                    CurrentSelectedDecalsAllLens::new_computed(move |_, decal, _| {
+                        let mut keys = decal.to_binel().attributes.into_keys().collect::<Vec<_>>();
+                        keys.sort();
+                        keys.into_iter().nth(index)
+                    })
+                },
+                |key_lens| {
+                    CurrentSelectedDecalsAllLens::new_computed(move |app, decal, _| {
+                        let key = key_lens.view(app, |key| key.cloned())?;
+                        decal
+                            .to_binel()
+                            .attributes
+                            .remove(&key)
+                            .map(Attribute::from)
+                    })
+                },
+                |cx, key, value| {
+                    edit_raw(cx, |attrs| {
+                        attrs.insert(key.clone(), (&value).into());
+                    })
+                },
+                |cx, key, ty| {
+                    let value = match ty {
+                        AttributeType::String => BinElAttr::Text(String::new()),
+                        AttributeType::Float => BinElAttr::Float(0.0),
+                        AttributeType::Int => BinElAttr::Int(0),
+                        AttributeType::Bool => BinElAttr::Bool(false),
+                    };
+                    edit_raw(cx, |attrs| {
+                        attrs.entry(key.clone()).or_insert_with(|| value.clone());
+                    })
+                },
+                |cx, key| {
+                    edit_raw(cx, |attrs| {
+                        attrs.remove(&key);
+                    })
+                },
+            );
+        }
+    });
+}
+
+/// Apply an edit to each selected decal's attributes as they would be saved. Decals only keep the
+/// attributes Arborio knows about.
+fn edit_raw<F: FnMut(&mut HashMap<String, BinElAttr>)>(cx: &mut EventContext, mut f: F) {
+    edit_decal(cx, |decal, _| {
+        let mut elem = decal.to_binel();
+        f(&mut elem.attributes);
+        match CelesteMapDecal::try_from_bin_el(&elem) {
+            Ok(edited) => {
+                *decal = CelesteMapDecal {
+                    id: decal.id,
+                    ..edited
+                }
+            }
+            Err(e) => log::error!("Invalid decal attributes: {}", e),
+        }
+    });
+}
+
+fn set_depth(cx: &mut EventContext, depth: i32) {
+    edit_decal(cx, |decal, _| decal.depth = Some(depth));
+}
+
+fn edit_decal<F: FnMut(&mut CelesteMapDecal, bool)>(cx: &mut EventContext, mut f: F) {
+    let app_state = cx.data::<AppState>().unwrap();
+    let Some(AppTab::Map(map_tab)) = app_state.tabs.get(app_state.current_tab) else { return };
+    let Some(room) = app_state
+        .loaded_maps
+        .get(&map_tab.id)
+        .and_then(|x| x.data.levels.get(map_tab.current_room)) else { return };
+
+    let phase = EventPhase::new();
+    let mut events = vec![];
+    for (id, fg) in map_tab
+        .current_selected
+        .iter()
+        .filter_map(|sel| sel.decal_info())
+    {
+        if let Some(mut decal) = room.decal(id, fg).cloned() {
+            f(&mut decal, fg);
+            events.push(map_tab.id.room_action(
+                map_tab.current_room,
+                phase,
+                RoomAction::DecalUpdate {
+                    fg,
+                    decal: Box::new(decal),
+                },
+            ));
+        }
+    }
+    for event in events {
+        cx.emit(event);
+    }
+}
@@ -1,21 +1,24 @@
+use crate::decal_tweaker::build_decal_tweaker;
 use arborio_maploader::map_struct::{Attribute, CelesteMapEntity};
 use arborio_modloader::config::AttributeType;
 use arborio_state::data::action::RoomAction;
 use arborio_state::data::app::{AppEvent, AppState};
+use arborio_state::data::project_map::LevelState;
 use arborio_state::data::selection::AppSelection;
 use arborio_state::data::tabs::AppTab;
-use arborio_state::data::{AppConfig, EventPhase};
+use arborio_state::data::AppConfig;
 use arborio_state::lenses::{
     current_selected_entity_lens, hash_map_nth_key_lens, AutoSaverLens,
-    CurrentSelectedEntitiesAllLens, CurrentSelectedEntitiesAttributesLens,
-    CurrentSelectedEntityConfigAttributesLens, CurrentSelectedEntityHasNodesLens,
-    CurrentSelectedEntityResizableLens, HashMapIndexWithLens, HashMapLenLens, IsFailedLens,
+    CurrentSelectedDecalsAllLens, CurrentSelectedEntitiesAllLens,
+    CurrentSelectedEntitiesAttributesLens, CurrentSelectedEntityConfigAttributesLens,
+    CurrentSelectedEntityHasNodesLens, CurrentSelectedEntityResizableLens, HashMapIndexWithLens,
+    HashMapLenLens, IsFailedLens,
 };
 use arborio_utils::vizia::fonts::icons_names::MINUS;
 use arborio_utils::vizia::prelude::*;
 use arborio_widgets_common::advanced_tweaker::advanced_attrs_editor;
 use arborio_widgets_common::basic_tweaker::basic_attrs_editor;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 pub struct EntityTweakerWidget {}
 
@@ -57,6 +60,12 @@ impl EntityTweakerWidget {
                         });
                     }
                 });
+                let any_decal_lens = CurrentSelectedDecalsAllLens::new_computed(|_, _, _| Some(()));
+                Binding::new(cx, IsFailedLens::new(any_decal_lens), move |cx, failed| {
+                    if !failed.get(cx) {
+                        VStack::new(cx, build_decal_tweaker).class("tweaker_container");
+                    }
+                });
             })
             .class("tweaker")
     }
@@ -82,6 +91,18 @@ pub fn build_tweaker(cx: &mut Context) {
         )
         .on_edit(edit_y);
     });
+    HStack::new(cx, move |cx| {
+        Button::new(
+            cx,
+            |cx| select_same_type(cx, false),
+            |cx| Label::new(cx, "Select all of type"),
+        );
+        Button::new(
+            cx,
+            |cx| select_same_type(cx, true),
+            |cx| Label::new(cx, "Select all of type in map"),
+        );
+    });
 
     Binding::new(cx, advanced_lens, move |cx, advanced| {
         let advanced = advanced.get(cx);
@@ -156,6 +177,7 @@ pub fn build_tweaker(cx: &mut Context) {
                 },
                 edit_attribute,
             );
+            Button::new(cx, reset_all_attributes, |cx| Label::new(cx, "Reset all"));
         }
 
         let entity_lens = current_selected_entity_lens();
@@ -206,40 +228,50 @@ impl View for EntityTweakerWidget {
     fn element(&self) -> Option<&'static str> {
         Some("entity-tweaker")
     }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        // tab moves between the fields by way of the usual focus navigation. enter commits the
+        // edits made so far so the next ones get their own undo step.
+        event.map(|window_event, _| {
+            if let WindowEvent::KeyDown(Code::Enter | Code::NumpadEnter, _) = window_event {
+                cx.emit(AppEvent::EndTweakerSession);
+            }
+        });
+    }
 }
 
 fn edit_entity<F: FnMut(&mut CelesteMapEntity, bool)>(cx: &mut EventContext, mut f: F) {
     let app_state = cx.data::<AppState>().unwrap();
-    let (current_map, current_room, current_selected) = match app_state
-        .tabs
-        .get(app_state.current_tab)
-    {
-        Some(AppTab::Map(map_tab)) => (map_tab.id, map_tab.current_room, &map_tab.current_selected),
-        _ => panic!("How'd you do that"),
+    let Some(AppTab::Map(map_tab)) = app_state.tabs.get(app_state.current_tab) else {
+        panic!("How'd you do that")
     };
+    let current_map = map_tab.id;
+    // anything selected across the whole map is edited along with the current room
+    let selected = std::iter::once((map_tab.current_room, &map_tab.current_selected))
+        .chain(map_tab.map_selected.iter().map(|(room, sel)| (*room, sel)));
 
-    let phase = EventPhase::new(); // TODO batch correctly (based on timeout)
+    // every edit until the session ends (enter, or a change in selection) is one undo step
+    let phase = app_state.tweaker_phase;
 
     let mut events = vec![];
-    for sel in current_selected {
-        if let AppSelection::EntityBody(id, trigger) | AppSelection::EntityNode(id, _, trigger) =
-            sel
-        {
+    for (room, room_selected) in selected {
+        for sel in room_selected {
+            let Some((id, trigger)) = sel.entity_info() else { continue };
             if let Some(mut entity) = app_state
                 .loaded_maps
                 .get(&current_map)
-                .and_then(|x| x.data.levels.get(current_room))
-                .and_then(|x| x.entity(*id, *trigger))
+                .and_then(|x| x.data.levels.get(room))
+                .and_then(|x| x.entity(id, trigger))
                 .cloned()
             {
-                f(&mut entity, *trigger);
+                f(&mut entity, trigger);
 
                 events.push(current_map.room_action(
-                    current_room,
+                    room,
                     phase,
                     RoomAction::EntityUpdate {
                         entity: Box::new(entity),
-                        trigger: *trigger,
+                        trigger,
                     },
                 ));
             }
@@ -250,12 +282,87 @@ fn edit_entity<F: FnMut(&mut CelesteMapEntity, bool)>(cx: &mut EventContext, mut
     }
 }
 
+/// Select every entity or trigger sharing a name with a selected one, in the current room or in
+/// every room of the map.
+fn select_same_type(cx: &mut EventContext, whole_map: bool) {
+    let app = cx.data::<AppState>().unwrap();
+    let Some(AppTab::Map(map_tab)) = app.tabs.get(app.current_tab) else { return };
+    let Some(map) = app.loaded_maps.get(&map_tab.id) else { return };
+    let Some(room) = map.data.levels.get(map_tab.current_room) else { return };
+
+    let types = map_tab
+        .current_selected
+        .iter()
+        .filter_map(|sel| sel.entity_info())
+        .filter_map(|(id, trigger)| room.entity(id, trigger).map(|e| (e.name.as_str(), trigger)))
+        .collect::<HashSet<_>>();
+    let matches = |room: &LevelState| {
+        let entities = room.data.entities.iter().map(|e| (e, false));
+        let triggers = room.data.triggers.iter().map(|e| (e, true));
+        entities
+            .chain(triggers)
+            .filter(|(e, trigger)| types.contains(&(e.name.as_str(), *trigger)))
+            .map(|(e, trigger)| AppSelection::EntityBody(e.id, trigger))
+            .collect::<HashSet<_>>()
+    };
+
+    let event = if whole_map {
+        let selection = map
+            .data
+            .levels
+            .iter()
+            .map(matches)
+            .enumerate()
+            .filter(|(_, selection)| !selection.is_empty())
+            .collect::<HashMap<_, _>>();
+        log::info!(
+            "Selected {} objects across {} rooms",
+            selection.values().map(HashSet::len).sum::<usize>(),
+            selection.len()
+        );
+        AppEvent::SelectAcrossMap {
+            tab: app.current_tab,
+            selection,
+        }
+    } else {
+        AppEvent::SelectObjects {
+            tab: app.current_tab,
+            selection: matches(room),
+        }
+    };
+    cx.emit(event);
+}
+
 fn edit_attribute(cx: &mut EventContext, key: String, value: Attribute) {
     edit_entity(cx, move |entity, _| {
         entity.attributes.insert(key.clone(), value.clone());
     });
 }
 
+/// Set every configured attribute of the selected entities back to its default value. Attributes
+/// the config doesn't know about are left alone.
+fn reset_all_attributes(cx: &mut EventContext) {
+    let palette = cx.data::<AppState>().unwrap().current_palette_unwrap();
+    let entity_config = palette.entity_config.clone();
+    let trigger_config = palette.trigger_config.clone();
+    edit_entity(cx, move |entity, trigger| {
+        let info = if trigger {
+            trigger_config
+                .get(entity.name.as_str())
+                .map(|c| &c.attribute_info)
+        } else {
+            entity_config
+                .get(entity.name.as_str())
+                .map(|c| &c.attribute_info)
+        };
+        for (key, info) in info.into_iter().flatten() {
+            entity
+                .attributes
+                .insert(key.clone(), info.default.to_binel());
+        }
+    });
+}
+
 fn remove_attribute(cx: &mut EventContext, key: String) {
     edit_entity(cx, move |entity, _| {
         entity.attributes.remove(&key);
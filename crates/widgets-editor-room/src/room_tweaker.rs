@@ -1,13 +1,18 @@
-use arborio_maploader::map_struct::{CelesteMapLevel, CelesteMapLevelUpdate};
-use arborio_state::data::action::RoomAction;
+use arborio_maploader::binel::BinElAttr;
+use arborio_maploader::map_struct::{Attribute, CelesteMapLevel, CelesteMapLevelUpdate};
+use arborio_modloader::config::AttributeType;
+use arborio_state::data::action::{MapAction, RoomAction};
 use arborio_state::data::app::AppState;
 use arborio_state::data::tabs::AppTab;
-use arborio_state::data::EventPhase;
+use arborio_state::data::{AppConfig, EventPhase};
 use arborio_state::lenses::{
-    current_room_lens, rect_h_lens, rect_w_lens, rect_x_lens, rect_y_lens, RoomTweakerScopeLens,
+    current_room_lens, rect_h_lens, rect_w_lens, rect_x_lens, rect_y_lens, AutoSaverLens,
+    CurrentRoomComputedLens, RoomTweakerScopeLens, StaticerLens,
 };
 use arborio_utils::vizia::prelude::*;
 use arborio_widgets_common::advanced_tweaker::*;
+use arborio_widgets_common::options::{AMBIENCE_OPTIONS, MUSIC_OPTIONS, WIND_PATTERN_OPTIONS};
+use std::collections::HashMap;
 
 pub struct RoomTweakerWidget {}
 
@@ -30,6 +35,25 @@ macro_rules! edit_text {
         );
     };
 }
+macro_rules! edit_text_dropdown {
+    ($cx: expr, $label:expr, $attr:ident, $options:expr) => {
+        tweak_attr_text_dropdown(
+            $cx,
+            $label,
+            current_room_lens().then(CelesteMapLevel::$attr),
+            StaticerLens::new($options.into_iter().map(|x| x.to_owned()).collect()),
+            |cx, x| {
+                emit(
+                    cx,
+                    CelesteMapLevelUpdate {
+                        $attr: Some(x),
+                        ..CelesteMapLevelUpdate::default()
+                    },
+                );
+            },
+        );
+    };
+}
 macro_rules! edit_check {
     ($cx: expr, $label:expr, $attr:ident) => {
         tweak_attr_check(
@@ -90,6 +114,23 @@ impl RoomTweakerWidget {
                 true
             },
         );
+        HStack::new(cx, move |cx| {
+            Label::new(cx, "Notes");
+            Textbox::new(
+                cx,
+                CurrentRoomComputedLens::new(|app, room| {
+                    let notes = &app.current_map_ref()?.data.notes;
+                    Some(notes.get(Some(&room.name)).to_owned())
+                }),
+            )
+            .on_edit(|cx, note| {
+                let app = cx.data::<AppState>().unwrap();
+                let Some(room) = app.current_room_ref() else { return };
+                let room = Some(room.data.name.clone());
+                let map = app.map_tab_unwrap().id;
+                cx.emit(map.action(EventPhase::new(), MapAction::SetNote { room, note }));
+            });
+        });
         HStack::new(cx, move |cx| {
             Label::new(cx, "X");
             Textbox::new(
@@ -174,20 +215,38 @@ impl RoomTweakerWidget {
                 }
             });
         });
+        HStack::new(cx, move |cx| {
+            Button::new(
+                cx,
+                |cx| emit_fit(cx, false),
+                |cx| Label::new(cx, "Fit to Content"),
+            );
+            Button::new(
+                cx,
+                |cx| emit_fit(cx, true),
+                |cx| Label::new(cx, "Fit to Screens"),
+            );
+        });
 
         edit_text!(cx, "Color", color);
+
+        section(cx, "Camera");
         edit_text!(cx, "Camera Offset X", camera_offset_x);
         edit_text!(cx, "Camera Offset Y", camera_offset_y);
-        edit_text!(cx, "Wind Pattern", wind_pattern);
+
+        section(cx, "Environment");
+        edit_text_dropdown!(cx, "Wind Pattern", wind_pattern, WIND_PATTERN_OPTIONS);
         edit_check!(cx, "Space", space);
         edit_check!(cx, "Underwater", underwater);
         edit_check!(cx, "Whisper", whisper);
         edit_check!(cx, "Dark", dark);
         edit_check!(cx, "Disable Down Transition", disable_down_transition);
         edit_text!(cx, "Enforce Dash Number", enforce_dash_number);
-        edit_text!(cx, "Music", music);
-        edit_text!(cx, "Alt Music", alt_music);
-        edit_text!(cx, "Ambience", ambience);
+
+        section(cx, "Audio");
+        edit_text_dropdown!(cx, "Music", music, MUSIC_OPTIONS);
+        edit_text_dropdown!(cx, "Alt Music", alt_music, MUSIC_OPTIONS);
+        edit_text_dropdown!(cx, "Ambience", ambience, AMBIENCE_OPTIONS);
         edit_text!(cx, "Music Progress", music_progress);
         edit_text!(cx, "Ambience Progress", ambience_progress);
         edit_text!(cx, "Delay Alt Music Fade", delay_alt_music_fade);
@@ -212,6 +271,43 @@ impl RoomTweakerWidget {
                 });
             }
         });
+
+        let advanced_lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::advanced);
+        Binding::new(cx, advanced_lens, |cx, advanced| {
+            if advanced.get(cx) {
+                Label::new(cx, "Raw attributes");
+                advanced_attrs_editor(
+                    cx,
+                    CurrentRoomComputedLens::new(|_, room| Some(room.misc_attributes().len())),
+                    |index| {
+                        CurrentRoomComputedLens::new(move |_, room| {
+                            let mut keys = room.misc_attributes().into_keys().collect::<Vec<_>>();
+                            keys.sort();
+                            keys.into_iter().nth(index)
+                        })
+                    },
+                    |key_lens| {
+                        CurrentRoomComputedLens::new(move |app, room| {
+                            let key = key_lens.view(app, |key| key.cloned())?;
+                            room.misc_attributes().remove(&key).map(Attribute::from)
+                        })
+                    },
+                    |cx, key, value| {
+                        edit_raw(cx, |attrs| {
+                            attrs.insert(key, (&value).into());
+                        })
+                    },
+                    |cx, key, ty| edit_raw(cx, |attrs| add_raw(attrs, key, ty)),
+                    |cx, key| {
+                        edit_raw(cx, |attrs| {
+                            attrs.remove(&key);
+                        })
+                    },
+                );
+            }
+        });
     }
 }
 
@@ -221,6 +317,10 @@ impl View for RoomTweakerWidget {
     }
 }
 
+fn section(cx: &mut Context, name: &'static str) {
+    Label::new(cx, name).class("tweaker_section");
+}
+
 fn emit(cx: &mut EventContext, update: CelesteMapLevelUpdate) {
     let app = cx.data::<AppState>().unwrap();
     let tab = app.map_tab_unwrap();
@@ -242,9 +342,8 @@ fn emit_bounds(
 ) {
     let app = cx.data::<AppState>().unwrap();
     let tab = app.map_tab_unwrap();
-    let mut bounds = app.loaded_maps.get(&tab.id).unwrap().data.levels[tab.current_room]
-        .data
-        .bounds;
+    let Some(room) = app.current_room_ref() else { return };
+    let mut bounds = room.data.bounds;
     if let Some(x) = update_x {
         bounds.origin.x = x;
     }
@@ -263,3 +362,51 @@ fn emit_bounds(
         RoomAction::MoveRoom { bounds },
     )); // TODO batch correctly
 }
+
+/// Resize the current room to fit its content, optionally rounded out to whole screens.
+fn emit_fit(cx: &mut EventContext, screens: bool) {
+    let app = cx.data::<AppState>().unwrap();
+    let tab = app.map_tab_unwrap();
+    let actions = app
+        .loaded_maps
+        .get(&tab.id)
+        .unwrap()
+        .data
+        .fit_room_actions(tab.current_room, screens);
+    if !actions.is_empty() {
+        cx.emit(app.batch_action_unique(actions));
+    }
+}
+
+/// Apply an edit to the room's attributes as they would be saved. Rooms only keep the attributes
+/// Arborio knows about, so removing one resets it to its default.
+fn edit_raw<F: FnOnce(&mut HashMap<String, BinElAttr>)>(cx: &mut EventContext, f: F) {
+    let app = cx.data::<AppState>().unwrap();
+    let update = current_room_lens().view(app, |room| {
+        let room = room?;
+        let mut attrs = room.misc_attributes();
+        f(&mut attrs);
+        Some(room.misc_update_from_attributes(attrs))
+    });
+    match update {
+        Some(Ok(update)) => emit(cx, update),
+        Some(Err(e)) => log::error!("Invalid room attributes: {}", e),
+        None => {}
+    }
+}
+
+fn add_raw(attrs: &mut HashMap<String, BinElAttr>, key: String, ty: AttributeType) {
+    if attrs.contains_key(&key) {
+        return;
+    }
+    log::warn!("Rooms don't keep unknown attributes like {}", key);
+    attrs.insert(
+        key,
+        match ty {
+            AttributeType::String => BinElAttr::Text(String::new()),
+            AttributeType::Float => BinElAttr::Float(0.0),
+            AttributeType::Int => BinElAttr::Int(0),
+            AttributeType::Bool => BinElAttr::Bool(false),
+        },
+    );
+}
@@ -1,3 +1,5 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use arborio_maploader::map_struct::{Attribute, CelesteMapStyleground};
@@ -8,8 +10,8 @@ use arborio_state::data::project_map::MapStateData;
 use arborio_state::data::EventPhase;
 use arborio_state::lenses::{
     current_map_impl_lens, current_map_lens, current_styleground_impl_lens,
-    current_styleground_lens, hash_map_nth_key_lens, HashMapIndexWithLens, HashMapLenLens,
-    IsFailedLens, StylegroundNameLens,
+    current_styleground_lens, current_styleground_set_lens, hash_map_nth_key_lens,
+    HashMapIndexWithLens, HashMapLenLens, IsFailedLens, StylegroundNameLens,
 };
 use arborio_utils::vizia::fonts::icons_names::{DOWN, MINUS, PLUS, UP};
 use arborio_utils::vizia::prelude::*;
@@ -45,6 +47,21 @@ macro_rules! edit_check {
         );
     };
 }
+macro_rules! edit_drag {
+    ($cx: expr, $label:expr, $attr:ident, $speed:expr) => {
+        tweak_attr_drag(
+            $cx,
+            $label,
+            current_styleground_impl_lens().then(CelesteMapStyleground::$attr),
+            $speed,
+            |cx, x, phase| {
+                let mut style = current_styleground_impl_lens().get(cx);
+                style.$attr = x;
+                emit_phase(cx, style, phase);
+            },
+        );
+    };
+}
 macro_rules! edit_optional_text {
     ($cx: expr, $label:expr, $attr:ident) => {
         tweak_attr_text(
@@ -105,22 +122,141 @@ where
             })
             .class("palette_item")
             .class("list_highlight")
-            .bind(current_styleground_lens(), move |handle, selected| {
-                let is_me =
-                    selected.get_fallible(handle.cx) == Some(StylegroundSelection { fg, idx });
-                handle.checked(is_me);
-            })
+            .bind(
+                current_styleground_set_lens()
+                    .map(move |selected| selected.contains(&StylegroundSelection { fg, idx })),
+                |handle, is_me| {
+                    let is_me = is_me.get_fallible(handle.cx).unwrap_or(false);
+                    handle.checked(is_me);
+                },
+            )
             .on_press(move |cx| {
+                let clicked = StylegroundSelection { fg, idx };
                 let tab = cx.data::<AppState>().unwrap().current_tab;
-                cx.emit(AppEvent::SelectStyleground {
-                    tab,
-                    styleground: Some(StylegroundSelection { fg, idx }),
-                });
+                if cx.modifiers.contains(Modifiers::CTRL) {
+                    let mut stylegrounds = current_styleground_set_lens().get(cx);
+                    if !stylegrounds.remove(&clicked) {
+                        stylegrounds.insert(clicked);
+                    }
+                    cx.emit(AppEvent::SelectStylegrounds { tab, stylegrounds });
+                } else if cx.modifiers.contains(Modifiers::SHIFT) {
+                    let stylegrounds = select_range(cx, clicked);
+                    cx.emit(AppEvent::SelectStylegrounds { tab, stylegrounds });
+                } else {
+                    cx.emit(AppEvent::SelectStyleground {
+                        tab,
+                        styleground: Some(clicked),
+                    });
+                }
             });
         }
     });
 }
 
+/// Where a styleground shows up in the list, top to bottom.
+fn list_position(sel: &StylegroundSelection) -> (bool, Reverse<usize>) {
+    (!sel.fg, Reverse(sel.idx))
+}
+
+/// The current selection plus everything between `clicked` and the nearest selected styleground.
+fn select_range(
+    cx: &mut EventContext,
+    clicked: StylegroundSelection,
+) -> HashSet<StylegroundSelection> {
+    let mut selected = current_styleground_set_lens().get(cx);
+    let (fg_len, bg_len) = current_map_impl_lens()
+        .map(|map| (map.foregrounds.len(), map.backgrounds.len()))
+        .get(cx);
+    let mut all = (0..fg_len)
+        .map(|idx| StylegroundSelection { fg: true, idx })
+        .chain((0..bg_len).map(|idx| StylegroundSelection { fg: false, idx }))
+        .collect::<Vec<_>>();
+    all.sort_by_key(list_position);
+    let Some(end) = all.iter().position(|sel| *sel == clicked) else { return selected };
+    let start = all
+        .iter()
+        .enumerate()
+        .filter(|(_, sel)| selected.contains(sel))
+        .min_by_key(|(pos, _)| pos.abs_diff(end))
+        .map_or(end, |(pos, _)| pos);
+    selected.extend(&all[start.min(end)..=start.max(end)]);
+    selected
+}
+
+/// The selected stylegrounds in list order, top to bottom.
+fn selected_in_order(cx: &mut EventContext) -> Vec<StylegroundSelection> {
+    let mut selected = current_styleground_set_lens()
+        .get_fallible(cx)
+        .unwrap_or_default()
+        .into_iter()
+        .collect::<Vec<_>>();
+    selected.sort_by_key(list_position);
+    selected
+}
+
+/// Move every selected styleground one index back (drawn sooner) or forward as one undo step. A
+/// lone styleground can hop between the foreground and background lists; a group stays put at the
+/// edge.
+fn move_selected(cx: &mut EventContext, backward: bool) {
+    let mut selected = selected_in_order(cx);
+    if selected.is_empty() {
+        return;
+    }
+    // move the one closest to the direction of travel first so nothing gets leapfrogged
+    if backward {
+        selected.reverse();
+    }
+    let lone = selected.len() == 1;
+
+    let mut actions = vec![];
+    let mut moved = HashSet::new();
+    for sel in selected {
+        let len = current_map_impl_lens()
+            .map(move |map| map.styles(sel.fg).len())
+            .get(cx);
+        let target = if backward {
+            if sel.idx == 0 {
+                if !sel.fg || !lone {
+                    return;
+                }
+                let max_idx = current_map_impl_lens()
+                    .map(move |map| map.styles(false).len())
+                    .get(cx);
+                StylegroundSelection {
+                    fg: false,
+                    idx: max_idx,
+                }
+            } else {
+                StylegroundSelection {
+                    fg: sel.fg,
+                    idx: sel.idx - 1,
+                }
+            }
+        } else if sel.idx + 1 == len {
+            if sel.fg || !lone {
+                return;
+            }
+            StylegroundSelection { fg: true, idx: 0 }
+        } else {
+            StylegroundSelection {
+                fg: sel.fg,
+                idx: sel.idx + 1,
+            }
+        };
+        actions.push(MapAction::MoveStyleground { loc: sel, target });
+        moved.insert(target);
+    }
+
+    let app = cx.data::<AppState>().unwrap();
+    let tab = app.current_tab;
+    let event = app.map_action_unique(actions);
+    cx.emit(event);
+    cx.emit(AppEvent::SelectStylegrounds {
+        tab,
+        stylegrounds: moved,
+    });
+}
+
 pub struct StyleTweakerWidget {}
 
 impl StyleTweakerWidget {
@@ -148,86 +284,35 @@ impl StyleTweakerWidget {
                     Button::new(
                         cx,
                         |cx| {
-                            if (current_styleground_lens()).get_fallible(cx).is_some() {
-                                cx.emit(current_map_lens().get(cx).action(
-                                    EventPhase::new(),
-                                    MapAction::RemoveStyleground {
-                                        loc: current_styleground_lens().get(cx),
-                                    },
-                                ));
+                            // remove from the highest index down so the rest stay put
+                            let mut selected = selected_in_order(cx);
+                            selected.sort_by_key(|sel| Reverse(sel.idx));
+                            if selected.is_empty() {
+                                return;
                             }
+                            let actions = selected
+                                .into_iter()
+                                .map(|loc| MapAction::RemoveStyleground { loc })
+                                .collect();
+                            let app = cx.data::<AppState>().unwrap();
+                            let tab = app.current_tab;
+                            let event = app.map_action_unique(actions);
+                            cx.emit(event);
+                            cx.emit(AppEvent::SelectStyleground {
+                                tab,
+                                styleground: None,
+                            });
                         },
                         |cx| Label::new(cx, MINUS).class("icon"),
                     );
                     Button::new(
                         cx,
-                        |cx| {
-                            let sel =
-                                if let Some(sel) = (current_styleground_lens()).get_fallible(cx) {
-                                    sel
-                                } else {
-                                    return;
-                                };
-                            let max_idx = current_map_impl_lens()
-                                .map(move |map| map.styles(sel.fg).len())
-                                .get(cx);
-                            let target = if sel.idx + 1 == max_idx {
-                                if sel.fg {
-                                    return;
-                                }
-                                StylegroundSelection { fg: true, idx: 0 }
-                            } else {
-                                StylegroundSelection {
-                                    fg: sel.fg,
-                                    idx: sel.idx + 1,
-                                }
-                            };
-                            cx.emit(current_map_lens().get(cx).action(
-                                EventPhase::new(),
-                                MapAction::MoveStyleground { loc: sel, target },
-                            ));
-                            cx.emit(AppEvent::SelectStyleground {
-                                tab: cx.data::<AppState>().unwrap().current_tab,
-                                styleground: Some(target),
-                            })
-                        },
+                        |cx| move_selected(cx, false),
                         |cx| Label::new(cx, DOWN).class("icon"),
                     );
                     Button::new(
                         cx,
-                        |cx| {
-                            let sel =
-                                if let Some(sel) = (current_styleground_lens()).get_fallible(cx) {
-                                    sel
-                                } else {
-                                    return;
-                                };
-                            let target = if sel.idx == 0 {
-                                if !sel.fg {
-                                    return;
-                                }
-                                let max_idx = current_map_impl_lens()
-                                    .map(move |map| map.styles(false).len())
-                                    .get(cx);
-                                StylegroundSelection {
-                                    fg: false,
-                                    idx: max_idx,
-                                }
-                            } else {
-                                StylegroundSelection {
-                                    fg: sel.fg,
-                                    idx: sel.idx - 1,
-                                }
-                            };
-                            cx.emit(current_map_lens().get(cx).action(
-                                EventPhase::new(),
-                                MapAction::MoveStyleground { loc: sel, target },
-                            ));
-                            cx.emit(AppEvent::SelectStyleground {
-                                tab: cx.data::<AppState>().unwrap().current_tab,
-                                styleground: Some(target),
-                            })
-                        },
+                        |cx| move_selected(cx, true),
                         |cx| Label::new(cx, UP).class("icon"),
                     );
                 });
@@ -249,10 +334,10 @@ impl StyleTweakerWidget {
     fn members(cx: &mut Context) {
         edit_text!(cx, "Name", name);
         edit_text!(cx, "Tag", tag);
-        edit_text!(cx, "X", x);
-        edit_text!(cx, "Y", y);
-        edit_text!(cx, "Scroll X", scroll_x);
-        edit_text!(cx, "Scroll Y", scroll_y);
+        edit_drag!(cx, "X", x, 1.0);
+        edit_drag!(cx, "Y", y, 1.0);
+        edit_drag!(cx, "Scroll X", scroll_x, 0.01);
+        edit_drag!(cx, "Scroll Y", scroll_y, 0.01);
         edit_text!(cx, "Speed X", speed_x);
         edit_text!(cx, "Speed Y", speed_y);
         edit_text!(cx, "Color", color); // TODO real validation
@@ -332,8 +417,12 @@ impl View for StyleTweakerWidget {
 }
 
 fn emit(cx: &mut EventContext, style: CelesteMapStyleground) {
+    emit_phase(cx, style, EventPhase::new());
+}
+
+fn emit_phase(cx: &mut EventContext, style: CelesteMapStyleground, phase: EventPhase) {
     cx.emit(current_map_lens().get(cx).action(
-        EventPhase::new(),
+        phase,
         MapAction::UpdateStyleground {
             loc: current_styleground_lens().get(cx),
             style: Box::new(style),
@@ -1,12 +1,19 @@
 use arborio_utils::vizia::prelude::*;
 use arborio_utils::vizia::vg::{Color, ImageFlags, Paint, Path, PixelFormat, RenderTarget};
 use lazy_static::lazy_static;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::sync::mpsc;
 use std::time;
 
-use arborio_state::data::app::AppState;
+use arborio_state::data::app::{AppEvent, AppState};
+use arborio_state::data::project_map::{LevelState, MapStateData};
+use arborio_state::data::selection::AppSelection;
+use arborio_state::lenses::{current_map_impl_lens, ClosureLens};
 use arborio_state::rendering;
+use arborio_state::tools::selection::rects_of;
+use arborio_state::tools::{ContextAction, ToolSpec};
+use arborio_utils::units::*;
 
 lazy_static! {
     static ref PERF_MONITOR: bool = env::var("ARBORIO_PERF_MONITOR").is_ok();
@@ -36,15 +43,220 @@ const ROOM_DESELECTED_COLOR: Color = Color {
     b: 0.00,
     a: 0.30,
 };
+const FOCUS_MASK_COLOR: Color = Color {
+    r: 0.00,
+    g: 0.00,
+    b: 0.00,
+    a: 0.60,
+};
 
-pub struct EditorWidget {}
+/// How long a frame may spend rendering rooms of a big map before leaving the rest for the next.
+/// The current room is always rendered.
+const BIG_MAP_RENDER_BUDGET: time::Duration = time::Duration::from_millis(12);
+
+/// The most rooms the room search lists at once.
+const ROOM_SEARCH_RESULTS: usize = 20;
+
+const EMPTY_MAP_PROMPT: &str = "This map has no rooms yet.\n\
+    Pick the Room tool (4) and drag with the right mouse button to create one.";
+
+pub struct EditorWidget {
+    /// Where the right click menu is open, relative to the widget, and what it offers.
+    context_menu: Option<(f32, f32)>,
+    context_actions: Vec<ContextAction>,
+    context_menu_version: u32,
+    /// What's typed into the jump-to-room search, while it's open.
+    room_search: Option<String>,
+    room_search_version: u32,
+    /// Asks for another frame when one ran out of time to render every room. `draw` can't ask for
+    /// one itself, so this wakes a thread which sends a `DeferredRenderEvent` back.
+    deferred_render: mpsc::Sender<()>,
+}
+
+struct DeferredRenderEvent;
+
+enum ContextMenuEvent {
+    Pick(usize),
+}
+
+enum RoomSearchEvent {
+    Query(String),
+    Pick(usize),
+}
+
+fn context_menu_version_lens() -> impl Lens<Source = EditorWidget, Target = u32> {
+    ClosureLens::new(|source: &EditorWidget| Some(&source.context_menu_version))
+}
+
+fn room_search_version_lens() -> impl Lens<Source = EditorWidget, Target = u32> {
+    ClosureLens::new(|source: &EditorWidget| Some(&source.room_search_version))
+}
+
+fn room_search_lens() -> impl Lens<Source = EditorWidget, Target = String> {
+    ClosureLens::new(|source: &EditorWidget| source.room_search.as_ref())
+}
+
+/// The rooms whose names contain `query`, ignoring case, along with how to list them. Rooms which
+/// share a name with another are told apart by their index.
+fn room_search_results(map: &MapStateData, query: &str) -> Vec<(usize, String)> {
+    let query = query.to_lowercase();
+    let mut name_counts = HashMap::new();
+    for room in &map.levels {
+        *name_counts.entry(room.data.name.as_str()).or_insert(0) += 1;
+    }
+    map.levels
+        .iter()
+        .enumerate()
+        .filter(|(_, room)| room.data.name.to_lowercase().contains(&query))
+        .map(|(idx, room)| {
+            let name = &room.data.name;
+            if name_counts[name.as_str()] > 1 {
+                (idx, format!("{name} (#{idx})"))
+            } else {
+                (idx, name.clone())
+            }
+        })
+        .take(ROOM_SEARCH_RESULTS)
+        .collect()
+}
 
 impl EditorWidget {
     pub fn new(cx: &mut Context) -> Handle<Self> {
-        Self {}.build(cx, |cx| {
+        let (deferred_render, deferred_rx) = mpsc::channel();
+        Self {
+            context_menu: None,
+            context_actions: vec![],
+            context_menu_version: 0,
+            room_search: None,
+            room_search_version: 0,
+            deferred_render,
+        }
+        .build(cx, |cx| {
             cx.focus();
+            // the editor only sees input aimed at itself or while it has focus, so the context
+            // menu also listens for presses and escape anywhere else
+            cx.add_listener(|editor: &mut EditorWidget, cx, event| {
+                if editor.context_menu.is_none() {
+                    return;
+                }
+                event.map(|window_event, _| match window_event {
+                    WindowEvent::MouseDown(_) => {
+                        let bounds = cx.bounds();
+                        let (x, y) = (cx.mouse.cursorx, cx.mouse.cursory);
+                        let inside = x >= bounds.x
+                            && y >= bounds.y
+                            && x < bounds.x + bounds.w
+                            && y < bounds.y + bounds.h;
+                        if !inside {
+                            editor.set_context_menu(None, vec![]);
+                        }
+                    }
+                    WindowEvent::KeyDown(Code::Escape, _) => {
+                        editor.set_context_menu(None, vec![]);
+                    }
+                    _ => {}
+                });
+            });
+            // ends once the editor is gone and drops the sender
+            cx.spawn(move |cx| {
+                while deferred_rx.recv().is_ok() {
+                    if cx.emit(DeferredRenderEvent).is_err() {
+                        break;
+                    }
+                }
+            });
+            let no_rooms = current_map_impl_lens()
+                .then(MapStateData::levels)
+                .map(|levels| levels.is_empty());
+            Binding::new(cx, no_rooms, |cx, no_rooms| {
+                if !no_rooms.get_fallible(cx).unwrap_or(false) {
+                    return;
+                }
+                Label::new(cx, EMPTY_MAP_PROMPT)
+                    .class("empty_map_prompt")
+                    .hoverable(false)
+                    .position_type(PositionType::SelfDirected)
+                    .space(Stretch(1.0));
+            });
+            Binding::new(cx, context_menu_version_lens(), |cx, _| {
+                let editor = cx.data::<EditorWidget>().unwrap();
+                let Some((x, y)) = editor.context_menu else { return };
+                let names = editor
+                    .context_actions
+                    .iter()
+                    .map(|action| action.name())
+                    .collect::<Vec<_>>();
+                VStack::new(cx, move |cx| {
+                    for (idx, name) in names.into_iter().enumerate() {
+                        Label::new(cx, name)
+                            .class("dropdown_element")
+                            .on_press(move |cx| cx.emit(ContextMenuEvent::Pick(idx)));
+                    }
+                })
+                .class("context_menu")
+                .position_type(PositionType::SelfDirected)
+                .left(Pixels(x))
+                .top(Pixels(y));
+            });
+            Binding::new(cx, room_search_version_lens(), |cx, _| {
+                if cx.data::<EditorWidget>().unwrap().room_search.is_none() {
+                    return;
+                }
+                VStack::new(cx, |cx| {
+                    let textbox = Textbox::new(cx, room_search_lens())
+                        .on_edit(|cx, query| cx.emit(RoomSearchEvent::Query(query)))
+                        .entity();
+                    cx.emit_to(textbox, TextEvent::StartEdit);
+                    Binding::new(cx, room_search_lens(), |cx, query| {
+                        let Some(query) = query.get_fallible(cx) else { return };
+                        let app = cx.data::<AppState>().unwrap();
+                        let Some(map) = app.current_map_ref() else { return };
+                        let results = room_search_results(&map.data, &query);
+                        for (idx, label) in results {
+                            Label::new(cx, &label)
+                                .class("dropdown_element")
+                                .on_press(move |cx| cx.emit(RoomSearchEvent::Pick(idx)));
+                        }
+                    });
+                })
+                .class("room_search")
+                .position_type(PositionType::SelfDirected)
+                .left(Pixels(10.0))
+                .top(Pixels(10.0));
+            });
         })
     }
+
+    fn set_context_menu(&mut self, menu: Option<(f32, f32)>, actions: Vec<ContextAction>) {
+        self.context_menu = menu;
+        self.context_actions = actions;
+        self.context_menu_version += 1;
+    }
+
+    fn set_room_search(&mut self, search: Option<String>) {
+        self.room_search = search;
+        self.room_search_version += 1;
+    }
+
+    /// Select a room and scroll it to the middle of the view.
+    fn jump_to_room(&mut self, cx: &mut EventContext, idx: usize) {
+        self.set_room_search(None);
+        let app = cx.data::<AppState>().unwrap();
+        let Some(map) = app.current_map_ref() else { return };
+        let Some(room) = map.data.levels.get(idx) else { return };
+        let map_tab = app.map_tab_unwrap();
+        let bounds = cx.bounds();
+        let view_center = ScreenPoint::new(bounds.x + bounds.w / 2.0, bounds.y + bounds.h / 2.0);
+        let room_center = map_tab
+            .transform
+            .transform_point(room.data.bounds.cast::<f32>().center());
+        let delta = map_tab
+            .inverse_transform()
+            .transform_vector(view_center - room_center);
+        let tab = app.current_tab;
+        cx.emit(AppEvent::SelectRoom { tab, idx });
+        cx.emit(AppEvent::Pan { tab, delta });
+    }
 }
 
 impl View for EditorWidget {
@@ -53,7 +265,29 @@ impl View for EditorWidget {
     }
 
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
-        event.map(|window_event, _| {
+        event.map(|menu_event, _| match menu_event {
+            ContextMenuEvent::Pick(idx) => {
+                let action = self.context_actions.get(*idx).copied();
+                self.set_context_menu(None, vec![]);
+                if let Some(action) = action {
+                    let app = cx.data::<AppState>().unwrap();
+                    let tool = app.current_tool.borrow_mut().take();
+                    if let Some(mut tool) = tool {
+                        let events = tool.context_action(action, cx);
+                        *cx.data::<AppState>().unwrap().current_tool.borrow_mut() = Some(tool);
+                        for event in events {
+                            cx.emit(event);
+                        }
+                    }
+                }
+            }
+        });
+        event.map(|_: &DeferredRenderEvent, _| cx.needs_redraw());
+        event.map(|search_event, _| match search_event {
+            RoomSearchEvent::Query(query) => self.room_search = Some(query.clone()),
+            RoomSearchEvent::Pick(idx) => self.jump_to_room(cx, *idx),
+        });
+        event.map(|window_event, meta| {
             if let WindowEvent::SetCursor(_) = window_event {
                 return;
             }
@@ -61,9 +295,90 @@ impl View for EditorWidget {
             // TODO: nuance
             cx.needs_redraw();
 
+            if self.context_menu.is_some() {
+                // the menu eats input until something is picked or it's clicked away
+                match window_event {
+                    WindowEvent::MouseDown(_) if meta.target == cx.current() => {
+                        self.set_context_menu(None, vec![]);
+                    }
+                    WindowEvent::KeyDown(Code::Escape, _) => {
+                        self.set_context_menu(None, vec![]);
+                    }
+                    _ => {}
+                }
+                return;
+            }
+
+            if let Some(query) = self.room_search.clone() {
+                // so does the room search, so that typing into it doesn't also switch tools
+                match window_event {
+                    WindowEvent::MouseDown(_) if meta.target == cx.current() => {
+                        self.set_room_search(None);
+                    }
+                    WindowEvent::KeyDown(Code::Escape, _) => {
+                        self.set_room_search(None);
+                    }
+                    WindowEvent::KeyDown(Code::Enter, _) => {
+                        let results = cx
+                            .data::<AppState>()
+                            .unwrap()
+                            .current_map_ref()
+                            .map(|map| room_search_results(&map.data, &query))
+                            .unwrap_or_default();
+                        match results.first() {
+                            Some(&(idx, _)) => self.jump_to_room(cx, idx),
+                            None => self.set_room_search(None),
+                        }
+                    }
+                    _ => {}
+                }
+                return;
+            }
+
+            if let WindowEvent::KeyDown(Code::KeyG, _) = window_event {
+                if cx.modifiers == &Modifiers::CTRL {
+                    self.set_room_search(Some(String::new()));
+                    return;
+                }
+            }
             if let WindowEvent::MouseDown(..) = &window_event {
                 cx.focus();
             }
+            if let WindowEvent::MouseDown(MouseButton::Right) = &window_event {
+                let app = cx.data::<AppState>().unwrap();
+                let tool = app.current_tool.borrow_mut().take();
+                if let Some(mut tool) = tool {
+                    let menu = tool.context_menu(cx);
+                    *cx.data::<AppState>().unwrap().current_tool.borrow_mut() = Some(tool);
+                    if let Some((events, actions)) = menu {
+                        for event in events {
+                            cx.emit(event);
+                        }
+                        if !actions.is_empty() {
+                            let bounds = cx.bounds();
+                            let dpi = cx.style.dpi_factor as f32;
+                            let pos = (
+                                (cx.mouse.cursorx - bounds.x) / dpi,
+                                (cx.mouse.cursory - bounds.y) / dpi,
+                            );
+                            self.set_context_menu(Some(pos), actions);
+                        }
+                        return;
+                    }
+                }
+            }
+            if let WindowEvent::KeyDown(code, _) = window_event {
+                if cx.modifiers.is_empty() {
+                    let spec = match code {
+                        Code::KeyX => Some(cx.data::<AppState>().unwrap().previous_toolspec),
+                        _ => ToolSpec::from_hotkey(*code),
+                    };
+                    if let Some(spec) = spec {
+                        cx.emit(AppEvent::SelectTool { spec });
+                        return;
+                    }
+                }
+            }
             let app = cx
                 .data::<AppState>()
                 .expect("EditorWidget must have an AppState in its ancestry");
@@ -131,6 +446,12 @@ impl View for EditorWidget {
 
         let current_room = app.map_tab_unwrap().current_room;
         let preview = app.map_tab_unwrap().preview_pos;
+        let no_flags = HashSet::new();
+        let style_flags = if app.flag_preview {
+            &app.preview_flags
+        } else {
+            &no_flags
+        };
 
         let mut path = Path::new();
         for room in &map.data.levels {
@@ -161,7 +482,7 @@ impl View for EditorWidget {
                 .levels
                 .get(current_room)
                 .map_or("", |lvl| lvl.data.name.as_str()),
-            &HashSet::new(),
+            style_flags,
             false,
         );
         canvas.restore();
@@ -177,26 +498,84 @@ impl View for EditorWidget {
         }
         canvas.fill_path(&mut path, &Paint::color(FILLER_COLOR));
 
+        let mut patterns_ready = false;
+
+        // on big maps, don't populate the render cache of rooms nobody can see yet, and spread the
+        // ones in view across frames
+        let render_deadline = map
+            .cache
+            .big
+            .then(|| time::Instant::now() + BIG_MAP_RENDER_BUDGET);
+        let mut deferred = false;
+        let view = t.inverse().map(|t| {
+            t.outer_transformed_rect(&ScreenRect::new(
+                ScreenPoint::new(bounds.x, bounds.y),
+                ScreenSize::new(bounds.w, bounds.h),
+            ))
+        });
         for (idx, room) in map.data.levels.iter().enumerate() {
+            if let Some(view) = view.filter(|_| map.cache.big) {
+                let room_rect = room.data.bounds.cast::<f32>();
+                let cache = room.cache.borrow();
+                let capturing = cache.copy_image_requested
+                    || cache.export_image_requested.is_some()
+                    || cache.copy_selection_requested.is_some();
+                if !view.intersects(&room_rect) && !capturing {
+                    continue;
+                }
+            }
+            let copy_selection = room.cache.borrow_mut().copy_selection_requested.take();
+            if let Some(selection) = copy_selection {
+                copy_selection_to_clipboard(app, canvas, room, &selection);
+            }
+            let copy_image = std::mem::take(&mut room.cache.borrow_mut().copy_image_requested);
+            if copy_image {
+                copy_room_to_clipboard(app, canvas, room);
+            }
+            let export_image = room.cache.borrow_mut().export_image_requested.take();
+            if let Some(path) = export_image {
+                export_room_image(app, canvas, room, &path);
+            }
             canvas.save();
             canvas.translate(
                 room.data.bounds.min_x() as f32,
                 room.data.bounds.min_y() as f32,
             );
-            let mut cache = room.cache.borrow_mut();
-            let target = cache.render_cache.unwrap_or_else(|| {
-                canvas
-                    .create_image_empty(
-                        room.data.bounds.width() as usize,
-                        room.data.bounds.height() as usize,
-                        PixelFormat::Rgba8,
-                        ImageFlags::NEAREST | ImageFlags::FLIP_Y,
-                    )
-                    .expect("Failed to allocate ")
-            });
-            cache.render_cache = Some(target);
+            // not held across drawing the room, which keeps its planned tile draws in there too
+            let (target, render_cache_valid) = {
+                let mut cache = room.cache.borrow_mut();
+                let target = cache.render_cache.unwrap_or_else(|| {
+                    canvas
+                        .create_image_empty(
+                            room.data.bounds.width() as usize,
+                            room.data.bounds.height() as usize,
+                            PixelFormat::Rgba8,
+                            ImageFlags::NEAREST | ImageFlags::FLIP_Y,
+                        )
+                        .expect("Failed to allocate ")
+                });
+                cache.render_cache = Some(target);
+                (target, cache.render_cache_valid)
+            };
 
-            if !cache.render_cache_valid {
+            let out_of_time = render_deadline.map_or(false, |deadline| {
+                idx != current_room && time::Instant::now() > deadline
+            });
+            if !render_cache_valid && out_of_time {
+                // keep showing whatever it had rendered before until a later frame gets to it
+                deferred = true;
+            } else if !render_cache_valid {
+                if !patterns_ready {
+                    // only needed when some room's tiles are actually being drawn
+                    rendering::prepare_tile_patterns(app.current_palette_unwrap(), canvas);
+                    patterns_ready = true;
+                }
+                let nil = HashSet::new();
+                let selected = if idx == app.map_tab_unwrap().current_room {
+                    &app.map_tab_unwrap().current_selected
+                } else {
+                    &nil
+                };
                 canvas.save();
                 canvas.reset();
                 canvas.set_render_target(RenderTarget::Image(target));
@@ -208,36 +587,18 @@ impl View for EditorWidget {
                     room.data.bounds.height() as u32,
                     Color::rgba(0, 0, 0, 0),
                 );
-                rendering::draw_tiles(app.current_palette_unwrap(), canvas, room, false);
-                rendering::draw_decals(app.current_palette_unwrap(), canvas, &room.data, false);
-                let nil = HashSet::new();
-                rendering::draw_triggers(
-                    app.current_palette_unwrap(),
-                    canvas,
-                    &room.data,
-                    if idx == app.map_tab_unwrap().current_room {
-                        &app.map_tab_unwrap().current_selected
-                    } else {
-                        &nil
-                    },
-                );
-                rendering::draw_entities(
+                rendering::draw_room(
                     app.current_palette_unwrap(),
                     canvas,
-                    &room.data,
-                    if idx == app.map_tab_unwrap().current_room {
-                        &app.map_tab_unwrap().current_selected
-                    } else {
-                        &nil
-                    },
+                    room,
+                    selected,
+                    true,
+                    app.flag_preview.then_some(&app.preview_flags),
                 );
-                rendering::draw_tiles(app.current_palette_unwrap(), canvas, room, true);
-                rendering::draw_decals(app.current_palette_unwrap(), canvas, &room.data, true);
-                rendering::draw_objtiles_float(app.current_palette_unwrap(), canvas, room);
 
                 canvas.restore();
                 canvas.set_render_target(RenderTarget::Screen);
-                cache.render_cache_valid = true;
+                room.cache.borrow_mut().render_cache_valid = true;
             }
 
             let mut path = Path::new();
@@ -262,6 +623,9 @@ impl View for EditorWidget {
             }
             canvas.restore();
         }
+        if deferred {
+            self.deferred_render.send(()).ok();
+        }
 
         canvas.save();
         canvas.intersect_scissor(preview.x as f32, preview.y as f32, 320.0, 180.0);
@@ -274,11 +638,67 @@ impl View for EditorWidget {
                 .levels
                 .get(current_room)
                 .map_or("", |lvl| lvl.data.name.as_str()),
-            &HashSet::new(),
+            style_flags,
             false,
         );
         canvas.restore();
 
+        // focus mode: darken everything in view except the current room
+        if let (true, Some(view), Some(room)) = (
+            app.config.focus_mode,
+            view,
+            map.data.levels.get(current_room),
+        ) {
+            let room_rect = room.data.bounds.cast::<f32>();
+            let mut path = Path::new();
+            path.rect(
+                view.min_x(),
+                view.min_y(),
+                view.width(),
+                room_rect.min_y() - view.min_y(),
+            );
+            path.rect(
+                view.min_x(),
+                room_rect.max_y(),
+                view.width(),
+                view.max_y() - room_rect.max_y(),
+            );
+            path.rect(
+                view.min_x(),
+                room_rect.min_y(),
+                room_rect.min_x() - view.min_x(),
+                room_rect.height(),
+            );
+            path.rect(
+                room_rect.max_x(),
+                room_rect.min_y(),
+                view.max_x() - room_rect.max_x(),
+                room_rect.height(),
+            );
+            canvas.fill_path(&mut path, &Paint::color(FOCUS_MASK_COLOR));
+        }
+
+        if app.config.show_transitions {
+            rendering::draw_transitions(canvas, &map.data.levels, t.m11);
+        }
+        rendering::draw_note_markers(canvas, &map.data.levels, &map.data.notes, t.m11);
+
+        if let Some(room) = map.data.levels.get(current_room) {
+            canvas.save();
+            canvas.translate(
+                room.data.bounds.min_x() as f32,
+                room.data.bounds.min_y() as f32,
+            );
+            rendering::draw_trigger_labels(
+                canvas,
+                &room.data,
+                t.m11,
+                app.config.label_font_size,
+                app.config.declutter_labels,
+            );
+            canvas.restore();
+        }
+
         let tool = { app.current_tool.borrow_mut().take() };
         if let Some(mut tool) = tool {
             tool.draw(canvas, app, cx);
@@ -286,3 +706,88 @@ impl View for EditorWidget {
         }
     }
 }
+
+fn copy_room_to_clipboard(app: &AppState, canvas: &mut Canvas, room: &LevelState) {
+    match rendering::render_level_to_image(
+        canvas,
+        app.current_palette_unwrap(),
+        room,
+        1.0,
+        true,
+        app.flag_preview.then_some(&app.preview_flags),
+    ) {
+        Ok(image) => {
+            let (width, height) = (image.width() as usize, image.height() as usize);
+            copy_image_to_clipboard(width, height, image.into_raw());
+        }
+        Err(e) => log::error!("Could not render room to an image: {e}"),
+    }
+}
+
+fn export_room_image(
+    app: &AppState,
+    canvas: &mut Canvas,
+    room: &LevelState,
+    path: &std::path::Path,
+) {
+    // triggers are invisible in game, so leave them out of exported images
+    let image = rendering::render_level_to_image(
+        canvas,
+        app.current_palette_unwrap(),
+        room,
+        1.0,
+        false,
+        app.flag_preview.then_some(&app.preview_flags),
+    );
+    match image {
+        Ok(image) => match image.save_with_format(path, image::ImageFormat::Png) {
+            Ok(()) => log::info!(
+                "Saved {}x{} room image to {}",
+                image.width(),
+                image.height(),
+                path.display()
+            ),
+            Err(e) => log::error!("Could not save room image to {}: {e}", path.display()),
+        },
+        Err(e) => log::error!("Could not render room to an image: {e}"),
+    }
+}
+
+fn copy_selection_to_clipboard(
+    app: &AppState,
+    canvas: &mut Canvas,
+    room: &LevelState,
+    selection: &HashSet<AppSelection>,
+) {
+    let rects = selection
+        .iter()
+        .flat_map(|sel| rects_of(app, room, *sel))
+        .collect::<Vec<_>>();
+    match rendering::render_selection_to_image(
+        canvas,
+        app.current_palette_unwrap(),
+        room,
+        selection,
+        &rects,
+    ) {
+        Ok(image) => {
+            let (width, height) = (image.width() as usize, image.height() as usize);
+            copy_image_to_clipboard(width, height, image.into_raw());
+        }
+        Err(e) => log::error!("Could not render the selection to an image: {e}"),
+    }
+}
+
+fn copy_image_to_clipboard(width: usize, height: usize, bytes: Vec<u8>) {
+    let result = arboard::Clipboard::new().and_then(|mut clipboard| {
+        clipboard.set_image(arboard::ImageData {
+            width,
+            height,
+            bytes: bytes.into(),
+        })
+    });
+    match result {
+        Ok(()) => log::info!("Copied {width}x{height} image to the clipboard"),
+        Err(e) => log::error!("Could not copy image to the clipboard: {e}"),
+    }
+}
@@ -1,5 +1,6 @@
+use arborio_gfxloader::atlas_img::ObjectTileset;
 use arborio_state::data::app::{AppEvent, AppState};
-use arborio_state::tools::SCROLL_SENSITIVITY;
+use arborio_state::tools::scroll_vector;
 use arborio_utils::units::*;
 use arborio_utils::vizia::prelude::*;
 use arborio_utils::vizia::vg::{Color, Paint, Path};
@@ -24,6 +25,16 @@ impl TilePaletteWidget {
     }
 }
 
+/// The object tileset of the room being edited.
+fn current_object_tileset(app: &AppState) -> ObjectTileset {
+    let tileset = app
+        .current_room_ref()
+        .and_then(|room| room.data.object_tileset);
+    app.current_palette_unwrap()
+        .gameplay_atlas
+        .object_tileset(tileset)
+}
+
 impl View for TilePaletteWidget {
     fn element(&self) -> Option<&'static str> {
         Some("tile_palette")
@@ -39,7 +50,7 @@ impl View for TilePaletteWidget {
         let t = app.objtiles_transform;
         let t = t.then_translate(ScreenVector::new(bounds.x, bounds.y));
         let screen_hovered = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
-        let tinv = t.inverse().unwrap();
+        let tinv = invert_transform(&t);
         let map_hovered = tinv.transform_point(screen_hovered);
 
         event.map(|msg, _| {
@@ -52,29 +63,25 @@ impl View for TilePaletteWidget {
                             focus: map_hovered,
                         });
                     } else {
-                        let (x, y) = if cx.modifiers.contains(Modifiers::SHIFT) {
-                            (y, x)
-                        } else {
-                            (x, y)
-                        };
-                        let screen_vec = ScreenVector::new(*x, *y) * SCROLL_SENSITIVITY;
-                        let map_vec = t.inverse().unwrap().transform_vector(screen_vec);
+                        let screen_vec = scroll_vector(*x, *y, cx.modifiers, &app.config);
+                        let map_vec = invert_transform(&t).transform_vector(screen_vec);
                         cx.emit(AppEvent::PanObjectTiles { delta: map_vec })
                     }
                 }
                 WindowEvent::MouseDown(MouseButton::Left) => {
                     let screen_hovered = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
-                    let map_hovered = t.inverse().unwrap().transform_point(screen_hovered);
+                    let map_hovered = invert_transform(&t).transform_point(screen_hovered);
                     let tile_hovered =
                         point_room_to_tile(&point_lose_precision(&map_hovered).cast_unit());
+                    let tileset = current_object_tileset(app);
                     if tile_hovered.x < 0
-                        || tile_hovered.x >= 32
+                        || tile_hovered.x >= tileset.columns
                         || tile_hovered.y < 0
-                        || tile_hovered.y >= 32
+                        || tile_hovered.y >= tileset.rows
                     {
                         return;
                     }
-                    let tile = (tile_hovered.x + tile_hovered.y * 32) as u32;
+                    let tile = (tile_hovered.x + tile_hovered.y * tileset.columns) as u32;
                     (self.callback)(cx, tile);
                 }
                 _ => {}
@@ -118,9 +125,10 @@ impl View for TilePaletteWidget {
         canvas.set_transform(t.m11, t.m12, t.m21, t.m22, t.m31.round(), t.m32.round());
 
         let palette = app.current_palette_unwrap();
+        let tileset = current_object_tileset(app);
         if let Err(e) = palette.gameplay_atlas.draw_sprite(
             canvas,
-            "tilesets/scenery",
+            &tileset.texture,
             Point2D::new(0.0, 0.0),
             None,
             Some(Vector2D::new(0.0, 0.0)),
@@ -133,7 +141,7 @@ impl View for TilePaletteWidget {
 
         let screen_hovered = ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory);
         let screen_hovered = screen_hovered - ScreenVector::new(bounds.x, bounds.y);
-        let map_hovered = t.inverse().unwrap().transform_point(screen_hovered);
+        let map_hovered = invert_transform(&t).transform_point(screen_hovered);
         let tile_hovered = point_room_to_tile(&point_lose_precision(&map_hovered).cast_unit());
         let map_hovered_snapped = point_tile_to_room(&tile_hovered);
 
@@ -146,9 +154,10 @@ impl View for TilePaletteWidget {
         );
         canvas.fill_path(&mut path, &Paint::color(Color::rgba(255, 255, 0, 128)));
 
+        let selected = self.selected as i32;
         let map_selected_snapped = RoomPoint::new(
-            ((self.selected % 32) * 8) as i32,
-            ((self.selected / 32) * 8) as i32,
+            (selected % tileset.columns) * 8,
+            (selected / tileset.columns) * 8,
         );
         let mut path = Path::new();
         path.rect(
@@ -1,2 +1,3 @@
 pub mod main_widget;
+pub mod minimap;
 pub mod tabs;
@@ -2,12 +2,31 @@ use crate::tabs::{build_tab_bar, build_tabs};
 use arborio_state::data::app::{AppEvent, AppState};
 use arborio_state::data::project_map::MapEvent;
 use arborio_state::data::tabs::AppTab;
+use arborio_state::data::{AppConfigSetter, Layer};
 use arborio_state::lenses::{current_tab_impl_lens, IsFailedLens};
 use arborio_utils::vizia::prelude::*;
+use dialog::DialogBox;
+use std::path::{Path, PathBuf};
 
 pub fn main_widget(cx: &mut Context) {
     cx.add_global_listener(|cx, event| {
-        event.map(|window_event, _| match window_event {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::GeometryChanged(_) if meta.target == Entity::root() => {
+                let bounds = cx.cache.get_bounds(Entity::root());
+                let size = (bounds.w, bounds.h);
+                if cx.data::<AppState>().unwrap().config.window_size != Some(size) {
+                    cx.emit(AppEvent::WindowResized { size });
+                }
+            }
+            WindowEvent::WindowMoved(position) => {
+                let position = (position.x, position.y);
+                if cx.data::<AppState>().unwrap().config.window_position != Some(position) {
+                    cx.emit(AppEvent::WindowMoved { position });
+                }
+            }
+            WindowEvent::WindowClose => {
+                cx.emit(AppEvent::WindowClosing);
+            }
             WindowEvent::KeyDown(Code::KeyZ, _) if cx.modifiers == &Modifiers::CTRL => {
                 cx.emit(AppEvent::MapEvent {
                     map: None,
@@ -76,6 +95,130 @@ fn is_map() -> impl Lens<Target = bool> {
     IsFailedLens::new(current_tab_impl_lens().then(AppTab::map)).map(|b| !b)
 }
 
+fn export_map(cx: &mut EventContext) {
+    let app = cx.data::<AppState>().unwrap();
+    let default = PathBuf::from(".");
+    let path = if !app.config.last_filepath.is_dir() {
+        &default
+    } else {
+        &app.config.last_filepath
+    };
+    let result = dialog::FileSelection::new("Save a Copy")
+        .mode(dialog::FileSelectionMode::Save)
+        .path(path)
+        .show()
+        .unwrap();
+    if let Some(result) = result {
+        let result_path: PathBuf = result.into();
+        cx.emit(AppEvent::EditSettings {
+            setter: AppConfigSetter::LastFilepath(
+                result_path
+                    .parent()
+                    .unwrap_or_else(|| Path::new("/"))
+                    .to_owned(),
+            ),
+        });
+        cx.emit(AppEvent::SaveMapAs { path: result_path });
+    }
+}
+
+fn export_room_image(cx: &mut EventContext) {
+    let app = cx.data::<AppState>().unwrap();
+    let Some(AppTab::Map(map_tab)) = app.tabs.get(app.current_tab) else { return };
+    let (tab, room) = (app.current_tab, map_tab.current_room);
+    let default = PathBuf::from(".");
+    let path = if !app.config.last_filepath.is_dir() {
+        &default
+    } else {
+        &app.config.last_filepath
+    };
+    let result = dialog::FileSelection::new("Export Room as Image")
+        .mode(dialog::FileSelectionMode::Save)
+        .path(path)
+        .show()
+        .unwrap();
+    if let Some(result) = result {
+        let mut result_path: PathBuf = result.into();
+        if result_path.extension().is_none() {
+            result_path.set_extension("png");
+        }
+        cx.emit(AppEvent::EditSettings {
+            setter: AppConfigSetter::LastFilepath(
+                result_path
+                    .parent()
+                    .unwrap_or_else(|| Path::new("/"))
+                    .to_owned(),
+            ),
+        });
+        cx.emit(AppEvent::ExportRoomImage {
+            tab,
+            room,
+            path: result_path,
+        });
+    }
+}
+
+fn import_tile_image(cx: &mut EventContext) {
+    let app = cx.data::<AppState>().unwrap();
+    let fg = app.current_layer != Layer::BgTiles;
+    let default = PathBuf::from(".");
+    let path = if !app.config.last_filepath.is_dir() {
+        &default
+    } else {
+        &app.config.last_filepath
+    };
+    let result = dialog::FileSelection::new("Import Tiles from Image")
+        .mode(dialog::FileSelectionMode::Open)
+        .path(path)
+        .show()
+        .unwrap();
+    if let Some(result) = result {
+        let result_path: PathBuf = result.into();
+        cx.emit(AppEvent::EditSettings {
+            setter: AppConfigSetter::LastFilepath(
+                result_path
+                    .parent()
+                    .unwrap_or_else(|| Path::new("/"))
+                    .to_owned(),
+            ),
+        });
+        cx.emit(AppEvent::MapEvent {
+            map: None,
+            event: MapEvent::ImportTileImage {
+                path: result_path,
+                fg,
+            },
+        });
+    }
+}
+
+fn open_zip(cx: &mut EventContext) {
+    let app = cx.data::<AppState>().unwrap();
+    let default = PathBuf::from(".");
+    let path = if !app.config.last_filepath.is_dir() {
+        &default
+    } else {
+        &app.config.last_filepath
+    };
+    let result = dialog::FileSelection::new("Open Zipped Mod")
+        .mode(dialog::FileSelectionMode::Open)
+        .path(path)
+        .show()
+        .unwrap();
+    if let Some(result) = result {
+        let result_path: PathBuf = result.into();
+        cx.emit(AppEvent::EditSettings {
+            setter: AppConfigSetter::LastFilepath(
+                result_path
+                    .parent()
+                    .unwrap_or_else(|| Path::new("/"))
+                    .to_owned(),
+            ),
+        });
+        cx.emit(AppEvent::OpenZip { path: result_path });
+    }
+}
+
 fn build_menu_bar(cx: &mut Context) {
     Menu::new(
         cx,
@@ -94,6 +237,21 @@ fn build_menu_bar(cx: &mut Context) {
                 },
             )
             .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Save a Copy...");
+                },
+                export_map,
+            )
+            .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Open Zipped Mod");
+                },
+                open_zip,
+            );
         },
     );
     Menu::new(
@@ -139,6 +297,111 @@ fn build_menu_bar(cx: &mut Context) {
                 },
             )
             .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Flag Inspector");
+                },
+                move |cx| {
+                    cx.emit(AppEvent::MapEvent {
+                        map: None,
+                        event: MapEvent::OpenFlags,
+                    });
+                },
+            )
+            .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Open Containing Mod");
+                },
+                move |cx| {
+                    cx.emit(AppEvent::MapEvent {
+                        map: None,
+                        event: MapEvent::OpenModule,
+                    });
+                },
+            )
+            .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Duplicate Map");
+                },
+                move |cx| {
+                    cx.emit(AppEvent::MapEvent {
+                        map: None,
+                        event: MapEvent::Duplicate,
+                    });
+                },
+            )
+            .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Fix Map");
+                },
+                move |cx| {
+                    cx.emit(AppEvent::MapEvent {
+                        map: None,
+                        event: MapEvent::FixMap,
+                    });
+                },
+            )
+            .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Check for Objects Outside Rooms");
+                },
+                move |cx| {
+                    cx.emit(AppEvent::MapEvent {
+                        map: None,
+                        event: MapEvent::CheckOutsideObjects,
+                    });
+                },
+            )
+            .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Copy Room as Image");
+                },
+                move |cx| {
+                    cx.emit(AppEvent::MapEvent {
+                        map: None,
+                        event: MapEvent::CopyRoomImage,
+                    });
+                },
+            )
+            .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Copy Selection as Image");
+                },
+                move |cx| {
+                    let tab = cx.data::<AppState>().unwrap().current_tab;
+                    cx.emit(AppEvent::CopySelectionImage { tab });
+                },
+            )
+            .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Export Room as Image");
+                },
+                export_room_image,
+            )
+            .display(is_map());
+            MenuButton::new(
+                cx,
+                move |cx| {
+                    Label::new(cx, "Import Tiles from Image");
+                },
+                import_tile_image,
+            )
+            .display(is_map());
         },
     );
     Menu::new(
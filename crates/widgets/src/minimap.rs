@@ -0,0 +1,277 @@
+use arborio_state::data::app::{AppEvent, AppState};
+use arborio_state::data::MapID;
+use arborio_utils::units::*;
+use arborio_utils::vizia::prelude::*;
+use arborio_utils::vizia::vg::{
+    Color, ImageFlags, ImageId, Paint, Path, PixelFormat, RenderTarget,
+};
+use std::cell::RefCell;
+
+/// Space left around the rooms, in pixels.
+const PADDING: f32 = 4.0;
+
+const BACKGROUND_COLOR: Color = Color {
+    r: 0.20,
+    g: 0.20,
+    b: 0.20,
+    a: 0.85,
+};
+const ROOM_COLOR: Color = Color {
+    r: 0.35,
+    g: 0.55,
+    b: 0.35,
+    a: 1.00,
+};
+const CURRENT_ROOM_COLOR: Color = Color {
+    r: 0.60,
+    g: 0.85,
+    b: 0.60,
+    a: 1.00,
+};
+
+/// An overview of every room in the current map, with the part the editor is showing outlined.
+/// Clicking or dragging on it moves the editor there, and scrolling zooms the editor.
+pub struct MinimapWidget {
+    /// The editor whose view is outlined and moved.
+    editor: Entity,
+    dragging: bool,
+}
+
+thread_local! {
+    /// The rooms as last drawn. They are only drawn again when one of them moves or is added,
+    /// removed or selected, or the minimap is resized or shows another map. The editor and its
+    /// minimap are built again whenever the tab changes, so this is shared rather than kept in the
+    /// widget, which would leave an image behind each time. There is only ever one image: it is
+    /// reused or deleted whenever it's drawn again.
+    static CACHE: RefCell<Option<MinimapCache>> = RefCell::new(None);
+}
+
+struct MinimapCache {
+    image: ImageId,
+    map: MapID,
+    size: (u32, u32),
+    rooms: Vec<MapRectStrict>,
+    current_room: usize,
+}
+
+/// Where the map is drawn in the minimap, with the rooms fit inside `bounds`.
+fn minimap_transform(rooms: &[MapRectStrict], bounds: BoundingBox) -> Option<MapToScreen> {
+    let extent = rooms
+        .iter()
+        .map(|room| room.cast::<f32>())
+        .reduce(|a, b| a.union(&b))?;
+    let scale = ((bounds.w - PADDING * 2.0) / extent.width())
+        .min((bounds.h - PADDING * 2.0) / extent.height());
+    if !scale.is_finite() || scale <= 0.0 {
+        return None;
+    }
+    let offset = ScreenVector::new(
+        bounds.x + (bounds.w - extent.width() * scale) / 2.0,
+        bounds.y + (bounds.h - extent.height() * scale) / 2.0,
+    );
+    Some(
+        MapToScreen::translation(-extent.min_x(), -extent.min_y())
+            .then_scale(scale, scale)
+            .then_translate(offset),
+    )
+}
+
+fn current_rooms(app: &AppState) -> Vec<MapRectStrict> {
+    app.current_map_ref()
+        .map(|map| {
+            map.data
+                .levels
+                .iter()
+                .map(|room| room.data.bounds)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl MinimapWidget {
+    pub fn new(cx: &mut Context, editor: Entity) -> Handle<'_, Self> {
+        Self {
+            editor,
+            dragging: false,
+        }
+        .build(cx, |_| {})
+    }
+
+    /// Pan the editor so the point under the mouse is in the middle of its view.
+    fn jump(&self, cx: &mut EventContext) {
+        let app = cx.data::<AppState>().unwrap();
+        if !app.map_tab_check() {
+            return;
+        }
+        let Some(t) = minimap_transform(&current_rooms(app), cx.bounds()) else { return };
+        let Some(t_inv) = t.inverse() else { return };
+        let target = t_inv.transform_point(ScreenPoint::new(cx.mouse.cursorx, cx.mouse.cursory));
+        let editor = cx.cache.get_bounds(self.editor);
+        let view_center =
+            app.map_tab_unwrap()
+                .inverse_transform()
+                .transform_point(ScreenPoint::new(
+                    editor.x + editor.w / 2.0,
+                    editor.y + editor.h / 2.0,
+                ));
+        let tab = app.current_tab;
+        cx.emit(AppEvent::Pan {
+            tab,
+            delta: view_center - target,
+        });
+    }
+}
+
+impl View for MinimapWidget {
+    fn element(&self) -> Option<&'static str> {
+        Some("minimap")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                self.dragging = true;
+                cx.capture();
+                self.jump(cx);
+                cx.needs_redraw();
+                meta.consume();
+            }
+            WindowEvent::MouseMove(..) if self.dragging => {
+                self.jump(cx);
+                cx.needs_redraw();
+            }
+            WindowEvent::MouseUp(MouseButton::Left) if self.dragging => {
+                self.dragging = false;
+                cx.release();
+            }
+            WindowEvent::MouseScroll(_, y) => {
+                let app = cx.data::<AppState>().unwrap();
+                if !app.map_tab_check() {
+                    return;
+                }
+                let editor = cx.cache.get_bounds(self.editor);
+                let focus =
+                    app.map_tab_unwrap()
+                        .inverse_transform()
+                        .transform_point(ScreenPoint::new(
+                            editor.x + editor.w / 2.0,
+                            editor.y + editor.h / 2.0,
+                        ));
+                let tab = app.current_tab;
+                cx.emit(AppEvent::Zoom {
+                    tab,
+                    delta: y.exp(),
+                    focus,
+                });
+                cx.needs_redraw();
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let app = cx.data::<AppState>().unwrap();
+        if !app.map_tab_check() || bounds.w < 1.0 || bounds.h < 1.0 {
+            return;
+        }
+        let map_tab = app.map_tab_unwrap();
+
+        canvas.save();
+        canvas.reset_transform();
+        let mut path = Path::new();
+        path.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.fill_path(&mut path, &Paint::color(BACKGROUND_COLOR));
+
+        let rooms = current_rooms(app);
+        let Some(t) = minimap_transform(&rooms, bounds) else {
+            canvas.restore();
+            return;
+        };
+
+        let size = (bounds.w as u32, bounds.h as u32);
+        CACHE.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            let fresh = matches!(&*cache, Some(cache) if cache.size == size
+                && cache.map == map_tab.id
+                && cache.current_room == map_tab.current_room
+                && cache.rooms == rooms);
+            if !fresh {
+                let image = match cache.take() {
+                    Some(old) if old.size == size => old.image,
+                    old => {
+                        if let Some(old) = old {
+                            canvas.delete_image(old.image);
+                        }
+                        canvas
+                            .create_image_empty(
+                                size.0 as usize,
+                                size.1 as usize,
+                                PixelFormat::Rgba8,
+                                ImageFlags::FLIP_Y,
+                            )
+                            .expect("Failed to allocate minimap")
+                    }
+                };
+                canvas.save();
+                canvas.reset();
+                canvas.set_render_target(RenderTarget::Image(image));
+                canvas.clear_rect(0, 0, size.0, size.1, Color::rgba(0, 0, 0, 0));
+                let local = t.then_translate(ScreenVector::new(-bounds.x, -bounds.y));
+                for (idx, room) in rooms.iter().enumerate() {
+                    let rect = local.outer_transformed_rect(&room.cast::<f32>());
+                    let mut path = Path::new();
+                    path.rect(rect.min_x(), rect.min_y(), rect.width(), rect.height());
+                    let color = if idx == map_tab.current_room {
+                        CURRENT_ROOM_COLOR
+                    } else {
+                        ROOM_COLOR
+                    };
+                    canvas.fill_path(&mut path, &Paint::color(color));
+                }
+                canvas.restore();
+                canvas.set_render_target(RenderTarget::Screen);
+                *cache = Some(MinimapCache {
+                    image,
+                    map: map_tab.id,
+                    size,
+                    rooms,
+                    current_room: map_tab.current_room,
+                });
+            }
+
+            if let Some(cache) = &*cache {
+                let mut path = Path::new();
+                path.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+                let paint = Paint::image(
+                    cache.image,
+                    bounds.x,
+                    bounds.y,
+                    bounds.w,
+                    bounds.h,
+                    0.0,
+                    1.0,
+                );
+                canvas.fill_path(&mut path, &paint);
+            }
+        });
+
+        let editor = cx.cache.get_bounds(self.editor);
+        let view = map_tab
+            .inverse_transform()
+            .outer_transformed_rect(&ScreenRect::new(
+                ScreenPoint::new(editor.x, editor.y),
+                ScreenSize::new(editor.w, editor.h),
+            ));
+        let view = t.outer_transformed_rect(&view);
+        canvas.scissor(bounds.x, bounds.y, bounds.w, bounds.h);
+        let mut path = Path::new();
+        path.rect(view.min_x(), view.min_y(), view.width(), view.height());
+        canvas.stroke_path(
+            &mut path,
+            &Paint::color(Color::white()).with_line_width(cx.style.dpi_factor as f32),
+        );
+        canvas.restore();
+    }
+}
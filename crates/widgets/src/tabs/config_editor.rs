@@ -9,6 +9,7 @@ use std::str::FromStr;
 use arborio_maploader::map_struct::{Attribute, CelesteMap, CelesteMapEntity};
 use arborio_modloader::aggregate::ModuleAggregate;
 use arborio_modloader::config::{AttributeInfo, AttributeType, AttributeValue, EntityConfig};
+use arborio_modloader::dialog::DEFAULT_LANGUAGE;
 use arborio_modloader::mapstruct_plus_config::{make_entity_env, make_node_env};
 use arborio_modloader::module::{CelesteModule, MapPath, ModuleID};
 use arborio_state::data::action::StylegroundSelection;
@@ -16,7 +17,7 @@ use arborio_state::data::app::{AppEvent, AppState};
 use arborio_state::data::config_editor::{
     AnyConfig, ConfigSearchFilter, ConfigSearchResult, ConfigSearchType, EntityConfigPreviewModel,
     EntityConfigPreviewModelSetter, EntityConfigSearchResult, SearchScope,
-    StylegroundConfigSearchResult, TriggerConfigSearchResult,
+    StylegroundConfigSearchResult, TriggerConfigSearchResult, UnknownAttributeSearchResult,
 };
 use arborio_state::data::tabs::{AppTab, ConfigEditorTab};
 use arborio_state::data::AppConfigSetter;
@@ -77,6 +78,8 @@ pub fn analyze_uses(
                 v.insert(AttributeInfo {
                     ignore: attribute_filter.contains(name.as_str()),
                     display_name: Some(name),
+                    description: None,
+                    group: None,
                     default: default_value(&suggestion),
                     ty: suggestion,
                     options: vec![],
@@ -227,6 +230,7 @@ pub fn build_search_settings(cx: &mut Context) {
                         ConfigSearchType::Entities,
                         ConfigSearchType::Triggers,
                         ConfigSearchType::Stylegrounds,
+                        ConfigSearchType::UnknownAttributes,
                     ] {
                         Label::new(cx, &format!("{target}"))
                             .class("dropdown_element")
@@ -358,6 +362,25 @@ pub fn build_search_settings(cx: &mut Context) {
                         .into_iter()
                         .map(ConfigSearchResult::Styleground)
                         .collect(),
+                        ConfigSearchType::UnknownAttributes => {
+                            let mut results = walk_maps(
+                                &modules,
+                                &scope,
+                                &filter,
+                                &targets,
+                                &attrs,
+                                scan_unknown_attributes,
+                            )
+                            .into_iter()
+                            .collect::<Vec<_>>();
+                            results.sort_by(|a, b| {
+                                (&a.map.sid, a.room, &a.key).cmp(&(&b.map.sid, b.room, &b.key))
+                            });
+                            results
+                                .into_iter()
+                                .map(ConfigSearchResult::UnknownAttribute)
+                                .collect()
+                        }
                     };
                     cx.emit(AppEvent::PopulateConfigSearchResults { tab, results })
                         .unwrap();
@@ -400,8 +423,14 @@ fn walk_maps<T>(
                 if let Ok(map) =
                     CelesteModule::load_map_static(module.filesystem_root.as_ref().unwrap(), map)
                 {
-                    let palette =
-                        ModuleAggregate::new(modules, &modules_lookup, &map.meta, *name, false);
+                    let palette = ModuleAggregate::new(
+                        modules,
+                        &modules_lookup,
+                        &map.meta,
+                        *name,
+                        DEFAULT_LANGUAGE,
+                        false,
+                    );
                     f(&mut results, filter, &attrs, &map, &map_path, &palette);
                 }
             }
@@ -581,6 +610,61 @@ fn scan_stylegrounds(
     }
 }
 
+/// Every attribute on a configured entity or trigger which isn't in its config. Unconfigured ones
+/// are left to the "Unconfigured" filter, since all of their attributes would show up.
+fn scan_unknown_attributes(
+    results: &mut HashSet<UnknownAttributeSearchResult>,
+    filter: &ConfigSearchFilter,
+    attrs: &HashSet<&str>,
+    map: &CelesteMap,
+    map_path: &MapPath,
+    palette: &ModuleAggregate,
+) {
+    for (room_idx, room) in map.levels.iter().enumerate() {
+        let placed = room
+            .entities
+            .iter()
+            .map(|entity| (entity, false))
+            .chain(room.triggers.iter().map(|trigger| (trigger, true)));
+        for (entity, trigger) in placed {
+            if let ConfigSearchFilter::Matches(s) = filter {
+                if !entity
+                    .name
+                    .to_ascii_lowercase()
+                    .contains(&s.to_ascii_lowercase())
+                {
+                    continue;
+                }
+            }
+            let known = if trigger {
+                palette
+                    .trigger_config
+                    .get(entity.name.as_str())
+                    .map(|config| &config.attribute_info)
+            } else {
+                palette
+                    .entity_config
+                    .get(entity.name.as_str())
+                    .map(|config| &config.attribute_info)
+            };
+            let Some(known) = known else { continue };
+            for key in entity.attributes.keys() {
+                if !attrs.contains(key.as_str()) && !known.contains_key(key.as_str()) {
+                    results.insert(UnknownAttributeSearchResult {
+                        name: intern_str(&entity.name),
+                        key: key.clone(),
+                        trigger,
+                        id: entity.id,
+                        map: map_path.clone(),
+                        room: room_idx,
+                        room_name: room.name.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
 fn build_search_results(cx: &mut Context) {
     let ctab = current_tab_impl_lens().then(AppTab::config_editor);
     ScrollView::new(cx, 0.0, 0.0, false, true, move |cx| {
@@ -591,19 +675,49 @@ fn build_search_results(cx: &mut Context) {
                 let display = item.view(cx.data().unwrap(), |item| {
                     item.map(|item| item.display_list()).unwrap_or_default()
                 });
-                Label::new(cx, &display)
-                    .class("list_highlight")
-                    .bind(
-                        ctab.then(ConfigEditorTab::selected_result),
-                        move |handle, selected| {
-                            let selected = selected.get(handle.cx);
-                            handle.checked(selected == idx);
-                        },
-                    )
-                    .on_press(move |cx| {
-                        let tab = cx.data::<AppState>().unwrap().current_tab;
-                        cx.emit(AppEvent::SelectConfigSearchResult { tab, idx });
-                    });
+                let has_usage = item.view(cx.data().unwrap(), |item| {
+                    item.map_or(false, |item| item.usage().is_some())
+                });
+                HStack::new(cx, move |cx| {
+                    Label::new(cx, &display)
+                        .class("list_highlight")
+                        .bind(
+                            ctab.then(ConfigEditorTab::selected_result),
+                            move |handle, selected| {
+                                let selected = selected.get(handle.cx);
+                                handle.checked(selected == idx);
+                            },
+                        )
+                        .on_press(move |cx| {
+                            let tab = cx.data::<AppState>().unwrap().current_tab;
+                            cx.emit(AppEvent::SelectConfigSearchResult { tab, idx });
+                            // a single placement has nothing else to look at, so go straight there
+                            let usage = item.view(cx.data().unwrap(), |item| {
+                                item.filter(|item| {
+                                    matches!(item, ConfigSearchResult::UnknownAttribute(_))
+                                })
+                                .and_then(|item| item.usage())
+                            });
+                            if let Some(usage) = usage {
+                                cx.emit(usage);
+                            }
+                        });
+                    if has_usage {
+                        Button::new(
+                            cx,
+                            move |cx| {
+                                let usage = item.view(cx.data().unwrap(), |item| {
+                                    item.and_then(|item| item.usage())
+                                });
+                                if let Some(usage) = usage {
+                                    cx.emit(usage);
+                                }
+                            },
+                            |cx| Label::new(cx, "Go to usage"),
+                        );
+                    }
+                })
+                .class("search_result");
             },
         );
     });
@@ -680,6 +794,14 @@ pub fn build_item_editor(cx: &mut Context) {
             },
             move |cx| Label::new(cx, "Save"),
         );
+        Button::new(
+            cx,
+            move |cx| {
+                let tab = cx.data::<AppState>().unwrap().current_tab;
+                cx.emit(AppEvent::SaveConfigToModule { tab });
+            },
+            move |cx| Label::new(cx, "Save to Mod"),
+        );
         Button::new(
             cx,
             move |cx| {
@@ -953,6 +1075,7 @@ where
                             &TileGrid::empty(),
                             EntityConfigPreviewModel::show_selected.get(cx),
                             &TileGrid::empty(),
+                            None,
                         );
 
                         if EntityConfigPreviewModel::show_boxes.get(cx) {
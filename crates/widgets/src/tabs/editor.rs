@@ -1,8 +1,11 @@
 use arborio_modloader::aggregate::ModuleAggregate;
+use arborio_modloader::config::PencilBehavior;
 use arborio_state::data::app::{AppEvent, AppState};
-use arborio_state::data::{AppConfig, AppConfigSetter, Layer};
+use arborio_state::data::project_map::MapEvent;
+use arborio_state::data::{AppConfig, AppConfigSetter, Layer, SnapMode};
 use arborio_state::lenses::{current_map_lens, current_palette_lens, AnotherLens, AutoSaverLens};
 use arborio_state::tools::ToolSpec;
+use arborio_utils::vizia::fonts::icons_names::DOWN;
 use arborio_utils::vizia::prelude::*;
 
 use arborio_widgets_common::list_palette::PaletteWidget;
@@ -12,6 +15,8 @@ use arborio_widgets_editor_room::room_tweaker::RoomTweakerWidget;
 use arborio_widgets_editor_style::style_tweaker::{StyleListWidget, StyleTweakerWidget};
 use arborio_widgets_tilepicker::tile_palette::TilePaletteWidget;
 
+use crate::minimap::MinimapWidget;
+
 pub fn build_editor(cx: &mut Context) {
     HStack::new(cx, |cx| {
         VStack::new(cx, |cx| {
@@ -24,9 +29,14 @@ pub fn build_editor(cx: &mut Context) {
                 build_tool_settings(cx);
             })
             .id("tool_settings");
-            EditorWidget::new(cx)
+            let editor = EditorWidget::new(cx)
                 .width(Stretch(1.0))
-                .height(Stretch(1.0));
+                .height(Stretch(1.0))
+                .entity();
+            MinimapWidget::new(cx, editor)
+                .position_type(PositionType::SelfDirected)
+                .left(Stretch(1.0))
+                .top(Stretch(1.0));
         });
 
         VStack::new(cx, |cx| {
@@ -41,18 +51,110 @@ pub fn build_editor(cx: &mut Context) {
 
 fn build_tool_settings(cx: &mut Context) {
     HStack::new(cx, move |cx| {
-        Label::new(cx, "Snap").describing("tool_settings_snap");
+        Label::new(cx, "Natural Scroll").describing("tool_settings_natural_scroll");
         let lens = AppState::config
             .then(AutoSaverLens::new())
-            .then(AppConfig::snap);
+            .then(AppConfig::natural_scroll);
+        Checkbox::new(cx, lens)
+            .on_toggle(move |cx| {
+                let val = !lens.get(cx);
+                cx.emit(AppEvent::EditSettings {
+                    setter: AppConfigSetter::NaturalScroll(val),
+                });
+            })
+            .id("tool_settings_natural_scroll");
+    });
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Focus Room").describing("tool_settings_focus_mode");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::focus_mode);
+        Checkbox::new(cx, lens)
+            .on_toggle(move |cx| {
+                let val = !lens.get(cx);
+                cx.emit(AppEvent::EditSettings {
+                    setter: AppConfigSetter::FocusMode(val),
+                });
+            })
+            .id("tool_settings_focus_mode");
+    });
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Transitions").describing("tool_settings_transitions");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::show_transitions);
         Checkbox::new(cx, lens)
             .on_toggle(move |cx| {
                 let val = !lens.get(cx);
                 cx.emit(AppEvent::EditSettings {
-                    setter: AppConfigSetter::Snap(val),
+                    setter: AppConfigSetter::ShowTransitions(val),
+                });
+            })
+            .id("tool_settings_transitions");
+    });
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Label Size").describing("tool_settings_label_size");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::label_font_size);
+        Slider::new(cx, lens)
+            .range(0.0..24.0)
+            .on_changing(|cx, val| {
+                cx.emit(AppEvent::EditSettings {
+                    setter: AppConfigSetter::LabelFontSize(val),
                 });
             })
-            .id("tool_settings_snap");
+            .id("tool_settings_label_size");
+        Label::new(cx, "Declutter").describing("tool_settings_declutter");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::declutter_labels);
+        Checkbox::new(cx, lens)
+            .on_toggle(move |cx| {
+                let val = !lens.get(cx);
+                cx.emit(AppEvent::EditSettings {
+                    setter: AppConfigSetter::DeclutterLabels(val),
+                });
+            })
+            .id("tool_settings_declutter");
+    })
+    .bind(AppState::current_layer, move |handle, layer| {
+        let layer = layer.get(handle.cx);
+        let show = layer == Layer::Triggers || layer == Layer::All;
+        handle.display(show);
+    });
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Snap");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::snap);
+        Dropdown::new(
+            cx,
+            move |cx| {
+                HStack::new(cx, move |cx| {
+                    Label::new(cx, lens.map(|mode| mode.name()));
+                    Label::new(cx, DOWN).class("icon").class("dropdown_icon");
+                })
+            },
+            move |cx| {
+                for mode in enum_iterator::all::<SnapMode>() {
+                    Label::new(cx, mode.name())
+                        .class("dropdown_element")
+                        .class("btn_highlight")
+                        .on_press(move |cx| {
+                            cx.emit(PopupEvent::Close);
+                            cx.emit(AppEvent::EditSettings {
+                                setter: AppConfigSetter::Snap(mode),
+                            });
+                        });
+                }
+            },
+        )
+        .id("tool_settings_snap");
     })
     .bind(AppState::current_toolspec, move |handle, spec| {
         let spec = spec.get(handle.cx);
@@ -60,6 +162,90 @@ fn build_tool_settings(cx: &mut Context) {
         handle.display(show);
     });
 
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Pin Palette").describing("tool_settings_pin_palette");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::pin_palette);
+        Checkbox::new(cx, lens)
+            .on_toggle(move |cx| {
+                let val = !lens.get(cx);
+                cx.emit(AppEvent::EditSettings {
+                    setter: AppConfigSetter::PinPalette(val),
+                });
+            })
+            .id("tool_settings_pin_palette");
+        Label::new(cx, AppState::eyedropped).bind(lens, |handle, pinned| {
+            let pinned = pinned.get(handle.cx);
+            handle.display(pinned);
+        });
+    })
+    .bind(AppState::current_toolspec, move |handle, spec| {
+        let spec = spec.get(handle.cx);
+        let show = spec == ToolSpec::Pencil || spec == ToolSpec::Bucket;
+        handle.display(show);
+    });
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Dither").describing("tool_settings_dither");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::dither);
+        Checkbox::new(cx, lens)
+            .on_toggle(move |cx| {
+                let val = !lens.get(cx);
+                cx.emit(AppEvent::EditSettings {
+                    setter: AppConfigSetter::Dither(val),
+                });
+            })
+            .id("tool_settings_dither");
+        HStack::new(cx, move |cx| {
+            Label::new(cx, "with");
+            let tile_lens = AppState::config
+                .then(AutoSaverLens::new())
+                .then(AppConfig::dither_tile);
+            Textbox::new(cx, tile_lens)
+                .on_edit(|cx, text| {
+                    if text.chars().count() == 1 {
+                        cx.emit(AppEvent::EditSettings {
+                            setter: AppConfigSetter::DitherTile(text),
+                        });
+                    }
+                })
+                .class("dither_tile");
+            let ratio_lens = AppState::config
+                .then(AutoSaverLens::new())
+                .then(AppConfig::dither_ratio);
+            Slider::new(cx, ratio_lens)
+                .range(0.0..1.0)
+                .on_changing(|cx, val| {
+                    cx.emit(AppEvent::EditSettings {
+                        setter: AppConfigSetter::DitherRatio(val),
+                    });
+                });
+            Label::new(cx, "seed");
+            let seed_lens = AppState::config
+                .then(AutoSaverLens::new())
+                .then(AppConfig::dither_seed);
+            Textbox::new(cx, seed_lens).on_edit(|cx, text| {
+                if let Ok(val) = text.parse() {
+                    cx.emit(AppEvent::EditSettings {
+                        setter: AppConfigSetter::DitherSeed(val),
+                    });
+                }
+            });
+        })
+        .bind(lens, |handle, dither| {
+            let dither = dither.get(handle.cx);
+            handle.display(dither);
+        });
+    })
+    .bind(AppState::current_toolspec, move |handle, spec| {
+        let spec = spec.get(handle.cx);
+        let show = spec == ToolSpec::Bucket;
+        handle.display(show);
+    });
+
     HStack::new(cx, move |cx| {
         Label::new(cx, "Interval").describing("tool_settings_interval");
         let lens = AppState::config
@@ -80,6 +266,31 @@ fn build_tool_settings(cx: &mut Context) {
         handle.display(show);
     });
 
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Merge Undo (ms)").describing("tool_settings_undo_coalesce");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::undo_coalesce_ms);
+        Textbox::new(cx, lens)
+            .on_edit(|cx, text| {
+                if let Ok(val) = text.parse() {
+                    cx.emit(AppEvent::EditSettings {
+                        setter: AppConfigSetter::UndoCoalesceMs(val),
+                    });
+                }
+            })
+            .id("tool_settings_undo_coalesce");
+    })
+    .bind(
+        AnotherLens::new(AppState::current_toolspec, AppState::current_layer),
+        move |handle, pair| {
+            let (spec, layer) = pair.get(handle.cx);
+            let show = (spec == ToolSpec::Pencil || spec == ToolSpec::Bucket)
+                && (layer == Layer::FgTiles || layer == Layer::BgTiles);
+            handle.display(show);
+        },
+    );
+
     HStack::new(cx, move |cx| {
         Label::new(cx, "Advanced Tweaker").describing("tool_settings_advanced");
         let lens = AppState::config
@@ -99,6 +310,221 @@ fn build_tool_settings(cx: &mut Context) {
         let show = spec == ToolSpec::Selection;
         handle.display(show);
     });
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Confirm Delete").describing("tool_settings_confirm_delete");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::confirm_room_delete);
+        Checkbox::new(cx, lens)
+            .on_toggle(move |cx| {
+                let val = !lens.get(cx);
+                cx.emit(AppEvent::EditSettings {
+                    setter: AppConfigSetter::ConfirmRoomDelete(val),
+                });
+            })
+            .id("tool_settings_confirm_delete");
+    })
+    .bind(AppState::current_toolspec, move |handle, spec| {
+        let spec = spec.get(handle.cx);
+        let show = spec == ToolSpec::Room;
+        handle.display(show);
+    });
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Far Node Distance").describing("tool_settings_far_node_distance");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::far_node_distance);
+        Textbox::new(cx, lens)
+            .on_edit(|cx, text| {
+                if let Ok(val @ 0..) = text.parse() {
+                    cx.emit(AppEvent::EditSettings {
+                        setter: AppConfigSetter::FarNodeDistance(val),
+                    });
+                }
+            })
+            .id("tool_settings_far_node_distance");
+    })
+    .bind(AppState::current_toolspec, move |handle, spec| {
+        let spec = spec.get(handle.cx);
+        let show = spec == ToolSpec::Room;
+        handle.display(show);
+    });
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Rectangle").describing("tool_settings_tile_rect");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::tile_pencil.map(|p| *p == PencilBehavior::Rect));
+        Checkbox::new(cx, lens)
+            .on_toggle(move |cx| {
+                let val = if lens.get(cx) {
+                    PencilBehavior::Line
+                } else {
+                    PencilBehavior::Rect
+                };
+                cx.emit(AppEvent::EditSettings {
+                    setter: AppConfigSetter::TilePencil(val),
+                });
+            })
+            .id("tool_settings_tile_rect");
+    })
+    .bind(
+        AnotherLens::new(AppState::current_toolspec, AppState::current_layer),
+        move |handle, pair| {
+            let (spec, layer) = pair.get(handle.cx);
+            let show =
+                spec == ToolSpec::Pencil && (layer == Layer::FgTiles || layer == Layer::BgTiles);
+            handle.display(show);
+        },
+    );
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Random Variant").describing("tool_settings_random_variant");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::randomize_decal_variant);
+        Checkbox::new(cx, lens)
+            .on_toggle(move |cx| {
+                let val = !lens.get(cx);
+                cx.emit(AppEvent::EditSettings {
+                    setter: AppConfigSetter::RandomizeDecalVariant(val),
+                });
+            })
+            .id("tool_settings_random_variant");
+    })
+    .bind(
+        AnotherLens::new(AppState::current_toolspec, AppState::current_layer),
+        move |handle, pair| {
+            let (spec, layer) = pair.get(handle.cx);
+            let show =
+                spec == ToolSpec::Pencil && (layer == Layer::FgDecals || layer == Layer::BgDecals);
+            handle.display(show);
+        },
+    );
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Select Placed").describing("tool_settings_select_placed");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::select_placed);
+        Checkbox::new(cx, lens)
+            .on_toggle(move |cx| {
+                let val = !lens.get(cx);
+                cx.emit(AppEvent::EditSettings {
+                    setter: AppConfigSetter::SelectPlaced(val),
+                });
+            })
+            .id("tool_settings_select_placed");
+    })
+    .bind(AppState::current_toolspec, move |handle, spec| {
+        let spec = spec.get(handle.cx);
+        let show = spec == ToolSpec::Pencil;
+        handle.display(show);
+    });
+
+    HStack::new(cx, build_replace_attribute).bind(
+        AppState::current_toolspec,
+        move |handle, spec| {
+            let spec = spec.get(handle.cx);
+            let show = spec == ToolSpec::Selection;
+            handle.display(show);
+        },
+    );
+}
+
+/// The search and replacement typed into the Replace Attribute popup.
+#[derive(Debug, Default, Lens, Setter)]
+struct ReplaceAttributeModel {
+    name: String,
+    attribute: String,
+    find: String,
+    replace: String,
+}
+
+impl Model for ReplaceAttributeModel {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        if let Some(msg) = event.take::<ReplaceAttributeModelSetter>() {
+            msg.apply(self);
+        }
+    }
+}
+
+fn build_replace_attribute(cx: &mut Context) {
+    ReplaceAttributeModel::default().build(cx);
+    Dropdown::new(
+        cx,
+        |cx| {
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Replace Attribute");
+                Label::new(cx, DOWN).class("icon").class("dropdown_icon");
+            })
+        },
+        |cx| {
+            VStack::new(cx, |cx| {
+                replace_attribute_field(
+                    cx,
+                    "Entity",
+                    ReplaceAttributeModel::name,
+                    ReplaceAttributeModelSetter::Name,
+                );
+                replace_attribute_field(
+                    cx,
+                    "Attribute",
+                    ReplaceAttributeModel::attribute,
+                    ReplaceAttributeModelSetter::Attribute,
+                );
+                replace_attribute_field(
+                    cx,
+                    "Find",
+                    ReplaceAttributeModel::find,
+                    ReplaceAttributeModelSetter::Find,
+                );
+                replace_attribute_field(
+                    cx,
+                    "Replace",
+                    ReplaceAttributeModel::replace,
+                    ReplaceAttributeModelSetter::Replace,
+                );
+                Button::new(
+                    cx,
+                    |cx| {
+                        let name = ReplaceAttributeModel::name.get(cx);
+                        let attribute = ReplaceAttributeModel::attribute.get(cx);
+                        if name.is_empty() || attribute.is_empty() {
+                            return;
+                        }
+                        cx.emit(PopupEvent::Close);
+                        cx.emit(AppEvent::MapEvent {
+                            map: None,
+                            event: MapEvent::ReplaceAttribute {
+                                name,
+                                attribute,
+                                find: ReplaceAttributeModel::find.get(cx),
+                                replace: ReplaceAttributeModel::replace.get(cx),
+                            },
+                        });
+                    },
+                    |cx| Label::new(cx, "Replace All"),
+                );
+            })
+            .id("replace_attribute_popup");
+        },
+    )
+    .id("tool_settings_replace_attribute");
+}
+
+fn replace_attribute_field(
+    cx: &mut Context,
+    label: &'static str,
+    lens: impl Lens<Target = String>,
+    setter: fn(String) -> ReplaceAttributeModelSetter,
+) {
+    HStack::new(cx, move |cx| {
+        Label::new(cx, label);
+        Textbox::new(cx, lens).on_edit(move |cx, text| cx.emit(setter(text)));
+    });
 }
 
 pub fn build_tool_picker(cx: &mut Context) {
@@ -0,0 +1,94 @@
+use arborio_state::data::app::{AppEvent, AppState};
+use arborio_state::data::project_map::MapEvent;
+use arborio_state::data::selection::AppSelection;
+use arborio_state::data::MapID;
+use arborio_utils::vizia::prelude::*;
+use std::collections::HashSet;
+
+pub fn build_flags_tab(cx: &mut Context, map: MapID) {
+    // recomputed whenever the tab is rebuilt, e.g. by switching away and back
+    let app = cx.data::<AppState>().unwrap();
+    let Some(state) = app.loaded_maps.get(&map) else { return };
+    let usages = state.data.flag_usages();
+    let usages = usages
+        .into_iter()
+        .map(|(name, usages)| {
+            let usages = usages
+                .into_iter()
+                .map(|usage| {
+                    let room = &state.data.levels[usage.room].data;
+                    let entity_name = room
+                        .entities
+                        .iter()
+                        .chain(room.triggers.iter())
+                        .find(|e| e.id == usage.id)
+                        .map_or("???", |e| e.name.as_str());
+                    let text = format!(
+                        "{}: {} #{} ({})",
+                        room.name, entity_name, usage.id, usage.attribute
+                    );
+                    (text, usage)
+                })
+                .collect::<Vec<_>>();
+            (name, usages)
+        })
+        .collect::<Vec<_>>();
+
+    ScrollView::new(cx, 0.0, 0.0, false, true, move |cx| {
+        HStack::new(cx, |cx| {
+            Checkbox::new(cx, AppState::flag_preview).on_toggle(|cx| {
+                let enabled = !AppState::flag_preview.get(cx);
+                cx.emit(AppEvent::SetFlagPreview { enabled });
+            });
+            Label::new(cx, "Preview entity conditions with the checked flags set");
+        })
+        .class("flag_preview_toggle");
+        if usages.is_empty() {
+            Label::new(
+                cx,
+                "No flags are referenced by any entity or trigger in this map.",
+            );
+        }
+        for (name, usages) in usages {
+            VStack::new(cx, move |cx| {
+                HStack::new(cx, |cx| {
+                    let active_lens = AppState::preview_flags.map({
+                        let name = name.clone();
+                        move |flags| flags.contains(&name)
+                    });
+                    Checkbox::new(cx, active_lens.clone()).on_toggle({
+                        let name = name.clone();
+                        move |cx| {
+                            let active = !active_lens.get(cx);
+                            cx.emit(AppEvent::SetPreviewFlag {
+                                flag: name.clone(),
+                                active,
+                            });
+                        }
+                    });
+                    Label::new(cx, &usages.len().to_string()).class("flag_count");
+                    Label::new(cx, &name).class("flag_name");
+                })
+                .class("flag_header");
+                for (text, usage) in usages {
+                    Label::new(cx, &text)
+                        .class("flag_usage")
+                        .class("list_highlight")
+                        .on_press(move |cx| {
+                            let mut selection = HashSet::new();
+                            selection.insert(AppSelection::EntityBody(usage.id, usage.trigger));
+                            cx.emit(AppEvent::MapEvent {
+                                map: Some(map),
+                                event: MapEvent::Reveal {
+                                    room: usage.room,
+                                    selection,
+                                },
+                            });
+                        });
+                }
+            })
+            .class("flag_entry");
+        }
+    })
+    .id("map_flags_tab");
+}
@@ -1,8 +1,11 @@
+use arborio_utils::vizia::fonts::icons_names::DOWN;
 use arborio_utils::vizia::prelude::*;
 use arborio_utils::vizia::state::UnwrapLens;
 use dialog::DialogBox;
+use std::collections::BTreeSet;
 use std::path::Path;
 
+use arborio_modloader::dialog::DEFAULT_LANGUAGE;
 use arborio_modloader::module::{CelesteModuleKind, ModuleID};
 use arborio_state::data::app::AppEvent;
 use arborio_state::data::app::AppState;
@@ -19,6 +22,22 @@ pub fn build_installation_tab(cx: &mut Context) {
         |cx, root| {
             if let Some(root) = root.get_fallible(cx) {
                 Label::new(cx, &format!("Current celeste install is {root:?}"));
+                HStack::new(cx, |cx| {
+                    let lens = AppState::config
+                        .then(AutoSaverLens::new())
+                        .then(AppConfig::reopen_last_on_startup);
+                    Checkbox::new(cx, lens).on_toggle(move |cx| {
+                        let val = !lens.get(cx);
+                        cx.emit(AppEvent::EditSettings {
+                            setter: AppConfigSetter::ReopenLastOnStartup(val),
+                        });
+                    });
+                    Label::new(cx, "Reopen the last map on startup");
+                })
+                .id("reopen_last_on_startup");
+                Binding::new(cx, AppState::modules_version, |cx, _| {
+                    build_language_picker(cx);
+                });
                 ScrollView::new(cx, 0.0, 0.0, false, true, move |cx| {
                     VStack::new(cx, move |cx| {
                         Binding::new(cx, AppState::modules_version, move |cx, _| {
@@ -117,6 +136,48 @@ pub fn build_installation_tab(cx: &mut Context) {
     )
 }
 
+fn build_language_picker(cx: &mut Context) {
+    let mut languages = cx
+        .data::<AppState>()
+        .unwrap()
+        .modules
+        .values()
+        .flat_map(|module| module.dialog.keys().cloned())
+        .collect::<BTreeSet<_>>();
+    languages.insert(DEFAULT_LANGUAGE.to_owned());
+
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "Language");
+        let lens = AppState::config
+            .then(AutoSaverLens::new())
+            .then(AppConfig::language);
+        Dropdown::new(
+            cx,
+            move |cx| {
+                HStack::new(cx, move |cx| {
+                    Label::new(cx, lens);
+                    Label::new(cx, DOWN).class("icon").class("dropdown_icon");
+                })
+            },
+            move |cx| {
+                for language in languages.iter() {
+                    let language = language.clone();
+                    Label::new(cx, &language)
+                        .class("dropdown_element")
+                        .class("btn_highlight")
+                        .on_press(move |cx| {
+                            cx.emit(PopupEvent::Close);
+                            cx.emit(AppEvent::SetLanguage {
+                                language: language.clone(),
+                            });
+                        });
+                }
+            },
+        );
+    })
+    .id("language_picker");
+}
+
 fn build_project_overview_card(cx: &mut Context, module: ModuleID, name: &str, num_maps: usize) {
     VStack::new(cx, move |cx| {
         Label::new(cx, name).class("module_title");
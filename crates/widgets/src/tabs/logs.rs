@@ -1,8 +1,8 @@
-use arborio_state::data::app::AppState;
+use arborio_state::data::app::{AppEvent, AppState};
 use arborio_state::data::ArborioRecord;
 use arborio_utils::vizia::prelude::*;
 use log::Level;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub fn build_logs(cx: &mut Context) {
     ScrollView::new(cx, 0.0, 1.0, false, true, |cx| {
@@ -31,10 +31,21 @@ pub fn build_logs(cx: &mut Context) {
                 } else {
                     ""
                 };
+                let reveal = message.reveal.clone();
                 HStack::new(cx, move |cx| {
                     Label::new(cx, count_text).class("log_icon");
                     Label::new(cx, &message.message).class("log_text");
                 })
+                .toggle_class("log_link", reveal.is_some())
+                .on_press(move |cx| {
+                    if let Some((path, room, selection)) = reveal.clone() {
+                        cx.emit(AppEvent::RevealInMap {
+                            path,
+                            room,
+                            selection: HashSet::from([selection]),
+                        });
+                    }
+                })
                 .class(match message.level {
                     Level::Error => "error",
                     Level::Warn => "warning",
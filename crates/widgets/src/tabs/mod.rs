@@ -1,5 +1,6 @@
 pub mod config_editor;
 pub mod editor;
+pub mod flags;
 pub mod installation;
 pub mod logs;
 pub mod map_meta;
@@ -23,6 +24,7 @@ pub fn build_tabs(cx: &mut Context) {
             AppTab::ConfigEditor(_) => config_editor::build_config_editor(cx),
             AppTab::Logs => logs::build_logs(cx),
             AppTab::MapMeta(id) => map_meta::build_map_meta_tab(cx, id),
+            AppTab::MapFlags(id) => flags::build_flags_tab(cx, id),
         })
         .class("tab_container");
     });
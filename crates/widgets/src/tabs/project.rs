@@ -1,16 +1,20 @@
+use arborio_modloader::everest_yaml::DependencyProblem;
 use arborio_modloader::module::{CelesteModuleKind, MapPath, ModuleID};
 use arborio_state::data::app::{AppEvent, AppState};
-use arborio_state::data::project_map::ProjectEvent;
+use arborio_state::data::batch::BatchOp;
+use arborio_state::data::project_map::{MapTemplate, ProjectEvent};
 use arborio_state::lenses::StaticerLens;
 use arborio_utils::vizia::prelude::*;
 use arborio_widgets_common::confirm_delete::deleter;
 use arborio_widgets_common::label_with_pencil::label_with_pencil;
+use dialog::DialogBox;
 
 pub fn build_project_tab(cx: &mut Context, project: ModuleID) {
     ScrollView::new(cx, 0.0, 0.0, false, true, move |cx| {
         VStack::new(cx, move |cx| {
             Binding::new(cx, AppState::modules_version, move |cx, _| {
                 build_title(cx, project);
+                build_dependency_warnings(cx, project);
                 build_map_list(cx, project);
                 build_controls(cx, project);
             });
@@ -93,6 +97,44 @@ fn build_title(cx: &mut Context, project: ModuleID) {
     });
 }
 
+fn build_dependency_warnings(cx: &mut Context, project: ModuleID) {
+    let app = cx.data::<AppState>().unwrap();
+    let available = app
+        .modules
+        .values()
+        .map(|module| {
+            (
+                module.everest_metadata.name.clone(),
+                module.everest_metadata.version.clone(),
+            )
+        })
+        .collect();
+    let (missing, outdated): (Vec<_>, Vec<_>) = app
+        .modules
+        .get(&project)
+        .unwrap()
+        .everest_metadata
+        .check_dependencies(&available)
+        .into_iter()
+        .partition(|problem| matches!(problem, DependencyProblem::Missing { .. }));
+
+    for (title, problems) in [
+        ("Missing dependencies", missing),
+        ("Outdated dependencies", outdated),
+    ] {
+        if problems.is_empty() {
+            continue;
+        }
+        VStack::new(cx, move |cx| {
+            Label::new(cx, title).class("dependency_warning_title");
+            for problem in problems {
+                Label::new(cx, &problem.to_string());
+            }
+        })
+        .class("dependency_warning");
+    }
+}
+
 fn build_map_list(cx: &mut Context, project: ModuleID) {
     let module = cx
         .data::<AppState>()
@@ -112,15 +154,40 @@ fn build_map_list(cx: &mut Context, project: ModuleID) {
     .on_press(move |cx| {
         cx.emit(AppEvent::ProjectEvent {
             project: Some(project),
-            event: ProjectEvent::NewMap,
+            event: ProjectEvent::NewMap {
+                template: MapTemplate::Empty,
+            },
+        })
+    });
+    HStack::new(cx, move |cx| {
+        Label::new(cx, "+").class("big_plus");
+        Label::new(cx, "New Map with a Starter Room").id("new_starter_map_text");
+    })
+    .class("btn_highlight")
+    .id("new_starter_map_button")
+    .on_press(move |cx| {
+        cx.emit(AppEvent::ProjectEvent {
+            project: Some(project),
+            event: ProjectEvent::NewMap {
+                template: MapTemplate::Starter,
+            },
         })
     });
 
     maps.sort();
     for map in maps.into_iter() {
         let map2 = map.clone();
+        let display_name = cx
+            .data::<AppState>()
+            .unwrap()
+            .omni_palette
+            .map_display_name(&map)
+            .map(str::to_owned);
         VStack::new(cx, move |cx| {
             Label::new(cx, &map2).class("map_title");
+            if let Some(display_name) = &display_name {
+                Label::new(cx, display_name).class("map_display_name");
+            }
         })
         .class("map_overview_card")
         .class("btn_highlight")
@@ -145,6 +212,41 @@ fn build_controls(cx: &mut Context, project: ModuleID) {
     let module_name = module.everest_metadata.name.clone();
     let editing = matches!(module.module_kind(), CelesteModuleKind::Directory);
     VStack::new(cx, move |cx| {
+        HStack::new(cx, move |cx| {
+            let ops = if editing {
+                &[BatchOp::Validate, BatchOp::Fix, BatchOp::Resave][..]
+            } else {
+                &[BatchOp::Validate][..]
+            };
+            for &op in ops {
+                Button::new(
+                    cx,
+                    move |cx| {
+                        cx.emit(AppEvent::ProjectEvent {
+                            project: Some(project),
+                            event: ProjectEvent::RunBatch { op },
+                        })
+                    },
+                    move |cx| Label::new(cx, &format!("{} all maps", op.name())),
+                );
+            }
+            Button::new(
+                cx,
+                move |cx| {
+                    cx.emit(AppEvent::ProjectEvent {
+                        project: Some(project),
+                        event: ProjectEvent::CancelBatch,
+                    })
+                },
+                |cx| Label::new(cx, "Cancel"),
+            );
+            Button::new(
+                cx,
+                move |cx| report_unused_assets(cx, project),
+                |cx| Label::new(cx, "Find unused assets"),
+            );
+        })
+        .id("project_batch");
         if editing {
             deleter(
                 cx,
@@ -162,3 +264,18 @@ fn build_controls(cx: &mut Context, project: ModuleID) {
     })
     .id("project_controls");
 }
+
+fn report_unused_assets(cx: &mut EventContext, project: ModuleID) {
+    let result = dialog::FileSelection::new("Save Unused Assets Report")
+        .mode(dialog::FileSelectionMode::Save)
+        .show()
+        .unwrap();
+    if let Some(result) = result {
+        cx.emit(AppEvent::ProjectEvent {
+            project: Some(project),
+            event: ProjectEvent::ReportUnusedAssets {
+                output: result.into(),
+            },
+        });
+    }
+}
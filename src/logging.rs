@@ -48,6 +48,7 @@ impl Log for ViziaLogger {
                 .send(ArborioRecord {
                     level: record.level(),
                     message: format!("{}", record.args()),
+                    reveal: None,
                 })
                 .unwrap();
         }
@@ -7,7 +7,7 @@ use std::error::Error;
 
 use crate::logging::setup_logger_thread;
 use arborio_state::data::app::{AppEvent, AppState};
-use arborio_state::data::{AppConfigSetter, Progress};
+use arborio_state::data::{AppConfig, AppConfigSetter, Progress};
 use arborio_utils::resources::fonts::{DROID_SANS_MONO, RENOGARE};
 use arborio_utils::vizia::prelude::*;
 use arborio_widgets::main_widget::main_widget;
@@ -15,7 +15,17 @@ use arborio_widgets::main_widget::main_widget;
 fn main() -> Result<(), Box<dyn Error>> {
     let icon_img = image::load_from_memory(include_bytes!("../icon.png")).unwrap();
     let (width, height) = (icon_img.width(), icon_img.height());
-    let app = Application::new(|cx| {
+    let config = AppConfig::load();
+    let window_size = config.initial_window_size();
+    let monitors = display_info::DisplayInfo::all()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|d| (d.x, d.y, d.width, d.height))
+        .collect::<Vec<_>>();
+    // without a saved size the window opens at vizia's default of 800x600
+    let window_position =
+        config.initial_window_position(window_size.unwrap_or((800, 600)), &monitors);
+    let mut app = Application::new(|cx| {
         let tx = setup_loader_thread(
             cx,
             |p, s| AppEvent::Progress {
@@ -49,6 +59,12 @@ fn main() -> Result<(), Box<dyn Error>> {
     .title("Arborio")
     .icon(icon_img.into_bytes(), width, height)
     .ignore_default_theme();
+    if let Some(size) = window_size {
+        app = app.inner_size(size);
+    }
+    if let Some(position) = window_position {
+        app = app.position(position);
+    }
 
     app.run();
     Ok(())